@@ -0,0 +1,35 @@
+# Copyright (c) Microsoft Corporation.
+# Licensed under the MIT License.
+
+# Passes a module built with PyQIR to llvmlite through its LLVMModuleRef. This only
+# works if llvmlite is linked against the same LLVM library as PyQIR, since LLVM
+# objects can't be shared between different copies of LLVM.
+
+import ctypes
+
+import llvmlite.binding as llvm
+
+import pyqir
+
+mod = pyqir.SimpleModule("llvmlite_interop", num_qubits=2, num_results=2)
+qis = pyqir.BasicQisBuilder(mod.builder)
+qis.h(mod.qubits[0])
+qis.cx(mod.qubits[0], mod.qubits[1])
+qis.mz(mod.qubits[0], mod.results[0])
+qis.mz(mod.qubits[1], mod.results[1])
+
+# The capsule owns a copy of the emitted module, so keep it alive while the pointer
+# is in use.
+capsule = mod.llvm_module_ptr()
+get_pointer = ctypes.pythonapi.PyCapsule_GetPointer
+get_pointer.argtypes = [ctypes.py_object, ctypes.c_char_p]
+get_pointer.restype = ctypes.c_void_p
+pointer = get_pointer(capsule, b"LLVMModuleRef")
+
+module = llvm.ModuleRef(ctypes.c_void_p(pointer), llvm.get_global_context())
+try:
+    module.verify()
+    print(module)
+finally:
+    # The module belongs to PyQIR, so llvmlite must not dispose of it.
+    module.detach()
@@ -8,6 +8,7 @@ from pyqir._native import (
     AttributeSet,
     BasicBlock,
     Builder,
+    BuilderReentrancyError,
     Call,
     Constant,
     ConstantAsMetadata,
@@ -30,35 +31,50 @@ from pyqir._native import (
     Opcode,
     Phi,
     PointerType,
+    QirEmitError,
     StructType,
     Switch,
     Type,
     Value,
+    add_compile_unit,
     add_string_attribute,
+    adjoint,
+    array_type,
+    batch_measurements,
     const,
+    defer_measurements,
     dynamic_qubit_management,
     dynamic_result_management,
     extract_byte_string,
     global_byte_string,
+    is_array_type,
     is_entry_point,
     is_interop_friendly,
     is_qubit_type,
     is_result_type,
+    power,
     qubit,
     qubit_id,
     qubit_type,
     qir_major_version,
     qir_minor_version,
     qir_module,
+    qis_declarations,
+    relabel_qubits,
     required_num_qubits,
     required_num_results,
     result,
     result_id,
     result_type,
+    to_matrix,
 )
-from pyqir._simple import SimpleModule
+from pyqir._operations import Conditional, Operation
+from pyqir._emit import EmitOptions
+from pyqir._simple import SimpleModule, load_module
 from pyqir._entry_point import entry_point
-from pyqir._basicqis import BasicQisBuilder
+from pyqir._basicqis import BasicQisBuilder, NoisyBasicQisBuilder
+from pyqir._random import random_circuit
+from pyqir._validate import Violation
 from pyqir._constants import ATTR_FUNCTION_INDEX, ATTR_RETURN_INDEX
 
 __all__ = [
@@ -69,10 +85,13 @@ __all__ = [
     "BasicBlock",
     "BasicQisBuilder",
     "Builder",
+    "BuilderReentrancyError",
     "Call",
+    "Conditional",
     "Constant",
     "ConstantAsMetadata",
     "Context",
+    "EmitOptions",
     "FCmp",
     "FloatConstant",
     "FloatPredicate",
@@ -88,36 +107,52 @@ __all__ = [
     "MetadataString",
     "Module",
     "ModuleFlagBehavior",
+    "NoisyBasicQisBuilder",
     "Opcode",
+    "Operation",
     "Phi",
     "PointerType",
+    "QirEmitError",
     "SimpleModule",
     "StructType",
     "Switch",
     "Type",
     "Value",
+    "Violation",
+    "add_compile_unit",
     "add_string_attribute",
+    "adjoint",
+    "array_type",
+    "batch_measurements",
     "const",
+    "defer_measurements",
     "dynamic_qubit_management",
     "dynamic_result_management",
     "entry_point",
     "extract_byte_string",
     "global_byte_string",
+    "is_array_type",
     "is_entry_point",
     "is_interop_friendly",
     "is_qubit_type",
     "is_result_type",
+    "power",
+    "load_module",
     "qubit_id",
     "qubit_type",
     "qubit",
     "qir_major_version",
     "qir_minor_version",
     "qir_module",
+    "qis_declarations",
+    "random_circuit",
+    "relabel_qubits",
     "required_num_qubits",
     "required_num_results",
     "result_id",
     "result_type",
     "result",
+    "to_matrix",
     "ATTR_FUNCTION_INDEX",
     "ATTR_RETURN_INDEX",
 ]
@@ -2,38 +2,209 @@
 # Licensed under the MIT License.
 
 from pyqir import Builder, Value
-from typing import Callable, Optional, Union
+from typing import (
+    Any,
+    Callable,
+    List,
+    Optional,
+    Sequence,
+    Set,
+    Tuple,
+    Union,
+)
+import cmath
+import math
+import warnings
+import pyqir
 import pyqir.qis as qis
+from pyqir.angles import normalize_angle
 
 
 class BasicQisBuilder:
     """An instruction builder that generates instructions from the basic quantum instruction set."""
 
-    def __init__(self, builder: Builder) -> None:
+    def __init__(
+        self,
+        builder: Builder,
+        normalize_angles: bool = False,
+        on_remeasure: str = "allow",
+    ) -> None:
         """
         Initializes a basic QIS builder.
 
         :param Builder builder: The IR Builder used to create the instructions
-        """
+        :param bool normalize_angles: Whether to reduce every constant rotation angle modulo
+            :math:`2\\pi` into :math:`(-\\pi, \\pi]` with :func:`pyqir.angles.normalize_angle`
+            before it's written to the IR. A rotation by the reduced angle may differ by a global
+            phase of :math:`-1`, which is only observable if the circuit is controlled later.
+            Angles that are values are unchanged.
+        :param str on_remeasure: What to do when a measurement through this builder measures a
+            qubit that an earlier one already measured, without a reset through this builder in
+            between: ``"allow"`` it, ``"warn"`` with a :class:`UserWarning` from the
+            :mod:`warnings` module, or raise an ``"error"`` before the measurement is inserted.
+            After a branch from :meth:`if_result`, a qubit counts as measured if it is measured in
+            either branch, and only counts as reset if it is reset in both. Qubits that aren't
+            statically allocated aren't tracked.
+        """
+        if on_remeasure not in ("allow", "warn", "error"):
+            raise ValueError(
+                "on_remeasure must be 'allow', 'warn' or 'error', "
+                f"not {on_remeasure!r}."
+            )
         self._builder = builder
+        self._normalize_angles = normalize_angles
+        self._on_remeasure = on_remeasure
+        self._measured: Set[int] = set()
 
-    def cx(self, control: Value, target: Value) -> None:
+    def cx(self, control: Value, target: Value, control_state: int = 1) -> None:
         """
         Inserts a controlled Pauli :math:`X` gate.
 
+        With a control state of zero, the gate applies when the control qubit is zero, and it's
+        wrapped in :math:`X` gates on the control.
+
         :param control: The control qubit.
         :param target: The target qubit.
+        :param control_state: The state of the control qubit that the gate applies in, 0 or 1.
         """
-        qis.cx(self._builder, control, target)
+        self._on_control_state(
+            control_state, control, lambda: self._gate(qis.cx, control, target)
+        )
 
-    def cz(self, control: Value, target: Value) -> None:
+    def cz(self, control: Value, target: Value, control_state: int = 1) -> None:
         """
         Inserts a controlled Pauli :math:`Z` gate.
 
+        With a control state of zero, the gate applies when the control qubit is zero, and it's
+        wrapped in :math:`X` gates on the control.
+
+        :param control: The control qubit.
+        :param target: The target qubit.
+        :param control_state: The state of the control qubit that the gate applies in, 0 or 1.
+        """
+        self._on_control_state(
+            control_state, control, lambda: self._gate(qis.cz, control, target)
+        )
+
+    def ch(self, control: Value, target: Value, control_state: int = 1) -> None:
+        """
+        Inserts a controlled Hadamard gate.
+
+        With a control state of zero, the gate applies when the control qubit is zero, and it's
+        wrapped in :math:`X` gates on the control.
+
+        :param control: The control qubit.
+        :param target: The target qubit.
+        :param control_state: The state of the control qubit that the gate applies in, 0 or 1.
+        """
+        self._on_control_state(
+            control_state, control, lambda: self._gate(qis.ch, control, target)
+        )
+
+    def cy(self, control: Value, target: Value, control_state: int = 1) -> None:
+        """
+        Inserts a controlled Pauli :math:`Y` gate.
+
+        With a control state of zero, the gate applies when the control qubit is zero, and it's
+        wrapped in :math:`X` gates on the control.
+
         :param control: The control qubit.
         :param target: The target qubit.
+        :param control_state: The state of the control qubit that the gate applies in, 0 or 1.
+        """
+        self._on_control_state(
+            control_state, control, lambda: self._gate(qis.cy, control, target)
+        )
+
+    def cx_ladder(
+        self,
+        controls: Sequence[Value],
+        target: Value,
+        ancillas: Sequence[Value] = (),
+    ) -> None:
         """
-        qis.cz(self._builder, control, target)
+        Inserts a multi-controlled Pauli :math:`X` gate, decomposed into a ladder of Toffoli gates.
+
+        With more than two controls, the conjunction of the controls is computed into the ancilla
+        qubits one control at a time, the target is flipped, and the ancillas are uncomputed again.
+        This needs ``len(controls) - 2`` ancilla qubits, which must start and end in the zero state.
+
+        :param controls: The control qubits.
+        :param target: The target qubit.
+        :param ancillas: The ancilla qubits available to the decomposition.
+        """
+        needed = max(len(controls) - 2, 0)
+        if len(ancillas) < needed:
+            raise ValueError(
+                f"{len(controls)} controls need {needed} ancilla qubits, "
+                f"but only {len(ancillas)} were given."
+            )
+
+        ancillas = ancillas[:needed]
+        qubits = [*controls, target, *ancillas]
+        if any(q in qubits[i + 1 :] for i, q in enumerate(qubits)):
+            raise ValueError("Controls, target and ancillas must be distinct qubits.")
+
+        if len(controls) == 0:
+            self.x(target)
+        elif len(controls) == 1:
+            self.cx(controls[0], target)
+        elif len(controls) == 2:
+            self._gate(qis.ccx, controls[0], controls[1], target)
+        else:
+            ladder = [(controls[0], controls[1], ancillas[0])]
+            for i in range(1, needed):
+                ladder.append((controls[i + 1], ancillas[i - 1], ancillas[i]))
+
+            for step in ladder:
+                self._gate(qis.ccx, *step)
+            self._gate(qis.ccx, controls[-1], ancillas[-1], target)
+            for step in reversed(ladder):
+                self._gate(qis.ccx, *step)
+
+    def extend(self, instructions: Sequence[Tuple[Any, ...]]) -> None:
+        """
+        Inserts a sequence of gates with one call to :func:`pyqir.qis.extend`, which is faster than
+        calling a method for each gate. Each instruction is a tuple of the name of the gate and its
+        arguments, like ``("cx", control, target)``, ``("rz", theta, qubit)`` or
+        ``("mz", qubit, result)``. See :func:`pyqir.qis.extend` for the supported gates.
+
+        Angles are normalized and measurements are tracked like they are by the methods for each
+        gate. Every instruction is checked before any is inserted, so nothing is inserted if one
+        of them is invalid, and the message of the error starts with its position.
+
+        :param instructions: The instructions.
+        """
+        instructions = list(instructions)
+        measured = set(self._measured)
+        messages: List[str] = []
+        # Measurements only need to be tracked if measuring again isn't allowed.
+        for index, instruction in enumerate(
+            instructions if self._on_remeasure != "allow" else []
+        ):
+            if (
+                not isinstance(instruction, tuple)
+                or len(instruction) < 2
+                or not isinstance(instruction[1], Value)
+            ):
+                continue
+            id = pyqir.qubit_id(instruction[1])
+            if id is None:
+                continue
+            if instruction[0] == "reset":
+                measured.discard(id)
+            elif instruction[0] == "mz":
+                if id in measured:
+                    message = f"Instruction {index}: {_remeasure_message(id)}"
+                    if self._on_remeasure == "error":
+                        raise ValueError(message)
+                    messages.append(message)
+                measured.add(id)
+
+        qis.extend(self._builder, instructions, self._normalize_angles, self._noise())
+        self._measured = measured
+        for message in messages:
+            warnings.warn(message, stacklevel=2)
 
     def h(self, qubit: Value) -> None:
         """
@@ -41,7 +212,59 @@ class BasicQisBuilder:
 
         :param qubit: The target qubit.
         """
-        qis.h(self._builder, qubit)
+        self._gate(qis.h, qubit)
+
+    def id(self, qubit: Value) -> None:
+        """
+        Inserts an identity gate, which does nothing to the qubit, for example as a placeholder
+        that takes up a time step.
+
+        :param qubit: The target qubit.
+        """
+        self._gate(qis.id, qubit)
+
+    def linear_entangle(self, qubits: Sequence[Value]) -> None:
+        """
+        Inserts the gates that entangle qubits in the zero state into a GHZ state:
+        :math:`H` on the first qubit, followed by a chain of CNOT gates from each qubit to the
+        next.
+
+        :param qubits: The qubits to entangle, in the order of the chain.
+        """
+        if any(q in qubits[i + 1 :] for i, q in enumerate(qubits)):
+            raise ValueError("Qubits in a linear entangler must be distinct.")
+
+        if len(qubits) > 0:
+            self._gate(qis.h, qubits[0])
+        for control, target in zip(qubits, qubits[1:]):
+            self._gate(qis.cx, control, target)
+
+    def m(self, qubit: Value) -> Value:
+        """
+        Inserts a Z-basis measurement operation that returns a new result.
+
+        Unlike :meth:`mz`, which writes into one of the module's statically allocated results, the
+        result is produced by the measurement itself. Pass it to :meth:`if_result` to branch on the
+        measurement immediately.
+
+        :param qubit: The qubit to measure.
+        :returns: The measurement result.
+        """
+        self._measure(qubit)
+        return qis.m(self._builder, qubit)
+
+    def measure_reset(self, qubit: Value, result: Value) -> None:
+        """
+        Inserts a Z-basis measurement into a result, immediately followed by a reset of the same
+        qubit, so that the qubit can be reused in the zero state, like in repeat-until-success
+        loops. Nothing is inserted between the two operations.
+
+        :param qubit: The qubit to measure and reset.
+        :param result: A result where the measurement result will be written to.
+        """
+        self._measure(qubit)
+        qis.mz(self._builder, qubit, result)
+        self.reset(qubit)
 
     def mz(self, qubit: Value, result: Value) -> None:
         """
@@ -50,8 +273,86 @@ class BasicQisBuilder:
         :param qubit: The qubit to measure.
         :param result: A result where the measurement result will be written to.
         """
+        self._measure(qubit)
         qis.mz(self._builder, qubit, result)
 
+    def prepare_bell_state(
+        self, qubit0: Value, qubit1: Value, variant: int = 0
+    ) -> None:
+        """
+        Inserts the gates that prepare a Bell state from two qubits in the zero state. The
+        variants are:
+
+        - 0: :math:`\\Phi^+ = (|00\\rangle + |11\\rangle) / \\sqrt{2}`
+        - 1: :math:`\\Phi^- = (|00\\rangle - |11\\rangle) / \\sqrt{2}`
+        - 2: :math:`\\Psi^+ = (|01\\rangle + |10\\rangle) / \\sqrt{2}`
+        - 3: :math:`\\Psi^- = (|01\\rangle - |10\\rangle) / \\sqrt{2}`
+
+        :math:`\\Phi^+` is prepared with :math:`H` on the first qubit and CNOT from the first qubit
+        to the second. The other variants first flip the first qubit to get the minus sign, the
+        second qubit to get odd parity, or both.
+
+        :param qubit0: The first qubit.
+        :param qubit1: The second qubit.
+        :param variant: The Bell state, from 0 to 3.
+        """
+        if variant not in range(4):
+            raise ValueError(
+                f"Bell state variant must be between 0 and 3, not {variant}."
+            )
+
+        if variant & 1:
+            self._gate(qis.x, qubit0)
+        if variant & 2:
+            self._gate(qis.x, qubit1)
+        self._gate(qis.h, qubit0)
+        self._gate(qis.cx, qubit0, qubit1)
+
+    def qft(self, qubits: Sequence[Value], inverse: bool = False) -> None:
+        """
+        Inserts the quantum Fourier transform, with ``qubits[0]`` as the least significant bit.
+
+        The circuit is the standard one: a Hadamard gate on each qubit from the most significant,
+        followed by controlled phase rotations from the less significant qubits, then SWAP gates
+        that reverse the qubits. Each controlled phase rotation is made of :math:`R_z` and CNOT
+        gates, so the transform is only exact up to a global phase.
+
+        :param qubits: The qubits to transform.
+        :param inverse: Whether to insert the inverse transform instead.
+        """
+        if any(q in qubits[i + 1 :] for i, q in enumerate(qubits)):
+            raise ValueError("Qubits in a QFT must be distinct.")
+
+        n = len(qubits)
+        if inverse:
+            for i in range(n // 2):
+                self._gate(qis.swap, qubits[i], qubits[n - 1 - i])
+            for i in range(n):
+                for j in range(i):
+                    self._controlled_phase(
+                        -math.pi / 2 ** (i - j), qubits[j], qubits[i]
+                    )
+                self._gate(qis.h, qubits[i])
+        else:
+            for i in reversed(range(n)):
+                self._gate(qis.h, qubits[i])
+                for j in reversed(range(i)):
+                    self._controlled_phase(
+                        math.pi / 2 ** (i - j), qubits[j], qubits[i]
+                    )
+            for i in range(n // 2):
+                self._gate(qis.swap, qubits[i], qubits[n - 1 - i])
+
+    def read_result(self, result: Value) -> Value:
+        """
+        Inserts a read of a measurement result as a boolean, without measuring again. Unlike
+        :meth:`if_result`, the boolean can be combined with others before branching on it.
+
+        :param result: The result to read.
+        :returns: The ``i1`` value of the result.
+        """
+        return qis.read_result(self._builder, result)
+
     def reset(self, qubit: Value) -> None:
         """
         Inserts a reset operation.
@@ -59,6 +360,9 @@ class BasicQisBuilder:
         :param qubit: The qubit to reset.
         """
         qis.reset(self._builder, qubit)
+        id = pyqir.qubit_id(qubit)
+        if id is not None:
+            self._measured.discard(id)
 
     def rx(self, theta: Union[Value, float], qubit: Value) -> None:
         """
@@ -67,7 +371,55 @@ class BasicQisBuilder:
         :param theta: The angle to rotate by.
         :param qubit: The qubit to rotate.
         """
-        qis.rx(self._builder, theta, qubit)
+        self._gate(qis.rx, self._angle(theta), qubit)
+
+    def rxx(
+        self, theta: Union[Value, float], qubit0: Value, qubit1: Value
+    ) -> None:
+        """
+        Inserts a rotation about :math:`X \\otimes X`, which is
+        :math:`e^{-i \\theta X \\otimes X / 2}` with :math:`X` on ``qubit0`` and
+        :math:`X` on ``qubit1``.
+
+        The gate is decomposed like :meth:`rzz`, with basis changes on the qubits.
+
+        :param theta: The angle to rotate by.
+        :param qubit0: The first qubit.
+        :param qubit1: The second qubit.
+        """
+        self._pauli_rotation(theta, "xx", qubit0, qubit1)
+
+    def rxy(
+        self, theta: Union[Value, float], qubit0: Value, qubit1: Value
+    ) -> None:
+        """
+        Inserts a rotation about :math:`X \\otimes Y`, which is
+        :math:`e^{-i \\theta X \\otimes Y / 2}` with :math:`X` on ``qubit0`` and
+        :math:`Y` on ``qubit1``. For a :math:`Y \\otimes X` rotation, swap the qubits.
+
+        The gate is decomposed like :meth:`rzz`, with basis changes on the qubits.
+
+        :param theta: The angle to rotate by.
+        :param qubit0: The first qubit.
+        :param qubit1: The second qubit.
+        """
+        self._pauli_rotation(theta, "xy", qubit0, qubit1)
+
+    def rxz(
+        self, theta: Union[Value, float], qubit0: Value, qubit1: Value
+    ) -> None:
+        """
+        Inserts a rotation about :math:`X \\otimes Z`, which is
+        :math:`e^{-i \\theta X \\otimes Z / 2}` with :math:`X` on ``qubit0`` and
+        :math:`Z` on ``qubit1``.
+
+        The gate is decomposed like :meth:`rzz`, with basis changes on the qubits.
+
+        :param theta: The angle to rotate by.
+        :param qubit0: The first qubit.
+        :param qubit1: The second qubit.
+        """
+        self._pauli_rotation(theta, "xz", qubit0, qubit1)
 
     def ry(self, theta: Union[Value, float], qubit: Value) -> None:
         """
@@ -76,7 +428,39 @@ class BasicQisBuilder:
         :param theta: The angle to rotate by.
         :param qubit: The qubit to rotate.
         """
-        qis.ry(self._builder, theta, qubit)
+        self._gate(qis.ry, self._angle(theta), qubit)
+
+    def ryy(
+        self, theta: Union[Value, float], qubit0: Value, qubit1: Value
+    ) -> None:
+        """
+        Inserts a rotation about :math:`Y \\otimes Y`, which is
+        :math:`e^{-i \\theta Y \\otimes Y / 2}` with :math:`Y` on ``qubit0`` and
+        :math:`Y` on ``qubit1``.
+
+        The gate is decomposed like :meth:`rzz`, with basis changes on the qubits.
+
+        :param theta: The angle to rotate by.
+        :param qubit0: The first qubit.
+        :param qubit1: The second qubit.
+        """
+        self._pauli_rotation(theta, "yy", qubit0, qubit1)
+
+    def ryz(
+        self, theta: Union[Value, float], qubit0: Value, qubit1: Value
+    ) -> None:
+        """
+        Inserts a rotation about :math:`Y \\otimes Z`, which is
+        :math:`e^{-i \\theta Y \\otimes Z / 2}` with :math:`Y` on ``qubit0`` and
+        :math:`Z` on ``qubit1``.
+
+        The gate is decomposed like :meth:`rzz`, with basis changes on the qubits.
+
+        :param theta: The angle to rotate by.
+        :param qubit0: The first qubit.
+        :param qubit1: The second qubit.
+        """
+        self._pauli_rotation(theta, "yz", qubit0, qubit1)
 
     def rz(self, theta: Union[Value, float], qubit: Value) -> None:
         """
@@ -85,7 +469,26 @@ class BasicQisBuilder:
         :param theta: The angle to rotate by.
         :param qubit: The qubit to rotate.
         """
-        qis.rz(self._builder, theta, qubit)
+        self._gate(qis.rz, self._angle(theta), qubit)
+
+    def rzz(
+        self, theta: Union[Value, float], qubit0: Value, qubit1: Value
+    ) -> None:
+        """
+        Inserts a rotation about :math:`Z \\otimes Z`, which is
+        :math:`e^{-i \\theta Z \\otimes Z / 2}` with :math:`Z` on ``qubit0`` and
+        :math:`Z` on ``qubit1``.
+
+        There's no QIS intrinsic for two-qubit rotations, so the gate is decomposed into a CNOT
+        gate, an :math:`R_z` gate on ``qubit1``, and another CNOT gate. The other two-qubit
+        rotations change the basis of each qubit to :math:`Z` with :math:`H` and :math:`S`
+        gates around this decomposition.
+
+        :param theta: The angle to rotate by.
+        :param qubit0: The first qubit.
+        :param qubit1: The second qubit.
+        """
+        self._pauli_rotation(theta, "zz", qubit0, qubit1)
 
     def s(self, qubit: Value) -> None:
         """
@@ -93,7 +496,7 @@ class BasicQisBuilder:
 
         :param qubit: The target qubit.
         """
-        qis.s(self._builder, qubit)
+        self._gate(qis.s, qubit)
 
     def s_adj(self, qubit: Value) -> None:
         """
@@ -101,7 +504,33 @@ class BasicQisBuilder:
 
         :param qubit: The target qubit.
         """
-        qis.s_adj(self._builder, qubit)
+        self._gate(qis.s_adj, qubit)
+
+    def swap_network(
+        self,
+        qubits: Sequence[Value],
+        interact: Optional[Callable[[Value, Value], None]] = None,
+    ) -> None:
+        """
+        Inserts a network of SWAP gates between neighboring qubits that brings every pair of qubit
+        states next to each other once, using only nearest-neighbor interactions.
+
+        The network is an odd-even transposition sort: there are ``len(qubits)`` layers, which
+        alternately swap the pairs starting at even and odd positions. At the end, the qubit
+        states are in reverse order.
+
+        :param qubits: The qubits, in the order of their neighbors.
+        :param interact: A callable that inserts the interaction between two neighboring qubits,
+            called right before they are swapped.
+        """
+        if any(q in qubits[i + 1 :] for i, q in enumerate(qubits)):
+            raise ValueError("Qubits in a swap network must be distinct.")
+
+        for layer in range(len(qubits)):
+            for i in range(layer % 2, len(qubits) - 1, 2):
+                if interact is not None:
+                    interact(qubits[i], qubits[i + 1])
+                self._gate(qis.swap, qubits[i], qubits[i + 1])
 
     def t(self, qubit: Value) -> None:
         """
@@ -109,7 +538,7 @@ class BasicQisBuilder:
 
         :param qubit: The target qubit.
         """
-        qis.t(self._builder, qubit)
+        self._gate(qis.t, qubit)
 
     def t_adj(self, qubit: Value) -> None:
         """
@@ -117,7 +546,77 @@ class BasicQisBuilder:
 
         :param qubit: The target qubit.
         """
-        qis.t_adj(self._builder, qubit)
+        self._gate(qis.t_adj, qubit)
+
+    def u1(self, lam: Union[Value, float], qubit: Value) -> None:
+        """
+        Inserts a :math:`U_1(\\lambda)` gate, which is the phase gate
+        :math:`\\operatorname{diag}(1, e^{i \\lambda})`. This is :math:`R_z(\\lambda)` up to a
+        global phase.
+
+        :param lam: The phase angle.
+        :param qubit: The target qubit.
+        """
+        self._gate(qis.u1, self._angle(lam), qubit)
+
+    def u2(
+        self, phi: Union[Value, float], lam: Union[Value, float], qubit: Value
+    ) -> None:
+        """
+        Inserts a :math:`U_2(\\phi, \\lambda)` gate, which is
+        :math:`U_1(\\phi) R_y(\\pi / 2) U_1(\\lambda)`.
+
+        :param phi: The phase angle applied last.
+        :param lam: The phase angle applied first.
+        :param qubit: The target qubit.
+        """
+        self._gate(qis.u2, self._angle(phi), self._angle(lam), qubit)
+
+    def unitary_1q(self, matrix: Sequence[Sequence[complex]], qubit: Value) -> None:
+        """
+        Inserts an arbitrary single-qubit gate given by its unitary matrix, decomposed into
+        :math:`R_z(\\delta)`, :math:`R_y(\\gamma)` and :math:`R_z(\\beta)` gates in that order, so
+        that the matrix is :math:`e^{i \\alpha} R_z(\\beta) R_y(\\gamma) R_z(\\delta)`. The global
+        phase :math:`e^{i \\alpha}` is dropped, so the gate is only exact up to a global phase.
+
+        A :class:`ValueError` is raised if the matrix isn't 2x2 or isn't unitary within a
+        tolerance of ``1e-9``.
+
+        :param matrix: The unitary matrix, as a list of rows.
+        :param qubit: The target qubit.
+        """
+        if len(matrix) != 2 or any(len(row) != 2 for row in matrix):
+            raise ValueError("The matrix of a single-qubit gate must be 2x2.")
+        (a, b), (c, d) = matrix
+        # The rows of a unitary matrix are orthonormal.
+        errors = [
+            abs(a) ** 2 + abs(b) ** 2 - 1,
+            abs(c) ** 2 + abs(d) ** 2 - 1,
+            a * c.conjugate() + b * d.conjugate(),
+        ]
+        if any(abs(e) > 1e-9 for e in errors):
+            raise ValueError("The matrix of a single-qubit gate must be unitary.")
+
+        # Dividing by a square root of the determinant leaves a matrix in SU(2):
+        #   [[e^(-i(beta+delta)/2) cos(gamma/2), -e^(-i(beta-delta)/2) sin(gamma/2)],
+        #    [e^(i(beta-delta)/2) sin(gamma/2), e^(i(beta+delta)/2) cos(gamma/2)]]
+        # If either cos or sin is zero, only the sum or difference of beta and delta
+        # matters, and delta is chosen to be zero.
+        root = cmath.sqrt(a * d - b * c)
+        a, c, d = a / root, c / root, d / root
+        gamma = 2 * math.atan2(abs(c), abs(a))
+        total = cmath.phase(d) - cmath.phase(a)
+        difference = 2 * cmath.phase(c)
+        if abs(c) < 1e-9:
+            difference = total
+        elif abs(a) < 1e-9:
+            total = difference
+        beta = (total + difference) / 2
+        delta = (total - difference) / 2
+
+        self._gate(qis.rz, self._angle(delta), qubit)
+        self._gate(qis.ry, self._angle(gamma), qubit)
+        self._gate(qis.rz, self._angle(beta), qubit)
 
     def x(self, qubit: Value) -> None:
         """
@@ -125,7 +624,7 @@ class BasicQisBuilder:
 
         :param qubit: The target qubit.
         """
-        qis.x(self._builder, qubit)
+        self._gate(qis.x, qubit)
 
     def y(self, qubit: Value) -> None:
         """
@@ -133,7 +632,7 @@ class BasicQisBuilder:
 
         :param qubit: The target qubit.
         """
-        qis.y(self._builder, qubit)
+        self._gate(qis.y, qubit)
 
     def z(self, qubit: Value) -> None:
         """
@@ -141,23 +640,174 @@ class BasicQisBuilder:
 
         :param qubit: The target qubit.
         """
-        qis.z(self._builder, qubit)
+        self._gate(qis.z, qubit)
+
+    def with_noise(self, p: float) -> "NoisyBasicQisBuilder":
+        """
+        Creates a builder with the same API that also inserts a depolarizing noise channel with
+        :func:`pyqir.qis.depolarize` on each qubit after every gate, including the gates that
+        make up composite operations like :meth:`qft`. Measurements, resets and branches aren't
+        followed by noise. Both builders insert instructions with the same IR builder.
+
+        :param p: The probability of depolarizing each qubit, between 0 and 1.
+        :returns: The noisy builder.
+        """
+        return NoisyBasicQisBuilder(
+            self._builder, p, self._normalize_angles, self._on_remeasure
+        )
 
     def if_result(
         self,
         cond: Value,
-        one: Callable[[], None] = lambda: None,
-        zero: Callable[[], None] = lambda: None,
+        one: Optional[Callable[[], None]] = None,
+        zero: Optional[Callable[[], None]] = None,
+        label: Optional[str] = None,
     ) -> None:
         """
         Inserts a branch conditioned on a measurement result.
 
         Instructions inserted when ``one`` is called will be inserted into the one branch.
         Instructions inserted when ``zero`` is called will be inserted into the zero branch. The one
-        and zero callables should use this module's builder to build instructions.
+        and zero callables should use this module's builder to build instructions. A branch that is
+        ``None`` is left empty, and if both are, the result is still read and branched on. A
+        :class:`TypeError` is raised before anything is inserted if a branch isn't callable.
 
         :param cond: The result condition to branch on.
         :param one: A callable that inserts instructions for the branch where the result is one.
         :param zero: A callable that inserts instructions for the branch where the result is zero.
+        :param label: A prefix for the names of the blocks of the branch, like ``correct_then``
+            for ``"correct"``, to make the IR easier to follow.
+        """
+        before = set(self._measured)
+        after: Set[int] = set()
+
+        def track(branch: Optional[Callable[[], None]]) -> Any:
+            if branch is None:
+                after.update(before)
+                return None
+            if not callable(branch):
+                return branch
+
+            def body() -> None:
+                self._measured = set(before)
+                branch()
+                after.update(self._measured)
+
+            return body
+
+        qis.if_result(self._builder, cond, track(one), track(zero), label)
+        self._measured = after
+
+    def _angle(self, angle: Union[Value, float]) -> Union[Value, float]:
+        if self._normalize_angles and not isinstance(angle, Value):
+            return normalize_angle(angle)
+        return angle
+
+    def _gate(self, gate: Callable[..., None], *args: Any) -> None:
+        gate(self._builder, *args)
+
+    def _measure(self, qubit: Value) -> None:
+        id = pyqir.qubit_id(qubit)
+        if id is None:
+            return
+        if id in self._measured and self._on_remeasure != "allow":
+            message = _remeasure_message(id)
+            if self._on_remeasure == "error":
+                raise ValueError(message)
+            warnings.warn(message, stacklevel=3)
+        self._measured.add(id)
+
+    def _noise(self) -> Optional[float]:
+        return None
+
+    def _controlled_phase(self, theta: float, control: Value, target: Value) -> None:
+        self._gate(qis.rz, self._angle(theta / 2), control)
+        self._gate(qis.rz, self._angle(theta / 2), target)
+        self._gate(qis.cx, control, target)
+        self._gate(qis.rz, self._angle(-theta / 2), target)
+        self._gate(qis.cx, control, target)
+
+    def _on_control_state(
+        self, control_state: int, control: Value, build: Callable[[], None]
+    ) -> None:
+        if control_state not in (0, 1):
+            raise ValueError(f"Control state must be 0 or 1, not {control_state}.")
+        if control_state == 0:
+            self._gate(qis.x, control)
+        build()
+        if control_state == 0:
+            self._gate(qis.x, control)
+
+    def _pauli_rotation(
+        self, theta: Union[Value, float], paulis: str, qubit0: Value, qubit1: Value
+    ) -> None:
+        if qubit0 == qubit1:
+            raise ValueError("Qubits in a two-qubit rotation must be distinct.")
+
+        # Each Pauli P is V Z V^dagger, where V is H for X and S H for Y.
+        qubits = [qubit0, qubit1]
+        for pauli, qubit in zip(paulis, qubits):
+            if pauli == "y":
+                self._gate(qis.s_adj, qubit)
+            if pauli != "z":
+                self._gate(qis.h, qubit)
+        self._gate(qis.cx, qubit0, qubit1)
+        self._gate(qis.rz, self._angle(theta), qubit1)
+        self._gate(qis.cx, qubit0, qubit1)
+        for pauli, qubit in zip(paulis, qubits):
+            if pauli != "z":
+                self._gate(qis.h, qubit)
+            if pauli == "y":
+                self._gate(qis.s, qubit)
+
+
+def _remeasure_message(id: int) -> str:
+    return (
+        f"Qubit {id} is measured again without a reset since it was last measured. A "
+        "measurement in either branch of a conditional counts as measuring the qubit."
+    )
+
+
+class NoisyBasicQisBuilder(BasicQisBuilder):
+    """
+    A basic QIS builder that inserts a depolarizing noise channel on each qubit after every gate.
+    Use :meth:`BasicQisBuilder.with_noise` to create one.
+    """
+
+    def __init__(
+        self,
+        builder: Builder,
+        p: float,
+        normalize_angles: bool = False,
+        on_remeasure: str = "allow",
+    ) -> None:
         """
-        qis.if_result(self._builder, cond, one, zero)
+        Initializes a noisy basic QIS builder.
+
+        :param Builder builder: The IR Builder used to create the instructions
+        :param float p: The probability of depolarizing each qubit, between 0 and 1.
+        :param bool normalize_angles: Whether to normalize constant rotation angles, like in
+            :class:`BasicQisBuilder`.
+        :param str on_remeasure: What to do when a qubit is measured again without a reset, like
+            in :class:`BasicQisBuilder`.
+        """
+        if not 0 <= p <= 1:
+            raise ValueError(
+                f"The probability of depolarizing must be between 0 and 1, not {p}."
+            )
+        super().__init__(builder, normalize_angles, on_remeasure)
+        self._p = p
+
+    @property
+    def p(self) -> float:
+        """The probability of depolarizing each qubit after a gate."""
+        return self._p
+
+    def _gate(self, gate: Callable[..., None], *args: Any) -> None:
+        gate(self._builder, *args)
+        for arg in args:
+            if isinstance(arg, Value) and pyqir.is_qubit_type(arg.type):
+                qis.depolarize(self._builder, self._p, arg)
+
+    def _noise(self) -> Optional[float]:
+        return self._p
@@ -0,0 +1,177 @@
+# Copyright (c) Microsoft Corporation.
+# Licensed under the MIT License.
+
+import json
+import math
+import re
+from dataclasses import replace
+from typing import Any, Dict, List, Optional, Sequence, Tuple, Union
+
+from pyqir._operations import Conditional, Operation
+
+_Json = Dict[str, Any]
+
+# Gates that are a power of a Pauli-like gate in Cirq, and the exponent.
+_POW_GATES: Dict[str, Tuple[str, float]] = {
+    "h": ("HPowGate", 1.0),
+    "x": ("XPowGate", 1.0),
+    "y": ("YPowGate", 1.0),
+    "z": ("ZPowGate", 1.0),
+    "s": ("ZPowGate", 0.5),
+    "s_adj": ("ZPowGate", -0.5),
+    "t": ("ZPowGate", 0.25),
+    "t_adj": ("ZPowGate", -0.25),
+    "cx": ("CXPowGate", 1.0),
+    "cz": ("CZPowGate", 1.0),
+    "swap": ("SwapPowGate", 1.0),
+    "ccx": ("CCXPowGate", 1.0),
+}
+
+_ROTATIONS = {"rx": "Rx", "ry": "Ry", "rz": "Rz"}
+
+# The Pauli gates are singletons in Cirq with their own classes.
+_PAULIS = {"_PauliX": "XPowGate", "_PauliY": "YPowGate", "_PauliZ": "ZPowGate"}
+
+
+def to_cirq_json(
+    ops: Sequence[Union[Operation, Conditional]], layers: Sequence[Sequence[int]]
+) -> str:
+    """
+    Serializes the operations as a Cirq circuit with one moment per layer, in the format that
+    ``cirq.read_json`` reads. Qubits are line qubits with the same IDs, and a measurement into a
+    result is keyed by the result, like ``"r0"``.
+    """
+
+    moments: List[_Json] = []
+    for layer in layers:
+        operations = [o for o in (_operation(ops[i]) for i in layer) if o is not None]
+        if len(operations) > 0:
+            moments.append({"cirq_type": "Moment", "operations": operations})
+    return json.dumps({"cirq_type": "Circuit", "moments": moments}, indent=2)
+
+
+def _operation(op: Union[Operation, Conditional]) -> Optional[_Json]:
+    if isinstance(op, Conditional):
+        raise ValueError("Branches on results can't be converted to Cirq.")
+    if op.name == "barrier" or op.name.startswith("__quantum__rt__"):
+        # These have no effect on the state of the qubits.
+        return None
+    return {
+        "cirq_type": "GateOperation",
+        "gate": _gate(op),
+        "qubits": [{"cirq_type": "LineQubit", "x": q} for q in op.qubits],
+    }
+
+
+def _gate(op: Operation) -> _Json:
+    if op.name in _POW_GATES:
+        name, exponent = _POW_GATES[op.name]
+        return {"cirq_type": name, "exponent": exponent, "global_shift": 0.0}
+    if op.name in _ROTATIONS:
+        return {"cirq_type": _ROTATIONS[op.name], "rads": op.angles[0]}
+    if op.name == "u1":
+        # diag(1, e^(i lambda)) is Z to the power of lambda / pi.
+        exponent = op.angles[0] / math.pi
+        return {"cirq_type": "ZPowGate", "exponent": exponent, "global_shift": 0.0}
+    if op.name == "id":
+        return {"cirq_type": "IdentityGate", "qid_shape": [2]}
+    if op.name == "reset":
+        return {"cirq_type": "ResetChannel", "dimension": 2}
+    if op.name == "mz":
+        return {
+            "cirq_type": "MeasurementGate",
+            "num_qubits": 1,
+            "key": f"r{op.results[0]}",
+            "invert_mask": [],
+        }
+    raise ValueError(f"Operation {op.name} has no Cirq equivalent.")
+
+
+def from_cirq_json(json_str: str) -> Tuple[int, int, List[Operation]]:
+    """
+    Reads a Cirq circuit in the format that ``cirq.to_json`` writes, and returns the number of
+    qubits, the number of results and the operations in moment order. Line qubits keep their
+    IDs, a measurement keyed like ``"r0"`` goes into that result, and a measurement with any
+    other key goes into the next unused result.
+    """
+
+    circuit = json.loads(json_str)
+    if circuit.get("cirq_type") not in ("Circuit", "FrozenCircuit"):
+        raise ValueError(f"Expected a Cirq circuit, not {circuit.get('cirq_type')}.")
+
+    ops: List[Tuple[Operation, Optional[str]]] = []
+    for moment in circuit["moments"]:
+        if moment.get("cirq_type") != "Moment":
+            raise ValueError(f"Expected a Cirq moment, not {moment.get('cirq_type')}.")
+        for operation in moment["operations"]:
+            ops.extend(_from_operation(operation))
+
+    results: Dict[str, int] = {}
+    for _, key in ops:
+        match = None if key is None else re.fullmatch("r([0-9]+)", key)
+        if match is not None:
+            results[key] = int(match.group(1))
+    next_result = max(results.values(), default=-1) + 1
+    for _, key in ops:
+        if key is not None and key not in results:
+            results[key] = next_result
+            next_result += 1
+
+    num_qubits = max((q + 1 for op, _ in ops for q in op.qubits), default=0)
+    num_results = max((r + 1 for r in results.values()), default=0)
+    return (
+        num_qubits,
+        num_results,
+        [op if k is None else replace(op, results=(results[k],)) for op, k in ops],
+    )
+
+
+def _from_operation(operation: _Json) -> List[Tuple[Operation, Optional[str]]]:
+    cirq_type = operation.get("cirq_type")
+    if cirq_type == "TaggedOperation":
+        return _from_operation(operation["sub_operation"])
+    if cirq_type != "GateOperation":
+        raise ValueError(f"Cirq operation {cirq_type} has no QIR equivalent.")
+
+    qubits = tuple(map(_from_qubit, operation["qubits"]))
+    gate = operation["gate"]
+    cirq_type = _PAULIS.get(gate["cirq_type"], gate["cirq_type"])
+    if cirq_type == "IdentityGate":
+        return [(Operation("id", (q,)), None) for q in qubits]
+    if cirq_type == "ResetChannel":
+        return [(Operation("reset", qubits), None)]
+    if cirq_type == "MeasurementGate":
+        if len(qubits) != 1 or any(gate.get("invert_mask", [])):
+            raise ValueError(
+                "Only measurements of one qubit without inversion are supported."
+            )
+        key = gate["key"]
+        # Newer versions of Cirq write the key as a MeasurementKey.
+        key = key if isinstance(key, str) else key["name"]
+        return [(Operation("mz", qubits), key)]
+    for name, rotation in _ROTATIONS.items():
+        if cirq_type == rotation:
+            return [(Operation(name, qubits, angles=(gate["rads"],)), None)]
+
+    exponent = gate.get("exponent")
+    if exponent is None or cirq_type not in (g for g, _ in _POW_GATES.values()):
+        raise ValueError(f"Cirq gate {cirq_type} has no QIR equivalent.")
+    for name, (pow_gate, pow_exponent) in _POW_GATES.items():
+        if cirq_type == pow_gate and math.isclose(exponent, pow_exponent):
+            return [(Operation(name, qubits), None)]
+    angle = exponent * math.pi
+    if cirq_type == "ZPowGate":
+        return [(Operation("u1", qubits, angles=(angle,)), None)]
+    if cirq_type in ("XPowGate", "YPowGate"):
+        # These are rotations up to a global phase.
+        name = "rx" if cirq_type == "XPowGate" else "ry"
+        return [(Operation(name, qubits, angles=(angle,)), None)]
+    raise ValueError(
+        f"Cirq gate {cirq_type} to the power of {exponent} has no QIR equivalent."
+    )
+
+
+def _from_qubit(qubit: _Json) -> int:
+    if qubit.get("cirq_type") != "LineQubit":
+        raise ValueError(f"Cirq qubit {qubit.get('cirq_type')} isn't a line qubit.")
+    return int(qubit["x"])
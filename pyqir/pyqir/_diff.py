@@ -0,0 +1,43 @@
+# Copyright (c) Microsoft Corporation.
+# Licensed under the MIT License.
+
+import difflib
+import re
+from typing import List
+
+# A comment at the end of a line, which can't contain a quote because it would be in a string.
+_TRAILING_COMMENT = re.compile(r"\s*;[^\"]*$")
+
+
+def normalize_ir(ir: str) -> List[str]:
+    """
+    The lines of LLVM IR that describe the module, without comments, blank lines or trailing
+    whitespace, so that IR from different sources can be compared line by line.
+    """
+
+    lines = []
+    for line in ir.splitlines():
+        line = _TRAILING_COMMENT.sub("", line).rstrip()
+        if line != "":
+            lines.append(line)
+    return lines
+
+
+def diff_ir(expected: str, actual: str, context: int = 3, limit: int = 20) -> List[str]:
+    """
+    A unified diff of the normalized lines of two pieces of LLVM IR, with at most ``limit``
+    lines. The diff is empty if the IR describes the same module.
+    """
+
+    diff = difflib.unified_diff(
+        normalize_ir(expected),
+        normalize_ir(actual),
+        "expected",
+        "actual",
+        n=context,
+        lineterm="",
+    )
+    lines = list(diff)
+    if len(lines) > limit:
+        lines = [*lines[:limit], f"... {len(lines) - limit} more lines"]
+    return lines
@@ -0,0 +1,178 @@
+# Copyright (c) Microsoft Corporation.
+# Licensed under the MIT License.
+
+from dataclasses import dataclass
+from typing import Dict, List, Sequence, Tuple, Union
+
+from pyqir._operations import Conditional, Operation
+
+_TEXT_NAMES = {
+    "id": "I",
+    "s_adj": "Sdg",
+    "t_adj": "Tdg",
+    "reset": "|0>",
+    "depolarize": "D",
+}
+
+_LATEX_NAMES = {
+    "id": "I",
+    "s_adj": "S^\\dagger",
+    "t_adj": "T^\\dagger",
+    "reset": "\\ket{0}",
+    "depolarize": "D",
+    "rx": "R_x",
+    "ry": "R_y",
+    "rz": "R_z",
+    "u1": "U_1",
+    "u2": "U_2",
+}
+
+
+@dataclass
+class _Cell:
+    text: str
+    """The label in the text output."""
+
+    command: str
+    """The quantikz command in the LaTeX output."""
+
+    arg: str = ""
+    """The argument to the quantikz command."""
+
+    gate: str = ""
+    """The label of a gate that replaces the command if a condition is attached."""
+
+    @property
+    def latex(self) -> str:
+        return f"\\{self.command}{{{self.arg}}}"
+
+    def add_condition(self, text: str, latex: str) -> None:
+        self.text += f"[{text}]"
+        if self.command not in ("gate", "meter", "ctrl", "swap"):
+            self.command = "gate"
+            self.arg = self.gate
+        if self.command in ("gate", "meter"):
+            self.arg += f" \\mid {latex}"
+
+
+@dataclass
+class _Column:
+    cells: Dict[int, _Cell]
+    """The cell on each qubit that the column touches."""
+
+    connected: bool = False
+    """Whether the cells are joined by a vertical line."""
+
+    @property
+    def span(self) -> Tuple[int, int]:
+        return min(self.cells), max(self.cells)
+
+
+def draw(
+    ops: Sequence[Union[Operation, Conditional]], num_qubits: int, output: str
+) -> str:
+    """
+    Draws a circuit diagram with one wire per qubit and one column per operation. Operations in a
+    branch on a result are drawn in order, labeled with the condition.
+    """
+
+    columns = _columns(ops, [])
+    if output == "text":
+        return _text(columns, num_qubits)
+    if output == "latex":
+        return _latex(columns, num_qubits)
+    raise ValueError(
+        f"Unsupported output {output!r}. The supported outputs are 'text' and 'latex'."
+    )
+
+
+def _columns(
+    ops: Sequence[Union[Operation, Conditional]], conditions: List[Tuple[str, str]]
+) -> List[_Column]:
+    columns: List[_Column] = []
+    for op in ops:
+        if isinstance(op, Conditional):
+            result = "?" if op.result is None else str(op.result)
+            for value, branch in [("1", op.one), ("0", op.zero)]:
+                condition = (f"r{result}={value}", f"r_{{{result}}} = {value}")
+                columns.extend(_columns(branch, [*conditions, condition]))
+        elif len(op.qubits) > 0:
+            column = _column(op)
+            if len(conditions) > 0:
+                text = ",".join(c[0] for c in conditions)
+                latex = ", ".join(c[1] for c in conditions)
+                for cell in column.cells.values():
+                    cell.add_condition(text, latex)
+            columns.append(column)
+    return columns
+
+
+def _column(op: Operation) -> _Column:
+    qubits = op.qubits
+    if op.name in ("ch", "cx", "cy", "cz", "ccx"):
+        target = qubits[-1]
+        cells = {q: _Cell("*", "ctrl", str(target - q)) for q in qubits[:-1]}
+        if op.name == "cz":
+            cells[target] = _Cell("*", "control", gate="Z")
+        elif op.name in ("ch", "cy"):
+            gate = op.name[1:].upper()
+            cells[target] = _Cell(gate, "gate", gate, gate)
+        else:
+            cells[target] = _Cell("X", "targ", gate="X")
+        return _Column(cells, True)
+    if op.name == "swap":
+        a, b = qubits
+        cells = {
+            a: _Cell("x", "swap", str(b - a)),
+            b: _Cell("x", "targX", gate="\\times"),
+        }
+        return _Column(cells, True)
+    if op.name in ("m", "mz"):
+        text = "M" if len(op.results) == 0 else f"M{op.results[0]}"
+        return _Column({qubits[0]: _Cell(text, "meter")})
+
+    text = _TEXT_NAMES.get(op.name, op.name.capitalize())
+    latex = _LATEX_NAMES.get(op.name, op.name.capitalize().replace("_", "\\_"))
+    if len(op.angles) > 0:
+        angles = ", ".join(f"{a:.3g}" for a in op.angles)
+        text += f"({angles})"
+        latex += f"({angles})"
+    cells = {q: _Cell(text, "gate", latex) for q in qubits}
+    return _Column(cells, len(qubits) > 1)
+
+
+def _text(columns: List[_Column], num_qubits: int) -> str:
+    label_width = len(f"q{num_qubits - 1}: ")
+    wires = [f"q{q}: ".ljust(label_width) + "-" for q in range(num_qubits)]
+    gaps = [" " * (label_width + 1) for _ in range(num_qubits - 1)]
+    for column in columns:
+        width = max(len(c.text) for c in column.cells.values()) + 2
+        low, high = column.span
+        for q in range(num_qubits):
+            cell = column.cells.get(q)
+            wires[q] += "-" * width if cell is None else cell.text.center(width, "-")
+        for q in range(num_qubits - 1):
+            line = "|" if column.connected and low <= q < high else " "
+            gaps[q] += line.center(width)
+
+    lines: List[str] = []
+    for q in range(num_qubits):
+        lines.append(wires[q] + "-")
+        if q < num_qubits - 1:
+            lines.append(gaps[q].rstrip())
+    return "\n".join(lines)
+
+
+def _latex(columns: List[_Column], num_qubits: int) -> str:
+    rows = [[f"\\lstick{{$q_{{{q}}}$}}"] for q in range(num_qubits)]
+    for column in columns:
+        for q in range(num_qubits):
+            cell = column.cells.get(q)
+            rows[q].append("\\qw" if cell is None else cell.latex)
+
+    lines = ["\\begin{quantikz}"]
+    for q, row in enumerate(rows):
+        end = " \\\\" if q < num_qubits - 1 else ""
+        lines.append(" & ".join([*row, "\\qw"]) + end)
+    lines.append("\\end{quantikz}")
+    return "\n".join(lines)
@@ -0,0 +1,42 @@
+# Copyright (c) Microsoft Corporation.
+# Licensed under the MIT License.
+
+from dataclasses import dataclass
+
+# The indentation of instructions in the IR that LLVM writes.
+_LLVM_INDENT = 2
+
+
+@dataclass(frozen=True)
+class EmitOptions:
+    """
+    Options for the layout of emitted LLVM IR. The defaults leave the IR exactly as LLVM writes
+    it, and the other options only change whitespace, so the IR parses to the same module.
+    """
+
+    indent: int = _LLVM_INDENT
+    """The number of spaces that instructions are indented by."""
+
+    newline: str = "\n"
+    """The line terminator, either ``"\\n"`` or ``"\\r\\n"``."""
+
+    def __post_init__(self) -> None:
+        if self.indent < 0:
+            raise ValueError(f"Indentation must not be negative, not {self.indent}.")
+        if self.newline not in ("\n", "\r\n"):
+            raise ValueError(
+                f"Unsupported newline {self.newline!r}. "
+                "The supported newlines are '\\n' and '\\r\\n'."
+            )
+
+
+def format_ir(ir: str, options: EmitOptions) -> str:
+    if options == EmitOptions():
+        return ir
+
+    lines = []
+    for line in ir.split("\n"):
+        stripped = line.lstrip(" ")
+        depth, extra = divmod(len(line) - len(stripped), _LLVM_INDENT)
+        lines.append(" " * (depth * options.indent + extra) + stripped)
+    return options.newline.join(lines)
@@ -3,7 +3,9 @@
 
 from enum import Enum
 from typing import (
+    Any,
     Callable,
+    Dict,
     Iterable,
     Iterator,
     List,
@@ -124,8 +126,22 @@ class BasicBlock(Value):
         """The terminating instruction of this basic block if there is one."""
         ...
 
+    def erase(self) -> None:
+        """
+        Removes this basic block from its parent function, then deletes it and its instructions
+        from memory. Instructions in other blocks must not use the instructions in this block.
+
+        Warning: Using this basic block or its instructions after erasing it is undefined behavior.
+        """
+        ...
+
 class Builder:
-    """An instruction builder."""
+    """
+    An instruction builder.
+
+    A builder can only be used on the thread that created it. Using it on another thread raises a
+    :class:`RuntimeError` before anything is built.
+    """
 
     def __init__(self, context: Context) -> None:
         """
@@ -143,6 +159,99 @@ class Builder:
         """
         ...
 
+    def insert_before(self, instruction: Instruction) -> None:
+        """
+        Tells this builder to insert subsequent instructions before the instruction.
+
+        :param instruction: The instruction to insert before.
+        """
+        ...
+
+    def set_debug_location(self, line: int, column: int = 0) -> None:
+        """
+        Attaches a source location to the instructions that this builder inserts from now on.
+
+        The location is scoped to the function that the builder is inserting into, which must have
+        a debug info subprogram. Use :func:`add_compile_unit` to add one.
+
+        :param line: The line number.
+        :param column: The column number.
+        """
+        ...
+
+    def clear_debug_location(self) -> None:
+        """Stops attaching a source location to the instructions that this builder inserts."""
+        ...
+
+    def annotate(
+        self, inst_index: int, metadata: Dict[str, Union[bool, int, float, str]]
+    ) -> None:
+        """
+        Attaches key-value annotations to an instruction in the function that this builder is
+        inserting into. The annotations are emitted as a ``!qir.annotations`` metadata node on the
+        instruction. An annotation replaces any existing annotation with the same key.
+
+        :param inst_index: The index of the instruction, counting every instruction in the
+            function in block order.
+        :param metadata: The annotations.
+        """
+        ...
+
+    def get_annotation(
+        self, inst_index: int
+    ) -> Dict[str, Union[bool, int, float, str, Metadata]]:
+        """
+        Gets the key-value annotations on an instruction in the function that this builder is
+        inserting into.
+
+        :param inst_index: The index of the instruction, counting every instruction in the
+            function in block order.
+        :returns: The annotations. Values that aren't a bool, int, float, or string are returned as
+            metadata.
+        """
+        ...
+
+    def controlled(
+        self, gate_instruction: Call, control: Value, control_state: int = 1
+    ) -> None:
+        """
+        Replaces a call to a QIS gate with the controlled form of the gate. Gates without a
+        controlled intrinsic, like :math:`H` and :math:`S`, are decomposed into gates that have
+        one, up to a global phase.
+
+        The controlled form is inserted where the call was, and the call is removed. Calls to
+        :math:`X`, :math:`Y`, :math:`Z`, :math:`H`, :math:`S`, :math:`T` and their adjoints, the
+        rotations, and CNOT can be controlled.
+
+        By default, the gate applies when the control qubit is one. With a control state of zero,
+        the gate applies when the control qubit is zero instead, and the controlled form is
+        wrapped in :math:`X` gates on the control.
+
+        :param gate_instruction: The call to the gate.
+        :param control: The control qubit.
+        :param control_state: The state of the control qubit that the gate applies in, 0 or 1.
+        """
+        ...
+
+    def append(
+        self, function: Function, qubit_map: Dict[int, int], result_map: Dict[int, int]
+    ) -> None:
+        """
+        Copies the body of a function to this builder's position, replacing each static qubit and
+        result with the one that its ID maps to. The function may be in another module, but must
+        be in the same context, and must not return.
+
+        The function's first basic block is copied to this builder's position, and its other
+        blocks are appended to this builder's function. If the function branches, the position
+        must be at the end of a block, or a :class:`ValueError` is raised. Afterwards, this builder
+        inserts after the copied instructions.
+
+        :param function: The function to copy.
+        :param qubit_map: The new ID of each qubit in the function.
+        :param result_map: The new ID of each result in the function.
+        """
+        ...
+
     def and_(self, lhs: Value, rhs: Value) -> Value:
         """
         Inserts a bitwise logical and instruction.
@@ -237,11 +346,15 @@ class Builder:
     def call(
         self,
         callee: Value,
-        args: Sequence[Union[Value, bool, int, float]],
+        args: Sequence[Union[Value, Sequence[Value], bool, int, float]],
     ) -> Value:
         """
         Inserts a call instruction.
 
+        An argument that is a sequence of qubits is passed as a new QIR array that holds the
+        qubits in order. The parameter must have the QIR array type. The array is created before
+        the call and released after it.
+
         :param value: The value to call.
         :param args: The arguments to the function.
         :returns: The instruction value.
@@ -251,15 +364,21 @@ class Builder:
     def if_(
         self,
         cond: Value,
-        true: Callable[[], None] = ...,
-        false: Callable[[], None] = ...,
+        true: Optional[Callable[[], None]] = ...,
+        false: Optional[Callable[[], None]] = ...,
     ) -> None:
         """
         Inserts a branch conditioned on a boolean.
 
         Instructions inserted when ``true`` is called will be inserted into the true branch.
         Instructions inserted when ``false`` is called will be inserted into the false branch. The
-        true and false callables should use this module's builder to build instructions.
+        true and false callables should use this module's builder to build instructions. A branch
+        that is ``None`` is left empty. A :class:`TypeError` is raised before anything is inserted
+        if a branch isn't callable.
+
+        The callables may use the builder in any way, including moving it, as long as they leave it
+        at the end of an unterminated block in the same function. Otherwise a
+        :class:`BuilderReentrancyError` is raised.
 
         :param cond: The boolean condition to branch on.
         :param true:
@@ -298,6 +417,35 @@ class Builder:
         """
         ...
 
+    def alloca(self, type: Type) -> Instruction:
+        """
+        Inserts a stack allocation.
+
+        :param type: The type of the allocated value.
+        :returns: A pointer to the allocated value.
+        """
+        ...
+
+    def load(self, type: Type, ptr: Value) -> Instruction:
+        """
+        Inserts a load instruction.
+
+        :param type: The type of the loaded value.
+        :param ptr: The pointer to load from.
+        :returns: The loaded value.
+        """
+        ...
+
+    def store(self, value: Value, ptr: Value) -> Instruction:
+        """
+        Inserts a store instruction.
+
+        :param value: The value to store.
+        :param ptr: The pointer to store to.
+        :returns: The store instruction.
+        """
+        ...
+
     def ret(self, value: Optional[Value]) -> Instruction:
         """
         Inserts a return instruction.
@@ -307,6 +455,12 @@ class Builder:
         """
         ...
 
+class BuilderReentrancyError(RuntimeError):
+    """
+    The error raised when a branch body that the builder calls leaves it where the branch can't be
+    finished: in another function, or after a terminator.
+    """
+
 class Call(Instruction):
     """A call instruction."""
 
@@ -341,8 +495,13 @@ class Constant(Value):
 class Context:
     """The context owns global state needed by most LLVM objects."""
 
-    def __init__(self) -> None:
-        """Initializes a context."""
+    def __init__(self, opaque_pointers: bool = False) -> None:
+        """
+        Initializes a context.
+
+        :param opaque_pointers: Whether every pointer type in the context is the opaque ``ptr``
+            type instead of a typed pointer like ``%Qubit*``. Requires LLVM 14.
+        """
         ...
 
 class FCmp(Instruction):
@@ -602,6 +761,24 @@ class Module:
         """
         ...
 
+    def __copy__(self) -> Module:
+        """
+        Copies this module into a new module in the same context.
+
+        :returns: The copy.
+        """
+        ...
+
+    def __deepcopy__(self, memo: Dict[int, Any]) -> Module:
+        """
+        Copies this module into a new module in the same context. The context is shared rather
+        than copied, so this is the same as :meth:`__copy__`.
+
+        :param memo: The memo dictionary of objects already copied.
+        :returns: The copy.
+        """
+        ...
+
     @property
     def source_filename(self) -> str:
         """The name of the original source file that this module was compiled from."""
@@ -610,6 +787,26 @@ class Module:
     @source_filename.setter
     def source_filename(self, value: str) -> None: ...
     @property
+    def triple(self) -> str:
+        """
+        The target triple of this module, or an empty string if it has none. Setting a triple with
+        an architecture unknown to LLVM raises a :class:`ValueError`.
+        """
+        ...
+
+    @triple.setter
+    def triple(self, value: str) -> None: ...
+    @property
+    def data_layout(self) -> str:
+        """
+        The data layout of this module, or an empty string for LLVM's default layout. Setting a
+        data layout that LLVM can't parse raises a :class:`ValueError`.
+        """
+        ...
+
+    @data_layout.setter
+    def data_layout(self, value: str) -> None: ...
+    @property
     def functions(self) -> List[Function]:
         """The functions declared in this module."""
         ...
@@ -649,6 +846,17 @@ class Module:
         """
         ...
 
+    def optimize(self, level: int) -> None:
+        """
+        Optimizes this module in place with LLVM's standard optimization pipeline for the level.
+
+        The module must be valid. See :meth:`SimpleModule.optimize` for the passes that run at
+        each level.
+
+        :param level: The optimization level, from 0 to 3.
+        """
+        ...
+
     def verify(self) -> Optional[str]:
         """
         Verifies that this module is valid.
@@ -657,6 +865,21 @@ class Module:
         """
         ...
 
+    def llvm_module_ptr(self) -> Any:
+        """
+        Wraps the underlying ``LLVMModuleRef`` in a capsule named ``"LLVMModuleRef"``, for use with
+        other libraries that call the LLVM C API directly.
+
+        The capsule keeps this module alive, but the pointer is only valid while the capsule
+        exists, and it must not be disposed of through the LLVM API. Changes made through the
+        pointer are changes to this module. The library that uses the pointer must be linked
+        against the same LLVM library as PyQIR, because LLVM objects can't be shared between
+        different copies of LLVM.
+
+        :returns: The capsule.
+        """
+        ...
+
     def __str__(self) -> str:
         """Converts this module into an LLVM IR string."""
         ...
@@ -789,6 +1012,9 @@ class PointerType(Type):
         """The pointer address space."""
         ...
 
+class QirEmitError(ValueError):
+    """The error raised when a module can't be emitted because it is invalid."""
+
 class StructType(Type):
     """A structure type."""
 
@@ -966,6 +1192,22 @@ def extract_byte_string(value: Value) -> Optional[bytes]:
     """
     ...
 
+def add_compile_unit(
+    module: Module, filename: str, directory: str, producer: str
+) -> Metadata:
+    """
+    Adds a compile unit to the module's debug info, identifying the source file that the module
+    was generated from. Every function defined in the module is given a subprogram, so that
+    instructions in it can be given source locations with :meth:`Builder.set_debug_location`.
+
+    :param module: The module.
+    :param filename: The name of the source file.
+    :param directory: The directory containing the source file.
+    :param producer: The tool that generated the module.
+    :returns: The compile unit.
+    """
+    ...
+
 def global_byte_string(module: Module, value: bytes) -> Constant:
     """
     Creates a global null-terminated byte string constant in a module.
@@ -976,6 +1218,97 @@ def global_byte_string(module: Module, value: bytes) -> Constant:
     """
     ...
 
+def adjoint(function: Function) -> None:
+    """
+    Replaces the circuit in the function with its adjoint, which undoes it. The gates are reversed
+    and each is replaced by its inverse, with rotation angles negated.
+
+    The function must have at most one basic block, and every call in it must be to a QIS gate.
+    Measurements and resets aren't unitary, so circuits with them have no adjoint. Otherwise the
+    function is left unchanged and a :class:`ValueError` is raised.
+
+    :param function: The function.
+    """
+    ...
+
+def power(function: Function, exponent: float) -> None:
+    """
+    Raises the circuit in the function to a power by multiplying the angle of every rotation by
+    the exponent. This is only the power of the whole circuit if its gates commute, like rotations
+    about the same axis.
+
+    The function must have at most one basic block, and every call in it must be to an
+    :math:`R_x`, :math:`R_y`, :math:`R_z` or :math:`U_1` rotation, or a barrier. Other gates, like
+    :math:`H`, have no power that's given by their arguments. Otherwise the function is left
+    unchanged and a :class:`ValueError` is raised.
+
+    :param function: The function.
+    :param exponent: The exponent.
+    """
+    ...
+
+def batch_measurements(function: Function) -> bool:
+    """
+    Replaces the contiguous run of ``mz`` calls at the end of the function's last basic block
+    with a single call to ``__quantum__qis__mz_batch__body``. The batched call takes the number of
+    measurements and pointers to constant arrays of the qubits and the results they're written to.
+
+    The run ends at the last instruction in the block that uses a qubit, and every qubit and
+    result in it must be static and distinct. Runs with fewer than two measurements are left
+    unchanged.
+
+    :param function: The function.
+    :returns: Whether the measurements were batched.
+    """
+    ...
+
+def defer_measurements(function: Function) -> None:
+    """
+    Moves every measurement in the function to the end of the circuit, after the last instruction
+    that uses a qubit, keeping the measurements in their original order.
+
+    Measurements can only be deferred if it doesn't change the meaning of the program. The
+    function must have at most one basic block and only use static qubits, no qubit may be used
+    after it is measured, and no result may be read before the end of the circuit. Otherwise the
+    function is left unchanged and a :class:`ValueError` is raised.
+
+    :param function: The function.
+    """
+    ...
+
+def to_matrix(function: Function) -> List[List[complex]]:
+    """
+    Computes the unitary matrix of the circuit in the function by multiplying the matrices of its
+    gates in order. Bit ``i`` of a row or column index is the state of qubit ``i``, so qubit 0 is
+    the least significant.
+
+    The circuit has the function's required number of qubits, which may be at most 10. The
+    function must have at most one basic block, use only static qubits and constant rotation
+    angles, and may not measure or reset qubits. Otherwise a :class:`ValueError` is raised.
+
+    :param function: The function.
+    :returns: The rows of the matrix.
+    """
+    ...
+
+def array_type(context: Context) -> Type:
+    """
+    The QIR array type, a pointer to the opaque ``%Array`` struct that the runtime manages.
+
+    :param context: The LLVM context.
+    :returns: The array type.
+    """
+    ...
+
+def is_array_type(ty: Type) -> bool:
+    """
+    Whether the type is the QIR array type.
+
+    :param ty: The type.
+    :returns: True if the type is the QIR array type.
+    """
+    ...
+
 def is_entry_point(function: Function) -> bool:
     """
     Whether the function is an entry point.
@@ -1040,6 +1373,16 @@ def qubit_type(context: Context) -> Type:
     """
     ...
 
+def relabel_qubits(function: Function, mapping: Dict[int, int]) -> None:
+    """
+    Replaces every static qubit operand in the function with the qubit that its ID maps to.
+    Qubits that aren't in the map, and dynamic qubits, are left unchanged.
+
+    :param function: The function.
+    :param mapping: The new ID of each qubit.
+    """
+    ...
+
 def required_num_qubits(function: Function) -> Optional[int]:
     """
     If the function declares a required number of qubits, extracts it.
@@ -1086,6 +1429,23 @@ def result_type(context: Context) -> Type:
     """
     ...
 
+# Angles
+
+PI: float
+PI_OVER_2: float
+PI_OVER_4: float
+TAU: float
+
+def normalize_angle(angle: float) -> float:
+    """
+    Reduces a rotation angle modulo :math:`2\\pi` into :math:`(-\\pi, \\pi]`. Angles that are
+    already in range, infinity and NaN are returned unchanged.
+
+    :param angle: The angle.
+    :returns: The reduced angle.
+    """
+    ...
+
 # Runtime
 
 def array_record_output(builder: Builder, num_elements: Value, label: Value) -> None:
@@ -1109,6 +1469,16 @@ def initialize(builder: Builder, data: Value) -> None:
     """
     ...
 
+def int_record_output(builder: Builder, value: Value, label: Value) -> None:
+    """
+    Adds an integer to the generated output.
+
+    :param Builder builder: The IR Builder used to create the instructions
+    :param Value value: An i64 value to record
+    :param Value label: A string label for the integer value. Depending on the output schema, the label is included in the output or omitted.
+    """
+    ...
+
 def result_record_output(builder: Builder, result: Value, label: Value) -> None:
     """
     Adds a measurement result to the generated output.
@@ -1164,6 +1534,16 @@ def ccx(builder: Builder, control1: Value, control2: Value, target: Value) -> No
     """
     ...
 
+def ch(builder: Builder, control: Value, target: Value) -> None:
+    """
+    Inserts a controlled Hadamard gate.
+
+    :param builder: The underlying builder used to build QIS instructions.
+    :param control: The control qubit.
+    :param target: The target qubit.
+    """
+    ...
+
 def cx(builder: Builder, control: Value, target: Value) -> None:
     """
     Inserts a controlled Pauli :math:`X` gate.
@@ -1174,6 +1554,16 @@ def cx(builder: Builder, control: Value, target: Value) -> None:
     """
     ...
 
+def cy(builder: Builder, control: Value, target: Value) -> None:
+    """
+    Inserts a controlled Pauli :math:`Y` gate.
+
+    :param builder: The underlying builder used to build QIS instructions.
+    :param control: The control qubit.
+    :param target: The target qubit.
+    """
+    ...
+
 def cz(builder: Builder, control: Value, target: Value) -> None:
     """
     Inserts a controlled Pauli :math:`Z` gate.
@@ -1184,6 +1574,47 @@ def cz(builder: Builder, control: Value, target: Value) -> None:
     """
     ...
 
+def depolarize(builder: Builder, probability: float, qubit: Value) -> None:
+    """
+    Inserts a depolarizing noise channel, which replaces the state of the qubit with the maximally
+    mixed state with the given probability. It's not part of any QIR profile, and only simulators
+    that model noise are expected to implement it.
+
+    :param builder: The underlying builder used to build QIS instructions.
+    :param probability: The probability of depolarizing, between 0 and 1.
+    :param qubit: The target qubit.
+    """
+    ...
+
+def extend(
+    builder: Builder,
+    instructions: Sequence[Tuple[Any, ...]],
+    normalize_angles: bool = False,
+    noise: Optional[float] = None,
+) -> None:
+    """
+    Inserts a sequence of gates with one call, which is faster than calling the function for each
+    gate from Python. Each instruction is a tuple of the name of the function in this module that
+    inserts the gate and the arguments that come after the builder, like
+    ``("cx", control, target)``, ``("rz", theta, qubit)`` or ``("mz", qubit, result)``. The
+    supported gates are ``barrier``, ``ccx``, ``ch``, ``cx``, ``cy``, ``cz``, ``h``, ``id``,
+    ``mz``, ``reset``, ``rx``, ``ry``, ``rz``, ``s``, ``s_adj``, ``swap``, ``t``, ``t_adj``,
+    ``u1``, ``u2``, ``x``, ``y`` and ``z``.
+
+    Every instruction is checked like the function for its gate would check it before any is
+    inserted, so nothing is inserted if one of them is invalid. The message of the error starts
+    with the position of the invalid instruction.
+
+    :param builder: The underlying builder used to build QIS instructions.
+    :param instructions: The instructions.
+    :param normalize_angles: Whether to reduce every constant angle with
+        :func:`normalize_angle`.
+    :param noise: The probability of a depolarizing noise channel from :func:`depolarize` on each
+        qubit after every gate other than a barrier, measurement or reset. Without it, no noise is
+        inserted.
+    """
+    ...
+
 def h(builder: Builder, qubit: Value) -> None:
     """
     Inserts a Hadamard gate.
@@ -1193,6 +1624,29 @@ def h(builder: Builder, qubit: Value) -> None:
     """
     ...
 
+def id(builder: Builder, qubit: Value) -> None:
+    """
+    Inserts an identity gate, which does nothing to the qubit, for example as a placeholder that
+    takes up a time step.
+
+    :param builder: The underlying builder used to build QIS instructions.
+    :param qubit: The target qubit.
+    """
+    ...
+
+def m(builder: Builder, qubit: Value) -> Value:
+    """
+    Inserts a Z-basis measurement operation that returns a new result.
+
+    Unlike :func:`mz`, the result does not need to be allocated in advance. The returned value can
+    be passed directly to :func:`if_result` to branch on the measurement.
+
+    :param builder: The underlying builder used to build QIS instructions.
+    :param qubit: The qubit to measure.
+    :returns: The measurement result.
+    """
+    ...
+
 def mz(builder: Builder, qubit: Value, result: Value) -> None:
     """
     Inserts a Z-basis measurement operation.
@@ -1203,6 +1657,20 @@ def mz(builder: Builder, qubit: Value, result: Value) -> None:
     """
     ...
 
+def read_result(builder: Builder, result: Value) -> Value:
+    """
+    Inserts a read of a measurement result as a boolean, without measuring again.
+
+    Unlike :func:`if_result`, which reads the result and branches on it, the returned ``i1`` value
+    can be combined with other values, like with :meth:`Builder.and_` or :meth:`Builder.icmp`,
+    before branching on it with :meth:`Builder.condbr`.
+
+    :param builder: The underlying builder used to build QIS instructions.
+    :param result: The result to read.
+    :returns: The value of the result, true if it's one.
+    """
+    ...
+
 def reset(builder: Builder, qubit: Value) -> None:
     """
     Inserts a reset operation.
@@ -1278,6 +1746,35 @@ def t_adj(builder: Builder, qubit: Value) -> None:
     """
     ...
 
+def u1(builder: Builder, lam: Union[Value, float], qubit: Value) -> None:
+    """
+    Inserts a :math:`U_1(\\lambda)` gate, which is the phase gate
+    :math:`\\operatorname{diag}(1, e^{i \\lambda})`. This is :math:`R_z(\\lambda)` up to a global
+    phase.
+
+    :param builder: The underlying builder used to build QIS instructions.
+    :param lam: The phase angle.
+    :param qubit: The target qubit.
+    """
+    ...
+
+def u2(
+    builder: Builder,
+    phi: Union[Value, float],
+    lam: Union[Value, float],
+    qubit: Value,
+) -> None:
+    """
+    Inserts a :math:`U_2(\\phi, \\lambda)` gate, which is
+    :math:`U_1(\\phi) R_y(\\pi / 2) U_1(\\lambda)`.
+
+    :param builder: The underlying builder used to build QIS instructions.
+    :param phi: The phase angle applied last.
+    :param lam: The phase angle applied first.
+    :param qubit: The target qubit.
+    """
+    ...
+
 def x(builder: Builder, qubit: Value) -> None:
     """
     Inserts a Pauli :math:`X` gate.
@@ -1308,20 +1805,41 @@ def z(builder: Builder, qubit: Value) -> None:
 def if_result(
     builder: Builder,
     cond: Value,
-    one: Callable[[], None] = ...,
-    zero: Callable[[], None] = ...,
+    one: Optional[Callable[[], None]] = ...,
+    zero: Optional[Callable[[], None]] = ...,
+    label: Optional[str] = ...,
 ) -> None:
     """
     Inserts a branch conditioned on a measurement result.
 
     Instructions inserted when ``one`` is called will be inserted into the one branch.
     Instructions inserted when ``zero`` is called will be inserted into the zero branch. The one
-    and zero callables should use this module's builder to build instructions.
+    and zero callables should use this module's builder to build instructions. A branch that is
+    ``None`` is left empty, and if both are, the result is still read and branched on. A
+    :class:`TypeError` is raised before anything is inserted if a branch isn't callable.
+
+    The callables may use the builder in any way, including moving it, as long as they leave it at
+    the end of an unterminated block in the same function. Otherwise a
+    :class:`BuilderReentrancyError` is raised.
 
     :param builder: The underlying builder used to build QIS instructions.
     :param cond: The result condition to branch on.
     :param one: A callable that inserts instructions for the branch where the result is one.
     :param zero: A callable that inserts instructions for the branch where the result is zero.
+    :param label: A prefix for the names of the blocks of the branch, like ``correct_then``,
+        ``correct_else`` and ``correct_continue`` for ``"correct"``. Without a label, the blocks
+        are named ``then``, ``else`` and ``continue``, and LLVM numbers the names that are already
+        taken.
+    """
+    ...
+
+def qis_declarations(profile: str) -> str:
+    """
+    Emits a module that contains only the declarations of the QIS intrinsics that programs for a
+    profile may call, with the same signatures that the QIS builder functions declare.
+
+    :param profile: The profile: ``"base_profile"``, ``"adaptive_profile"`` or ``"custom"``.
+    :returns: The LLVM IR for the module.
     """
     ...
 
@@ -0,0 +1,190 @@
+# Copyright (c) Microsoft Corporation.
+# Licensed under the MIT License.
+
+from dataclasses import dataclass
+from typing import List, Optional, Sequence, Tuple, Union
+
+import pyqir
+from pyqir import BasicBlock, Call, FloatConstant, Function, Opcode
+
+_QIS_PREFIX = "__quantum__qis__"
+
+# QIS functions whose operation name differs from the function name.
+_RENAMED = {"cnot": "cx"}
+
+
+@dataclass(frozen=True)
+class Operation:
+    """
+    A snapshot of a call in a circuit, like a gate or a measurement.
+
+    QIS calls are named after the :mod:`pyqir.qis` function that builds them, like ``"cx"`` or
+    ``"s_adj"``. Other calls are named after the function that they call.
+    """
+
+    name: str
+    """The name of the operation."""
+
+    qubits: Tuple[int, ...] = ()
+    """The IDs of the qubit arguments, in order."""
+
+    results: Tuple[int, ...] = ()
+    """The IDs of the result arguments, in order."""
+
+    angles: Tuple[float, ...] = ()
+    """The floating-point arguments, in order."""
+
+
+@dataclass(frozen=True)
+class Conditional:
+    """A snapshot of a branch on a measurement result, like one built by ``if_result``."""
+
+    result: Optional[int]
+    """The ID of the result, or ``None`` if the result isn't statically allocated."""
+
+    one: Tuple[Union[Operation, "Conditional"], ...] = ()
+    """The operations in the branch where the result is one."""
+
+    zero: Tuple[Union[Operation, "Conditional"], ...] = ()
+    """The operations in the branch where the result is zero."""
+
+
+def operations(
+    function: Function, shallow: bool = False
+) -> List[Union[Operation, Conditional]]:
+    """
+    The operations of the function. If ``shallow`` is true, only the names of operations are read,
+    so their qubits, results and angles are empty, and may be dynamic or non-constant.
+    """
+
+    ops: List[Union[Operation, Conditional]] = []
+    if len(function.basic_blocks) == 0:
+        return ops
+
+    block: Optional[BasicBlock] = function.basic_blocks[0]
+    visited: List[BasicBlock] = []
+    while block is not None:
+        if block in visited:
+            raise ValueError("Loops are not supported.")
+        visited.append(block)
+        more, block = _walk(block, shallow)
+        ops.extend(more)
+    return ops
+
+
+def _walk(
+    block: BasicBlock, shallow: bool
+) -> Tuple[List[Union[Operation, Conditional]], Optional[BasicBlock]]:
+    """
+    Reads operations from the block until control flow leaves it with an unconditional branch,
+    following conditional branches on results into their bodies. Returns the operations and the
+    target of the unconditional branch.
+    """
+
+    ops: List[Union[Operation, Conditional]] = []
+    while True:
+        terminator = block.terminator
+        for instruction in block.instructions:
+            if (
+                instruction != terminator
+                and isinstance(instruction, Call)
+                and instruction.callee.name != f"{_QIS_PREFIX}read_result__body"
+            ):
+                ops.append(
+                    Operation(operation_name(instruction))
+                    if shallow
+                    else _operation(instruction)
+                )
+
+        if terminator is None or terminator.opcode == Opcode.RET:
+            return ops, None
+        if terminator.opcode != Opcode.BR:
+            raise ValueError(f"Unsupported terminator {terminator.opcode}.")
+
+        operands = terminator.operands
+        if len(operands) == 1:
+            assert isinstance(operands[0], BasicBlock)
+            return ops, operands[0]
+
+        cond, zero_block, one_block = operands
+        if not (
+            isinstance(cond, Call)
+            and cond.callee.name == f"{_QIS_PREFIX}read_result__body"
+        ):
+            raise ValueError("Only branches on measurement results are supported.")
+        assert isinstance(one_block, BasicBlock) and isinstance(zero_block, BasicBlock)
+        one, one_exit = _walk(one_block, shallow)
+        zero, zero_exit = _walk(zero_block, shallow)
+        if one_exit is None or one_exit != zero_exit:
+            raise ValueError("Branches on measurement results must join afterwards.")
+        ops.append(Conditional(pyqir.result_id(cond.args[0]), tuple(one), tuple(zero)))
+        block = one_exit
+
+
+def approx_equal(
+    ops1: Sequence[Union[Operation, Conditional]],
+    ops2: Sequence[Union[Operation, Conditional]],
+    atol: float,
+) -> bool:
+    """
+    Whether two sequences of operations are equal, except that corresponding angles may differ by
+    at most ``atol``.
+    """
+
+    if len(ops1) != len(ops2):
+        return False
+    for op1, op2 in zip(ops1, ops2):
+        if isinstance(op1, Conditional) and isinstance(op2, Conditional):
+            if not (
+                op1.result == op2.result
+                and approx_equal(op1.one, op2.one, atol)
+                and approx_equal(op1.zero, op2.zero, atol)
+            ):
+                return False
+        elif isinstance(op1, Operation) and isinstance(op2, Operation):
+            if not (
+                op1.name == op2.name
+                and op1.qubits == op2.qubits
+                and op1.results == op2.results
+                and len(op1.angles) == len(op2.angles)
+                and all(abs(a - b) <= atol for a, b in zip(op1.angles, op2.angles))
+            ):
+                return False
+        else:
+            return False
+    return True
+
+
+def operation_name(call: Call) -> str:
+    name = call.callee.name
+    if name.startswith(_QIS_PREFIX):
+        name = name[len(_QIS_PREFIX) :]
+        name, functor = name.rsplit("__", 1)
+        name = _RENAMED.get(name, name)
+        if functor == "adj":
+            name += "_adj"
+    return name
+
+
+def _operation(call: Call) -> Operation:
+    name = operation_name(call)
+    qubits: List[int] = []
+    results: List[int] = []
+    angles: List[float] = []
+    for arg in call.args:
+        if pyqir.is_qubit_type(arg.type):
+            qubits.append(_static_id(pyqir.qubit_id(arg), "qubit", name))
+        elif pyqir.is_result_type(arg.type):
+            results.append(_static_id(pyqir.result_id(arg), "result", name))
+        elif arg.type.is_double:
+            if not isinstance(arg, FloatConstant):
+                raise ValueError(f"Angle of {name} isn't constant.")
+            angles.append(arg.value)
+    return Operation(name, tuple(qubits), tuple(results), tuple(angles))
+
+
+def _static_id(id: Optional[int], kind: str, name: str) -> int:
+    if id is None:
+        raise ValueError(f"A {kind} of {name} isn't statically allocated.")
+    return id
+
@@ -0,0 +1,73 @@
+# Copyright (c) Microsoft Corporation.
+# Licensed under the MIT License.
+
+import math
+import random
+from typing import Optional
+
+import pyqir.qis as qis
+from pyqir._simple import SimpleModule
+
+_ONE_QUBIT_GATES = [
+    qis.h,
+    qis.s,
+    qis.s_adj,
+    qis.t,
+    qis.t_adj,
+    qis.x,
+    qis.y,
+    qis.z,
+]
+
+_ROTATIONS = [qis.rx, qis.ry, qis.rz]
+
+_TWO_QUBIT_GATES = [qis.cx, qis.cz, qis.swap]
+
+
+def random_circuit(
+    num_qubits: int, depth: int, seed: Optional[int] = None
+) -> SimpleModule:
+    """
+    Generates a circuit of random gates, for tests and benchmarks.
+
+    Each layer visits the qubits in a random order and applies a gate drawn uniformly from the
+    one-qubit gates, the rotations and the two-qubit gates. A two-qubit gate acts on the visited
+    qubit and the next one in the order, so every qubit is used at most once per layer. Rotation
+    angles are drawn uniformly from :math:`[0, 2\\pi)`.
+
+    :param int num_qubits: The number of qubits.
+    :param int depth: The number of layers.
+    :param Optional[int] seed: The seed of the random number generator. The circuit is the same
+        every time for the same seed.
+    :returns: A module with the circuit and no results.
+    """
+
+    if num_qubits < 0 or depth < 0:
+        raise ValueError(
+            "The number of qubits and the depth must not be negative, "
+            f"not {num_qubits} and {depth}."
+        )
+
+    rng = random.Random(seed)
+    simple = SimpleModule("random_circuit", num_qubits, 0)
+    builder = simple.builder
+    qubits = simple.qubits
+    num_gates = len(_ONE_QUBIT_GATES) + len(_ROTATIONS) + len(_TWO_QUBIT_GATES)
+    for _ in range(depth):
+        order = list(range(num_qubits))
+        rng.shuffle(order)
+        while len(order) > 0:
+            qubit = qubits[order.pop()]
+            # A lone qubit at the end of the layer can't take a two-qubit gate.
+            i = rng.randrange(
+                num_gates - (0 if len(order) > 0 else len(_TWO_QUBIT_GATES))
+            )
+            if i < len(_ONE_QUBIT_GATES):
+                _ONE_QUBIT_GATES[i](builder, qubit)
+            elif i < len(_ONE_QUBIT_GATES) + len(_ROTATIONS):
+                rotation = _ROTATIONS[i - len(_ONE_QUBIT_GATES)]
+                rotation(builder, rng.uniform(0, 2 * math.pi), qubit)
+            else:
+                gate = _TWO_QUBIT_GATES[i - len(_ONE_QUBIT_GATES) - len(_ROTATIONS)]
+                gate(builder, qubit, qubits[order.pop()])
+    return simple
@@ -0,0 +1,153 @@
+# Copyright (c) Microsoft Corporation.
+# Licensed under the MIT License.
+
+import cmath
+import math
+import random
+from typing import Dict, List, Optional, Sequence, Tuple, Union
+
+from pyqir._operations import Conditional, Operation
+
+_Matrix = Tuple[Tuple[complex, complex], Tuple[complex, complex]]
+
+_SQRT_HALF = math.sqrt(0.5)
+
+_GATES: Dict[str, _Matrix] = {
+    "h": ((_SQRT_HALF, _SQRT_HALF), (_SQRT_HALF, -_SQRT_HALF)),
+    "id": ((1, 0), (0, 1)),
+    "s": ((1, 0), (0, 1j)),
+    "s_adj": ((1, 0), (0, -1j)),
+    "t": ((1, 0), (0, cmath.exp(1j * math.pi / 4))),
+    "t_adj": ((1, 0), (0, cmath.exp(-1j * math.pi / 4))),
+    "x": ((0, 1), (1, 0)),
+    "y": ((0, -1j), (1j, 0)),
+    "z": ((1, 0), (0, -1)),
+}
+
+# Controlled gates and the number of controls that come before the target.
+_CONTROLLED: Dict[str, Tuple[str, int]] = {
+    "ch": ("h", 1),
+    "cx": ("x", 1),
+    "cy": ("y", 1),
+    "cz": ("z", 1),
+    "ccx": ("x", 2),
+}
+
+
+def simulate(
+    ops: Sequence[Union[Operation, Conditional]],
+    num_qubits: int,
+    num_results: int,
+    shots: int,
+    seed: Optional[int] = None,
+) -> Dict[str, int]:
+    """
+    Runs the operations on a state vector once per shot, with every qubit starting in the zero
+    state and every result starting at zero, and counts the values of the results at the end.
+
+    :returns: The number of shots for each string of result values, with result 0 first.
+    """
+
+    rng = random.Random(seed)
+    counts: Dict[str, int] = {}
+    for _ in range(shots):
+        state = _State(num_qubits, rng)
+        results = [False] * num_results
+        _run(ops, state, results)
+        key = "".join("1" if r else "0" for r in results)
+        counts[key] = counts.get(key, 0) + 1
+    return counts
+
+
+class _State:
+    def __init__(self, num_qubits: int, rng: random.Random) -> None:
+        self._amplitudes: List[complex] = [0j] * (1 << num_qubits)
+        self._amplitudes[0] = 1
+        self._rng = rng
+
+    def apply(self, matrix: _Matrix, target: int, controls: Sequence[int] = ()) -> None:
+        bit = 1 << target
+        mask = sum(1 << c for c in controls)
+        amplitudes = self._amplitudes
+        for i in range(len(amplitudes)):
+            if i & bit == 0 and i & mask == mask:
+                a0 = amplitudes[i]
+                a1 = amplitudes[i | bit]
+                amplitudes[i] = matrix[0][0] * a0 + matrix[0][1] * a1
+                amplitudes[i | bit] = matrix[1][0] * a0 + matrix[1][1] * a1
+
+    def measure(self, qubit: int) -> bool:
+        bit = 1 << qubit
+        amplitudes = self._amplitudes
+        one = sum(abs(a) ** 2 for i, a in enumerate(amplitudes) if i & bit)
+        outcome = self._rng.random() < one
+        norm = math.sqrt(one if outcome else 1 - one)
+        for i in range(len(amplitudes)):
+            if bool(i & bit) == outcome:
+                amplitudes[i] /= norm
+            else:
+                amplitudes[i] = 0
+        return outcome
+
+    def depolarize(self, qubit: int, probability: float) -> None:
+        # The maximally mixed state is the average over the identity and the three Paulis.
+        if self._rng.random() < probability:
+            pauli = self._rng.choice(["id", "x", "y", "z"])
+            self.apply(_GATES[pauli], qubit)
+
+
+def _run(
+    ops: Sequence[Union[Operation, Conditional]], state: _State, results: List[bool]
+) -> None:
+    for op in ops:
+        if isinstance(op, Conditional):
+            if op.result is None:
+                raise ValueError("Branches on dynamic results can't be simulated.")
+            _run(op.one if results[op.result] else op.zero, state, results)
+        elif op.name in _GATES:
+            state.apply(_GATES[op.name], op.qubits[0])
+        elif op.name in _CONTROLLED:
+            gate, num_controls = _CONTROLLED[op.name]
+            state.apply(
+                _GATES[gate], op.qubits[num_controls], op.qubits[:num_controls]
+            )
+        elif op.name in ("rx", "ry", "rz", "u1", "u2"):
+            state.apply(_rotation(op.name, op.angles), op.qubits[0])
+        elif op.name == "depolarize":
+            state.depolarize(op.qubits[0], op.angles[0])
+        elif op.name == "swap":
+            a, b = op.qubits
+            for control, target in [(a, b), (b, a), (a, b)]:
+                state.apply(_GATES["x"], target, [control])
+        elif op.name == "mz":
+            results[op.results[0]] = state.measure(op.qubits[0])
+        elif op.name == "m":
+            state.measure(op.qubits[0])
+        elif op.name == "reset":
+            if state.measure(op.qubits[0]):
+                state.apply(_GATES["x"], op.qubits[0])
+        elif op.name != "barrier" and not op.name.startswith("__quantum__rt__"):
+            raise ValueError(f"Operation {op.name} can't be simulated.")
+
+
+def _rotation(name: str, angles: Tuple[float, ...]) -> _Matrix:
+    if name == "u1":
+        return ((1, 0), (0, cmath.exp(1j * angles[0])))
+    if name == "u2":
+        phi, lam = angles
+        return (
+            (_SQRT_HALF, -_SQRT_HALF * cmath.exp(1j * lam)),
+            (
+                _SQRT_HALF * cmath.exp(1j * phi),
+                _SQRT_HALF * cmath.exp(1j * (phi + lam)),
+            ),
+        )
+
+    theta = angles[0]
+    c = math.cos(theta / 2)
+    s = math.sin(theta / 2)
+    if name == "rx":
+        return ((c, -1j * s), (-1j * s, c))
+    if name == "ry":
+        return ((c, -s), (s, c))
+    return ((cmath.exp(-0.5j * theta), 0), (0, cmath.exp(0.5j * theta)))
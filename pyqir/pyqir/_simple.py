@@ -1,21 +1,83 @@
 # Copyright (c) Microsoft Corporation.
 # Licensed under the MIT License.
 
-from typing import List, Optional
+import cmath
+import copy
+import inspect
+import math
+import os
+import re
+import struct
+import sys
+import threading
+from types import FrameType
+from typing import (
+    Any,
+    Callable,
+    Dict,
+    Iterator,
+    List,
+    Optional,
+    Sequence,
+    Set,
+    TextIO,
+    Tuple,
+    Union,
+)
 
 import pyqir
+import pyqir.qis as qis
+import pyqir.rt as rt
 from pyqir import (
     BasicBlock,
     Builder,
+    Call,
     Constant,
     Context,
+    FloatConstant,
     Function,
     FunctionType,
+    Instruction,
+    IntConstant,
+    IntType,
     Linkage,
+    Metadata,
     Module,
     ModuleFlagBehavior,
+    Opcode,
+    PointerType,
+    QirEmitError,
     Value,
 )
+from pyqir._cirq import from_cirq_json, to_cirq_json
+from pyqir._diff import diff_ir
+from pyqir._draw import draw
+from pyqir._emit import EmitOptions, format_ir
+from pyqir._operations import (
+    Conditional,
+    Operation,
+    approx_equal,
+    operation_name,
+    operations,
+)
+from pyqir._sim import simulate
+from pyqir._validate import Violation, validate
+
+
+# Entry point attributes that describe the program and are managed by the simple module itself.
+_RESERVED_ATTRIBUTES = {
+    "entry_point",
+    "output_labeling_schema",
+    "qir_profiles",
+    "required_num_qubits",
+    "required_num_results",
+}
+
+_MEASUREMENTS = {"__quantum__qis__m__body", "__quantum__qis__mz__body"}
+
+_RESETS = {"__quantum__qis__reset__body"}
+
+_DOUBLE = re.compile(r"\bdouble (0x[0-9A-F]{16}|-?[0-9.]+e[+-][0-9]+)")
 
 
 class SimpleModule:
@@ -24,6 +86,27 @@ class SimpleModule:
 
     - All qubits and results are statically allocated.
     - There is exactly one function that is not externally linked, which is the entry point.
+
+    Emitting the module with :meth:`ir` or :meth:`bitcode` does not finalize it. Instructions can
+    still be added afterwards, and every call emits the module as it is at the time of the call.
+
+    The output is deterministic: building the same program always emits the same IR, byte for
+    byte, in any process. Functions are declared in the order they're first used, and values and
+    blocks are numbered in the order they're created.
+
+    A module can only be used on the thread that created it, and a :class:`RuntimeError` is raised
+    otherwise. The same goes for its :attr:`builder`, even if it was taken before moving to
+    another thread, and for a :class:`BasicQisBuilder` that uses it. Independent modules can be
+    built on separate threads at the same time, and their bitcode can be loaded into one context
+    with :func:`load_module` to combine them with :meth:`extend`.
+    """
+
+    max_register_size: int = 1 << 24
+    """
+    The largest number of qubits or results that a simple module can be created with. A larger
+    register is almost certainly a mistake, and since :attr:`qubits` and :attr:`results` list
+    every ID, using it would try to allocate more memory than there is. Assign to the class
+    attribute to change the limit.
     """
 
     def __init__(
@@ -37,17 +120,30 @@ class SimpleModule:
         """
         Initializes a simple module.
 
+        Names can contain any character except null, including spaces, quotes and non-ASCII
+        characters, which are escaped in the emitted IR. A :class:`ValueError` is raised for a
+        name with a null character or an empty entry point name, and for a number of qubits or
+        results that is negative or greater than :attr:`max_register_size`.
+
         :param str name: The name of the module.
-        :param str num_qubits: The number of statically allocated qubits.
+        :param int num_qubits: The number of statically allocated qubits.
         :param int num_results: The number of statically allocated results.
         :param Optional[Context] context: The LLVM context.
         :param str entry_point_name: The name of the entry point function.
         """
 
+        if entry_point_name == "":
+            raise ValueError("The entry point name must not be empty.")
+        for kind, size in [("qubits", num_qubits), ("results", num_results)]:
+            if not 0 <= size <= self.max_register_size:
+                raise ValueError(
+                    f"The number of {kind} must be between 0 and "
+                    f"{self.max_register_size}, not {size}."
+                )
         if context is None:
             context = Context()
 
-        self._module = pyqir.qir_module(
+        module = pyqir.qir_module(
             context,
             name,
             qir_major_version=1,
@@ -55,44 +151,180 @@ class SimpleModule:
             dynamic_qubit_management=False,
             dynamic_result_management=False,
         )
-        self._builder = Builder(context)
-        self._num_qubits = num_qubits
-        self._num_results = num_results
-
-        self._entry_point = pyqir.entry_point(
-            self._module, entry_point_name, num_qubits, num_results
+        entry_point = pyqir.entry_point(
+            module, entry_point_name, num_qubits, num_results
         )
-        self._entry_block = BasicBlock(context, "entry", self._entry_point)
-        self._builder.insert_at_end(self._entry_block)
+        BasicBlock(context, "entry", entry_point)
+        self._init_state(module, name, num_qubits, num_results, entry_point)
 
     @property
     def context(self) -> Context:
         """The LLVM context."""
+        self._check_thread()
         return self._module.context
 
+    @property
+    def num_qubits(self) -> int:
+        """The number of statically allocated qubits."""
+        self._check_thread()
+        return self._num_qubits
+
+    @property
+    def num_results(self) -> int:
+        """The number of statically allocated results."""
+        self._check_thread()
+        return self._num_results
+
+    def num_required_results(self) -> int:
+        """
+        Counts the distinct statically allocated results that ``mz`` measurements in the entry
+        point write to. This can be less than :attr:`num_results` if some results are never
+        written.
+
+        :returns: The number of results written.
+        """
+
+        self._check_thread()
+        written: Set[int] = set()
+        for block in self._entry_point.basic_blocks:
+            for instruction in block.instructions:
+                if (
+                    isinstance(instruction, Call)
+                    and instruction.callee.name == "__quantum__qis__mz__body"
+                ):
+                    id = pyqir.result_id(instruction.args[1])
+                    if id is not None:
+                        written.add(id)
+        return len(written)
+
+    def result_sources(self) -> Dict[int, List[int]]:
+        """
+        Finds the qubits that ``mz`` measurements in the entry point write to each statically
+        allocated result, to trace where a result's value comes from. A result that's written more
+        than once lists each qubit in the order that the measurements appear, with blocks scanned
+        in order, and a result that's never written has an empty list. Measurements of qubits that
+        aren't statically allocated are left out.
+
+        :returns: The IDs of the qubits measured into each result, by result ID.
+        """
+
+        self._check_thread()
+        sources: Dict[int, List[int]] = {id: [] for id in range(self._num_results)}
+        for block in self._entry_point.basic_blocks:
+            for instruction in block.instructions:
+                if (
+                    isinstance(instruction, Call)
+                    and instruction.callee.name == "__quantum__qis__mz__body"
+                ):
+                    qubit = pyqir.qubit_id(instruction.args[0])
+                    result = pyqir.result_id(instruction.args[1])
+                    if qubit is not None and result in sources:
+                        sources[result].append(qubit)
+        return sources
+
+    def count_qubits_used(self) -> int:
+        """
+        Counts the distinct statically allocated qubits that calls in the entry point use,
+        including in branches. This can be less than :attr:`num_qubits` if some qubits are never
+        used, which means the module could run on a smaller register.
+
+        :returns: The number of qubits used.
+        """
+
+        self._check_thread()
+        used: Set[int] = set()
+        for block in self._entry_point.basic_blocks:
+            for instruction in block.instructions:
+                if isinstance(instruction, Call):
+                    ids = (pyqir.qubit_id(arg) for arg in instruction.args)
+                    used.update(id for id in ids if id is not None)
+        return len(used)
+
+    @property
+    def instructions(self) -> List[Union[Operation, Conditional]]:
+        """
+        A snapshot of the operations in the entry point, in order, with conditional branches on
+        results as :class:`Conditional` operations that contain the operations in each branch.
+        Instructions other than calls, like arithmetic on angles, aren't included.
+
+        Every qubit and result must be statically allocated, and every angle must be a constant.
+        Otherwise, or if the control flow wasn't built by ``if_result``, a :class:`ValueError` is
+        raised.
+        """
+        self._check_thread()
+        return operations(self._entry_point)
+
     @property
     def qubits(self) -> List[Value]:
-        """The list of statically allocated qubits indexed by their numeric ID."""
+        """
+        The list of statically allocated qubits indexed by their numeric ID.
+
+        Qubits are constants that every module in a context shares, so they don't belong to
+        this module. Builders only reject a qubit whose ID is out of range for the module they
+        build, so a qubit of another module in the same context is accepted if its ID is in range.
+        """
         return [pyqir.qubit(self.context, id) for id in range(self._num_qubits)]
 
+    def name_qubit(self, name: str, id: int) -> None:
+        """
+        Gives a qubit a name, so that it can be looked up with :meth:`qubit` instead of by its ID.
+        Names are only kept by the simple module, and aren't emitted.
+
+        :param str name: The name.
+        :param int id: The ID of the qubit.
+        """
+
+        self._check_thread()
+        if not 0 <= id < self._num_qubits:
+            raise ValueError(f"Qubit {id} is out of range.")
+        if name in self._qubit_names:
+            raise ValueError(
+                f"Name {name!r} is already used by qubit {self._qubit_names[name]}."
+            )
+        self._qubit_names[name] = id
+
+    def qubit(self, name: str) -> Value:
+        """
+        Looks up a qubit by the name given to it with :meth:`name_qubit`. A :class:`KeyError` is
+        raised if no qubit has the name.
+
+        :param str name: The name.
+        :returns: The qubit.
+        """
+
+        self._check_thread()
+        id = self._qubit_names.get(name)
+        if id is None:
+            raise KeyError(f"No qubit is named {name!r}.")
+        return pyqir.qubit(self.context, id)
+
     @property
     def results(self) -> List[Value]:
-        """The list of statically allocated results indexed by their numeric ID."""
+        """
+        The list of statically allocated results indexed by their numeric ID.
+
+        Results are constants that every module in a context shares, so they don't belong to
+        this module. Builders only reject a result whose ID is out of range for the module they
+        build, so a result of another module in the same context is accepted if its ID is in range.
+        """
         return [pyqir.result(self.context, id) for id in range(self._num_results)]
 
     @property
     def builder(self) -> Builder:
         """The instruction builder."""
+        self._check_thread()
         return self._builder
 
     @property
     def entry_point(self) -> Function:
         """The entry point function (automatically generated)."""
+        self._check_thread()
         return self._entry_point
 
     @property
     def entry_block(self) -> BasicBlock:
         """The first basic block of the entry point (automatically generated)."""
+        self._check_thread()
         return self._entry_block
 
     def add_external_function(self, name: str, ty: FunctionType) -> Function:
@@ -103,8 +335,63 @@ class SimpleModule:
         :param ty: The type of the function.
         :returns: The function value.
         """
+        self._check_thread()
         return Function(ty, Linkage.EXTERNAL, name, self._module)
 
+    def add_entry_point_attribute(self, key: str, value: str) -> None:
+        """
+        Adds a string attribute, like ``"backend"="h1-2"``, to the entry point. Adding an attribute
+        with the same key as an existing one replaces its value.
+
+        The attributes that the simple module manages itself, like ``required_num_qubits``, are
+        reserved and can't be added. The value is escaped in the emitted IR, but LLVM writes keys
+        as they are, so keys can't contain quotes, backslashes or unprintable characters.
+
+        :param str key: The attribute key.
+        :param str value: The attribute value.
+        """
+
+        self._check_thread()
+        if key in _RESERVED_ATTRIBUTES:
+            raise ValueError(f"The entry point attribute {key} is reserved.")
+        if any(c in '"\\' or not c.isprintable() for c in key):
+            raise ValueError(f"The entry point attribute key {key!r} can't be written as IR.")
+        pyqir.add_string_attribute(self._entry_point, key, value)
+
+    def extend(
+        self,
+        other: "SimpleModule",
+        qubit_map: Dict[int, int],
+        result_map: Dict[int, int],
+    ) -> None:
+        """
+        Appends the circuit built so far in another simple module to this one, replacing each of
+        the other module's qubits and results with the one in this module that its ID maps to.
+        Branches are copied along with both of their bodies, and instructions added afterwards go
+        after the copy.
+
+        The other module must use the same context as this one. A :class:`ValueError` is raised if
+        a qubit or result used by the other module isn't mapped, or is mapped to one that's out of
+        range.
+
+        :param SimpleModule other: The module to copy from.
+        :param Dict[int, int] qubit_map: The ID in this module of each qubit in the other module.
+        :param Dict[int, int] result_map: The ID in this module of each result in the other
+            module.
+        """
+
+        self._check_thread()
+        if other.context is not self.context:
+            raise ValueError("Modules must share a context to be combined.")
+        for id in qubit_map.values():
+            if not 0 <= id < self._num_qubits:
+                raise ValueError(f"Qubit {id} is out of range.")
+        for id in result_map.values():
+            if not 0 <= id < self._num_results:
+                raise ValueError(f"Result {id} is out of range.")
+
+        self._builder.append(other._entry_point, qubit_map, result_map)
+
     def add_byte_string(self, value: bytes) -> Constant:
         """
         Adds a global null-terminated byte string constant to the module.
@@ -112,26 +399,1411 @@ class SimpleModule:
         :param Value: The byte string value without a null terminator.
         :returns: A pointer to the start of the null-terminated byte string.
         """
+        self._check_thread()
         return pyqir.global_byte_string(self._module, value)
 
-    def ir(self) -> str:
-        """Emits the LLVM IR for the module as plain text."""
+    def add_int_register(self, initial: int = 0) -> int:
+        """
+        Adds a classical 64-bit integer register, for arithmetic on measurement outcomes such as
+        counting how many times a result is one. The register is allocated and set to its initial
+        value at the start of the entry point, so it can be used anywhere after that, including
+        inside branches.
+
+        The supported operations are :meth:`increment_if` and :meth:`add_register`, and the value
+        can be added to the output with :meth:`record_int_register`. Registers are emitted as
+        ``i64`` loads and stores, which the base profile doesn't allow.
+
+        Registers are removed by :meth:`clear`, and by :meth:`optimize` at level 1 or higher,
+        which promotes them to SSA values. Using a removed register raises a :class:`ValueError`.
+
+        :param int initial: The initial value of the register.
+        :returns: The ID of the register. Registers are numbered in the order they're added.
+        """
+
+        self._check_thread()
+        i64 = IntType(self.context, 64)
+        builder = Builder(self.context)
+        rest = [i for i in self._entry_block.instructions if i.opcode != Opcode.ALLOCA]
+        if len(rest) > 0:
+            builder.insert_before(rest[0])
+        else:
+            builder.insert_at_end(self._entry_block)
+        register = builder.alloca(i64)
+        builder.store(pyqir.const(i64, initial), register)
+        self._int_registers.append(register)
+        return len(self._int_registers) - 1
+
+    def increment_if(self, register: int, result: Value, amount: int = 1) -> None:
+        """
+        Adds an amount to an integer register if a measurement result is one.
+
+        :param int register: The ID of the register.
+        :param Value result: The result to branch on.
+        :param int amount: The amount to add.
+        """
+
+        ptr = self._int_register(register)
+        i64 = IntType(self.context, 64)
+
+        def add() -> None:
+            value = self._builder.load(i64, ptr)
+            total = self._builder.add(value, pyqir.const(i64, amount))
+            self._builder.store(total, ptr)
+
+        qis.if_result(self._builder, result, one=add)
+
+    def add_register(self, register: int, other: int) -> None:
+        """
+        Adds the value of another integer register to an integer register.
+
+        :param int register: The ID of the register to add to.
+        :param int other: The ID of the register whose value is added.
+        """
+
+        ptr = self._int_register(register)
+        other_ptr = self._int_register(other)
+        i64 = IntType(self.context, 64)
+        value = self._builder.load(i64, ptr)
+        other_value = self._builder.load(i64, other_ptr)
+        self._builder.store(self._builder.add(value, other_value), ptr)
+
+    def record_int_register(self, register: int) -> None:
+        """
+        Adds the current value of an integer register to the output, without a label.
+
+        :param int register: The ID of the register.
+        """
+
+        ptr = self._int_register(register)
+        value = self._builder.load(IntType(self.context, 64), ptr)
+        label = Constant.null(PointerType(IntType(self.context, 8)))
+        rt.int_record_output(self._builder, value, label)
+
+    def use_opaque_pointers(self, enabled: bool = True) -> None:
+        """
+        Chooses the pointer style of the emitted IR and bitcode. With opaque pointers, every
+        pointer, including qubits and results, has the type ``ptr``, as newer LLVM toolchains
+        require. Otherwise pointers are typed, like ``%Qubit*`` and ``%Result*``, as older QIR
+        consumers expect. Pointers are typed by default.
+
+        The module is always built with typed pointers, and is only converted when it is emitted.
+        Opaque pointers require LLVM 14.
+
+        :param bool enabled: Whether to emit opaque pointers.
+        """
+
+        self._check_thread()
+        if enabled:
+            # Fail now if opaque pointers aren't supported, rather than when emitting.
+            Context(opaque_pointers=True)
+        self._opaque_pointers = enabled
+
+    def use_batched_measurements(self, enabled: bool = True) -> None:
+        """
+        Chooses how the measurements at the end of the circuit are emitted. When batched, the run
+        of ``mz`` calls after the last gate is emitted as a single call to
+        ``__quantum__qis__mz_batch__body``, which takes the number of measurements and pointers to
+        arrays of the qubits and results, for hardware that measures every qubit at once.
+        Otherwise each measurement is its own call to ``__quantum__qis__mz__body``, which is the
+        default.
+
+        The module is always built with one call per measurement, and is only batched when it is
+        emitted. See :func:`pyqir.batch_measurements` for the measurements that can be batched.
+
+        :param bool enabled: Whether to batch measurements.
+        """
+        self._check_thread()
+        self._batched_measurements = enabled
+
+    def use_strict_results(self, enabled: bool = True) -> None:
+        """
+        Chooses whether writing a result twice is an error. In strict mode, emitting the module
+        raises a :class:`QirEmitError` if an ``mz`` measurement writes into a result that has
+        definitely been written already, which usually means that the circuit measures into the
+        wrong result. A write in only one branch of a conditional doesn't count as definite.
+
+        The check reads the module through :attr:`instructions`, so in strict mode every qubit and
+        result must be statically allocated and every angle must be a constant.
+
+        :param bool enabled: Whether to reject results that are written twice.
+        """
+        self._check_thread()
+        self._strict_results = enabled
+
+    def use_pi_comments(self, enabled: bool = True) -> None:
+        """
+        Chooses whether the LLVM IR from :meth:`ir` annotates angles that are simple fractions of
+        :math:`\\pi`. When enabled, each line with such a ``double`` constant ends with a comment
+        like ``; pi/2``. The constants themselves are unchanged, and bitcode has no comments.
+
+        :param bool enabled: Whether to annotate angles.
+        """
+        self._check_thread()
+        self._pi_comments = enabled
+
+    def use_result_output(self, enabled: bool = True) -> None:
+        """
+        Chooses whether the entry point records every result as output before it returns, which is
+        how a base profile program returns its results to the runtime. When enabled, the emitted
+        entry point ends with a call to ``__quantum__rt__array_record_output`` with the number of
+        results, followed by a call to ``__quantum__rt__result_record_output`` for each result in
+        order, all without labels.
+
+        Like the return instruction, the calls are only added when the module is emitted, so they
+        come after everything that was built.
+
+        :param bool enabled: Whether to record the results.
+        """
+        self._check_thread()
+        self._result_output = enabled
+
+    def defer_measurements(self) -> None:
+        """
+        Moves every measurement to the end of the circuit, after the last gate, keeping the
+        measurements in their original order. This puts a freely-written circuit into the shape
+        that the base profile expects.
+
+        Measurements can only be deferred if it doesn't change the meaning of the program. A
+        :class:`ValueError` is raised, and the module is left unchanged, if the program branches on
+        a result, uses a qubit after measuring it, or reads a result before the end of the
+        circuit.
+        """
+
+        self._check_thread()
+        pyqir.defer_measurements(self._entry_point)
+
+    def relabel(self, mapping: Dict[int, int]) -> None:
+        """
+        Renumbers the qubits in place, for example to map logical qubits to physical ones. Every
+        qubit operand in the entry point is replaced by the qubit that its ID maps to, and names
+        given with :meth:`name_qubit` move with their qubits.
+
+        The mapping must be a permutation of the module's qubits, with every qubit ID from 0 to
+        :attr:`num_qubits` as a key and as a value exactly once. Otherwise a :class:`ValueError`
+        is raised and the module is unchanged.
+
+        :param Dict[int, int] mapping: The new ID of each qubit.
+        """
+
+        self._check_thread()
+        qubits = set(range(self._num_qubits))
+        if set(mapping.keys()) != qubits or set(mapping.values()) != qubits:
+            raise ValueError(
+                "The mapping must be a permutation of the "
+                f"{_count(self._num_qubits, 'qubit')}."
+            )
+        pyqir.relabel_qubits(self._entry_point, mapping)
+        self._qubit_names = {n: mapping[id] for n, id in self._qubit_names.items()}
+
+    def relabel_qubits(self, mapping: Dict[int, int]) -> "SimpleModule":
+        """
+        Copies this module with some qubits renumbered, for example to move a circuit onto other
+        qubits before extending another module with it. Qubits that aren't in the mapping keep
+        their ID, and this module is unchanged.
+
+        Together with the unmapped qubits, the mapping must still be a permutation of the module's
+        qubits, so two qubits can't end up with the same ID. See :meth:`relabel`.
+
+        :param Dict[int, int] mapping: The new ID of each qubit to renumber.
+        :returns: The renumbered copy.
+        """
+
+        other = copy.copy(self)
+        other.relabel({**{id: id for id in range(self._num_qubits)}, **mapping})
+        return other
+
+    def adjoint(self) -> "SimpleModule":
+        """
+        Copies this module with the circuit in the entry point replaced by its adjoint, which
+        undoes it. See :func:`pyqir.adjoint` for the circuits that have an adjoint. Extending this
+        module with its adjoint gives a mirror circuit, which is equivalent to the identity.
+
+        :returns: The adjoint copy.
+        """
+
+        other = copy.copy(self)
+        pyqir.adjoint(other._entry_point)
+        return other
+
+    def power(self, exponent: float) -> "SimpleModule":
+        """
+        Copies this module with the angle of every rotation in the entry point multiplied by the
+        exponent. See :func:`pyqir.power` for the circuits that can be raised to a power. This is
+        only the power of the whole circuit if its gates commute, like rotations about the same
+        axis.
+
+        :param float exponent: The exponent.
+        :returns: The copy raised to the power.
+        """
+
+        other = copy.copy(self)
+        pyqir.power(other._entry_point, exponent)
+        return other
+
+    def controlled(self, control: Union[int, Value, None] = None) -> "SimpleModule":
+        """
+        Copies this module with the circuit in the entry point replaced by its controlled form,
+        which only applies the circuit when the control qubit is one. By default, a new control
+        qubit is added to the copy with the ID :attr:`num_qubits`.
+
+        The control can instead be a statically allocated qubit of this module that the circuit
+        doesn't use, given by its ID or as a value. A :class:`ValueError` is raised if it's out of
+        range, isn't a statically allocated qubit, or is used by the circuit.
+
+        Every call in the entry point is replaced by its controlled form, as built by
+        :meth:`Builder.controlled`, so the controlled circuit is only exact up to a global phase.
+        A :class:`ValueError` is raised if a call has no controlled form, like a measurement or a
+        :math:`CZ` gate.
+
+        :param Union[int, Value, None] control: The control qubit.
+        :returns: The controlled copy.
+        """
+
+        self._check_thread()
+        if control is None:
+            id = self._num_qubits
+        else:
+            id = control if isinstance(control, int) else pyqir.qubit_id(control)
+            if id is None:
+                raise ValueError("The control must be a statically allocated qubit.")
+            if not 0 <= id < self._num_qubits:
+                raise ValueError(f"Qubit {id} is out of range.")
+            used = {
+                pyqir.qubit_id(arg)
+                for block in self._entry_point.basic_blocks
+                for instruction in block.instructions
+                if isinstance(instruction, Call)
+                for arg in instruction.args
+            }
+            if id in used:
+                raise ValueError(f"Qubit {id} can't be the control because it's used.")
+
+        other = copy.copy(self)
+        if control is None:
+            other._num_qubits += 1
+            pyqir.add_string_attribute(
+                other._entry_point, "required_num_qubits", str(other._num_qubits)
+            )
+        qubit = pyqir.qubit(other.context, id)
+        calls = [
+            i
+            for block in other._entry_point.basic_blocks
+            for i in block.instructions
+            if isinstance(i, Call)
+        ]
+        for call in calls:
+            other._builder.controlled(call, qubit)
+        return other
+
+    def to_matrix(self) -> List[List[complex]]:
+        """
+        Computes the unitary matrix of the circuit in the entry point. See :func:`pyqir.to_matrix`
+        for the qubit ordering and the circuits that are supported.
+
+        The matrix has ``4 ** num_qubits`` entries, so this is only practical for small circuits.
+
+        :returns: The rows of the matrix.
+        """
+        self._check_thread()
+        return pyqir.to_matrix(self._entry_point)
+
+    def approx_equal(self, other: "SimpleModule", atol: float = 1e-9) -> bool:
+        """
+        Checks whether this module and another one have the same :attr:`instructions`, except that
+        corresponding rotation angles may differ by a small amount, for example to test a
+        transformation that changes angles by rounding errors. Every operation must have the same
+        name, qubits and results, and branches must match.
+
+        :param SimpleModule other: The other module.
+        :param float atol: The largest allowed difference between corresponding angles.
+        :returns: Whether the instructions are approximately equal.
+        """
+        return approx_equal(self.instructions, other.instructions, atol)
+
+    def equivalent_to(self, other: "SimpleModule", tolerance: float = 1e-9) -> bool:
+        """
+        Checks whether the circuits in this module and another one have the same unitary matrix
+        up to a global phase, for example to test that a synthesized circuit implements its
+        specification. The matrices are computed with :meth:`to_matrix`, so a :class:`ValueError`
+        is raised if either circuit isn't unitary, like one with a measurement.
+
+        Circuits with different numbers of qubits are never equivalent.
+
+        :param SimpleModule other: The other module.
+        :param float tolerance: The largest allowed difference between corresponding entries of
+            the matrices, after removing the global phase.
+        :returns: Whether the circuits are equivalent.
+        """
+
+        u1 = self.to_matrix()
+        u2 = other.to_matrix()
+        if len(u1) != len(u2):
+            return False
+
+        # Align the phases at the largest entry of the other matrix, which a unitary always has
+        # with a magnitude of at least 1/sqrt(n).
+        i, j = max(
+            ((i, j) for i in range(len(u2)) for j in range(len(u2))),
+            key=lambda ij: abs(u2[ij[0]][ij[1]]),
+        )
+        phase = cmath.exp(1j * (cmath.phase(u1[i][j]) - cmath.phase(u2[i][j])))
+        return all(
+            abs(a - phase * b) < tolerance
+            for row1, row2 in zip(u1, u2)
+            for a, b in zip(row1, row2)
+        )
+
+    def simulate(self, shots: int, seed: Optional[int] = None) -> Dict[str, int]:
+        """
+        Runs the circuit on a small reference state vector simulator, for quick checks while
+        building a circuit. The simulator is written in plain Python and keeps ``2 ** num_qubits``
+        amplitudes, so it's only practical for a handful of qubits, and emitting the module is
+        unaffected.
+
+        Every qubit starts in the zero state and every result starts at zero. The circuit is read
+        through :attr:`instructions`, and can use the gates, measurements, resets and branches on
+        results that :class:`BasicQisBuilder` builds. Runtime calls, like recording output, are
+        ignored.
+
+        :param int shots: The number of times to run the circuit.
+        :param Optional[int] seed: The seed for the random measurement outcomes.
+        :returns: The number of shots for each string of result values, like ``"01"`` for
+            result 0 being zero and result 1 being one.
+        """
+        return simulate(
+            self.instructions, self._num_qubits, self._num_results, shots, seed
+        )
+
+    def draw(self, output: str = "text") -> str:
+        """
+        Draws a diagram of the circuit, with a horizontal wire for each qubit and a column for each
+        operation in :attr:`instructions`. Operations in a branch on a result are drawn in order,
+        labeled with the value of the result that they run for, like ``X[r0=1]``.
+
+        The ``"text"`` output is plain ASCII, with controls drawn as ``*`` and measurements into a
+        result as ``M`` followed by the result ID. The ``"latex"`` output is a ``quantikz``
+        environment.
+
+        :param str output: The kind of diagram: ``"text"`` or ``"latex"``.
+        :returns: The diagram.
+        """
+        return draw(self.instructions, self._num_qubits, output)
+
+    def to_cirq_json(self) -> str:
+        """
+        Serializes the circuit in Cirq's JSON format, which ``cirq.read_json(json_text=...)``
+        reads into a ``cirq.Circuit``. Each layer from :meth:`layers` becomes a moment, qubits
+        become line qubits with the same IDs, and a measurement into a result is keyed by the
+        result, like ``"r0"``.
+
+        Gates become their Cirq equivalents, like ``cirq.H`` for ``h``, ``cirq.S**-1`` for
+        ``s_adj`` and ``cirq.Rx(rads=theta)`` for ``rx``. Barriers and runtime calls are left out.
+        A :class:`ValueError` is raised for an operation without an equivalent, like ``cy``, and
+        for a branch on a result.
+
+        :returns: The JSON.
+        """
+        return to_cirq_json(self.instructions, self.layers())
+
+    @staticmethod
+    def from_cirq_json(
+        json_str: str, name: str = "cirq", context: Optional[Context] = None
+    ) -> "SimpleModule":
+        """
+        Creates a simple module from a circuit in Cirq's JSON format, like the output of
+        ``cirq.to_json`` or :meth:`to_cirq_json`. Operations are added in moment order, with one
+        qubit for each line qubit ID up to the largest one. A measurement keyed like ``"r0"`` goes
+        into that result, and a measurement with any other key goes into the next unused result.
+
+        Gates become their QIR equivalents, like ``h`` for ``cirq.H`` and ``s_adj`` for
+        ``cirq.S**-1``. Other powers of ``cirq.Z`` become ``u1``, and other powers of ``cirq.X``
+        and ``cirq.Y`` become rotations, which are only equal up to a global phase. A
+        :class:`ValueError` naming the Cirq class is raised for an operation, gate or qubit
+        without an equivalent, like ``cirq.GridQubit``.
+
+        :param str json_str: The JSON.
+        :param str name: The name of the module.
+        :param Optional[Context] context: The LLVM context.
+        :returns: The module.
+        """
+
+        num_qubits, num_results, ops = from_cirq_json(json_str)
+        module = SimpleModule(name, num_qubits, num_results, context)
+        all_qubits, all_results = module.qubits, module.results
+        for op in ops:
+            qubits = [all_qubits[q] for q in op.qubits]
+            results = [all_results[r] for r in op.results]
+            getattr(qis, op.name)(module.builder, *op.angles, *qubits, *results)
+        return module
+
+    def dependency_graph(self) -> Dict[int, List[int]]:
+        """
+        Finds the operations that each operation in :attr:`instructions` must wait for, as a
+        directed acyclic graph for analyzing how the circuit can be parallelized. An operation
+        depends on the last operation before it that used any of the same qubits or results, so
+        a branch on a result depends on the measurement that wrote it. A :class:`Conditional`
+        counts as one operation that uses everything its branches and its condition use.
+
+        :returns: The indices in :attr:`instructions` of the operations that each operation
+            depends on, in ascending order, keyed by the index of the operation.
+        """
+
+        last_qubit: Dict[int, int] = {}
+        last_result: Dict[int, int] = {}
+        graph: Dict[int, List[int]] = {}
+        for index, op in enumerate(self.instructions):
+            qubits, results = _uses(op)
+            dependencies = {last_qubit[q] for q in qubits if q in last_qubit}
+            dependencies |= {last_result[r] for r in results if r in last_result}
+            graph[index] = sorted(dependencies)
+            last_qubit.update((q, index) for q in qubits)
+            last_result.update((r, index) for r in results)
+        return graph
+
+    def layers(self) -> List[List[int]]:
+        """
+        Sorts the operations in :attr:`instructions` topologically into layers that can run in
+        parallel, following :meth:`dependency_graph`. Each operation is placed in the earliest
+        layer after every operation it depends on, so the number of layers is the depth of the
+        circuit.
+
+        :returns: The indices in :attr:`instructions` of the operations in each layer, in
+            ascending order.
+        """
+
+        layers: List[List[int]] = []
+        layer_of: Dict[int, int] = {}
+        for index, dependencies in self.dependency_graph().items():
+            layer = max((layer_of[d] + 1 for d in dependencies), default=0)
+            layer_of[index] = layer
+            if layer == len(layers):
+                layers.append([])
+            layers[layer].append(index)
+        return layers
+
+    def filter_instructions(
+        self, predicate: Callable[[Instruction], bool]
+    ) -> "SimpleModule":
+        """
+        Copies this module, keeping only the instructions in the entry point for which the
+        predicate returns true. Every basic block is filtered, including the bodies of conditional
+        branches, which stay in place even if they become empty. Terminators are always kept, and
+        this module is unchanged.
+
+        A :class:`ValueError` is raised if a removed instruction is used by one that is kept, like
+        a measurement whose result is branched on.
+
+        :param Callable[[Instruction], bool] predicate: Whether to keep an instruction.
+        :returns: The filtered copy.
+        """
+
+        other = copy.copy(self)
+        kept = []
+        removed = []
+        for block in other._entry_point.basic_blocks:
+            terminator = block.terminator
+            for instruction in block.instructions:
+                if (
+                    terminator is not None and instruction == terminator
+                ) or predicate(instruction):
+                    kept.append(instruction)
+                else:
+                    removed.append(instruction)
+
+        removed_set = set(removed)
+        for instruction in kept:
+            if any(operand in removed_set for operand in instruction.operands):
+                raise ValueError(
+                    "A removed instruction is used by an instruction that is kept."
+                )
+
+        # Erase users before the instructions they use.
+        for instruction in reversed(removed):
+            instruction.erase()
+        return other
+
+    def strip_measurements(self) -> "SimpleModule":
+        """
+        Copies this module without its ``m`` and ``mz`` measurements, for example to run it on a
+        state vector simulator. See :meth:`filter_instructions`.
+
+        :returns: The copy without measurements.
+        """
+        return self.filter_instructions(lambda i: not _calls(i, _MEASUREMENTS))
+
+    def strip_resets(self) -> "SimpleModule":
+        """
+        Copies this module without its resets. See :meth:`filter_instructions`.
+
+        :returns: The copy without resets.
+        """
+        return self.filter_instructions(lambda i: not _calls(i, _RESETS))
+
+    def clear(self) -> None:
+        """
+        Removes every instruction and basic block from the entry point, so the module can be built
+        again from scratch with the same qubits, results and attributes. The builder is moved to
+        the end of the now empty entry block. Integer registers are removed too, and their IDs
+        aren't reused.
+        """
+
+        self._check_thread()
+        blocks = self._entry_point.basic_blocks
+        # Erase users before the instructions they use, which can be in earlier blocks.
+        for block in reversed(blocks):
+            for instruction in reversed(block.instructions):
+                instruction.erase()
+        for block in blocks[1:]:
+            block.erase()
+        self._remove_int_registers()
+        self._builder.insert_at_end(self._entry_block)
+
+    def check_use_after_measure(self) -> List[Call]:
+        """
+        Finds the calls in the entry point that use a qubit after it has been measured and before
+        it has been reset, which the base profile doesn't allow and is often a mistake. Measuring a
+        qubit again is not reported.
+
+        Basic blocks are scanned in the order they appear, so a reset in only one branch of a
+        conditional counts as resetting the qubit afterwards. This is only advisory: the module is
+        unchanged and can still be emitted.
+
+        :returns: The offending calls, in order.
+        """
+
+        self._check_thread()
+        measured: Set[int] = set()
+        findings: List[Call] = []
+        for block in self._entry_point.basic_blocks:
+            for instruction in block.instructions:
+                if not isinstance(instruction, Call):
+                    continue
+                ids = (pyqir.qubit_id(arg) for arg in instruction.args)
+                qubits = {id for id in ids if id is not None}
+                if _calls(instruction, _MEASUREMENTS):
+                    measured |= qubits
+                elif _calls(instruction, _RESETS):
+                    measured -= qubits
+                elif not measured.isdisjoint(qubits):
+                    findings.append(instruction)
+        return findings
+
+    def check_gate_set(self, allowed: Set[str]) -> List[Call]:
+        """
+        Finds the calls in the entry point to QIS functions that aren't in the allowed set, for
+        example to check that a circuit only uses the native gates of a backend. Calls are named
+        like the operations in :attr:`instructions`, so measurements and resets need to be allowed
+        too, as ``"mz"`` and ``"reset"``. Calls that read results and calls to the runtime are
+        always allowed. This is only advisory: the module is unchanged and can still be emitted.
+
+        :param Set[str] allowed: The names of the allowed operations, like ``{"h", "cx", "rz"}``.
+        :returns: The offending calls, in order.
+        """
+
+        self._check_thread()
+        findings: List[Call] = []
+        for block in self._entry_point.basic_blocks:
+            for instruction in block.instructions:
+                if (
+                    isinstance(instruction, Call)
+                    and instruction.callee.name.startswith("__quantum__qis__")
+                    and instruction.callee.name != "__quantum__qis__read_result__body"
+                    and operation_name(instruction) not in allowed
+                ):
+                    findings.append(instruction)
+        return findings
+
+    def validate(
+        self, profile: str = "base_profile", raise_errors: bool = False
+    ) -> List[Violation]:
+        """
+        Checks that the entry point conforms to a QIR profile, to catch problems before the module
+        is sent to a backend that only supports the profile. Only ``"base_profile"`` is supported.
+        Its rules are:
+
+        - ``"gate_set"``: Only the QIS functions in the profile may be called, as listed by
+          :func:`qis_declarations`. Resets aren't included.
+        - ``"use_after_measure"``: A qubit can't be used after it's measured, except to measure
+          it again.
+        - ``"branching"``: There can be no conditional branches, like those built by
+          ``if_result``.
+        - ``"result_use"``: Results can only be written by measurements and recorded as output.
+
+        :param str profile: The profile.
+        :param bool raise_errors: Whether to raise a :class:`ValueError` that lists the
+            violations, if there are any, instead of returning them.
+        :returns: The violations, in order.
+        """
+
+        self._check_thread()
+        violations = validate(self._entry_point, profile)
+        if raise_errors and len(violations) > 0:
+            lines = [f"Module {self._name} doesn't conform to {profile}:"]
+            lines.extend(f"- {v.message}" for v in violations)
+            raise ValueError("\n".join(lines))
+        return violations
+
+    def set_target(self, triple: str, data_layout: Optional[str] = None) -> None:
+        """
+        Sets the target triple and data layout of the emitted module. If either is invalid, a
+        :class:`ValueError` is raised with LLVM's error message and the module is unchanged.
+
+        If this is never called, the module is emitted without a target triple or data layout.
+        No default is applied, because the QIR specification doesn't recommend a target: the
+        triple and data layout depend on the backend that the program is compiled for, and tools
+        that need them should set them here.
+
+        :param str triple: The target triple, like ``x86_64-unknown-linux-gnu``.
+        :param Optional[str] data_layout: The data layout. Without one, the module has no data
+            layout, which LLVM reads as its default data layout.
+        """
+
+        self._check_thread()
+        previous_triple = self._module.triple
+        self._module.triple = triple
+        try:
+            self._module.data_layout = "" if data_layout is None else data_layout
+        except ValueError:
+            self._module.triple = previous_triple
+            raise
+
+    def set_source_location(
+        self, filename: Optional[str] = None, producer: str = "pyqir"
+    ) -> None:
+        """
+        Records the source file that generated this module.
+
+        The file name is stored as the module's source file name and in a debug info compile unit,
+        both of which are kept when the module is emitted as IR or bitcode. The source location
+        can only be set once.
+
+        :param Optional[str] filename: The source file name. Defaults to the ``__file__`` of the
+            calling module.
+        :param str producer: The tool that generated the module.
+        """
+
+        self._check_thread()
+        if self._compile_unit is not None:
+            raise ValueError("The source location has already been set.")
+
+        if filename is None:
+            caller = _caller_frame()
+            filename = None if caller is None else caller.f_globals.get("__file__")
+            if filename is None:
+                raise ValueError(
+                    "The caller's source file name could not be determined."
+                )
+
+        path = os.path.abspath(filename)
+        self._module.source_filename = filename
+        self._compile_unit = pyqir.add_compile_unit(
+            self._module, os.path.basename(path), os.path.dirname(path), producer
+        )
+
+    def mark_location(self, line: Optional[int] = None, column: int = 0) -> None:
+        """
+        Attaches a source location to the instructions added from now on, as ``!dbg`` metadata
+        that LLVM tools can use to trace them back to the code that generated them.
+        :meth:`set_source_location` must be called first. Use
+        :meth:`Builder.clear_debug_location` to stop attaching locations.
+
+        :param Optional[int] line: The line number. Defaults to the line of the caller.
+        :param int column: The column number.
+        """
+
+        self._check_thread()
+        if self._compile_unit is None:
+            raise ValueError("The source location has not been set.")
+
+        if line is None:
+            caller = _caller_frame()
+            if caller is None:
+                raise ValueError("The caller's line number could not be determined.")
+            line = caller.f_lineno
+
+        self._builder.set_debug_location(line, column)
+
+    def optimize(self, level: int) -> "SimpleModule":
+        """
+        Optimizes the module in place with LLVM's standard optimization pipeline.
+
+        The passes that run at each level are those of LLVM's legacy pass manager builder:
+
+        - 0: No passes.
+        - 1: Scalar simplification, including SROA, EarlyCSE, SimplifyCFG, InstCombine, dead code
+          and dead global elimination, and interprocedural constant propagation (IPSCCP).
+        - 2: Level 1, plus GVN, MemCpyOpt, jump threading, loop optimizations such as LICM,
+          rotation and unrolling, and vectorization.
+        - 3: Level 2, plus argument promotion and more aggressive instruction combining.
+
+        Calls to QIS and runtime functions are never removed, since they are external. Basic
+        blocks may be merged or removed, so blocks obtained before optimizing should not be used
+        afterwards. At level 1 or higher, integer registers from :meth:`add_int_register` are
+        promoted to SSA values and removed, so they can't be used afterwards. The builder is
+        positioned at the end of the entry point so that building can continue.
+
+        :param int level: The optimization level, from 0 to 3.
+        :returns: This module.
+        """
+
+        self._check_thread()
         ret = self._builder.ret(None)
         try:
             error = self._module.verify()
             if error is not None:
                 raise ValueError(error)
-            return str(self._module)
-        finally:
+            self._module.optimize(level)
+        except Exception:
             ret.erase()
+            raise
+        if level > 0:
+            self._remove_int_registers()
 
-    def bitcode(self) -> bytes:
-        """Emits the LLVM bitcode for the module as a sequence of bytes."""
-        ret = self._builder.ret(None)
+        returning = [
+            block
+            for block in self._entry_point.basic_blocks
+            if block.terminator is not None and block.terminator.opcode == Opcode.RET
+        ]
+        if len(returning) == 1:
+            returning[0].terminator.erase()
+            self._builder.insert_at_end(returning[0])
+        else:
+            end = BasicBlock(self.context, "continue", self._entry_point)
+            for block in returning:
+                block.terminator.erase()
+                self._builder.insert_at_end(block)
+                self._builder.br(end)
+            self._builder.insert_at_end(end)
+
+        return self
+
+    def __copy__(self) -> "SimpleModule":
+        """
+        Copies this module, so that the copy and the original can continue to be built
+        independently, for example with different instructions after a common prefix.
+
+        The copy shares the original's context. Its builder inserts at the end of the last basic
+        block of the entry point, without a debug location.
+
+        :returns: The copy.
+        """
+
+        self._check_thread()
+        module = copy.copy(self._module)
+        entry_point = next(
+            f for f in module.functions if f.name == self._entry_point.name
+        )
+        other = SimpleModule.__new__(SimpleModule)
+        other._init_state(
+            module, self._name, self._num_qubits, self._num_results, entry_point, self
+        )
+        return other
+
+    def __deepcopy__(self, memo: Dict[int, Any]) -> "SimpleModule":
+        """
+        Copies this module. The context is shared rather than copied, so this is the same as
+        :meth:`__copy__`.
+
+        :param memo: The memo dictionary of objects already copied.
+        :returns: The copy.
+        """
+        return self.__copy__()
+
+    def __add__(self, other: "SimpleModule") -> "SimpleModule":
+        """
+        Composes two circuits in sequence, as a copy of this module with the other module's circuit
+        appended to it by :meth:`extend`. Each qubit and result of the other module is the one
+        with the same ID in this module, so the other module can't have more qubits or results
+        than this one, and a :class:`ValueError` is raised otherwise.
+
+        :param SimpleModule other: The module whose circuit comes second.
+        :returns: The composed module.
+        """
+
+        if not isinstance(other, SimpleModule):
+            return NotImplemented
+        composed = copy.copy(self)
+        composed += other
+        return composed
+
+    def __iadd__(self, other: "SimpleModule") -> "SimpleModule":
+        """
+        Appends the other module's circuit to this one in place, as in :meth:`__add__`.
+
+        :param SimpleModule other: The module whose circuit is appended.
+        :returns: This module.
+        """
+
+        if not isinstance(other, SimpleModule):
+            return NotImplemented
+        if other._num_qubits > self._num_qubits or other._num_results > self._num_results:
+            raise ValueError(
+                f"Module {other._name} doesn't fit in module {self._name}, which has "
+                f"{_count(self._num_qubits, 'qubit')} and "
+                f"{_count(self._num_results, 'result')}."
+            )
+        self.extend(
+            other,
+            {id: id for id in range(other._num_qubits)},
+            {id: id for id in range(other._num_results)},
+        )
+        return self
+
+    def __mul__(self, n: int) -> "SimpleModule":
+        """
+        Repeats the circuit, as a copy of this module whose circuit is this module's circuit
+        appended :math:`n` times in sequence. Repeating it zero times gives an empty circuit.
+
+        :param int n: The number of repetitions, which must not be negative.
+        :returns: The repeated module.
+        """
+
+        if not isinstance(n, int):
+            return NotImplemented
+        if n < 0:
+            raise ValueError(f"The number of repetitions must not be negative, not {n}.")
+        repeated = copy.copy(self)
+        repeated.clear()
+        for _ in range(n):
+            repeated += self
+        return repeated
+
+    def __rmul__(self, n: int) -> "SimpleModule":
+        """
+        Repeats the circuit, as in :meth:`__mul__`.
+
+        :param int n: The number of repetitions, which must not be negative.
+        :returns: The repeated module.
+        """
+        return self.__mul__(n)
+
+    def __len__(self) -> int:
+        """
+        The number of operations in :attr:`instructions`. A conditional branch counts as one
+        operation, whatever is inside it. Unlike :attr:`instructions`, operations on dynamic
+        qubits and with non-constant angles are counted too.
+
+        :returns: The number of operations.
+        """
+        self._check_thread()
+        return len(operations(self._entry_point, shallow=True))
+
+    def __bool__(self) -> bool:
+        """
+        Whether the module has any operations. An empty module is false, so check for ``None``
+        explicitly where a module is optional.
+
+        :returns: True if :attr:`instructions` isn't empty.
+        """
+        return len(self) > 0
+
+    def __iter__(self) -> Iterator[Union[Operation, Conditional]]:
+        """
+        Iterates over a snapshot of :attr:`instructions`, so instructions built while iterating
+        aren't included.
+
+        :returns: An iterator over the operations.
+        """
+        return iter(self.instructions)
+
+    def estimated_ir_lines(self) -> int:
+        """
+        Estimates the number of lines in the LLVM IR from :meth:`ir` without emitting it, to decide
+        how to handle a large module before paying for the emission. The estimate counts a line
+        for each instruction, block label and function, plus a fixed allowance for the header,
+        attribute groups and module flags. It's a heuristic: debug info and other metadata aren't
+        counted.
+
+        :returns: The approximate number of lines.
+        """
+        self._check_thread()
+        # The header, type definitions, attribute groups and module flags.
+        lines = 12
+        for function in self._module.functions:
+            blocks = function.basic_blocks
+            if len(blocks) == 0:
+                # The declaration and a blank line.
+                lines += 2
+            else:
+                # The signature, the closing brace and a blank line, and for each block, a label
+                # and a blank line.
+                lines += 3 + sum(2 + len(b.instructions) for b in blocks)
+        # The return instruction that ends the entry point.
+        lines += 1
+        if self._result_output:
+            # The output recording calls, and the declarations of the functions they call if an
+            # earlier emission hasn't already added them.
+            lines += self._num_results + 1
+            declared = {f.name for f in self._module.functions}
+            for name in ["array_record_output", "result_record_output"]:
+                if f"__quantum__rt__{name}" not in declared:
+                    lines += 2
+        return lines
+
+    def ir(self, verify: bool = True, options: Optional[EmitOptions] = None) -> str:
+        """
+        Emits the LLVM IR for the module as plain text.
+
+        The return instruction that terminates the entry point, and the output recording enabled
+        by :meth:`use_result_output`, are only added for the duration of this call, so the module
+        can continue to be built afterwards.
+
+        A :class:`QirEmitError` is raised if the module is invalid, with a description of the
+        problem that includes the offending instruction. When the instruction is in the entry
+        point, the message starts with its index, counting every instruction in block order, and
+        its operation and operands, like ``at instruction 3 (cx qubit 0, qubit 1)``.
+
+        :param bool verify: Whether to check that the module is valid. Emitting an invalid module
+            is only useful for debugging the code that built it.
+        :param Optional[EmitOptions] options: The indentation and line terminator of the IR. By
+            default, the IR is laid out like LLVM writes it.
+        :returns: The IR.
+        """
+        ending = self._add_ending()
         try:
-            error = self._module.verify()
-            if error is not None:
-                raise ValueError(error)
-            return self._module.bitcode
+            ir = str(self._emitted_module(verify))
         finally:
-            ret.erase()
+            _erase(ending)
+        return self._format(ir, options)
+
+    def write_ir(
+        self,
+        file: Optional[TextIO] = None,
+        verify: bool = True,
+        options: Optional[EmitOptions] = None,
+    ) -> None:
+        """
+        Emits the LLVM IR for the module like :meth:`ir`, and writes it to a text file.
+
+        The IR is written as is, so a file that translates newlines, like :data:`sys.stdout` on
+        Windows, should be used with the default ``"\\n"`` line terminator.
+
+        :param Optional[TextIO] file: The file to write to, or ``None`` for :data:`sys.stdout`.
+        :param bool verify: Whether to check that the module is valid.
+        :param Optional[EmitOptions] options: The indentation and line terminator of the IR.
+        """
+        ir = self.ir(verify, options)
+        (sys.stdout if file is None else file).write(ir)
+
+    def function_ir(
+        self, name: str, verify: bool = True, options: Optional[EmitOptions] = None
+    ) -> str:
+        """
+        Emits the LLVM IR for one function in the module as plain text, for diffing or testing a
+        piece of a larger module. The function is followed by the declarations of the external
+        functions that it calls, in the order they're first called. Type definitions, attribute
+        groups and metadata aren't included, so the text can't be parsed on its own.
+
+        The function is emitted like :meth:`ir` emits it, so the entry point includes its return
+        instruction. A :class:`KeyError` is raised if the module has no function with the name.
+
+        :param str name: The name of the function, like ``"main"`` for the entry point.
+        :param bool verify: Whether to check that the module is valid.
+        :param Optional[EmitOptions] options: The indentation and line terminator of the IR.
+        :returns: The IR.
+        """
+
+        ending = self._add_ending()
+        try:
+            module = self._emitted_module(verify)
+            function = next((f for f in module.functions if f.name == name), None)
+            if function is None:
+                raise KeyError(f"Module {self._name} has no function named {name!r}.")
+            callees: List[Function] = []
+            for block in function.basic_blocks:
+                for instruction in block.instructions:
+                    if (
+                        isinstance(instruction, Call)
+                        and isinstance(instruction.callee, Function)
+                        and len(instruction.callee.basic_blocks) == 0
+                        and instruction.callee not in callees
+                    ):
+                        callees.append(instruction.callee)
+            ir = "\n".join(str(f) for f in [function, *callees])
+        finally:
+            _erase(ending)
+        return self._format(ir, options)
+
+    def bitcode(self, verify: bool = True) -> bytes:
+        """
+        Emits the LLVM bitcode for the module as a sequence of bytes.
+
+        The return instruction that terminates the entry point, and the output recording enabled
+        by :meth:`use_result_output`, are only added for the duration of this call, so the module
+        can continue to be built afterwards.
+
+        A :class:`QirEmitError` is raised if the module is invalid, with a description of the
+        problem that includes the offending instruction.
+
+        :param bool verify: Whether to check that the module is valid. See :meth:`ir`.
+        :returns: The bitcode.
+        """
+        ending = self._add_ending()
+        try:
+            return self._emitted_module(verify).bitcode
+        finally:
+            _erase(ending)
+
+    def verify_consistency(self) -> None:
+        """
+        Checks that :meth:`ir` and :meth:`bitcode` describe the same module, by reading the
+        bitcode back into a new context and comparing its IR with :meth:`ir` line by line.
+        Comments, like the module ID and those added by :meth:`use_pi_comments`, blank lines and
+        trailing whitespace are ignored. A :class:`ValueError` with an excerpt of the differences
+        is raised if they don't match.
+        """
+
+        ir = self.ir()
+        context = Context(opaque_pointers=self._opaque_pointers)
+        module = Module.from_bitcode(context, self.bitcode(), self._name)
+        diff = diff_ir(ir, str(module))
+        if len(diff) > 0:
+            raise ValueError(
+                f"The IR and bitcode of module {self._name} differ:\n" + "\n".join(diff)
+            )
+
+    def llvm_module_ptr(self) -> Any:
+        """
+        Emits the module and wraps its ``LLVMModuleRef`` in a capsule named ``"LLVMModuleRef"``,
+        for running LLVM passes or linking with other modules through libraries that call the LLVM
+        C API directly. See ``examples/llvmlite_interop.py`` for an example using llvmlite.
+
+        The capsule wraps a copy of the module as emitted by :meth:`ir`, so changes made through
+        the pointer don't affect this simple module. The pointer is invalidated when the capsule is
+        destroyed, and it must not be disposed of through the LLVM API. The library using the
+        pointer must be linked against the same LLVM library as PyQIR.
+
+        :returns: The capsule.
+        """
+        ending = self._add_ending()
+        try:
+            module = self._emitted_module()
+            if module is self._module:
+                module = copy.copy(module)
+        finally:
+            _erase(ending)
+        return module.llvm_module_ptr()
+
+    def _add_ending(self) -> List[Instruction]:
+        """
+        Adds the instructions that end the entry point only while it's emitted: the output
+        recording, if it's enabled, and the return. Returns them in order.
+        """
+
+        self._check_thread()
+        count = 1
+        if self._result_output:
+            label = Constant.null(PointerType(IntType(self.context, 8)))
+            num_results = pyqir.const(IntType(self.context, 64), self._num_results)
+            rt.array_record_output(self._builder, num_results, label)
+            for result in self.results:
+                rt.result_record_output(self._builder, result, label)
+            count += 1 + self._num_results
+
+        ret = self._builder.ret(None)
+        block = next(b for b in self._entry_point.basic_blocks if b.terminator == ret)
+        return block.instructions[-count:]
+
+    def _int_register(self, id: int) -> Value:
+        self._check_thread()
+        if not 0 <= id < len(self._int_registers):
+            raise ValueError(f"Integer register {id} is out of range.")
+        register = self._int_registers[id]
+        if register is None:
+            raise ValueError(f"Integer register {id} was removed by clear or optimize.")
+        return register
+
+    def _remove_int_registers(self) -> None:
+        # The allocas may have been erased, so their values must not be used again. The IDs stay
+        # taken, so that a removed register can't be mistaken for a new one.
+        self._int_registers = [None] * len(self._int_registers)
+
+    def _init_state(
+        self,
+        module: Module,
+        name: str,
+        num_qubits: int,
+        num_results: int,
+        entry_point: Function,
+        settings: Optional["SimpleModule"] = None,
+    ) -> None:
+        # Every field is set here, so that creating, copying and loading a module can't miss one.
+        # The settings are copied from another module if one is given, and are the defaults
+        # otherwise. The builder inserts at the end of the last block of the entry point.
+        self._module = module
+        self._builder = Builder(module.context)
+        self._num_qubits = num_qubits
+        self._num_results = num_results
+        self._name = name
+        self._thread = threading.get_ident()
+        self._entry_point = entry_point
+        self._entry_block = entry_point.basic_blocks[0]
+        self._builder.insert_at_end(entry_point.basic_blocks[-1])
+
+        self._opaque_pointers = False if settings is None else settings._opaque_pointers
+        self._batched_measurements = (
+            False if settings is None else settings._batched_measurements
+        )
+        self._strict_results = False if settings is None else settings._strict_results
+        self._pi_comments = False if settings is None else settings._pi_comments
+        self._result_output = False if settings is None else settings._result_output
+        self._qubit_names: Dict[str, int] = (
+            {} if settings is None else dict(settings._qubit_names)
+        )
+        self._compile_unit: Optional[Metadata] = (
+            None if settings is None else settings._compile_unit
+        )
+
+        # Integer registers are allocas in the entry block. A copy's are at the same positions as
+        # the original's, and a loaded module's are all of the allocas in its entry block.
+        instructions = self._entry_block.instructions
+        self._int_registers: List[Optional[Value]] = (
+            [i for i in instructions if i.opcode == Opcode.ALLOCA]
+            if settings is None
+            else [
+                None
+                if r is None
+                else instructions[settings._entry_block.instructions.index(r)]
+                for r in settings._int_registers
+            ]
+        )
+
+    def _check_thread(self) -> None:
+        if threading.get_ident() != self._thread:
+            raise RuntimeError(
+                f"Module {self._name} can only be used on the thread that created it."
+            )
+
+    def _emitted_module(self, verify: bool = True) -> Module:
+        if verify:
+            self._verify(self._module)
+        if self._strict_results:
+            _check_result_writes(self.instructions, set(), self._name)
+
+        module = self._module
+        if self._batched_measurements:
+            module = copy.copy(module)
+            entry_point = next(
+                f for f in module.functions if f.name == self._entry_point.name
+            )
+            pyqir.batch_measurements(entry_point)
+        if not self._opaque_pointers:
+            return module
+
+        # Pointer types can't be changed in place, so read the module into a new context where
+        # they're all opaque.
+        context = Context(opaque_pointers=True)
+        module = Module.from_bitcode(context, module.bitcode, self._name)
+        if verify:
+            self._verify(module)
+        return module
+
+    def _format(self, ir: str, options: Optional[EmitOptions]) -> str:
+        if self._pi_comments:
+            ir = _annotate_pi(ir)
+        return ir if options is None else format_ir(ir, options)
+
+    def _verify(self, module: Module) -> None:
+        error = module.verify()
+        if error is not None:
+            entry_point = next(
+                f for f in module.functions if f.name == self._entry_point.name
+            )
+            location = _error_location(entry_point, error)
+            at = "" if location is None else f" at {location}"
+            raise QirEmitError(
+                f"Module {self._name} with {_count(self._num_qubits, 'qubit')} and "
+                f"{_count(self._num_results, 'result')} is invalid{at}: {error.strip()}"
+            )
+
+
+def load_module(path: str, context: Optional[Context] = None) -> SimpleModule:
+    """
+    Loads a simple module from a file, which is read as bitcode if its extension is ``.bc`` and
+    as LLVM IR if its extension is ``.ll``.
+
+    The module must have exactly one entry point, with the number of qubits and results that it
+    requires, and the last basic block of the entry point must end with a return. The return is
+    removed so that the module can continue to be built, and is added back when it's emitted.
+
+    :param str path: The path to the file.
+    :param Optional[Context] context: The LLVM context.
+    :returns: The simple module.
+    """
+
+    name, extension = os.path.splitext(os.path.basename(path))
+    if context is None:
+        context = Context()
+    if extension == ".bc":
+        with open(path, "rb") as f:
+            module = Module.from_bitcode(context, f.read(), name)
+    elif extension == ".ll":
+        with open(path) as f:
+            module = Module.from_ir(context, f.read(), name)
+    else:
+        raise ValueError(
+            f"Unsupported file extension {extension!r}. "
+            "The supported extensions are '.bc' and '.ll'."
+        )
+
+    entry_points = [f for f in module.functions if pyqir.is_entry_point(f)]
+    if len(entry_points) != 1:
+        raise ValueError(
+            f"Module {name} has {len(entry_points)} entry points instead of one."
+        )
+    [entry_point] = entry_points
+    num_qubits = pyqir.required_num_qubits(entry_point)
+    num_results = pyqir.required_num_results(entry_point)
+    if num_qubits is None or num_results is None:
+        raise ValueError(
+            f"Entry point {entry_point.name} doesn't say how many qubits and results it "
+            "requires."
+        )
+    ret = entry_point.basic_blocks[-1].terminator
+    if ret is None or ret.opcode != Opcode.RET:
+        raise ValueError(
+            f"The last block of entry point {entry_point.name} doesn't end with a "
+            "return."
+        )
+    ret.erase()
+
+    simple = SimpleModule.__new__(SimpleModule)
+    simple._init_state(module, name, num_qubits, num_results, entry_point)
+    return simple
+
+
+def _check_result_writes(
+    ops: Sequence[Union[Operation, Conditional]], written: Set[int], name: str
+) -> Set[int]:
+    """
+    Raises if an ``mz`` writes into a result in ``written`` or one written by an earlier ``mz`` in
+    ``ops``. Returns the results that have definitely been written after the operations.
+    """
+
+    written = set(written)
+    for op in ops:
+        if isinstance(op, Conditional):
+            one = _check_result_writes(op.one, written, name)
+            zero = _check_result_writes(op.zero, written, name)
+            written = one & zero
+        elif op.name == "mz":
+            for id in op.results:
+                if id in written:
+                    raise QirEmitError(
+                        f"Result {id} is written twice in module {name}."
+                    )
+                written.add(id)
+    return written
+
+
+def _uses(op: Union[Operation, Conditional]) -> Tuple[Set[int], Set[int]]:
+    """The IDs of the qubits and results that an operation uses, including inside branches."""
+
+    if isinstance(op, Operation):
+        return set(op.qubits), set(op.results)
+
+    qubits: Set[int] = set()
+    results: Set[int] = set() if op.result is None else {op.result}
+    for inner in (*op.one, *op.zero):
+        inner_qubits, inner_results = _uses(inner)
+        qubits |= inner_qubits
+        results |= inner_results
+    return qubits, results
+
+
+def _annotate_pi(ir: str) -> str:
+    lines = []
+    for line in ir.split("\n"):
+        fractions = (_pi_fraction(_parse_double(m)) for m in _DOUBLE.findall(line))
+        comments = [f for f in fractions if f is not None]
+        if len(comments) > 0:
+            line += f"  ; {', '.join(comments)}"
+        lines.append(line)
+    return "\n".join(lines)
+
+
+def _parse_double(literal: str) -> float:
+    if literal.startswith("0x"):
+        # LLVM writes doubles that aren't exact in decimal as their bits in hexadecimal.
+        value: float = struct.unpack(">d", bytes.fromhex(literal[2:]))[0]
+        return value
+    return float(literal)
+
+
+def _count(n: int, noun: str) -> str:
+    """The number with the noun, which is plural unless the number is one, like ``1 qubit``."""
+    return f"{n} {noun}" if n == 1 else f"{n} {noun}s"
+
+
+def _pi_fraction(angle: float) -> Optional[str]:
+    """
+    The angle as a multiple of pi over a small denominator in lowest terms, like ``3*pi/4``, if
+    it is one.
+    """
+
+    if not math.isfinite(angle):
+        return None
+    for denominator in (1, 2, 3, 4, 6, 8, 12, 16):
+        numerator = round(angle * denominator / math.pi)
+        if numerator == 0 or abs(numerator * math.pi / denominator - angle) > 1e-12:
+            continue
+        sign = "-" if numerator < 0 else ""
+        fraction = "pi" if abs(numerator) == 1 else f"{abs(numerator)}*pi"
+        return sign + (fraction if denominator == 1 else f"{fraction}/{denominator}")
+    return None
+
+
+def _error_location(function: Function, error: str) -> Optional[str]:
+    """
+    Finds the instruction of the function that the verifier error is about, and describes it by
+    its index, counting every instruction in block order, and its operation and operands. The
+    verifier quotes the operands of the instruction before the instruction itself, so the last
+    quoted instruction is the one that is invalid.
+    """
+
+    instructions = [
+        (str(instruction).strip(), instruction)
+        for block in function.basic_blocks
+        for instruction in block.instructions
+    ]
+    for line in reversed(error.splitlines()):
+        for index, (text, instruction) in enumerate(instructions):
+            if text in line:
+                return f"instruction {index} ({_describe(instruction, text)})"
+    return None
+
+
+def _describe(instruction: Instruction, text: str) -> str:
+    if not isinstance(instruction, Call):
+        return text
+    args = ", ".join(map(_describe_arg, instruction.args))
+    return f"{operation_name(instruction)} {args}".rstrip()
+
+
+def _describe_arg(arg: Value) -> str:
+    if pyqir.is_qubit_type(arg.type):
+        id = pyqir.qubit_id(arg)
+        return "dynamic qubit" if id is None else f"qubit {id}"
+    if pyqir.is_result_type(arg.type):
+        id = pyqir.result_id(arg)
+        return "dynamic result" if id is None else f"result {id}"
+    if isinstance(arg, (FloatConstant, IntConstant)):
+        return str(arg.value)
+    return "value"
+
+
+def _erase(instructions: List[Instruction]) -> None:
+    for instruction in reversed(instructions):
+        instruction.erase()
+
+
+def _calls(instruction: Instruction, names: Set[str]) -> bool:
+    return isinstance(instruction, Call) and instruction.callee.name in names
+
+
+def _caller_frame() -> Optional[FrameType]:
+    frame = inspect.currentframe()
+    # Skip this function and the SimpleModule method that called it.
+    for _ in range(2):
+        frame = None if frame is None else frame.f_back
+    return frame
@@ -0,0 +1,115 @@
+# Copyright (c) Microsoft Corporation.
+# Licensed under the MIT License.
+
+from dataclasses import dataclass
+from typing import List, Set
+
+import pyqir
+from pyqir import Call, Context, Function, Module, Opcode
+
+_MEASUREMENTS = {"__quantum__qis__m__body", "__quantum__qis__mz__body"}
+
+_RESETS = {"__quantum__qis__reset__body"}
+
+_RECORD_OUTPUT = "__quantum__rt__result_record_output"
+
+
+@dataclass(frozen=True)
+class Violation:
+    """A place where a module breaks a rule of a QIR profile."""
+
+    index: int
+    """
+    The index of the offending instruction, counting every instruction in the entry point in
+    block order.
+    """
+
+    rule: str
+    """
+    The rule that is broken: ``"gate_set"``, ``"use_after_measure"``, ``"branching"`` or
+    ``"result_use"``.
+    """
+
+    message: str
+    """A description of the problem."""
+
+
+def validate(function: Function, profile: str) -> List[Violation]:
+    """
+    Checks the entry point against the rules of the profile. Only the base profile is supported,
+    which allows only its QIS gates and measurements, no use of a qubit after it's measured, no
+    conditional branches, and no use of a measured result other than recording it as output.
+    """
+
+    if profile != "base_profile":
+        raise ValueError(
+            f"Unsupported profile {profile!r}. The only supported profile is 'base_profile'."
+        )
+
+    allowed = _qis_functions(profile)
+    violations: List[Violation] = []
+    measured: Set[int] = set()
+    index = 0
+    for block in function.basic_blocks:
+        for instruction in block.instructions:
+            if instruction.opcode == Opcode.SWITCH or (
+                instruction.opcode == Opcode.BR and len(instruction.operands) > 1
+            ):
+                violations.append(
+                    Violation(
+                        index,
+                        "branching",
+                        "Conditional branches aren't allowed in the base profile.",
+                    )
+                )
+            if isinstance(instruction, Call):
+                violations.extend(_check_call(instruction, index, allowed, measured))
+            index += 1
+    return violations
+
+
+def _check_call(
+    call: Call, index: int, allowed: Set[str], measured: Set[int]
+) -> List[Violation]:
+    name = call.callee.name
+    violations: List[Violation] = []
+    if name.startswith("__quantum__qis__") and name not in allowed:
+        violations.append(
+            Violation(index, "gate_set", f"{name} isn't in the base profile.")
+        )
+
+    qubits = [id for id in map(pyqir.qubit_id, call.args) if id is not None]
+    if name in _MEASUREMENTS:
+        measured.update(qubits)
+    elif name in _RESETS:
+        measured.difference_update(qubits)
+    else:
+        for id in qubits:
+            if id in measured:
+                violations.append(
+                    Violation(
+                        index,
+                        "use_after_measure",
+                        f"Qubit {id} is used by {name} after it is measured.",
+                    )
+                )
+
+    if name not in _MEASUREMENTS and name != _RECORD_OUTPUT:
+        for arg in call.args:
+            if pyqir.is_result_type(arg.type):
+                id = pyqir.result_id(arg)
+                result = "A result" if id is None else f"Result {id}"
+                violations.append(
+                    Violation(
+                        index,
+                        "result_use",
+                        f"{result} is used by {name}, but results can only be recorded "
+                        "as output.",
+                    )
+                )
+    return violations
+
+
+def _qis_functions(profile: str) -> Set[str]:
+    module = Module.from_ir(Context(), pyqir.qis_declarations(profile))
+    return {f.name for f in module.functions}
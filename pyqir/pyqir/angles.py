@@ -0,0 +1,12 @@
+# Copyright (c) Microsoft Corporation.
+# Licensed under the MIT License.
+
+"""
+Common rotation angles, in radians, for gates like :func:`pyqir.qis.rx`. Each constant is the
+double closest to its exact value, so it is written to the IR as the same ``double`` literal that
+the equivalent expression with :data:`math.pi` gives.
+"""
+
+from pyqir._native import PI, PI_OVER_2, PI_OVER_4, TAU, normalize_angle
+
+__all__ = ["PI", "PI_OVER_2", "PI_OVER_4", "TAU", "normalize_angle"]
@@ -0,0 +1,194 @@
+# Copyright (c) Microsoft Corporation.
+# Licensed under the MIT License.
+
+"""
+A read-only summary of an existing QIR module, for tools that count gates, check programs or
+convert them to circuits. Every function is copied into plain data when the module is read, so
+the summary doesn't depend on the LLVM module afterwards. Use :class:`pyqir.Module` to inspect
+or change anything that isn't summarized.
+"""
+
+import os
+from dataclasses import dataclass
+from typing import Optional, Tuple
+
+import pyqir
+from pyqir import (
+    BasicBlock,
+    Call,
+    Constant,
+    Context,
+    Function,
+    Instruction,
+    Module,
+    Value,
+)
+from pyqir._operations import operation_name
+
+
+@dataclass(frozen=True)
+class QirInstruction:
+    """An instruction in a function."""
+
+    name: str
+    """
+    The name of the operation. Calls are named like :attr:`pyqir.Operation.name`, so a QIS call
+    is named after the :mod:`pyqir.qis` function that builds it, like ``"cx"``, and other calls
+    are named after the function that they call. Other instructions are named after their opcode,
+    like ``"br"``.
+    """
+
+    operands: Tuple[str, ...]
+    """
+    The operands as written in the IR, without the callee of a call. Constants include their
+    type, like ``"%Qubit* null"``, instructions are referred to by name, like ``"%0"``, and basic
+    blocks are referred to by label, like ``"label %then"``.
+    """
+
+    text: str
+    """The instruction as written in the IR."""
+
+
+@dataclass(frozen=True)
+class QirFunction:
+    """A function in a module."""
+
+    name: str
+    """The name of the function."""
+
+    is_entry_point: bool
+    """Whether the function is an entry point."""
+
+    required_num_qubits: Optional[int]
+    """The number of qubits that an entry point requires, if it's known."""
+
+    required_num_results: Optional[int]
+    """The number of results that an entry point requires, if it's known."""
+
+    instructions: Tuple[QirInstruction, ...]
+    """
+    The instructions in every basic block, in block order. A declaration has no instructions.
+    """
+
+
+class QirModule:
+    """A read-only summary of a QIR module."""
+
+    def __init__(self, module: Module) -> None:
+        """
+        Summarizes a module.
+
+        :param Module module: The module.
+        """
+        self._name = module.source_filename
+        self._functions = tuple(_function(f) for f in module.functions)
+
+    @staticmethod
+    def from_ir(ir: str, name: str = "") -> "QirModule":
+        """
+        Reads a module from LLVM IR.
+
+        :param str ir: The LLVM IR.
+        :param str name: The name of the module.
+        :returns: The module.
+        """
+        return QirModule(Module.from_ir(Context(), ir, name))
+
+    @staticmethod
+    def from_bitcode(bitcode: bytes, name: str = "") -> "QirModule":
+        """
+        Reads a module from LLVM bitcode.
+
+        :param bytes bitcode: The LLVM bitcode.
+        :param str name: The name of the module.
+        :returns: The module.
+        """
+        return QirModule(Module.from_bitcode(Context(), bitcode, name))
+
+    @staticmethod
+    def from_file(path: str) -> "QirModule":
+        """
+        Reads a module from a file, which is read as bitcode if its extension is ``.bc`` and as
+        LLVM IR if its extension is ``.ll``. The module is named after the file.
+
+        :param str path: The path to the file.
+        :returns: The module.
+        """
+        name, extension = os.path.splitext(os.path.basename(path))
+        if extension == ".bc":
+            with open(path, "rb") as f:
+                return QirModule.from_bitcode(f.read(), name)
+        if extension == ".ll":
+            with open(path) as f:
+                return QirModule.from_ir(f.read(), name)
+        raise ValueError(
+            f"Unsupported file extension {extension!r}. "
+            "The supported extensions are '.bc' and '.ll'."
+        )
+
+    @property
+    def name(self) -> str:
+        """The source file name of the module."""
+        return self._name
+
+    @property
+    def functions(self) -> Tuple[QirFunction, ...]:
+        """Every function in the module, including declarations, in order."""
+        return self._functions
+
+    @property
+    def entry_points(self) -> Tuple[QirFunction, ...]:
+        """The entry points of the module, in order."""
+        return tuple(f for f in self._functions if f.is_entry_point)
+
+    def get_function(self, name: str) -> QirFunction:
+        """
+        Finds a function by name. A :class:`KeyError` is raised if there's no such function.
+
+        :param str name: The name of the function.
+        :returns: The function.
+        """
+        function = next((f for f in self._functions if f.name == name), None)
+        if function is None:
+            raise KeyError(f"Module {self._name} has no function named {name!r}.")
+        return function
+
+
+def _function(function: Function) -> QirFunction:
+    is_entry_point = pyqir.is_entry_point(function)
+    return QirFunction(
+        function.name,
+        is_entry_point,
+        pyqir.required_num_qubits(function) if is_entry_point else None,
+        pyqir.required_num_results(function) if is_entry_point else None,
+        tuple(
+            _instruction(instruction)
+            for block in function.basic_blocks
+            for instruction in block.instructions
+        ),
+    )
+
+
+def _instruction(instruction: Instruction) -> QirInstruction:
+    if isinstance(instruction, Call):
+        name = operation_name(instruction)
+        operands = instruction.args
+    else:
+        name = str(instruction.opcode).rsplit(".", 1)[-1].lower()
+        operands = instruction.operands
+    return QirInstruction(
+        name, tuple(map(_operand, operands)), str(instruction).strip()
+    )
+
+
+def _operand(value: Value) -> str:
+    if isinstance(value, BasicBlock):
+        return f"label %{value.name}"
+    if isinstance(value, Function):
+        return f"@{value.name}"
+    if isinstance(value, Instruction):
+        # An instruction is written with its name, like "%0 = ...".
+        return str(value).strip().split(" = ", 1)[0]
+    if isinstance(value, Constant):
+        return str(value)
+    return str(value).strip()
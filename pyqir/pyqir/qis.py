@@ -4,10 +4,17 @@
 from pyqir._native import (
     barrier,
     ccx,
+    ch,
     cx,
+    cy,
     cz,
+    depolarize,
+    extend,
     h,
+    id,
+    m,
     mz,
+    read_result,
     reset,
     rx,
     ry,
@@ -17,6 +24,8 @@ from pyqir._native import (
     swap,
     t,
     t_adj,
+    u1,
+    u2,
     x,
     y,
     z,
@@ -26,10 +35,17 @@ from pyqir._native import (
 __all__ = [
     "barrier",
     "ccx",
+    "ch",
     "cx",
+    "cy",
     "cz",
+    "depolarize",
+    "extend",
     "h",
+    "id",
+    "m",
     "mz",
+    "read_result",
     "reset",
     "rx",
     "ry",
@@ -39,6 +55,8 @@ __all__ = [
     "swap",
     "t",
     "t_adj",
+    "u1",
+    "u2",
     "x",
     "y",
     "z",
@@ -4,6 +4,7 @@
 from pyqir._native import (
     array_record_output,
     initialize,
+    int_record_output,
     result_record_output,
     tuple_record_output,
 )
@@ -11,6 +12,7 @@ from pyqir._native import (
 __all__ = [
     "array_record_output",
     "initialize",
+    "int_record_output",
     "result_record_output",
     "tuple_record_output",
 ]
@@ -0,0 +1,35 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use crate::values::Function;
+use pyo3::{exceptions::PyValueError, prelude::*, types::PyComplex};
+use qirlib::analysis;
+
+/// Computes the unitary matrix of the circuit in the function by multiplying the matrices of its
+/// gates in order. Bit ``i`` of a row or column index is the state of qubit ``i``, so qubit 0 is
+/// the least significant.
+///
+/// The circuit has the function's required number of qubits, which may be at most 10. The function
+/// must have at most one basic block, use only static qubits and constant rotation angles, and may
+/// not measure or reset qubits. Otherwise a :class:`ValueError` is raised.
+///
+/// :param Function function: The function.
+/// :returns: The rows of the matrix.
+/// :rtype: typing.List[typing.List[complex]]
+#[pyfunction]
+#[pyo3(text_signature = "(function)")]
+pub(crate) fn to_matrix(
+    py: Python,
+    function: PyRef<Function>,
+) -> PyResult<Vec<Vec<Py<PyComplex>>>> {
+    let matrix = unsafe { analysis::to_matrix(function.into_super().into_super().as_ptr()) }
+        .map_err(PyValueError::new_err)?;
+    Ok(matrix
+        .into_iter()
+        .map(|row| {
+            row.into_iter()
+                .map(|c| PyComplex::from_doubles(py, c.re, c.im).into())
+                .collect()
+        })
+        .collect())
+}
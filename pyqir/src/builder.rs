@@ -4,37 +4,68 @@
 use crate::{
     core::Context,
     instructions::IntPredicate,
+    metadata::Metadata,
     types::Type,
     values::{BasicBlock, Literal, Owner, Value},
 };
 use const_str::raw_cstr;
 #[allow(clippy::wildcard_imports)]
 use llvm_sys::{core::*, prelude::*, LLVMBuilder, LLVMType, LLVMTypeKind};
-use pyo3::{exceptions::PyValueError, prelude::*};
-use qirlib::builder::try_build_if;
+use pyo3::{
+    create_exception,
+    exceptions::{PyIndexError, PyRuntimeError, PyTypeError, PyValueError},
+    prelude::*,
+    types::PyDict,
+};
+use qirlib::{builder::try_build_if, debug, metadata, qis, rt, types, values};
 use std::{
+    cell::{Ref, RefCell},
+    collections::HashMap,
     convert::{Into, TryInto},
+    iter,
     ops::Deref,
-    ptr::NonNull,
+    ptr::{self, NonNull},
+    slice, str,
+    thread::{self, ThreadId},
 };
 
+create_exception!(
+    pyqir,
+    BuilderReentrancyError,
+    PyRuntimeError,
+    "The error raised when a branch body that the builder calls leaves it where the branch can't \
+    be finished: in another function, or after a terminator."
+);
+
 /// An instruction builder.
 ///
+/// A builder can only be used on the thread that created it. Using it on another thread raises a
+/// :class:`RuntimeError` before anything is built.
+///
 /// :param Context context: The LLVM context.
-#[pyclass(unsendable)]
+#[pyclass]
 pub(crate) struct Builder {
-    builder: NonNull<LLVMBuilder>,
-    owner: Owner,
+    raw: NonNull<LLVMBuilder>,
+    // Only borrowed for the length of a statement, so that a Python callback can move the builder
+    // while a method that called it is still running.
+    owner: RefCell<Owner>,
+    thread: ThreadId,
 }
 
+// SAFETY: Every method and function that uses the builder takes it as a `BuilderRef`, which checks
+// that it's on the thread that created it. On another thread, the builder can only be dropped,
+// which happens while the GIL is held.
+unsafe impl Send for Builder {}
+
 #[pymethods]
 impl Builder {
     #[new]
     pub(crate) fn new(py: Python, context: Py<Context>) -> Self {
         let builder = unsafe { LLVMCreateBuilderInContext(context.borrow(py).as_ptr()) };
         Self {
-            builder: NonNull::new(builder).unwrap(),
-            owner: context.into(),
+            raw: NonNull::new(builder).unwrap(),
+            owner: RefCell::new(context.into()),
+            thread: thread::current().id(),
         }
     }
 
@@ -43,21 +74,260 @@ impl Builder {
     /// :param BasicBlock block: The block to insert into.
     /// :rtype: None
     #[pyo3(text_signature = "(block)")]
-    fn insert_at_end(&mut self, py: Python, block: PyRef<BasicBlock>) -> PyResult<()> {
+    fn insert_at_end(slf: BuilderRef, py: Python, block: PyRef<BasicBlock>) -> PyResult<()> {
         let owner = block.as_ref().owner();
-        if *owner.context(py).borrow(py) != *self.owner.context(py).borrow(py) {
+        if *owner.context(py).borrow(py) != *slf.owner.borrow().context(py).borrow(py) {
             Err(PyValueError::new_err(
                 "Block is not from the same context as builder.",
             ))?;
         }
 
-        self.owner = owner.clone_ref(py);
+        *slf.owner.borrow_mut() = owner.clone_ref(py);
+        unsafe {
+            LLVMPositionBuilderAtEnd(slf.as_ptr(), block.as_ptr());
+        }
+        Ok(())
+    }
+
+    /// Tells this builder to insert subsequent instructions before the instruction.
+    ///
+    /// :param Instruction instruction: The instruction to insert before.
+    /// :rtype: None
+    #[pyo3(text_signature = "(instruction)")]
+    fn insert_before(slf: BuilderRef, py: Python, instruction: &Value) -> PyResult<()> {
+        let owner = instruction.owner();
+        if *owner.context(py).borrow(py) != *slf.owner.borrow().context(py).borrow(py) {
+            Err(PyValueError::new_err(
+                "Instruction is not from the same context as builder.",
+            ))?;
+        }
+        if unsafe { LLVMIsAInstruction(instruction.as_ptr()) }.is_null() {
+            return Err(PyValueError::new_err("Value is not an instruction."));
+        }
+
+        *slf.owner.borrow_mut() = owner.clone_ref(py);
         unsafe {
-            LLVMPositionBuilderAtEnd(self.as_ptr(), block.as_ptr());
+            LLVMPositionBuilderBefore(slf.as_ptr(), instruction.as_ptr());
         }
         Ok(())
     }
 
+    /// Attaches a source location to the instructions that this builder inserts from now on.
+    ///
+    /// The location is scoped to the function that the builder is inserting into, which must have a
+    /// debug info subprogram. Use :func:`add_compile_unit` to add one.
+    ///
+    /// :param int line: The line number.
+    /// :param int column: The column number.
+    /// :rtype: None
+    #[pyo3(text_signature = "(self, line, column=0)")]
+    fn set_debug_location(slf: BuilderRef, line: u32, column: Option<u32>) -> PyResult<()> {
+        unsafe {
+            let function = NonNull::new(LLVMGetInsertBlock(slf.as_ptr()))
+                .map(|b| LLVMGetBasicBlockParent(b.as_ptr()))
+                .ok_or_else(|| PyValueError::new_err("The builder's position has not been set."))?;
+            let scope = debug::subprogram(function).ok_or_else(|| {
+                PyValueError::new_err("The builder's function has no debug info subprogram.")
+            })?;
+            debug::set_debug_location(slf.as_ptr(), line, column.unwrap_or(0), scope);
+        }
+        Ok(())
+    }
+
+    /// Stops attaching a source location to the instructions that this builder inserts.
+    ///
+    /// :rtype: None
+    fn clear_debug_location(slf: BuilderRef) {
+        unsafe {
+            debug::clear_debug_location(slf.as_ptr());
+        }
+    }
+
+    /// Attaches key-value annotations to an instruction in the function that this builder is
+    /// inserting into. The annotations are emitted as a ``!qir.annotations`` metadata node on the
+    /// instruction. An annotation replaces any existing annotation with the same key.
+    ///
+    /// :param int inst_index: The index of the instruction, counting every instruction in the
+    ///     function in block order.
+    /// :param typing.Dict[str, typing.Union[bool, int, float, str]] metadata: The annotations.
+    /// :rtype: None
+    #[pyo3(text_signature = "(self, inst_index, metadata)")]
+    #[allow(clippy::doc_markdown)]
+    fn annotate(slf: BuilderRef, inst_index: usize, metadata: &PyDict) -> PyResult<()> {
+        let annotations = metadata
+            .iter()
+            .map(|(key, value)| {
+                let value = value.extract::<Annotation>().map_err(|_| {
+                    PyTypeError::new_err("Annotation values must be bool, int, float, or str.")
+                })?;
+                Ok((key.extract::<String>()?, value))
+            })
+            .collect::<PyResult<Vec<_>>>()?;
+
+        unsafe {
+            let instruction = slf.instruction(inst_index)?;
+            let context = LLVMGetTypeContext(LLVMTypeOf(instruction));
+            let annotations: Vec<_> = annotations
+                .iter()
+                .map(|(key, value)| (key.as_str(), value.to_metadata(context)))
+                .collect();
+            metadata::annotate(instruction, &annotations);
+        }
+        Ok(())
+    }
+
+    /// Gets the key-value annotations on an instruction in the function that this builder is
+    /// inserting into.
+    ///
+    /// :param int inst_index: The index of the instruction, counting every instruction in the
+    ///     function in block order.
+    /// :returns: The annotations. Values that aren't a bool, int, float, or string are returned as
+    ///     metadata.
+    /// :rtype: typing.Dict[str, typing.Union[bool, int, float, str, Metadata]]
+    #[pyo3(text_signature = "(self, inst_index)")]
+    #[allow(clippy::doc_markdown)]
+    fn get_annotation<'py>(
+        slf: BuilderRef,
+        py: Python<'py>,
+        inst_index: usize,
+    ) -> PyResult<&'py PyDict> {
+        let dict = PyDict::new(py);
+        unsafe {
+            let instruction = slf.instruction(inst_index)?;
+            let context = LLVMGetTypeContext(LLVMTypeOf(instruction));
+            for (key, value) in metadata::annotations(instruction) {
+                let value = match Annotation::from_metadata(context, value) {
+                    Some(annotation) => annotation.into_py(py),
+                    None => Metadata::from_raw(py, slf.owner.borrow().clone_ref(py), value)?,
+                };
+                dict.set_item(key, value)?;
+            }
+        }
+        Ok(dict)
+    }
+
+    /// Replaces a call to a QIS gate with the controlled form of the gate. Gates without a
+    /// controlled intrinsic, like :math:`H` and :math:`S`, are decomposed into gates that have one,
+    /// up to a global phase.
+    ///
+    /// The controlled form is inserted where the call was, and the call is removed. Calls to
+    /// :math:`X`, :math:`Y`, :math:`Z`, :math:`H`, :math:`S`, :math:`T` and their adjoints, the
+    /// rotations, and CNOT can be controlled.
+    ///
+    /// By default, the gate applies when the control qubit is one. With a control state of zero,
+    /// the gate applies when the control qubit is zero instead, and the controlled form is wrapped
+    /// in :math:`X` gates on the control.
+    ///
+    /// :param Call gate_instruction: The call to the gate.
+    /// :param Value control: The control qubit.
+    /// :param int control_state: The state of the control qubit that the gate applies in, 0 or 1.
+    /// :rtype: None
+    #[pyo3(text_signature = "(self, gate_instruction, control, control_state=1)")]
+    #[allow(clippy::doc_markdown)]
+    fn controlled(
+        slf: BuilderRef,
+        py: Python,
+        gate_instruction: &Value,
+        control: &Value,
+        control_state: Option<u8>,
+    ) -> PyResult<()> {
+        Owner::merge(
+            py,
+            [
+                &*slf.owner.borrow(),
+                gate_instruction.owner(),
+                control.owner(),
+            ],
+        )?;
+        let control_state = match control_state.unwrap_or(1) {
+            0 => false,
+            1 => true,
+            state => {
+                return Err(PyValueError::new_err(format!(
+                    "Control state must be 0 or 1, not {state}."
+                )))
+            }
+        };
+        let call = gate_instruction.as_ptr();
+        unsafe {
+            if LLVMIsACallInst(call).is_null() {
+                return Err(PyValueError::new_err("Instruction is not a call."));
+            }
+
+            // Positioning the builder before the call also takes the call's debug location, so
+            // both are restored afterwards.
+            let block = LLVMGetInsertBlock(slf.as_ptr());
+            let location = LLVMGetCurrentDebugLocation2(slf.as_ptr());
+            LLVMPositionBuilderBefore(slf.as_ptr(), call);
+            let built = qis::build_controlled(slf.as_ptr(), call, control.as_ptr(), control_state);
+            if block.is_null() {
+                LLVMClearInsertionPosition(slf.as_ptr());
+            } else {
+                LLVMPositionBuilderAtEnd(slf.as_ptr(), block);
+            }
+            LLVMSetCurrentDebugLocation2(slf.as_ptr(), location);
+
+            if built {
+                LLVMInstructionEraseFromParent(call);
+                Ok(())
+            } else {
+                let mut len = 0;
+                let name = LLVMGetValueName2(LLVMGetCalledValue(call), ptr::addr_of_mut!(len));
+                let name = str::from_utf8(slice::from_raw_parts(name.cast(), len)).unwrap();
+                Err(PyValueError::new_err(format!(
+                    "Gate {name} has no controlled form."
+                )))
+            }
+        }
+    }
+
+    /// Copies the body of a function to this builder's position, replacing each static qubit and
+    /// result with the one that its ID maps to. The function may be in another module, but must be
+    /// in the same context, and must not return.
+    ///
+    /// The function's first basic block is copied to this builder's position, and its other blocks
+    /// are appended to this builder's function. If the function branches, the position must be at
+    /// the end of a block, or a :class:`ValueError` is raised. Afterwards, this builder inserts
+    /// after the copied instructions.
+    ///
+    /// :param Function function: The function to copy.
+    /// :param typing.Dict[int, int] qubit_map: The new ID of each qubit in the function.
+    /// :param typing.Dict[int, int] result_map: The new ID of each result in the function.
+    /// :rtype: None
+    #[pyo3(text_signature = "(self, function, qubit_map, result_map)")]
+    #[allow(clippy::doc_markdown)]
+    fn append(
+        slf: BuilderRef,
+        py: Python,
+        function: &Value,
+        qubit_map: HashMap<u64, u64>,
+        result_map: HashMap<u64, u64>,
+    ) -> PyResult<()> {
+        if *slf.owner.borrow().context(py).borrow(py) != *function.owner().context(py).borrow(py) {
+            return Err(PyValueError::new_err(
+                "Some values are from different contexts or modules.",
+            ));
+        }
+
+        unsafe {
+            if LLVMIsAFunction(function.as_ptr()).is_null() {
+                return Err(PyValueError::new_err("Value is not a function."));
+            }
+            if LLVMGetInsertBlock(slf.as_ptr()).is_null() {
+                return Err(PyValueError::new_err(
+                    "The builder's position has not been set.",
+                ));
+            }
+            qirlib::builder::append_function(
+                slf.as_ptr(),
+                function.as_ptr(),
+                &qubit_map,
+                &result_map,
+            )
+            .map_err(PyValueError::new_err)
+        }
+    }
+
     /// Inserts a bitwise logical and instruction.
     ///
     /// :param Value lhs: The left-hand side.
@@ -65,10 +335,10 @@ impl Builder {
     /// :returns: The result.
     /// :rtype: Value
     #[pyo3(text_signature = "(self, lhs, rhs)")]
-    fn and_(&self, py: Python, lhs: &Value, rhs: &Value) -> PyResult<PyObject> {
-        let owner = Owner::merge(py, [&self.owner, lhs.owner(), rhs.owner()])?;
+    fn and_(slf: BuilderRef, py: Python, lhs: &Value, rhs: &Value) -> PyResult<PyObject> {
+        let owner = Owner::merge(py, [&*slf.owner.borrow(), lhs.owner(), rhs.owner()])?;
         unsafe {
-            let value = LLVMBuildAnd(self.as_ptr(), lhs.as_ptr(), rhs.as_ptr(), raw_cstr!(""));
+            let value = LLVMBuildAnd(slf.as_ptr(), lhs.as_ptr(), rhs.as_ptr(), raw_cstr!(""));
             Value::from_raw(py, owner, value)
         }
     }
@@ -80,10 +350,10 @@ impl Builder {
     /// :returns: The result.
     /// :rtype: Value
     #[pyo3(text_signature = "(self, lhs, rhs)")]
-    fn or_(&self, py: Python, lhs: &Value, rhs: &Value) -> PyResult<PyObject> {
-        let owner = Owner::merge(py, [&self.owner, lhs.owner(), rhs.owner()])?;
+    fn or_(slf: BuilderRef, py: Python, lhs: &Value, rhs: &Value) -> PyResult<PyObject> {
+        let owner = Owner::merge(py, [&*slf.owner.borrow(), lhs.owner(), rhs.owner()])?;
         unsafe {
-            let value = LLVMBuildOr(self.as_ptr(), lhs.as_ptr(), rhs.as_ptr(), raw_cstr!(""));
+            let value = LLVMBuildOr(slf.as_ptr(), lhs.as_ptr(), rhs.as_ptr(), raw_cstr!(""));
             Value::from_raw(py, owner, value)
         }
     }
@@ -95,10 +365,10 @@ impl Builder {
     /// :returns: The result.
     /// :rtype: Value
     #[pyo3(text_signature = "(self, lhs, rhs)")]
-    fn xor(&self, py: Python, lhs: &Value, rhs: &Value) -> PyResult<PyObject> {
-        let owner = Owner::merge(py, [&self.owner, lhs.owner(), rhs.owner()])?;
+    fn xor(slf: BuilderRef, py: Python, lhs: &Value, rhs: &Value) -> PyResult<PyObject> {
+        let owner = Owner::merge(py, [&*slf.owner.borrow(), lhs.owner(), rhs.owner()])?;
         unsafe {
-            let value = LLVMBuildXor(self.as_ptr(), lhs.as_ptr(), rhs.as_ptr(), raw_cstr!(""));
+            let value = LLVMBuildXor(slf.as_ptr(), lhs.as_ptr(), rhs.as_ptr(), raw_cstr!(""));
             Value::from_raw(py, owner, value)
         }
     }
@@ -110,10 +380,10 @@ impl Builder {
     /// :returns: The sum.
     /// :rtype: Value
     #[pyo3(text_signature = "(self, lhs, rhs)")]
-    fn add(&self, py: Python, lhs: &Value, rhs: &Value) -> PyResult<PyObject> {
-        let owner = Owner::merge(py, [&self.owner, lhs.owner(), rhs.owner()])?;
+    fn add(slf: BuilderRef, py: Python, lhs: &Value, rhs: &Value) -> PyResult<PyObject> {
+        let owner = Owner::merge(py, [&*slf.owner.borrow(), lhs.owner(), rhs.owner()])?;
         unsafe {
-            let value = LLVMBuildAdd(self.as_ptr(), lhs.as_ptr(), rhs.as_ptr(), raw_cstr!(""));
+            let value = LLVMBuildAdd(slf.as_ptr(), lhs.as_ptr(), rhs.as_ptr(), raw_cstr!(""));
             Value::from_raw(py, owner, value)
         }
     }
@@ -125,10 +395,10 @@ impl Builder {
     /// :returns: The difference.
     /// :rtype: Value
     #[pyo3(text_signature = "(self, lhs, rhs)")]
-    fn sub(&self, py: Python, lhs: &Value, rhs: &Value) -> PyResult<PyObject> {
-        let owner = Owner::merge(py, [&self.owner, lhs.owner(), rhs.owner()])?;
+    fn sub(slf: BuilderRef, py: Python, lhs: &Value, rhs: &Value) -> PyResult<PyObject> {
+        let owner = Owner::merge(py, [&*slf.owner.borrow(), lhs.owner(), rhs.owner()])?;
         unsafe {
-            let value = LLVMBuildSub(self.as_ptr(), lhs.as_ptr(), rhs.as_ptr(), raw_cstr!(""));
+            let value = LLVMBuildSub(slf.as_ptr(), lhs.as_ptr(), rhs.as_ptr(), raw_cstr!(""));
             Value::from_raw(py, owner, value)
         }
     }
@@ -140,10 +410,10 @@ impl Builder {
     /// :returns: The product.
     /// :rtype: Value
     #[pyo3(text_signature = "(self, lhs, rhs)")]
-    fn mul(&self, py: Python, lhs: &Value, rhs: &Value) -> PyResult<PyObject> {
-        let owner = Owner::merge(py, [&self.owner, lhs.owner(), rhs.owner()])?;
+    fn mul(slf: BuilderRef, py: Python, lhs: &Value, rhs: &Value) -> PyResult<PyObject> {
+        let owner = Owner::merge(py, [&*slf.owner.borrow(), lhs.owner(), rhs.owner()])?;
         unsafe {
-            let value = LLVMBuildMul(self.as_ptr(), lhs.as_ptr(), rhs.as_ptr(), raw_cstr!(""));
+            let value = LLVMBuildMul(slf.as_ptr(), lhs.as_ptr(), rhs.as_ptr(), raw_cstr!(""));
             Value::from_raw(py, owner, value)
         }
     }
@@ -155,10 +425,10 @@ impl Builder {
     /// :returns: The result.
     /// :rtype: Value
     #[pyo3(text_signature = "(self, lhs, rhs)")]
-    fn shl(&self, py: Python, lhs: &Value, rhs: &Value) -> PyResult<PyObject> {
-        let owner = Owner::merge(py, [&self.owner, lhs.owner(), rhs.owner()])?;
+    fn shl(slf: BuilderRef, py: Python, lhs: &Value, rhs: &Value) -> PyResult<PyObject> {
+        let owner = Owner::merge(py, [&*slf.owner.borrow(), lhs.owner(), rhs.owner()])?;
         unsafe {
-            let value = LLVMBuildShl(self.as_ptr(), lhs.as_ptr(), rhs.as_ptr(), raw_cstr!(""));
+            let value = LLVMBuildShl(slf.as_ptr(), lhs.as_ptr(), rhs.as_ptr(), raw_cstr!(""));
             Value::from_raw(py, owner, value)
         }
     }
@@ -170,10 +440,10 @@ impl Builder {
     /// :returns: The result.
     /// :rtype: Value
     #[pyo3(text_signature = "(self, lhs, rhs)")]
-    fn lshr(&self, py: Python, lhs: &Value, rhs: &Value) -> PyResult<PyObject> {
-        let owner = Owner::merge(py, [&self.owner, lhs.owner(), rhs.owner()])?;
+    fn lshr(slf: BuilderRef, py: Python, lhs: &Value, rhs: &Value) -> PyResult<PyObject> {
+        let owner = Owner::merge(py, [&*slf.owner.borrow(), lhs.owner(), rhs.owner()])?;
         unsafe {
-            let value = LLVMBuildLShr(self.as_ptr(), lhs.as_ptr(), rhs.as_ptr(), raw_cstr!(""));
+            let value = LLVMBuildLShr(slf.as_ptr(), lhs.as_ptr(), rhs.as_ptr(), raw_cstr!(""));
             Value::from_raw(py, owner, value)
         }
     }
@@ -186,11 +456,17 @@ impl Builder {
     /// :returns: The boolean result.
     /// :rtype: Value
     #[pyo3(text_signature = "(self, pred, lhs, rhs)")]
-    fn icmp(&self, py: Python, pred: IntPredicate, lhs: &Value, rhs: &Value) -> PyResult<PyObject> {
-        let owner = Owner::merge(py, [&self.owner, lhs.owner(), rhs.owner()])?;
+    fn icmp(
+        slf: BuilderRef,
+        py: Python,
+        pred: IntPredicate,
+        lhs: &Value,
+        rhs: &Value,
+    ) -> PyResult<PyObject> {
+        let owner = Owner::merge(py, [&*slf.owner.borrow(), lhs.owner(), rhs.owner()])?;
         unsafe {
             let value = LLVMBuildICmp(
-                self.as_ptr(),
+                slf.as_ptr(),
                 pred.into(),
                 lhs.as_ptr(),
                 rhs.as_ptr(),
@@ -202,15 +478,24 @@ impl Builder {
 
     /// Inserts a call instruction.
     ///
+    /// An argument that is a sequence of qubits is passed as a new QIR array that holds the qubits
+    /// in order. The parameter must have the QIR array type. The array is created before the call
+    /// and released after it.
+    ///
     /// :param Value value: The value to call.
-    /// :param typing.Sequence[typing.Union[Value, bool, int, float]] args:
+    /// :param typing.Sequence[typing.Union[Value, typing.Sequence[Value], bool, int, float]] args:
     ///     The arguments to the function.
     /// :returns: The return value, or None if the function has a void return type.
     /// :rtype: Value
     #[pyo3(text_signature = "(self, callee, args)")]
-    fn call(&self, py: Python, callee: &Value, args: Vec<Argument>) -> PyResult<PyObject> {
-        let arg_owners = args.iter().filter_map(Argument::owner);
-        let owner = Owner::merge(py, arg_owners.chain([&self.owner, callee.owner()]))?;
+    fn call(
+        slf: BuilderRef,
+        py: Python,
+        callee: &Value,
+        args: Vec<Argument>,
+    ) -> PyResult<PyObject> {
+        let arg_owners = args.iter().flat_map(Argument::owners);
+        let owner = Owner::merge(py, arg_owners.chain([&*slf.owner.borrow(), callee.owner()]))?;
 
         unsafe {
             let fn_type = callable_fn_type(callee.as_ptr())
@@ -229,20 +514,49 @@ impl Builder {
                 )))?;
             }
 
-            let mut args = args
+            // Convert every argument before building anything, so that an error leaves the block
+            // unchanged.
+            let values = args
                 .iter()
                 .zip(param_types)
-                .map(|(arg, ty)| arg.to_value(ty).map_err(Into::into))
+                .map(|(arg, ty)| arg.to_value(ty))
                 .collect::<PyResult<Vec<_>>>()?;
+            for arg in &args {
+                if let Argument::Qubits(qubits) = arg {
+                    let qubits = qubits.iter().map(|q| &**q).collect::<Vec<_>>();
+                    check_in_range(&slf, &qubits, &[])?;
+                }
+            }
+
+            let mut arrays = Vec::new();
+            let mut args = args
+                .iter()
+                .zip(values)
+                .map(|(arg, value)| match (arg, value) {
+                    (Argument::Qubits(qubits), None) => {
+                        let qubits = qubits.iter().map(|q| q.as_ptr()).collect::<Vec<_>>();
+                        let array = rt::build_qubit_array(slf.as_ptr(), &qubits);
+                        arrays.push(array);
+                        array
+                    }
+                    (_, value) => value.expect("Argument should have a value."),
+                })
+                .collect::<Vec<_>>();
 
             #[allow(deprecated)]
             let value = LLVMBuildCall(
-                self.as_ptr(),
+                slf.as_ptr(),
                 callee.as_ptr(),
                 args.as_mut_ptr(),
                 args.len().try_into().unwrap(),
                 raw_cstr!(""),
             );
+
+            let context = LLVMGetTypeContext(LLVMTypeOf(value));
+            let release = LLVMConstInt(LLVMInt32TypeInContext(context), u64::MAX, 1);
+            for array in arrays {
+                rt::build_array_update_reference_count(slf.as_ptr(), array, release);
+            }
             Value::from_raw(py, owner, value)
         }
     }
@@ -251,28 +565,36 @@ impl Builder {
     ///
     /// Instructions inserted when ``true`` is called will be inserted into the true branch.
     /// Instructions inserted when ``false`` is called will be inserted into the false branch. The
-    /// true and false callables should use this module's builder to build instructions.
+    /// true and false callables should use this module's builder to build instructions. A branch
+    /// that is ``None`` is left empty.
+    ///
+    /// The callables may use the builder in any way, including moving it, as long as they leave it
+    /// at the end of an unterminated block in the same function. Otherwise a
+    /// :class:`BuilderReentrancyError` is raised.
     ///
     /// :param Value cond: The boolean condition to branch on.
-    /// :param typing.Callable[[], None] true:
+    /// :param typing.Optional[typing.Callable[[], None]] true:
     ///     A callable that inserts instructions for the branch where the condition is true.
-    /// :param typing.Callable[[], None] false:
+    /// :param typing.Optional[typing.Callable[[], None]] false:
     ///     A callable that inserts instructions for the branch where the condition is false.
     #[pyo3(text_signature = "(self, cond, true, false)")]
     fn if_(
-        &self,
+        slf: BuilderRef,
         py: Python,
         cond: &Value,
         r#true: Option<&PyAny>,
         r#false: Option<&PyAny>,
     ) -> PyResult<()> {
-        Owner::merge(py, [&self.owner, cond.owner()])?;
+        check_branch("true", r#true)?;
+        check_branch("false", r#false)?;
+        Owner::merge(py, [&*slf.owner.borrow(), cond.owner()])?;
         unsafe {
             try_build_if(
-                self.as_ptr(),
+                slf.as_ptr(),
                 cond.as_ptr(),
-                || r#true.iter().try_for_each(|f| f.call0().map(|_| ())),
-                || r#false.iter().try_for_each(|f| f.call0().map(|_| ())),
+                None,
+                || build_branch(slf.as_ptr(), r#true),
+                || build_branch(slf.as_ptr(), r#false),
             )
         }
     }
@@ -283,10 +605,10 @@ impl Builder {
     /// :returns: The branch instruction.
     /// :rtype: Instruction
     #[pyo3(text_signature = "(dest)")]
-    fn br(&self, py: Python, dest: PyRef<BasicBlock>) -> PyResult<PyObject> {
-        let owner = Owner::merge(py, [&self.owner, dest.as_ref().owner()])?;
+    fn br(slf: BuilderRef, py: Python, dest: PyRef<BasicBlock>) -> PyResult<PyObject> {
+        let owner = Owner::merge(py, [&*slf.owner.borrow(), dest.as_ref().owner()])?;
         unsafe {
-            let value = LLVMBuildBr(self.builder.as_ptr(), dest.as_ptr());
+            let value = LLVMBuildBr(slf.as_ptr(), dest.as_ptr());
             Value::from_raw(py, owner, value)
         }
     }
@@ -300,7 +622,7 @@ impl Builder {
     /// :rtype: Instruction
     #[pyo3(text_signature = "(if_, then, else_)")]
     fn condbr(
-        &self,
+        slf: BuilderRef,
         py: Python,
         if_: &Value,
         then: PyRef<BasicBlock>,
@@ -309,19 +631,14 @@ impl Builder {
         let owner = Owner::merge(
             py,
             [
-                &self.owner,
+                &*slf.owner.borrow(),
                 if_.owner(),
                 then.as_ref().owner(),
                 else_.as_ref().owner(),
             ],
         )?;
         unsafe {
-            let value = LLVMBuildCondBr(
-                self.builder.as_ptr(),
-                if_.as_ptr(),
-                then.as_ptr(),
-                else_.as_ptr(),
-            );
+            let value = LLVMBuildCondBr(slf.as_ptr(), if_.as_ptr(), then.as_ptr(), else_.as_ptr());
             Value::from_raw(py, owner, value)
         }
     }
@@ -331,10 +648,54 @@ impl Builder {
     /// :returns: The phi node.
     /// :rtype: Instruction
     #[pyo3(text_signature = "(type)")]
-    fn phi(&self, py: Python, r#type: &Type) -> PyResult<PyObject> {
+    fn phi(slf: BuilderRef, py: Python, r#type: &Type) -> PyResult<PyObject> {
         unsafe {
-            let owner = self.owner.clone_ref(py);
-            let value = LLVMBuildPhi(self.builder.as_ptr(), r#type.as_ptr(), raw_cstr!(""));
+            let owner = slf.owner.borrow().clone_ref(py);
+            let value = LLVMBuildPhi(slf.as_ptr(), r#type.as_ptr(), raw_cstr!(""));
+            Value::from_raw(py, owner, value)
+        }
+    }
+
+    /// Inserts a stack allocation.
+    ///
+    /// :param Type type: The type of the allocated value.
+    /// :returns: A pointer to the allocated value.
+    /// :rtype: Instruction
+    #[pyo3(text_signature = "(type)")]
+    fn alloca(slf: BuilderRef, py: Python, r#type: &Type) -> PyResult<PyObject> {
+        unsafe {
+            let owner = slf.owner.borrow().clone_ref(py);
+            let value = LLVMBuildAlloca(slf.as_ptr(), r#type.as_ptr(), raw_cstr!(""));
+            Value::from_raw(py, owner, value)
+        }
+    }
+
+    /// Inserts a load instruction.
+    ///
+    /// :param Type type: The type of the loaded value.
+    /// :param Value ptr: The pointer to load from.
+    /// :returns: The loaded value.
+    /// :rtype: Instruction
+    #[pyo3(text_signature = "(type, ptr)")]
+    fn load(slf: BuilderRef, py: Python, r#type: &Type, ptr: &Value) -> PyResult<PyObject> {
+        let owner = Owner::merge(py, [&*slf.owner.borrow(), ptr.owner()])?;
+        unsafe {
+            let value = LLVMBuildLoad2(slf.as_ptr(), r#type.as_ptr(), ptr.as_ptr(), raw_cstr!(""));
+            Value::from_raw(py, owner, value)
+        }
+    }
+
+    /// Inserts a store instruction.
+    ///
+    /// :param Value value: The value to store.
+    /// :param Value ptr: The pointer to store to.
+    /// :returns: The store instruction.
+    /// :rtype: Instruction
+    #[pyo3(text_signature = "(value, ptr)")]
+    fn store(slf: BuilderRef, py: Python, value: &Value, ptr: &Value) -> PyResult<PyObject> {
+        let owner = Owner::merge(py, [&*slf.owner.borrow(), value.owner(), ptr.owner()])?;
+        unsafe {
+            let value = LLVMBuildStore(slf.as_ptr(), value.as_ptr(), ptr.as_ptr());
             Value::from_raw(py, owner, value)
         }
     }
@@ -345,15 +706,15 @@ impl Builder {
     /// :returns: The return instruction.
     /// :rtype: Instruction
     #[pyo3(text_signature = "(value)")]
-    fn ret(&self, py: Python, value: Option<&Value>) -> PyResult<PyObject> {
+    fn ret(slf: BuilderRef, py: Python, value: Option<&Value>) -> PyResult<PyObject> {
         let (value, owner) = match value {
             None => (
-                unsafe { LLVMBuildRetVoid(self.as_ptr()) },
-                self.owner.clone_ref(py),
+                unsafe { LLVMBuildRetVoid(slf.as_ptr()) },
+                slf.owner.borrow().clone_ref(py),
             ),
             Some(value) => {
-                let owner = Owner::merge(py, [&self.owner, value.owner()])?;
-                let inst = unsafe { LLVMBuildRet(self.as_ptr(), value.as_ptr()) };
+                let owner = Owner::merge(py, [&*slf.owner.borrow(), value.owner()])?;
+                let inst = unsafe { LLVMBuildRet(slf.as_ptr(), value.as_ptr()) };
                 (inst, owner)
             }
         };
@@ -362,8 +723,25 @@ impl Builder {
 }
 
 impl Builder {
-    pub(crate) fn owner(&self) -> &Owner {
-        &self.owner
+    pub(crate) fn owner(&self) -> Ref<'_, Owner> {
+        self.owner.borrow()
+    }
+
+    unsafe fn instruction(&self, index: usize) -> PyResult<LLVMValueRef> {
+        let block = NonNull::new(LLVMGetInsertBlock(self.as_ptr()))
+            .ok_or_else(|| PyValueError::new_err("The builder's position has not been set."))?;
+        let function = LLVMGetBasicBlockParent(block.as_ptr());
+        iter::successors(NonNull::new(LLVMGetFirstBasicBlock(function)), |b| {
+            NonNull::new(LLVMGetNextBasicBlock(b.as_ptr()))
+        })
+        .flat_map(|b| {
+            iter::successors(NonNull::new(LLVMGetFirstInstruction(b.as_ptr())), |i| {
+                NonNull::new(LLVMGetNextInstruction(i.as_ptr()))
+            })
+        })
+        .nth(index)
+        .map(NonNull::as_ptr)
+        .ok_or_else(|| PyIndexError::new_err("Instruction index out of range."))
     }
 }
 
@@ -371,14 +749,48 @@ impl Deref for Builder {
     type Target = NonNull<LLVMBuilder>;
 
     fn deref(&self) -> &Self::Target {
-        &self.builder
+        &self.raw
+    }
+}
+
+/// A borrowed builder that's on the thread that created it. Methods and functions take this
+/// instead of a reference to the builder, so that using the builder on another thread raises an
+/// error before the other arguments, which may also be tied to the thread, are extracted.
+pub(crate) struct BuilderRef<'py>(PyRef<'py, Builder>);
+
+impl<'py> TryFrom<&'py PyCell<Builder>> for BuilderRef<'py> {
+    type Error = PyErr;
+
+    fn try_from(cell: &'py PyCell<Builder>) -> PyResult<Self> {
+        let builder = cell.try_borrow()?;
+        if builder.thread == thread::current().id() {
+            Ok(Self(builder))
+        } else {
+            Err(PyRuntimeError::new_err(
+                "The builder can only be used on the thread that created it.",
+            ))
+        }
+    }
+}
+
+impl<'py> FromPyObject<'py> for BuilderRef<'py> {
+    fn extract(ob: &'py PyAny) -> PyResult<Self> {
+        ob.downcast::<PyCell<Builder>>()?.try_into()
+    }
+}
+
+impl Deref for BuilderRef<'_> {
+    type Target = Builder;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
     }
 }
 
 impl Drop for Builder {
     fn drop(&mut self) {
         unsafe {
-            LLVMDisposeBuilder(self.builder.as_ptr());
+            LLVMDisposeBuilder(self.raw.as_ptr());
         }
     }
 }
@@ -386,23 +798,195 @@ impl Drop for Builder {
 #[derive(FromPyObject)]
 enum Argument<'py> {
     Value(PyRef<'py, Value>),
+    Qubits(Vec<PyRef<'py, Value>>),
     Literal(Literal<'py>),
 }
 
 impl Argument<'_> {
-    fn owner(&self) -> Option<&Owner> {
+    fn owners(&self) -> Vec<&Owner> {
         match self {
-            Argument::Value(v) => Some(v.owner()),
-            Argument::Literal(_) => None,
+            Argument::Value(v) => vec![v.owner()],
+            Argument::Qubits(qubits) => qubits.iter().map(|q| q.owner()).collect(),
+            Argument::Literal(_) => Vec::new(),
         }
     }
 
-    unsafe fn to_value(&self, ty: LLVMTypeRef) -> PyResult<LLVMValueRef> {
+    /// Converts the argument into a value of the parameter type. A sequence of qubits has no value
+    /// until its array is built, so it's only checked.
+    unsafe fn to_value(&self, ty: LLVMTypeRef) -> PyResult<Option<LLVMValueRef>> {
+        match self {
+            Argument::Value(v) => Ok(Some(v.as_ptr())),
+            Argument::Qubits(qubits) => {
+                if !types::is_array(ty) {
+                    Err(PyTypeError::new_err(
+                        "A sequence of qubits can only be passed as an array parameter.",
+                    ))
+                } else if qubits
+                    .iter()
+                    .any(|q| !types::is_qubit(LLVMTypeOf(q.as_ptr())))
+                {
+                    Err(PyTypeError::new_err("Expected a sequence of qubits."))
+                } else {
+                    Ok(None)
+                }
+            }
+            Argument::Literal(l) => l.to_value(ty).map(Some),
+        }
+    }
+}
+
+#[derive(FromPyObject)]
+enum Annotation {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+}
+
+impl Annotation {
+    unsafe fn to_metadata(&self, context: LLVMContextRef) -> LLVMMetadataRef {
         match self {
-            Argument::Value(v) => Ok(v.as_ptr()),
-            Argument::Literal(l) => l.to_value(ty),
+            &Self::Bool(b) => {
+                LLVMValueAsMetadata(LLVMConstInt(LLVMInt1TypeInContext(context), b.into(), 0))
+            }
+            &Self::Int(i) => LLVMValueAsMetadata(LLVMConstInt(
+                LLVMInt64TypeInContext(context),
+                u64::from_ne_bytes(i.to_ne_bytes()),
+                1,
+            )),
+            &Self::Float(f) => {
+                LLVMValueAsMetadata(LLVMConstReal(LLVMDoubleTypeInContext(context), f))
+            }
+            Self::String(s) => LLVMMDStringInContext2(context, s.as_ptr().cast(), s.len()),
+        }
+    }
+
+    unsafe fn from_metadata(context: LLVMContextRef, md: LLVMMetadataRef) -> Option<Self> {
+        let value = LLVMMetadataAsValue(context, md);
+        if !LLVMIsAMDString(value).is_null() {
+            let mut len = 0;
+            let s = LLVMGetMDString(value, ptr::addr_of_mut!(len));
+            let s =
+                str::from_utf8(slice::from_raw_parts(s.cast(), len.try_into().unwrap())).ok()?;
+            return Some(Self::String(s.to_string()));
+        }
+
+        let constant = qirlib::metadata::extract_constant(value)?;
+        if !LLVMIsAConstantInt(constant).is_null() {
+            if LLVMGetIntTypeWidth(LLVMTypeOf(constant)) == 1 {
+                Some(Self::Bool(LLVMConstIntGetZExtValue(constant) != 0))
+            } else {
+                Some(Self::Int(LLVMConstIntGetSExtValue(constant)))
+            }
+        } else if !LLVMIsAConstantFP(constant).is_null() {
+            let mut loses_info = 0;
+            Some(Self::Float(LLVMConstRealGetDouble(
+                constant,
+                ptr::addr_of_mut!(loses_info),
+            )))
+        } else {
+            None
         }
     }
+
+    fn into_py(self, py: Python) -> PyObject {
+        match self {
+            Self::Bool(b) => b.into_py(py),
+            Self::Int(i) => i.into_py(py),
+            Self::Float(f) => f.into_py(py),
+            Self::String(s) => s.into_py(py),
+        }
+    }
+}
+
+/// Checks that static qubit and result IDs are in range for the entry point that the builder is
+/// inserting into. Qubits and results are constants that every module in a context shares, so a
+/// qubit from another module is only caught here if this module has fewer qubits. The bounds are
+/// read from the entry point's attributes when the instruction is built, so they reflect its
+/// current register sizes.
+pub(crate) fn check_in_range(
+    builder: &Builder,
+    qubits: &[&Value],
+    results: &[&Value],
+) -> PyResult<()> {
+    unsafe {
+        let block = LLVMGetInsertBlock(builder.as_ptr());
+        if block.is_null() {
+            return Ok(());
+        }
+        let function = LLVMGetBasicBlockParent(block);
+
+        let mut len = 0;
+        let module = LLVMGetModuleIdentifier(LLVMGetGlobalParent(function), ptr::addr_of_mut!(len));
+        let module = str::from_utf8(slice::from_raw_parts(module.cast(), len)).unwrap();
+
+        let checks: [(_, _, _, unsafe fn(_) -> _, _); 2] = [
+            (
+                "Qubit",
+                "qubit",
+                qubits,
+                values::qubit_id,
+                values::required_num_qubits(function),
+            ),
+            (
+                "Result",
+                "result",
+                results,
+                values::result_id,
+                values::required_num_results(function),
+            ),
+        ];
+        for (kind, noun, args, id, required) in checks {
+            if let Some(required) = required {
+                let s = if required == 1 { "" } else { "s" };
+                for arg in args {
+                    match id(arg.as_ptr()) {
+                        Some(id) if id >= required => {
+                            return Err(PyIndexError::new_err(format!(
+                                "{kind} {id} is out of range for module {module}, which has {required} {noun}{s}."
+                            )));
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Checks that a branch body is callable before any of the branch is built, so that passing the
+/// result of calling the body instead of the body itself fails right away.
+pub(crate) fn check_branch(name: &str, body: Option<&PyAny>) -> PyResult<()> {
+    match body {
+        Some(body) if !body.is_callable() => Err(PyTypeError::new_err(format!(
+            "The {name} branch must be callable or None, not {}.",
+            body.get_type().name()?
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// Calls a branch body, which may use the builder in any way, including moving it, as long as it
+/// leaves the builder at the end of an unterminated block in the same function.
+pub(crate) unsafe fn build_branch(builder: LLVMBuilderRef, body: Option<&PyAny>) -> PyResult<()> {
+    let function = LLVMGetBasicBlockParent(LLVMGetInsertBlock(builder));
+    if let Some(body) = body {
+        body.call0()?;
+    }
+
+    let block = LLVMGetInsertBlock(builder);
+    if block.is_null() || LLVMGetBasicBlockParent(block) != function {
+        Err(BuilderReentrancyError::new_err(
+            "The branch body left the builder outside the function of the branch.",
+        ))
+    } else if !LLVMGetBasicBlockTerminator(block).is_null() {
+        Err(BuilderReentrancyError::new_err(
+            "The branch body left the builder after a terminator.",
+        ))
+    } else {
+        Ok(())
+    }
 }
 
 unsafe fn callable_fn_type(value: LLVMValueRef) -> Option<NonNull<LLVMType>> {
@@ -10,14 +10,18 @@ use llvm_sys::{
     prelude::*,
     LLVMContext, LLVMMemoryBuffer,
 };
-use pyo3::prelude::*;
+use pyo3::{exceptions::PyValueError, prelude::*};
 use std::{
-    ffi::{c_char, CStr},
+    ffi::{c_char, CStr, CString},
     ops::Deref,
     ptr::NonNull,
 };
 
 /// The context owns global state needed by most LLVM objects.
+///
+/// :param bool opaque_pointers: Whether every pointer type in the context is the opaque ``ptr``
+///     type instead of a typed pointer like ``%Qubit*``. Requires LLVM 14.
+#[allow(clippy::doc_markdown)]
 #[pyclass(unsendable)]
 #[derive(Eq, PartialEq)]
 pub(crate) struct Context(NonNull<LLVMContext>);
@@ -25,6 +29,25 @@ pub(crate) struct Context(NonNull<LLVMContext>);
 #[pymethods]
 impl Context {
     #[new]
+    #[pyo3(text_signature = "(opaque_pointers=False)")]
+    #[cfg_attr(feature = "llvm14-0", allow(clippy::unnecessary_wraps))]
+    fn py_new(opaque_pointers: Option<bool>) -> PyResult<Self> {
+        let context = Self::new();
+        if opaque_pointers.unwrap_or(false) {
+            #[cfg(feature = "llvm14-0")]
+            unsafe {
+                qirlib::types::enable_opaque_pointers(context.as_ptr());
+            }
+            #[cfg(not(feature = "llvm14-0"))]
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                "Opaque pointers require LLVM 14.",
+            ));
+        }
+        Ok(context)
+    }
+}
+
+impl Context {
     pub(crate) fn new() -> Self {
         Self(NonNull::new(unsafe { LLVMContextCreate() }).unwrap())
     }
@@ -91,3 +114,13 @@ impl Drop for Message {
         unsafe { LLVMDisposeMessage(self.0.as_ptr()) }
     }
 }
+
+/// Converts a name to a C string for LLVM, which can't represent names with a null character.
+pub(crate) fn c_name(name: &str) -> PyResult<CString> {
+    CString::new(name).map_err(|e| {
+        PyValueError::new_err(format!(
+            "Name {name:?} contains a null character at index {}.",
+            e.nul_position()
+        ))
+    })
+}
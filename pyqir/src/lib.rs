@@ -14,11 +14,13 @@ extern crate llvm_sys_130 as llvm_sys;
 #[cfg(feature = "llvm14-0")]
 extern crate llvm_sys_140 as llvm_sys;
 
+mod analysis;
 mod builder;
 mod core;
 mod instructions;
 mod metadata;
 mod module;
+mod passes;
 mod python;
 mod qis;
 mod rt;
@@ -14,7 +14,7 @@ use llvm_sys::{
     LLVMOpaqueMetadata,
 };
 use pyo3::{conversion::ToPyObject, exceptions::PyValueError, prelude::*};
-use std::{ffi::CString, ops::Deref, ptr::NonNull, slice, str};
+use std::{ops::Deref, ptr::NonNull, slice, str};
 
 /// A metadata value or node.
 #[pyclass(subclass, unsendable)]
@@ -88,9 +88,8 @@ impl MetadataString {
         string: &str,
     ) -> PyResult<PyClassInitializer<Self>> {
         let owner = context.clone_ref(py).into();
-        let c_string = CString::new(string).unwrap();
         let context = context.borrow(py).as_ptr();
-        let md = LLVMMDStringInContext2(context, c_string.as_ptr(), string.len());
+        let md = LLVMMDStringInContext2(context, string.as_ptr().cast(), string.len());
         MetadataString::from_raw(py, owner, md)
     }
 
@@ -105,7 +104,7 @@ impl MetadataString {
             let context = slf.owner.context(py).borrow(py).as_ptr();
             let value = LLVMMetadataAsValue(context, slf.as_ptr());
             let mds = LLVMGetMDString(value, &mut len);
-            str::from_utf8(slice::from_raw_parts(mds.cast(), len as usize))
+            str::from_utf8(slice::from_raw_parts(mds.cast(), len.try_into().unwrap()))
                 .unwrap()
                 .to_string()
         }
@@ -5,10 +5,11 @@
 
 use crate::{
     core::Context,
-    core::{MemoryBuffer, Message},
+    core::{c_name, MemoryBuffer, Message},
     metadata::Metadata,
     values::{Constant, Owner, Value},
 };
+use const_str::raw_cstr;
 use core::slice;
 #[allow(clippy::wildcard_imports, deprecated)]
 use llvm_sys::{
@@ -19,17 +20,26 @@ use llvm_sys::{
     ir_reader::LLVMParseIRInContext,
     LLVMLinkage, LLVMModule,
 };
-use pyo3::{exceptions::PyValueError, prelude::*, pyclass::CompareOp, types::PyBytes};
+use pyo3::{
+    create_exception, exceptions::PyValueError, ffi, prelude::*, pyclass::CompareOp, types::PyBytes,
+};
 use qirlib::module::FlagBehavior;
 use std::{
     collections::hash_map::DefaultHasher,
-    ffi::CString,
+    ffi::CStr,
     hash::{Hash, Hasher},
     ops::Deref,
     ptr::{self, NonNull},
     str,
 };
 
+create_exception!(
+    pyqir,
+    QirEmitError,
+    PyValueError,
+    "The error raised when a module can't be emitted because it is invalid."
+);
+
 /// A module is a collection of global values.
 ///
 /// :param Context context: The LLVM context.
@@ -44,15 +54,15 @@ pub(crate) struct Module {
 impl Module {
     #[new]
     #[pyo3(text_signature = "(context, name)")]
-    pub(crate) fn new(py: Python, context: Py<Context>, name: &str) -> Self {
-        let name = CString::new(name).unwrap();
+    pub(crate) fn new(py: Python, context: Py<Context>, name: &str) -> PyResult<Self> {
+        let name = c_name(name)?;
         let module = unsafe {
             LLVMModuleCreateWithNameInContext(name.as_ptr(), context.borrow(py).as_ptr())
         };
-        Self {
+        Ok(Self {
             module: NonNull::new(module).unwrap(),
             context,
-        }
+        })
     }
 
     /// Creates a module from LLVM IR.
@@ -64,7 +74,7 @@ impl Module {
     #[staticmethod]
     #[pyo3(text_signature = "(context, ir, name=\"\")")]
     fn from_ir(py: Python, context: Py<Context>, ir: &str, name: Option<&str>) -> PyResult<Self> {
-        let name = CString::new(name.unwrap_or_default()).unwrap();
+        let name = c_name(name.unwrap_or_default())?;
 
         // Don't dispose this buffer. LLVMParseIRInContext takes ownership.
         let buffer = unsafe {
@@ -101,7 +111,7 @@ impl Module {
         bitcode: &[u8],
         name: Option<&str>,
     ) -> PyResult<Self> {
-        let name = CString::new(name.unwrap_or_default()).unwrap();
+        let name = c_name(name.unwrap_or_default())?;
         let buffer = unsafe {
             MemoryBuffer::from_raw(LLVMCreateMemoryBufferWithMemoryRange(
                 bitcode.as_ptr().cast(),
@@ -130,6 +140,29 @@ impl Module {
         }
     }
 
+    /// Copies this module into a new module in the same context.
+    ///
+    /// :returns: The copy.
+    /// :rtype: Module
+    fn __copy__(&self, py: Python) -> Self {
+        let module = unsafe { LLVMCloneModule(self.as_ptr()) };
+        Self {
+            module: NonNull::new(module).unwrap(),
+            context: self.context.clone_ref(py),
+        }
+    }
+
+    /// Copies this module into a new module in the same context. The context is shared rather than
+    /// copied, so this is the same as :meth:`__copy__`.
+    ///
+    /// :param dict memo: The memo dictionary of objects already copied.
+    /// :returns: The copy.
+    /// :rtype: Module
+    #[allow(unused_variables)]
+    fn __deepcopy__(&self, py: Python, memo: &PyAny) -> Self {
+        self.__copy__(py)
+    }
+
     /// The name of the original source file that this module was compiled from.
     ///
     /// :type: str
@@ -149,6 +182,40 @@ impl Module {
         }
     }
 
+    /// The target triple of this module, or an empty string if it has none. Setting a triple with
+    /// an architecture unknown to LLVM raises a :class:`ValueError`.
+    ///
+    /// :type: str
+    #[getter]
+    fn triple(&self) -> &str {
+        unsafe { CStr::from_ptr(LLVMGetTarget(self.as_ptr())) }
+            .to_str()
+            .unwrap()
+    }
+
+    #[setter]
+    fn set_triple(&self, value: &str) -> PyResult<()> {
+        unsafe { qirlib::module::set_target_triple(self.as_ptr(), value) }
+            .map_err(PyValueError::new_err)
+    }
+
+    /// The data layout of this module, or an empty string for LLVM's default layout. Setting a data
+    /// layout that LLVM can't parse raises a :class:`ValueError`.
+    ///
+    /// :type: str
+    #[getter]
+    fn data_layout(&self) -> &str {
+        unsafe { CStr::from_ptr(LLVMGetDataLayoutStr(self.as_ptr())) }
+            .to_str()
+            .unwrap()
+    }
+
+    #[setter]
+    fn set_data_layout(&self, value: &str) -> PyResult<()> {
+        unsafe { qirlib::module::set_data_layout(self.as_ptr(), value) }
+            .map_err(PyValueError::new_err)
+    }
+
     /// The functions declared in this module.
     ///
     /// :type: typing.List[Function]
@@ -235,6 +302,27 @@ impl Module {
         value.ok()
     }
 
+    /// Optimizes this module in place with LLVM's standard optimization pipeline for the level.
+    ///
+    /// The module must be valid. See :meth:`SimpleModule.optimize` for the passes that run at each
+    /// level.
+    ///
+    /// :param int level: The optimization level, from 0 to 3.
+    /// :rtype: None
+    #[pyo3(text_signature = "(level)")]
+    fn optimize(&self, level: u32) -> PyResult<()> {
+        if level > 3 {
+            Err(PyValueError::new_err(
+                "Optimization level must be between 0 and 3.",
+            ))
+        } else {
+            unsafe {
+                qirlib::module::optimize(self.as_ptr(), level);
+            }
+            Ok(())
+        }
+    }
+
     /// Verifies that this module is valid.
     ///
     /// :returns: An error description if this module is invalid or `None` if this module is valid.
@@ -252,10 +340,39 @@ impl Module {
         }
     }
 
+    /// Wraps the underlying ``LLVMModuleRef`` in a capsule named ``"LLVMModuleRef"``, for use with
+    /// other libraries that call the LLVM C API directly.
+    ///
+    /// The capsule keeps this module alive, but the pointer is only valid while the capsule
+    /// exists, and it must not be disposed of through the LLVM API. Changes made through the
+    /// pointer are changes to this module. The library that uses the pointer must be linked
+    /// against the same LLVM library as ``pyqir``, because LLVM objects can't be shared between
+    /// different copies of LLVM.
+    ///
+    /// :returns: The capsule.
+    /// :rtype: typing.Any
+    fn llvm_module_ptr(slf: Py<Module>, py: Python) -> PyResult<PyObject> {
+        unsafe extern "C" fn release(capsule: *mut ffi::PyObject) {
+            ffi::Py_XDECREF(ffi::PyCapsule_GetContext(capsule).cast());
+        }
+
+        let module = slf.borrow(py).as_ptr();
+        unsafe {
+            let raw = ffi::PyCapsule_New(module.cast(), raw_cstr!("LLVMModuleRef"), Some(release));
+            let capsule = PyObject::from_owned_ptr_or_err(py, raw)?;
+            let owner = slf.into_ptr();
+            if ffi::PyCapsule_SetContext(raw, owner.cast()) != 0 {
+                ffi::Py_DECREF(owner);
+                return Err(PyErr::fetch(py));
+            }
+            Ok(capsule)
+        }
+    }
+
     /// Converts this module into an LLVM IR string.
     ///
     /// :rtype: str
-    fn __str__(&self) -> String {
+    pub(crate) fn __str__(&self) -> String {
         unsafe {
             Message::from_raw(LLVMPrintModuleToString(self.as_ptr()))
                 .to_str()
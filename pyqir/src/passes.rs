@@ -0,0 +1,87 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use crate::values::Function;
+use pyo3::{exceptions::PyValueError, prelude::*};
+use qirlib::passes;
+use std::collections::HashMap;
+
+/// Moves every measurement in the function to the end of the circuit, after the last instruction
+/// that uses a qubit, keeping the measurements in their original order.
+///
+/// Measurements can only be deferred if it doesn't change the meaning of the program. The function
+/// must have at most one basic block and only use static qubits, no qubit may be used after it is
+/// measured, and no result may be read before the end of the circuit. Otherwise the function is
+/// left unchanged and a :class:`ValueError` is raised.
+///
+/// :param Function function: The function.
+/// :rtype: None
+#[pyfunction]
+#[pyo3(text_signature = "(function)")]
+pub(crate) fn defer_measurements(function: PyRef<Function>) -> PyResult<()> {
+    unsafe { passes::defer_measurements(function.into_super().into_super().as_ptr()) }
+        .map_err(PyValueError::new_err)
+}
+
+/// Replaces the contiguous run of ``mz`` calls at the end of the function's last basic block with a
+/// single call to ``__quantum__qis__mz_batch__body``. The batched call takes the number of
+/// measurements and pointers to constant arrays of the qubits and the results they're written to.
+///
+/// The run ends at the last instruction in the block that uses a qubit, and every qubit and result
+/// in it must be static and distinct. Runs with fewer than two measurements are left unchanged.
+///
+/// :param Function function: The function.
+/// :returns: Whether the measurements were batched.
+/// :rtype: bool
+#[pyfunction]
+#[pyo3(text_signature = "(function)")]
+pub(crate) fn batch_measurements(function: PyRef<Function>) -> bool {
+    unsafe { passes::batch_measurements(function.into_super().into_super().as_ptr()) }
+}
+
+/// Replaces the circuit in the function with its adjoint, which undoes it. The gates are reversed
+/// and each is replaced by its inverse, with rotation angles negated.
+///
+/// The function must have at most one basic block, and every call in it must be to a QIS gate.
+/// Measurements and resets aren't unitary, so circuits with them have no adjoint. Otherwise the
+/// function is left unchanged and a :class:`ValueError` is raised.
+///
+/// :param Function function: The function.
+/// :rtype: None
+#[pyfunction]
+#[pyo3(text_signature = "(function)")]
+pub(crate) fn adjoint(function: PyRef<Function>) -> PyResult<()> {
+    unsafe { passes::adjoint(function.into_super().into_super().as_ptr()) }
+        .map_err(PyValueError::new_err)
+}
+
+/// Raises the circuit in the function to a power by multiplying the angle of every rotation by the
+/// exponent. This is only the power of the whole circuit if its gates commute, like rotations about
+/// the same axis.
+///
+/// The function must have at most one basic block, and every call in it must be to an
+/// :math:`R_x`, :math:`R_y`, :math:`R_z` or :math:`U_1` rotation, or a barrier. Other gates, like
+/// :math:`H`, have no power that's given by their arguments. Otherwise the function is left
+/// unchanged and a :class:`ValueError` is raised.
+///
+/// :param Function function: The function.
+/// :param float exponent: The exponent.
+/// :rtype: None
+#[pyfunction]
+#[pyo3(text_signature = "(function, exponent)")]
+pub(crate) fn power(function: PyRef<Function>, exponent: f64) -> PyResult<()> {
+    unsafe { passes::power(function.into_super().into_super().as_ptr(), exponent) }
+        .map_err(PyValueError::new_err)
+}
+
+/// Replaces every static qubit operand in the function with the qubit that its ID maps to. Qubits
+/// that aren't in the map, and dynamic qubits, are left unchanged.
+///
+/// :param Function function: The function.
+/// :param typing.Dict[int, int] mapping: The new ID of each qubit.
+/// :rtype: None
+#[pyfunction]
+#[pyo3(text_signature = "(function, mapping)")]
+pub(crate) fn relabel_qubits(function: PyRef<Function>, mapping: HashMap<u64, u64>) {
+    unsafe { passes::relabel_qubits(function.into_super().into_super().as_ptr(), &mapping) }
+}
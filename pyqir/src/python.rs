@@ -2,34 +2,43 @@
 // Licensed under the MIT License.
 
 use crate::{
-    builder::Builder,
+    analysis::to_matrix,
+    builder::{Builder, BuilderReentrancyError},
     core::Context,
     instructions::{
         Call, FCmp, FloatPredicate, ICmp, Instruction, IntPredicate, Opcode, Phi, Switch,
     },
     metadata::{ConstantAsMetadata, Metadata, MetadataString},
-    module::{Linkage, Module, ModuleFlagBehavior},
+    module::{Linkage, Module, ModuleFlagBehavior, QirEmitError},
+    passes::{adjoint, batch_measurements, defer_measurements, power, relabel_qubits},
     qis::{
-        barrier, ccx, cx, cz, h, if_result, mz, reset, rx, ry, rz, s, s_adj, swap, t, t_adj, x, y,
+        barrier, ccx, ch, cx, cy, cz, depolarize, extend, h, id, if_result, m, mz, normalize_angle,
+        qis_declarations, read_result, reset, rx, ry, rz, s, s_adj, swap, t, t_adj, u1, u2, x, y,
         z,
     },
-    rt::{array_record_output, initialize, result_record_output, tuple_record_output},
+    rt::{
+        array_record_output, initialize, int_record_output, result_record_output,
+        tuple_record_output,
+    },
     types::{
-        is_qubit_type, is_result_type, qubit_type, result_type, ArrayType, FunctionType, IntType,
-        PointerType, StructType, Type,
+        array_type, is_array_type, is_qubit_type, is_result_type, qubit_type, result_type,
+        ArrayType, FunctionType, IntType, PointerType, StructType, Type,
     },
     values::{
-        add_string_attribute, dynamic_qubit_management, dynamic_result_management,
-        extract_byte_string, global_byte_string, is_entry_point, is_interop_friendly,
-        qir_major_version, qir_minor_version, qir_module, qubit, qubit_id, r#const,
-        required_num_qubits, required_num_results, result, result_id, Attribute, AttributeList,
-        AttributeSet, BasicBlock, Constant, FloatConstant, Function, IntConstant, Value,
+        add_compile_unit, add_string_attribute, dynamic_qubit_management,
+        dynamic_result_management, extract_byte_string, global_byte_string, is_entry_point,
+        is_interop_friendly, qir_major_version, qir_minor_version, qir_module, qubit, qubit_id,
+        r#const, required_num_qubits, required_num_results, result, result_id, Attribute,
+        AttributeList, AttributeSet, BasicBlock, Constant, FloatConstant, Function, IntConstant,
+        Value,
     },
 };
 use pyo3::prelude::*;
+use std::f64::consts::{FRAC_PI_2, FRAC_PI_4, PI, TAU};
 
 #[pymodule]
-fn _native(_py: Python, m: &PyModule) -> PyResult<()> {
+#[allow(clippy::too_many_lines)]
+fn _native(py: Python, m: &PyModule) -> PyResult<()> {
     m.add_class::<ArrayType>()?;
     m.add_class::<Attribute>()?;
     m.add_class::<AttributeList>()?;
@@ -62,11 +71,23 @@ fn _native(_py: Python, m: &PyModule) -> PyResult<()> {
     m.add_class::<Switch>()?;
     m.add_class::<Type>()?;
     m.add_class::<Value>()?;
+    m.add(
+        "BuilderReentrancyError",
+        py.get_type::<BuilderReentrancyError>(),
+    )?;
+    m.add("QirEmitError", py.get_type::<QirEmitError>())?;
+    m.add_function(wrap_pyfunction!(add_compile_unit, m)?)?;
     m.add_function(wrap_pyfunction!(add_string_attribute, m)?)?;
+    m.add_function(wrap_pyfunction!(adjoint, m)?)?;
+    m.add_function(wrap_pyfunction!(array_type, m)?)?;
+    m.add_function(wrap_pyfunction!(power, m)?)?;
+    m.add_function(wrap_pyfunction!(batch_measurements, m)?)?;
+    m.add_function(wrap_pyfunction!(defer_measurements, m)?)?;
     m.add_function(wrap_pyfunction!(dynamic_qubit_management, m)?)?;
     m.add_function(wrap_pyfunction!(dynamic_result_management, m)?)?;
     m.add_function(wrap_pyfunction!(extract_byte_string, m)?)?;
     m.add_function(wrap_pyfunction!(global_byte_string, m)?)?;
+    m.add_function(wrap_pyfunction!(is_array_type, m)?)?;
     m.add_function(wrap_pyfunction!(is_entry_point, m)?)?;
     m.add_function(wrap_pyfunction!(is_interop_friendly, m)?)?;
     m.add_function(wrap_pyfunction!(is_qubit_type, m)?)?;
@@ -78,20 +99,29 @@ fn _native(_py: Python, m: &PyModule) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(qubit_type, m)?)?;
     m.add_function(wrap_pyfunction!(qubit, m)?)?;
     m.add_function(wrap_pyfunction!(r#const, m)?)?;
+    m.add_function(wrap_pyfunction!(relabel_qubits, m)?)?;
     m.add_function(wrap_pyfunction!(required_num_qubits, m)?)?;
     m.add_function(wrap_pyfunction!(required_num_results, m)?)?;
     m.add_function(wrap_pyfunction!(result_id, m)?)?;
     m.add_function(wrap_pyfunction!(result_type, m)?)?;
     m.add_function(wrap_pyfunction!(result, m)?)?;
+    m.add_function(wrap_pyfunction!(to_matrix, m)?)?;
 
     // qis
     m.add_function(wrap_pyfunction!(barrier, m)?)?;
     m.add_function(wrap_pyfunction!(swap, m)?)?;
     m.add_function(wrap_pyfunction!(ccx, m)?)?;
+    m.add_function(wrap_pyfunction!(ch, m)?)?;
     m.add_function(wrap_pyfunction!(cx, m)?)?;
+    m.add_function(wrap_pyfunction!(cy, m)?)?;
     m.add_function(wrap_pyfunction!(cz, m)?)?;
+    m.add_function(wrap_pyfunction!(depolarize, m)?)?;
+    m.add_function(wrap_pyfunction!(extend, m)?)?;
     m.add_function(wrap_pyfunction!(h, m)?)?;
+    m.add_function(wrap_pyfunction!(id, m)?)?;
+    m.add_function(wrap_pyfunction!(self::m, m)?)?;
     m.add_function(wrap_pyfunction!(mz, m)?)?;
+    m.add_function(wrap_pyfunction!(read_result, m)?)?;
     m.add_function(wrap_pyfunction!(reset, m)?)?;
     m.add_function(wrap_pyfunction!(rx, m)?)?;
     m.add_function(wrap_pyfunction!(ry, m)?)?;
@@ -100,14 +130,25 @@ fn _native(_py: Python, m: &PyModule) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(s_adj, m)?)?;
     m.add_function(wrap_pyfunction!(t, m)?)?;
     m.add_function(wrap_pyfunction!(t_adj, m)?)?;
+    m.add_function(wrap_pyfunction!(u1, m)?)?;
+    m.add_function(wrap_pyfunction!(u2, m)?)?;
     m.add_function(wrap_pyfunction!(x, m)?)?;
     m.add_function(wrap_pyfunction!(y, m)?)?;
     m.add_function(wrap_pyfunction!(z, m)?)?;
     m.add_function(wrap_pyfunction!(if_result, m)?)?;
+    m.add_function(wrap_pyfunction!(qis_declarations, m)?)?;
+
+    // angles
+    m.add("PI", PI)?;
+    m.add("PI_OVER_2", FRAC_PI_2)?;
+    m.add("PI_OVER_4", FRAC_PI_4)?;
+    m.add("TAU", TAU)?;
+    m.add_function(wrap_pyfunction!(normalize_angle, m)?)?;
 
     // rt
     m.add_function(wrap_pyfunction!(array_record_output, m)?)?;
     m.add_function(wrap_pyfunction!(initialize, m)?)?;
+    m.add_function(wrap_pyfunction!(int_record_output, m)?)?;
     m.add_function(wrap_pyfunction!(result_record_output, m)?)?;
     m.add_function(wrap_pyfunction!(tuple_record_output, m)?)?;
 
@@ -2,15 +2,42 @@
 // Licensed under the MIT License.
 
 use crate::{
-    builder::Builder,
+    builder::{self, check_in_range, Builder, BuilderRef},
+    core::Context,
+    module::Module,
     values::{Owner, Value},
 };
 use llvm_sys::{
-    core::{LLVMConstReal, LLVMDoubleTypeInContext},
+    core::{LLVMConstReal, LLVMConstRealGetDouble, LLVMDoubleTypeInContext, LLVMIsAConstantFP},
     prelude::*,
 };
-use pyo3::prelude::*;
-use qirlib::qis;
+use pyo3::{
+    exceptions::{PyTypeError, PyValueError},
+    prelude::*,
+    types::PyTuple,
+};
+use qirlib::{
+    qis::{self, Intrinsic, Profile},
+    values,
+};
+
+/// Emits a module that contains only the declarations of the QIS intrinsics that programs for a
+/// profile may call, with the same signatures that the QIS builder functions declare.
+///
+/// :param str profile: The profile: ``"base_profile"``, ``"adaptive_profile"`` or ``"custom"``.
+/// :returns: The LLVM IR for the module.
+/// :rtype: str
+#[pyfunction]
+#[pyo3(text_signature = "(profile)")]
+pub(crate) fn qis_declarations(py: Python, profile: &str) -> PyResult<String> {
+    let profile = Profile::from_name(profile)
+        .ok_or_else(|| PyValueError::new_err(format!("Unknown profile: {profile}.")))?;
+    let module = Module::new(py, Py::new(py, Context::new())?, "qis")?;
+    unsafe {
+        qis::declare_all(module.as_ptr(), profile);
+    }
+    Ok(module.__str__())
+}
 
 /// Inserts a barrier instruction
 ///
@@ -19,7 +46,7 @@ use qirlib::qis;
 #[pyfunction]
 #[pyo3(text_signature = "(builder)")]
 #[allow(clippy::needless_pass_by_value)]
-pub(crate) fn barrier(builder: &Builder) {
+pub(crate) fn barrier(builder: BuilderRef) {
     unsafe {
         qis::build_barrier(builder.as_ptr());
     }
@@ -34,8 +61,14 @@ pub(crate) fn barrier(builder: &Builder) {
 #[pyfunction]
 #[pyo3(text_signature = "(builder, qubit1, qubit2)")]
 #[allow(clippy::needless_pass_by_value)]
-pub(crate) fn swap(py: Python, builder: &Builder, qubit1: &Value, qubit2: &Value) -> PyResult<()> {
-    Owner::merge(py, [builder.owner(), qubit1.owner(), qubit2.owner()])?;
+pub(crate) fn swap(
+    py: Python,
+    builder: BuilderRef,
+    qubit1: &Value,
+    qubit2: &Value,
+) -> PyResult<()> {
+    Owner::merge(py, [&*builder.owner(), qubit1.owner(), qubit2.owner()])?;
+    check_in_range(&builder, &[qubit1, qubit2], &[])?;
     unsafe {
         qis::build_swap(builder.as_ptr(), qubit1.as_ptr(), qubit2.as_ptr());
     }
@@ -53,7 +86,7 @@ pub(crate) fn swap(py: Python, builder: &Builder, qubit1: &Value, qubit2: &Value
 #[pyo3(text_signature = "(builder, control1, control2, target)")]
 pub(crate) fn ccx(
     py: Python,
-    builder: &Builder,
+    builder: BuilderRef,
     control1: &Value,
     control2: &Value,
     target: &Value,
@@ -61,12 +94,14 @@ pub(crate) fn ccx(
     Owner::merge(
         py,
         [
-            builder.owner(),
+            &*builder.owner(),
             control1.owner(),
             control2.owner(),
             target.owner(),
         ],
     )?;
+    check_distinct(&[control1, control2, target])?;
+    check_in_range(&builder, &[control1, control2, target], &[])?;
     unsafe {
         qis::build_ccx(
             builder.as_ptr(),
@@ -78,6 +113,24 @@ pub(crate) fn ccx(
     Ok(())
 }
 
+/// Inserts a controlled Hadamard gate.
+///
+/// :param Builder builder: The IR Builder used to create the instructions
+/// :param Value control: The control qubit.
+/// :param Value target: The target qubit.
+/// :rtype: None
+#[pyfunction]
+#[pyo3(text_signature = "(builder, control, target)")]
+pub(crate) fn ch(py: Python, builder: BuilderRef, control: &Value, target: &Value) -> PyResult<()> {
+    Owner::merge(py, [&*builder.owner(), control.owner(), target.owner()])?;
+    check_distinct(&[control, target])?;
+    check_in_range(&builder, &[control, target], &[])?;
+    unsafe {
+        qis::build_ch(builder.as_ptr(), control.as_ptr(), target.as_ptr());
+    }
+    Ok(())
+}
+
 /// Inserts a controlled Pauli :math:`X` gate.
 ///
 /// :param Builder builder: The IR Builder used to create the instructions
@@ -86,14 +139,34 @@ pub(crate) fn ccx(
 /// :rtype: None
 #[pyfunction]
 #[pyo3(text_signature = "(builder, control, target)")]
-pub(crate) fn cx(py: Python, builder: &Builder, control: &Value, target: &Value) -> PyResult<()> {
-    Owner::merge(py, [builder.owner(), control.owner(), target.owner()])?;
+pub(crate) fn cx(py: Python, builder: BuilderRef, control: &Value, target: &Value) -> PyResult<()> {
+    Owner::merge(py, [&*builder.owner(), control.owner(), target.owner()])?;
+    check_distinct(&[control, target])?;
+    check_in_range(&builder, &[control, target], &[])?;
     unsafe {
         qis::build_cx(builder.as_ptr(), control.as_ptr(), target.as_ptr());
     }
     Ok(())
 }
 
+/// Inserts a controlled Pauli :math:`Y` gate.
+///
+/// :param Builder builder: The IR Builder used to create the instructions
+/// :param Value control: The control qubit.
+/// :param Value target: The target qubit.
+/// :rtype: None
+#[pyfunction]
+#[pyo3(text_signature = "(builder, control, target)")]
+pub(crate) fn cy(py: Python, builder: BuilderRef, control: &Value, target: &Value) -> PyResult<()> {
+    Owner::merge(py, [&*builder.owner(), control.owner(), target.owner()])?;
+    check_distinct(&[control, target])?;
+    check_in_range(&builder, &[control, target], &[])?;
+    unsafe {
+        qis::build_cy(builder.as_ptr(), control.as_ptr(), target.as_ptr());
+    }
+    Ok(())
+}
+
 /// Inserts a controlled Pauli :math:`Z` gate.
 ///
 /// :param Builder builder: The IR Builder used to create the instructions
@@ -102,14 +175,49 @@ pub(crate) fn cx(py: Python, builder: &Builder, control: &Value, target: &Value)
 /// :rtype: None
 #[pyfunction]
 #[pyo3(text_signature = "(builder, control, target)")]
-pub(crate) fn cz(py: Python, builder: &Builder, control: &Value, target: &Value) -> PyResult<()> {
-    Owner::merge(py, [builder.owner(), control.owner(), target.owner()])?;
+pub(crate) fn cz(py: Python, builder: BuilderRef, control: &Value, target: &Value) -> PyResult<()> {
+    Owner::merge(py, [&*builder.owner(), control.owner(), target.owner()])?;
+    check_distinct(&[control, target])?;
+    check_in_range(&builder, &[control, target], &[])?;
     unsafe {
         qis::build_cz(builder.as_ptr(), control.as_ptr(), target.as_ptr());
     }
     Ok(())
 }
 
+/// Inserts a depolarizing noise channel, which replaces the state of the qubit with the maximally
+/// mixed state with the given probability. It's not part of any QIR profile, and only simulators
+/// that model noise are expected to implement it.
+///
+/// :param Builder builder: The IR Builder used to create the instructions
+/// :param float probability: The probability of depolarizing, between 0 and 1.
+/// :param Value qubit: The target qubit.
+/// :rtype: None
+#[pyfunction]
+#[pyo3(text_signature = "(builder, probability, qubit)")]
+pub(crate) fn depolarize(
+    py: Python,
+    builder: BuilderRef,
+    probability: f64,
+    qubit: &Value,
+) -> PyResult<()> {
+    if !(0.0..=1.0).contains(&probability) {
+        return Err(PyValueError::new_err(format!(
+            "The probability of depolarizing must be between 0 and 1, not {probability}."
+        )));
+    }
+    Owner::merge(py, [&*builder.owner(), qubit.owner()])?;
+    check_in_range(&builder, &[qubit], &[])?;
+
+    let context = builder.owner().context(py);
+    let context = context.borrow(py);
+    unsafe {
+        let probability = LLVMConstReal(LLVMDoubleTypeInContext(context.as_ptr()), probability);
+        qis::build_depolarize(builder.as_ptr(), probability, qubit.as_ptr());
+    }
+    Ok(())
+}
+
 /// Inserts a Hadamard gate.
 ///
 /// :param Builder builder: The IR Builder used to create the instructions
@@ -117,14 +225,52 @@ pub(crate) fn cz(py: Python, builder: &Builder, control: &Value, target: &Value)
 /// :rtype: None
 #[pyfunction]
 #[pyo3(text_signature = "(builder, qubit)")]
-pub(crate) fn h(py: Python, builder: &Builder, qubit: &Value) -> PyResult<()> {
-    Owner::merge(py, [builder.owner(), qubit.owner()])?;
+pub(crate) fn h(py: Python, builder: BuilderRef, qubit: &Value) -> PyResult<()> {
+    Owner::merge(py, [&*builder.owner(), qubit.owner()])?;
+    check_in_range(&builder, &[qubit], &[])?;
     unsafe {
         qis::build_h(builder.as_ptr(), qubit.as_ptr());
     }
     Ok(())
 }
 
+/// Inserts an identity gate, which does nothing to the qubit, for example as a placeholder that
+/// takes up a time step.
+///
+/// :param Builder builder: The IR Builder used to create the instructions
+/// :param Value qubit: The target qubit.
+/// :rtype: None
+#[pyfunction]
+#[pyo3(text_signature = "(builder, qubit)")]
+pub(crate) fn id(py: Python, builder: BuilderRef, qubit: &Value) -> PyResult<()> {
+    Owner::merge(py, [&*builder.owner(), qubit.owner()])?;
+    check_in_range(&builder, &[qubit], &[])?;
+    unsafe {
+        qis::build_id(builder.as_ptr(), qubit.as_ptr());
+    }
+    Ok(())
+}
+
+/// Inserts a Z-basis measurement operation that returns a new result.
+///
+/// Unlike :func:`mz`, the result does not need to be allocated in advance. The returned value can
+/// be passed directly to :func:`if_result` to branch on the measurement.
+///
+/// :param Builder builder: The IR Builder used to create the instructions
+/// :param Value qubit: The qubit to measure.
+/// :returns: The measurement result.
+/// :rtype: Value
+#[pyfunction]
+#[pyo3(text_signature = "(builder, qubit)")]
+pub(crate) fn m(py: Python, builder: BuilderRef, qubit: &Value) -> PyResult<PyObject> {
+    let owner = Owner::merge(py, [&*builder.owner(), qubit.owner()])?;
+    check_in_range(&builder, &[qubit], &[])?;
+    unsafe {
+        let result = qis::build_m(builder.as_ptr(), qubit.as_ptr());
+        Value::from_raw(py, owner, result)
+    }
+}
+
 /// Inserts a Z-basis measurement operation.
 ///
 /// :param Builder builder: The IR Builder used to create the instructions
@@ -133,14 +279,36 @@ pub(crate) fn h(py: Python, builder: &Builder, qubit: &Value) -> PyResult<()> {
 /// :rtype: None
 #[pyfunction]
 #[pyo3(text_signature = "(builder, qubit, result)")]
-pub(crate) fn mz(py: Python, builder: &Builder, qubit: &Value, result: &Value) -> PyResult<()> {
-    Owner::merge(py, [builder.owner(), qubit.owner(), result.owner()])?;
+pub(crate) fn mz(py: Python, builder: BuilderRef, qubit: &Value, result: &Value) -> PyResult<()> {
+    Owner::merge(py, [&*builder.owner(), qubit.owner(), result.owner()])?;
+    check_in_range(&builder, &[qubit], &[result])?;
     unsafe {
         qis::build_mz(builder.as_ptr(), qubit.as_ptr(), result.as_ptr());
     }
     Ok(())
 }
 
+/// Inserts a read of a measurement result as a boolean, without measuring again.
+///
+/// Unlike :func:`if_result`, which reads the result and branches on it, the returned ``i1`` value
+/// can be combined with other values, like with :meth:`Builder.and_` or :meth:`Builder.icmp`,
+/// before branching on it with :meth:`Builder.condbr`.
+///
+/// :param Builder builder: The IR Builder used to create the instructions
+/// :param Value result: The result to read.
+/// :returns: The value of the result, true if it's one.
+/// :rtype: Value
+#[pyfunction]
+#[pyo3(text_signature = "(builder, result)")]
+pub(crate) fn read_result(py: Python, builder: BuilderRef, result: &Value) -> PyResult<PyObject> {
+    let owner = Owner::merge(py, [&*builder.owner(), result.owner()])?;
+    check_in_range(&builder, &[], &[result])?;
+    unsafe {
+        let value = qis::build_read_result(builder.as_ptr(), result.as_ptr());
+        Value::from_raw(py, owner, value)
+    }
+}
+
 /// Inserts a reset operation.
 ///
 /// :param Builder builder: The IR Builder used to create the instructions
@@ -148,14 +316,103 @@ pub(crate) fn mz(py: Python, builder: &Builder, qubit: &Value, result: &Value) -
 /// :rtype: None
 #[pyfunction]
 #[pyo3(text_signature = "(builder, qubit)")]
-pub(crate) fn reset(py: Python, builder: &Builder, qubit: &Value) -> PyResult<()> {
-    Owner::merge(py, [builder.owner(), qubit.owner()])?;
+pub(crate) fn reset(py: Python, builder: BuilderRef, qubit: &Value) -> PyResult<()> {
+    Owner::merge(py, [&*builder.owner(), qubit.owner()])?;
+    check_in_range(&builder, &[qubit], &[])?;
     unsafe {
         qis::build_reset(builder.as_ptr(), qubit.as_ptr());
     }
     Ok(())
 }
 
+/// Inserts a sequence of gates with one call, which is faster than calling the function for each
+/// gate from Python. Each instruction is a tuple of the name of the function in this module that
+/// inserts the gate and the arguments that come after the builder, like ``("cx", control,
+/// target)``, ``("rz", theta, qubit)`` or ``("mz", qubit, result)``. The supported gates are
+/// ``barrier``, ``ccx``, ``ch``, ``cx``, ``cy``, ``cz``, ``h``, ``id``, ``mz``, ``reset``,
+/// ``rx``, ``ry``, ``rz``, ``s``, ``s_adj``, ``swap``, ``t``, ``t_adj``, ``u1``, ``u2``, ``x``,
+/// ``y`` and ``z``.
+///
+/// Every instruction is checked like the function for its gate would check it before any is
+/// inserted, so nothing is inserted if one of them is invalid. The message of the error starts
+/// with the position of the invalid instruction.
+///
+/// :param Builder builder: The IR Builder used to create the instructions
+/// :param typing.Sequence[typing.Tuple] instructions: The instructions.
+/// :param bool normalize_angles: Whether to reduce every constant angle with
+///     :func:`normalize_angle`.
+/// :param typing.Optional[float] noise: The probability of a depolarizing noise channel from
+///     :func:`depolarize` on each qubit after every gate other than a barrier, measurement or
+///     reset. Without it, no noise is inserted.
+/// :rtype: None
+#[pyfunction]
+#[pyo3(text_signature = "(builder, instructions, normalize_angles=False, noise=None)")]
+#[allow(clippy::doc_markdown)]
+pub(crate) fn extend(
+    py: Python,
+    builder: BuilderRef,
+    instructions: Vec<&PyAny>,
+    normalize_angles: Option<bool>,
+    noise: Option<f64>,
+) -> PyResult<()> {
+    if let Some(probability) = noise {
+        if !(0.0..=1.0).contains(&probability) {
+            return Err(PyValueError::new_err(format!(
+                "The probability of depolarizing must be between 0 and 1, not {probability}."
+            )));
+        }
+    }
+
+    let owner = builder.owner();
+    let mut gates = Vec::with_capacity(instructions.len());
+    for (index, instruction) in instructions.into_iter().enumerate() {
+        match Gate::extract(py, &owner, instruction, normalize_angles.unwrap_or(false)) {
+            Ok(gate) => gates.push(gate),
+            Err(e) => {
+                // An earlier gate that's out of range is reported first.
+                check_each_in_range(py, &builder, &gates)?;
+                return Err(at_instruction(py, index, &e));
+            }
+        }
+    }
+
+    // Reading the register sizes once is much faster than reading them for every gate, so the
+    // gates are only checked one at a time to find the position of one that's out of range.
+    let qubits: Vec<&Value> = gates
+        .iter()
+        .flat_map(|g| g.qubits.iter().map(|q| &**q))
+        .collect();
+    let results: Vec<&Value> = gates
+        .iter()
+        .flat_map(|g| g.results.iter().map(|r| &**r))
+        .collect();
+    if check_in_range(&builder, &qubits, &results).is_err() {
+        check_each_in_range(py, &builder, &gates)?;
+    }
+
+    let context = owner.context(py);
+    let context = context.borrow(py);
+    unsafe {
+        let noise = noise.map(|p| LLVMConstReal(LLVMDoubleTypeInContext(context.as_ptr()), p));
+        for gate in &gates {
+            gate.build(builder.as_ptr(), context.as_ptr(), noise);
+        }
+    }
+    Ok(())
+}
+
+/// Reduces a rotation angle modulo :math:`2\pi` into :math:`(-\pi, \pi]`. Angles that are already
+/// in range, infinity and NaN are returned unchanged.
+///
+/// :param float angle: The angle.
+/// :returns: The reduced angle.
+/// :rtype: float
+#[pyfunction]
+#[pyo3(text_signature = "(angle)")]
+pub(crate) fn normalize_angle(angle: f64) -> f64 {
+    qis::normalize_angle(angle)
+}
+
 /// Inserts a rotation gate about the :math:`x` axis.
 ///
 /// :param Builder builder: The IR Builder used to create the instructions
@@ -164,13 +421,15 @@ pub(crate) fn reset(py: Python, builder: &Builder, qubit: &Value) -> PyResult<()
 /// :rtype: None
 #[pyfunction]
 #[pyo3(text_signature = "(builder, theta, qubit)")]
-pub(crate) fn rx(py: Python, builder: &Builder, theta: Angle, qubit: &Value) -> PyResult<()> {
+pub(crate) fn rx(py: Python, builder: BuilderRef, theta: Angle, qubit: &Value) -> PyResult<()> {
+    theta.check_finite("rx", qubit)?;
     Owner::merge(
         py,
-        [Some(builder.owner()), theta.owner(), Some(qubit.owner())]
+        [Some(&*builder.owner()), theta.owner(), Some(qubit.owner())]
             .into_iter()
             .flatten(),
     )?;
+    check_in_range(&builder, &[qubit], &[])?;
 
     let context = builder.owner().context(py);
     let context = context.borrow(py);
@@ -192,13 +451,15 @@ pub(crate) fn rx(py: Python, builder: &Builder, theta: Angle, qubit: &Value) ->
 /// :rtype: None
 #[pyfunction]
 #[pyo3(text_signature = "(builder, theta, qubit)")]
-pub(crate) fn ry(py: Python, builder: &Builder, theta: Angle, qubit: &Value) -> PyResult<()> {
+pub(crate) fn ry(py: Python, builder: BuilderRef, theta: Angle, qubit: &Value) -> PyResult<()> {
+    theta.check_finite("ry", qubit)?;
     Owner::merge(
         py,
-        [Some(builder.owner()), theta.owner(), Some(qubit.owner())]
+        [Some(&*builder.owner()), theta.owner(), Some(qubit.owner())]
             .into_iter()
             .flatten(),
     )?;
+    check_in_range(&builder, &[qubit], &[])?;
 
     let context = builder.owner().context(py);
     let context = context.borrow(py);
@@ -220,13 +481,15 @@ pub(crate) fn ry(py: Python, builder: &Builder, theta: Angle, qubit: &Value) ->
 /// :rtype: None
 #[pyfunction]
 #[pyo3(text_signature = "(builder, theta, qubit)")]
-pub(crate) fn rz(py: Python, builder: &Builder, theta: Angle, qubit: &Value) -> PyResult<()> {
+pub(crate) fn rz(py: Python, builder: BuilderRef, theta: Angle, qubit: &Value) -> PyResult<()> {
+    theta.check_finite("rz", qubit)?;
     Owner::merge(
         py,
-        [Some(builder.owner()), theta.owner(), Some(qubit.owner())]
+        [Some(&*builder.owner()), theta.owner(), Some(qubit.owner())]
             .into_iter()
             .flatten(),
     )?;
+    check_in_range(&builder, &[qubit], &[])?;
 
     let context = builder.owner().context(py);
     let context = context.borrow(py);
@@ -247,8 +510,9 @@ pub(crate) fn rz(py: Python, builder: &Builder, theta: Angle, qubit: &Value) ->
 /// :rtype: None
 #[pyfunction]
 #[pyo3(text_signature = "(builder, qubit)")]
-pub(crate) fn s(py: Python, builder: &Builder, qubit: &Value) -> PyResult<()> {
-    Owner::merge(py, [builder.owner(), qubit.owner()])?;
+pub(crate) fn s(py: Python, builder: BuilderRef, qubit: &Value) -> PyResult<()> {
+    Owner::merge(py, [&*builder.owner(), qubit.owner()])?;
+    check_in_range(&builder, &[qubit], &[])?;
     unsafe {
         qis::build_s(builder.as_ptr(), qubit.as_ptr());
     }
@@ -262,8 +526,9 @@ pub(crate) fn s(py: Python, builder: &Builder, qubit: &Value) -> PyResult<()> {
 /// :rtype: None
 #[pyfunction]
 #[pyo3(text_signature = "(builder, qubit)")]
-pub(crate) fn s_adj(py: Python, builder: &Builder, qubit: &Value) -> PyResult<()> {
-    Owner::merge(py, [builder.owner(), qubit.owner()])?;
+pub(crate) fn s_adj(py: Python, builder: BuilderRef, qubit: &Value) -> PyResult<()> {
+    Owner::merge(py, [&*builder.owner(), qubit.owner()])?;
+    check_in_range(&builder, &[qubit], &[])?;
     unsafe {
         qis::build_s_adj(builder.as_ptr(), qubit.as_ptr());
     }
@@ -277,8 +542,9 @@ pub(crate) fn s_adj(py: Python, builder: &Builder, qubit: &Value) -> PyResult<()
 /// :rtype: None
 #[pyfunction]
 #[pyo3(text_signature = "(builder, qubit)")]
-pub(crate) fn t(py: Python, builder: &Builder, qubit: &Value) -> PyResult<()> {
-    Owner::merge(py, [builder.owner(), qubit.owner()])?;
+pub(crate) fn t(py: Python, builder: BuilderRef, qubit: &Value) -> PyResult<()> {
+    Owner::merge(py, [&*builder.owner(), qubit.owner()])?;
+    check_in_range(&builder, &[qubit], &[])?;
     unsafe {
         qis::build_t(builder.as_ptr(), qubit.as_ptr());
     }
@@ -292,14 +558,92 @@ pub(crate) fn t(py: Python, builder: &Builder, qubit: &Value) -> PyResult<()> {
 /// :rtype: None
 #[pyfunction]
 #[pyo3(text_signature = "(builder, qubit)")]
-pub(crate) fn t_adj(py: Python, builder: &Builder, qubit: &Value) -> PyResult<()> {
-    Owner::merge(py, [builder.owner(), qubit.owner()])?;
+pub(crate) fn t_adj(py: Python, builder: BuilderRef, qubit: &Value) -> PyResult<()> {
+    Owner::merge(py, [&*builder.owner(), qubit.owner()])?;
+    check_in_range(&builder, &[qubit], &[])?;
     unsafe {
         qis::build_t_adj(builder.as_ptr(), qubit.as_ptr());
     }
     Ok(())
 }
 
+/// Inserts a :math:`U_1(\lambda)` gate, which is the phase gate
+/// :math:`\operatorname{diag}(1, e^{i \lambda})`. This is :math:`R_z(\lambda)` up to a global
+/// phase.
+///
+/// :param Builder builder: The IR Builder used to create the instructions
+/// :param typing.Union[Value, float] lam: The phase angle.
+/// :param Value qubit: The target qubit.
+/// :rtype: None
+#[pyfunction]
+#[pyo3(text_signature = "(builder, lam, qubit)")]
+pub(crate) fn u1(py: Python, builder: BuilderRef, lam: Angle, qubit: &Value) -> PyResult<()> {
+    lam.check_finite("u1", qubit)?;
+    Owner::merge(
+        py,
+        [Some(&*builder.owner()), lam.owner(), Some(qubit.owner())]
+            .into_iter()
+            .flatten(),
+    )?;
+    check_in_range(&builder, &[qubit], &[])?;
+
+    let context = builder.owner().context(py);
+    let context = context.borrow(py);
+    unsafe {
+        qis::build_u1(
+            builder.as_ptr(),
+            lam.to_value(context.as_ptr()),
+            qubit.as_ptr(),
+        );
+    }
+    Ok(())
+}
+
+/// Inserts a :math:`U_2(\phi, \lambda)` gate, which is
+/// :math:`U_1(\phi) R_y(\pi / 2) U_1(\lambda)`.
+///
+/// :param Builder builder: The IR Builder used to create the instructions
+/// :param typing.Union[Value, float] phi: The phase angle applied last.
+/// :param typing.Union[Value, float] lam: The phase angle applied first.
+/// :param Value qubit: The target qubit.
+/// :rtype: None
+#[pyfunction]
+#[pyo3(text_signature = "(builder, phi, lam, qubit)")]
+pub(crate) fn u2(
+    py: Python,
+    builder: BuilderRef,
+    phi: Angle,
+    lam: Angle,
+    qubit: &Value,
+) -> PyResult<()> {
+    phi.check_finite("u2", qubit)?;
+    lam.check_finite("u2", qubit)?;
+    Owner::merge(
+        py,
+        [
+            Some(&*builder.owner()),
+            phi.owner(),
+            lam.owner(),
+            Some(qubit.owner()),
+        ]
+        .into_iter()
+        .flatten(),
+    )?;
+    check_in_range(&builder, &[qubit], &[])?;
+
+    let context = builder.owner().context(py);
+    let context = context.borrow(py);
+    unsafe {
+        qis::build_u2(
+            builder.as_ptr(),
+            phi.to_value(context.as_ptr()),
+            lam.to_value(context.as_ptr()),
+            qubit.as_ptr(),
+        );
+    }
+    Ok(())
+}
+
 /// Inserts a Pauli :math:`X` gate.
 ///
 /// :param Builder builder: The IR Builder used to create the instructions
@@ -307,8 +651,9 @@ pub(crate) fn t_adj(py: Python, builder: &Builder, qubit: &Value) -> PyResult<()
 /// :rtype: None
 #[pyfunction]
 #[pyo3(text_signature = "(builder, qubit)")]
-pub(crate) fn x(py: Python, builder: &Builder, qubit: &Value) -> PyResult<()> {
-    Owner::merge(py, [builder.owner(), qubit.owner()])?;
+pub(crate) fn x(py: Python, builder: BuilderRef, qubit: &Value) -> PyResult<()> {
+    Owner::merge(py, [&*builder.owner(), qubit.owner()])?;
+    check_in_range(&builder, &[qubit], &[])?;
     unsafe {
         qis::build_x(builder.as_ptr(), qubit.as_ptr());
     }
@@ -322,8 +667,9 @@ pub(crate) fn x(py: Python, builder: &Builder, qubit: &Value) -> PyResult<()> {
 /// :rtype: None
 #[pyfunction]
 #[pyo3(text_signature = "(builder, qubit)")]
-pub(crate) fn y(py: Python, builder: &Builder, qubit: &Value) -> PyResult<()> {
-    Owner::merge(py, [builder.owner(), qubit.owner()])?;
+pub(crate) fn y(py: Python, builder: BuilderRef, qubit: &Value) -> PyResult<()> {
+    Owner::merge(py, [&*builder.owner(), qubit.owner()])?;
+    check_in_range(&builder, &[qubit], &[])?;
     unsafe {
         qis::build_y(builder.as_ptr(), qubit.as_ptr());
     }
@@ -337,8 +683,9 @@ pub(crate) fn y(py: Python, builder: &Builder, qubit: &Value) -> PyResult<()> {
 /// :rtype: None
 #[pyfunction]
 #[pyo3(text_signature = "(builder, qubit)")]
-pub(crate) fn z(py: Python, builder: &Builder, qubit: &Value) -> PyResult<()> {
-    Owner::merge(py, [builder.owner(), qubit.owner()])?;
+pub(crate) fn z(py: Python, builder: BuilderRef, qubit: &Value) -> PyResult<()> {
+    Owner::merge(py, [&*builder.owner(), qubit.owner()])?;
+    check_in_range(&builder, &[qubit], &[])?;
     unsafe {
         qis::build_z(builder.as_ptr(), qubit.as_ptr());
     }
@@ -349,42 +696,289 @@ pub(crate) fn z(py: Python, builder: &Builder, qubit: &Value) -> PyResult<()> {
 ///
 /// Instructions inserted when ``one`` is called will be inserted into the one branch.
 /// Instructions inserted when ``zero`` is called will be inserted into the zero branch. The one
-/// and zero callables should use this module's builder to build instructions.
+/// and zero callables should use this module's builder to build instructions. A branch that is
+/// ``None`` is left empty, and if both are, the result is still read and branched on.
+///
+/// The callables may use the builder in any way, including moving it, as long as they leave it at
+/// the end of an unterminated block in the same function. Otherwise a
+/// :class:`BuilderReentrancyError` is raised.
 ///
 /// :param Builder builder: The IR Builder used to create the instructions
 /// :param Value cond: The result condition to branch on.
-/// :param typing.Callable[[], None] one:
+/// :param typing.Optional[typing.Callable[[], None]] one:
 ///     A callable that inserts instructions for the branch where the result is one.
-/// :param typing.Callable[[], None] zero:
+/// :param typing.Optional[typing.Callable[[], None]] zero:
 ///     A callable that inserts instructions for the branch where the result is zero.
+/// :param typing.Optional[str] label: A prefix for the names of the blocks of the branch, like
+///     ``correct_then``, ``correct_else`` and ``correct_continue`` for ``"correct"``. Without
+///     a label, the blocks are named ``then``, ``else`` and ``continue``, and LLVM numbers the
+///     names that are already taken.
 /// :rtype: None
 #[pyfunction]
-#[pyo3(text_signature = "(builder, cond, one, zero)")]
+#[pyo3(text_signature = "(builder, cond, one, zero, label)")]
 pub(crate) fn if_result(
     py: Python,
-    builder: &Builder,
+    builder: BuilderRef,
     cond: &Value,
     one: Option<&PyAny>,
     zero: Option<&PyAny>,
+    label: Option<&str>,
 ) -> PyResult<()> {
-    Owner::merge(py, [builder.owner(), cond.owner()])?;
+    builder::check_branch("one", one)?;
+    builder::check_branch("zero", zero)?;
+    if let Some(label) = label {
+        if label.is_empty() || label.contains('\0') {
+            return Err(PyValueError::new_err(format!(
+                "Label must be non-empty and have no null characters, not {label:?}."
+            )));
+        }
+    }
+    Owner::merge(py, [&*builder.owner(), cond.owner()])?;
+    check_in_range(&builder, &[], &[cond])?;
     unsafe {
         qis::try_build_if_result(
             builder.as_ptr(),
             cond.as_ptr(),
-            || one.iter().try_for_each(|f| f.call0().map(|_| ())),
-            || zero.iter().try_for_each(|f| f.call0().map(|_| ())),
+            label,
+            || builder::build_branch(builder.as_ptr(), one),
+            || builder::build_branch(builder.as_ptr(), zero),
         )
     }
 }
 
-#[derive(FromPyObject)]
+/// A gate from :func:`extend` that has been checked and is ready to insert.
+struct Gate<'py> {
+    intrinsic: Intrinsic,
+    angles: Vec<Angle<'py>>,
+    qubits: Vec<PyRef<'py, Value>>,
+    results: Vec<PyRef<'py, Value>>,
+}
+
+impl<'py> Gate<'py> {
+    fn extract(
+        py: Python,
+        owner: &Owner,
+        instruction: &'py PyAny,
+        normalize_angles: bool,
+    ) -> PyResult<Self> {
+        let tuple: &PyTuple = instruction.downcast().map_err(|_| {
+            PyTypeError::new_err(format!(
+                "Expected a tuple, not {}.",
+                instruction.get_type().name().unwrap_or("?")
+            ))
+        })?;
+        let name: &str = tuple
+            .get_item(0)
+            .map_err(|_| PyValueError::new_err("Expected a gate name, not an empty tuple."))?
+            .extract()?;
+        let (intrinsic, num_angles, num_qubits, num_results) = gate_signature(name)
+            .ok_or_else(|| PyValueError::new_err(format!("Unknown gate {name:?}.")))?;
+        let num_args = num_angles + num_qubits + num_results;
+        if tuple.len() != num_args + 1 {
+            return Err(PyValueError::new_err(format!(
+                "Gate {name} takes {num_args} arguments, not {}.",
+                tuple.len() - 1
+            )));
+        }
+
+        let args: Vec<_> = tuple.iter().skip(1).collect();
+        let angles = args[..num_angles]
+            .iter()
+            .map(|a| match a.extract()? {
+                Angle::Constant(angle) if normalize_angles => {
+                    Ok(Angle::Constant(qis::normalize_angle(angle)))
+                }
+                angle => Ok(angle),
+            })
+            .collect::<PyResult<Vec<_>>>()?;
+        let qubits = args[num_angles..num_angles + num_qubits]
+            .iter()
+            .map(|q| q.extract())
+            .collect::<PyResult<Vec<PyRef<Value>>>>()?;
+        let results = args[num_angles + num_qubits..]
+            .iter()
+            .map(|r| r.extract())
+            .collect::<PyResult<Vec<PyRef<Value>>>>()?;
+
+        if let Some(qubit) = qubits.last() {
+            for angle in &angles {
+                angle.check_finite(name, qubit)?;
+            }
+        }
+        Owner::merge(
+            py,
+            [owner]
+                .into_iter()
+                .chain(angles.iter().filter_map(Angle::owner))
+                .chain(qubits.iter().chain(&results).map(|v| v.owner())),
+        )?;
+        if matches!(
+            intrinsic,
+            Intrinsic::Ccx | Intrinsic::Ch | Intrinsic::Cx | Intrinsic::Cy | Intrinsic::Cz
+        ) {
+            check_distinct(&qubits.iter().map(|q| &**q).collect::<Vec<_>>())?;
+        }
+
+        Ok(Self {
+            intrinsic,
+            angles,
+            qubits,
+            results,
+        })
+    }
+
+    unsafe fn build(
+        &self,
+        builder: LLVMBuilderRef,
+        context: LLVMContextRef,
+        noise: Option<LLVMValueRef>,
+    ) {
+        let a: Vec<_> = self.angles.iter().map(|a| a.to_value(context)).collect();
+        let q: Vec<_> = self.qubits.iter().map(|q| q.as_ptr()).collect();
+        match self.intrinsic {
+            Intrinsic::Barrier => qis::build_barrier(builder),
+            Intrinsic::Ccx => qis::build_ccx(builder, q[0], q[1], q[2]),
+            Intrinsic::Ch => qis::build_ch(builder, q[0], q[1]),
+            Intrinsic::Cx => qis::build_cx(builder, q[0], q[1]),
+            Intrinsic::Cy => qis::build_cy(builder, q[0], q[1]),
+            Intrinsic::Cz => qis::build_cz(builder, q[0], q[1]),
+            Intrinsic::H => qis::build_h(builder, q[0]),
+            Intrinsic::Id => qis::build_id(builder, q[0]),
+            Intrinsic::Mz => qis::build_mz(builder, q[0], self.results[0].as_ptr()),
+            Intrinsic::Reset => qis::build_reset(builder, q[0]),
+            Intrinsic::Rx => qis::build_rx(builder, a[0], q[0]),
+            Intrinsic::Ry => qis::build_ry(builder, a[0], q[0]),
+            Intrinsic::Rz => qis::build_rz(builder, a[0], q[0]),
+            Intrinsic::S => qis::build_s(builder, q[0]),
+            Intrinsic::SAdj => qis::build_s_adj(builder, q[0]),
+            Intrinsic::Swap => qis::build_swap(builder, q[0], q[1]),
+            Intrinsic::T => qis::build_t(builder, q[0]),
+            Intrinsic::TAdj => qis::build_t_adj(builder, q[0]),
+            Intrinsic::U1 => qis::build_u1(builder, a[0], q[0]),
+            Intrinsic::U2 => qis::build_u2(builder, a[0], a[1], q[0]),
+            Intrinsic::X => qis::build_x(builder, q[0]),
+            Intrinsic::Y => qis::build_y(builder, q[0]),
+            Intrinsic::Z => qis::build_z(builder, q[0]),
+            Intrinsic::Depolarize | Intrinsic::M | Intrinsic::ReadResult => {
+                unreachable!("{:?} isn't supported by extend.", self.intrinsic)
+            }
+        }
+
+        if let Some(probability) = noise {
+            if !matches!(
+                self.intrinsic,
+                Intrinsic::Barrier | Intrinsic::Mz | Intrinsic::Reset
+            ) {
+                for &qubit in &q {
+                    qis::build_depolarize(builder, probability, qubit);
+                }
+            }
+        }
+    }
+}
+
+fn check_each_in_range(py: Python, builder: &Builder, gates: &[Gate]) -> PyResult<()> {
+    for (index, gate) in gates.iter().enumerate() {
+        let qubits: Vec<&Value> = gate.qubits.iter().map(|q| &**q).collect();
+        let results: Vec<&Value> = gate.results.iter().map(|r| &**r).collect();
+        check_in_range(builder, &qubits, &results).map_err(|e| at_instruction(py, index, &e))?;
+    }
+    Ok(())
+}
+
+fn at_instruction(py: Python, index: usize, error: &PyErr) -> PyErr {
+    PyErr::from_type(
+        error.get_type(py),
+        format!("Instruction {index}: {}", error.value(py)),
+    )
+}
+
+/// The intrinsic that a gate name in :func:`extend` refers to, and its numbers of angle, qubit
+/// and result arguments.
+fn gate_signature(name: &str) -> Option<(Intrinsic, usize, usize, usize)> {
+    match name {
+        "barrier" => Some((Intrinsic::Barrier, 0, 0, 0)),
+        "ccx" => Some((Intrinsic::Ccx, 0, 3, 0)),
+        "ch" => Some((Intrinsic::Ch, 0, 2, 0)),
+        "cx" => Some((Intrinsic::Cx, 0, 2, 0)),
+        "cy" => Some((Intrinsic::Cy, 0, 2, 0)),
+        "cz" => Some((Intrinsic::Cz, 0, 2, 0)),
+        "h" => Some((Intrinsic::H, 0, 1, 0)),
+        "id" => Some((Intrinsic::Id, 0, 1, 0)),
+        "mz" => Some((Intrinsic::Mz, 0, 1, 1)),
+        "reset" => Some((Intrinsic::Reset, 0, 1, 0)),
+        "rx" => Some((Intrinsic::Rx, 1, 1, 0)),
+        "ry" => Some((Intrinsic::Ry, 1, 1, 0)),
+        "rz" => Some((Intrinsic::Rz, 1, 1, 0)),
+        "s" => Some((Intrinsic::S, 0, 1, 0)),
+        "s_adj" => Some((Intrinsic::SAdj, 0, 1, 0)),
+        "swap" => Some((Intrinsic::Swap, 0, 2, 0)),
+        "t" => Some((Intrinsic::T, 0, 1, 0)),
+        "t_adj" => Some((Intrinsic::TAdj, 0, 1, 0)),
+        "u1" => Some((Intrinsic::U1, 1, 1, 0)),
+        "u2" => Some((Intrinsic::U2, 2, 1, 0)),
+        "x" => Some((Intrinsic::X, 0, 1, 0)),
+        "y" => Some((Intrinsic::Y, 0, 1, 0)),
+        "z" => Some((Intrinsic::Z, 0, 1, 0)),
+        _ => None,
+    }
+}
+
+/// Checks that the qubits of a controlled gate are distinct. Every use of a qubit is the same value,
+/// so repeats of static and dynamic qubits are both found by comparing pointers.
+fn check_distinct(qubits: &[&Value]) -> PyResult<()> {
+    for (i, qubit) in qubits.iter().enumerate() {
+        if qubits[i + 1..].iter().any(|q| q.as_ptr() == qubit.as_ptr()) {
+            return Err(PyValueError::new_err(
+                "The qubits of a controlled gate must be distinct.",
+            ));
+        }
+    }
+    Ok(())
+}
+
 pub(crate) enum Angle<'py> {
     Value(PyRef<'py, Value>),
     Constant(f64),
 }
 
+impl<'py> FromPyObject<'py> for Angle<'py> {
+    fn extract(ob: &'py PyAny) -> PyResult<Self> {
+        if let Ok(value) = ob.extract() {
+            return Ok(Angle::Value(value));
+        }
+
+        ob.extract().map(Angle::Constant)
+    }
+}
+
 impl Angle<'_> {
+    /// Checks that a constant angle is finite, since NaN and infinity are only rejected by some
+    /// consumers of the IR, and much later. Both floats and constant float values are checked.
+    fn check_finite(&self, gate: &str, qubit: &Value) -> PyResult<()> {
+        let angle = match self {
+            &Angle::Constant(angle) => angle,
+            Angle::Value(v) => unsafe {
+                if LLVMIsAConstantFP(v.as_ptr()).is_null() {
+                    return Ok(());
+                }
+                LLVMConstRealGetDouble(v.as_ptr(), &mut 0)
+            },
+        };
+
+        if angle.is_finite() {
+            Ok(())
+        } else {
+            let qubit = match unsafe { values::qubit_id(qubit.as_ptr()) } {
+                Some(id) => format!("qubit {id}"),
+                None => "a dynamic qubit".to_string(),
+            };
+            Err(PyValueError::new_err(format!(
+                "Angle of {gate} on {qubit} must be finite, not {angle}."
+            )))
+        }
+    }
+
     fn owner(&self) -> Option<&Owner> {
         match self {
             Angle::Value(v) => Some(v.owner()),
@@ -2,7 +2,7 @@
 // Licensed under the MIT License.
 
 use crate::{
-    builder::Builder,
+    builder::{check_in_range, BuilderRef},
     values::{Owner, Value},
 };
 use pyo3::prelude::*;
@@ -19,18 +19,38 @@ use qirlib::rt;
 #[allow(clippy::needless_pass_by_value)]
 pub(crate) fn array_record_output(
     py: Python,
-    builder: Py<Builder>,
+    builder: BuilderRef,
     num_elements: &Value,
     label: &Value,
 ) -> PyResult<()> {
-    let builder: PyRef<Builder> = builder.borrow(py);
-    Owner::merge(py, [builder.owner(), num_elements.owner(), label.owner()])?;
+    Owner::merge(py, [&*builder.owner(), num_elements.owner(), label.owner()])?;
     unsafe {
         rt::build_array_record_output(builder.as_ptr(), num_elements.as_ptr(), label.as_ptr());
     }
     Ok(())
 }
 
+/// Adds an integer to the generated output.
+///
+/// :param Builder builder: The IR Builder used to create the instructions
+/// :param Value value: An i64 value to record
+/// :param Value label: A string label for the integer value. Depending on the output schema, the label is included in the output or omitted.
+#[pyfunction]
+#[pyo3(text_signature = "(builder, value, label)")]
+#[allow(clippy::needless_pass_by_value)]
+pub(crate) fn int_record_output(
+    py: Python,
+    builder: BuilderRef,
+    value: &Value,
+    label: &Value,
+) -> PyResult<()> {
+    Owner::merge(py, [&*builder.owner(), value.owner(), label.owner()])?;
+    unsafe {
+        rt::build_int_record_output(builder.as_ptr(), value.as_ptr(), label.as_ptr());
+    }
+    Ok(())
+}
+
 /// Adds a measurement result to the generated output.
 ///
 /// :param Builder builder: The IR Builder used to create the instructions
@@ -41,12 +61,12 @@ pub(crate) fn array_record_output(
 #[allow(clippy::needless_pass_by_value)]
 pub(crate) fn result_record_output(
     py: Python,
-    builder: Py<Builder>,
+    builder: BuilderRef,
     result: &Value,
     label: &Value,
 ) -> PyResult<()> {
-    let builder = builder.borrow(py);
-    Owner::merge(py, [builder.owner(), result.owner(), label.owner()])?;
+    Owner::merge(py, [&*builder.owner(), result.owner(), label.owner()])?;
+    check_in_range(&builder, &[], &[result])?;
     unsafe {
         rt::build_result_record_output(builder.as_ptr(), result.as_ptr(), label.as_ptr());
     }
@@ -61,9 +81,8 @@ pub(crate) fn result_record_output(
 #[pyfunction]
 #[pyo3(text_signature = "(builder, data)")]
 #[allow(clippy::needless_pass_by_value)]
-pub(crate) fn initialize(py: Python, builder: Py<Builder>, data: &Value) -> PyResult<()> {
-    let builder = builder.borrow(py);
-    Owner::merge(py, [builder.owner(), data.owner()])?;
+pub(crate) fn initialize(py: Python, builder: BuilderRef, data: &Value) -> PyResult<()> {
+    Owner::merge(py, [&*builder.owner(), data.owner()])?;
     unsafe {
         rt::build_initialize(builder.as_ptr(), data.as_ptr());
     }
@@ -81,12 +100,11 @@ pub(crate) fn initialize(py: Python, builder: Py<Builder>, data: &Value) -> PyRe
 #[allow(clippy::needless_pass_by_value)]
 pub(crate) fn tuple_record_output(
     py: Python,
-    builder: Py<Builder>,
+    builder: BuilderRef,
     num_elements: &Value,
     label: &Value,
 ) -> PyResult<()> {
-    let builder = builder.borrow(py);
-    Owner::merge(py, [builder.owner(), num_elements.owner(), label.owner()])?;
+    Owner::merge(py, [&*builder.owner(), num_elements.owner(), label.owner()])?;
     unsafe {
         rt::build_tuple_record_output(builder.as_ptr(), num_elements.as_ptr(), label.as_ptr());
     }
@@ -315,6 +315,31 @@ impl PointerType {
     }
 }
 
+/// The QIR array type, a pointer to the opaque ``%Array`` struct that the runtime manages.
+///
+/// :param Context context: The LLVM context.
+/// :returns: The array type.
+/// :rtype: Type
+#[pyfunction]
+#[pyo3(text_signature = "(context)")]
+pub(crate) fn array_type(py: Python, context: Py<Context>) -> PyResult<PyObject> {
+    unsafe {
+        let ty = types::array(context.borrow(py).as_ptr());
+        Type::from_raw(py, context, ty)
+    }
+}
+
+/// Whether the type is the QIR array type.
+///
+/// :param Type ty: The type.
+/// :returns: True if the type is the QIR array type.
+/// :rtype: bool
+#[pyfunction]
+#[pyo3(text_signature = "(ty)")]
+pub(crate) fn is_array_type(ty: &Type) -> bool {
+    unsafe { types::is_array(ty.as_ptr()) }
+}
+
 /// The QIR qubit type.
 ///
 /// :param Context context: The LLVM context.
@@ -5,8 +5,9 @@
 
 use crate::{
     core::Context,
-    core::Message,
+    core::{c_name, Message},
     instructions::Instruction,
+    metadata::Metadata,
     module::{Linkage, Module},
     types::{FunctionType, Type},
 };
@@ -28,7 +29,6 @@ use std::{
     borrow::Borrow,
     collections::hash_map::DefaultHasher,
     convert::{Into, TryInto},
-    ffi::CString,
     hash::{Hash, Hasher},
     ops::Deref,
     ptr::NonNull,
@@ -259,7 +259,7 @@ impl BasicBlock {
 
         let block = {
             let context = context.borrow(py);
-            let name = CString::new(name).unwrap();
+            let name = c_name(name)?;
             match (parent, before) {
                 (None, None) => Err(PyValueError::new_err("Can't create block without parent.")),
                 (Some(parent), None) => Ok(unsafe {
@@ -317,6 +317,20 @@ impl BasicBlock {
             }
         }
     }
+
+    /// Removes this basic block from its parent function, then deletes it and its instructions
+    /// from memory. Instructions in other blocks must not use the instructions in this block.
+    ///
+    /// .. warning:: Using this basic block or its instructions after erasing it is undefined
+    ///     behavior.
+    ///
+    /// :rtype: None
+    #[allow(clippy::doc_markdown)]
+    fn erase(&self) {
+        unsafe {
+            LLVMDeleteBasicBlock(self.as_ptr());
+        }
+    }
 }
 
 impl BasicBlock {
@@ -449,7 +463,7 @@ impl Function {
             ],
         )?;
 
-        let name = CString::new(name).unwrap();
+        let name = c_name(name)?;
         unsafe {
             let value = NonNull::new(LLVMAddFunction(
                 module.borrow(py).as_ptr(),
@@ -624,12 +638,11 @@ impl AttributeSet {
     /// :rtype: Attribute
     fn __getitem__(&self, py: Python, key: &str) -> PyResult<Attribute> {
         let function = self.function.borrow(py).into_super().into_super();
-        let kind = CString::new(key).unwrap();
         let attr = unsafe {
             LLVMGetStringAttributeAtIndex(
                 function.as_ptr(),
                 self.index,
-                kind.as_ptr(),
+                key.as_ptr().cast(),
                 key.len().try_into().unwrap(),
             )
         };
@@ -814,7 +827,7 @@ pub(crate) fn qir_module(
     dynamic_qubit_management: Option<bool>,
     dynamic_result_management: Option<bool>,
 ) -> PyResult<PyObject> {
-    let module = crate::module::Module::new(py, context, name);
+    let module = crate::module::Module::new(py, context, name)?;
     let ptr = module.as_ptr();
     unsafe {
         qirlib::module::set_qir_major_version(ptr, qir_major_version.unwrap_or(1));
@@ -881,6 +894,36 @@ pub(crate) fn global_byte_string(py: Python, module: &Module, value: &[u8]) -> P
     }
 }
 
+/// Adds a compile unit to the module's debug info, identifying the source file that the module was
+/// generated from. Every function defined in the module is given a subprogram, so that instructions
+/// in it can be given source locations with :meth:`Builder.set_debug_location`.
+///
+/// :param Module module: The module.
+/// :param str filename: The name of the source file.
+/// :param str directory: The directory containing the source file.
+/// :param str producer: The tool that generated the module.
+/// :returns: The compile unit.
+/// :rtype: Metadata
+#[pyfunction]
+#[pyo3(text_signature = "(module, filename, directory, producer)")]
+pub(crate) fn add_compile_unit(
+    py: Python,
+    module: Py<Module>,
+    filename: &str,
+    directory: &str,
+    producer: &str,
+) -> PyResult<PyObject> {
+    unsafe {
+        let compile_unit = qirlib::debug::add_compile_unit(
+            module.borrow(py).as_ptr(),
+            filename,
+            directory,
+            producer,
+        );
+        Metadata::from_raw(py, module.into(), compile_unit)
+    }
+}
+
 /// If the value is a pointer to a constant byte string, extracts it.
 ///
 /// :param Value value: The value.
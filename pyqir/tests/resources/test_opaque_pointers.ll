@@ -0,0 +1,41 @@
+; ModuleID = 'test_pointers'
+source_filename = "test_pointers"
+
+define void @main() #0 {
+entry:
+  call void @__quantum__qis__h__body(ptr null)
+  call void @__quantum__qis__cnot__body(ptr null, ptr inttoptr (i64 1 to ptr))
+  call void @__quantum__qis__mz__body(ptr inttoptr (i64 1 to ptr), ptr null)
+  %0 = call i1 @__quantum__qis__read_result__body(ptr null)
+  br i1 %0, label %then, label %else
+
+then:                                             ; preds = %entry
+  call void @__quantum__qis__x__body(ptr inttoptr (i64 1 to ptr))
+  br label %continue
+
+else:                                             ; preds = %entry
+  br label %continue
+
+continue:                                         ; preds = %else, %then
+  ret void
+}
+
+declare void @__quantum__qis__h__body(ptr)
+
+declare void @__quantum__qis__cnot__body(ptr, ptr)
+
+declare void @__quantum__qis__mz__body(ptr, ptr writeonly) #1
+
+declare i1 @__quantum__qis__read_result__body(ptr)
+
+declare void @__quantum__qis__x__body(ptr)
+
+attributes #0 = { "entry_point" "output_labeling_schema" "qir_profiles"="custom" "required_num_qubits"="2" "required_num_results"="1" }
+attributes #1 = { "irreversible" }
+
+!llvm.module.flags = !{!0, !1, !2, !3}
+
+!0 = !{i32 1, !"qir_major_version", i32 1}
+!1 = !{i32 7, !"qir_minor_version", i32 0}
+!2 = !{i32 1, !"dynamic_qubit_management", i1 false}
+!3 = !{i32 1, !"dynamic_result_management", i1 false}
@@ -0,0 +1,143 @@
+# Copyright (c) Microsoft Corporation.
+# Licensed under the MIT License.
+
+from typing import Callable, List, Tuple
+
+import pytest
+
+import pyqir
+import pyqir.qis as qis
+from pyqir import Builder, SimpleModule, Value
+
+Gate = Callable[[Builder, List[Value]], None]
+
+# Each gate with the name of the function that its adjoint calls.
+_GATES: List[Tuple[Gate, str]] = [
+    (lambda b, q: qis.barrier(b), "__quantum__qis__barrier__body"),
+    (lambda b, q: qis.ccx(b, q[0], q[1], q[2]), "__quantum__qis__ccx__body"),
+    (lambda b, q: qis.cx(b, q[0], q[1]), "__quantum__qis__cnot__body"),
+    (lambda b, q: qis.cz(b, q[0], q[1]), "__quantum__qis__cz__body"),
+    (lambda b, q: qis.h(b, q[0]), "__quantum__qis__h__body"),
+    (lambda b, q: qis.rx(b, 0.7, q[0]), "__quantum__qis__rx__body"),
+    (lambda b, q: qis.ry(b, -1.3, q[1]), "__quantum__qis__ry__body"),
+    (lambda b, q: qis.rz(b, 2.5, q[2]), "__quantum__qis__rz__body"),
+    (lambda b, q: qis.s(b, q[0]), "__quantum__qis__s__adj"),
+    (lambda b, q: qis.s_adj(b, q[0]), "__quantum__qis__s__body"),
+    (lambda b, q: qis.swap(b, q[0], q[2]), "__quantum__qis__swap__body"),
+    (lambda b, q: qis.t(b, q[1]), "__quantum__qis__t__adj"),
+    (lambda b, q: qis.t_adj(b, q[1]), "__quantum__qis__t__body"),
+    (lambda b, q: qis.u1(b, 0.9, q[2]), "__quantum__qis__u1__body"),
+    (lambda b, q: qis.u2(b, 0.4, -2.1, q[1]), "__quantum__qis__u2__body"),
+    (lambda b, q: qis.x(b, q[0]), "__quantum__qis__x__body"),
+    (lambda b, q: qis.y(b, q[0]), "__quantum__qis__y__body"),
+    (lambda b, q: qis.z(b, q[0]), "__quantum__qis__z__body"),
+]
+
+
+def _circuit(*gates: Gate) -> SimpleModule:
+    simple = SimpleModule("test_adjoint", 3, 1)
+    for gate in gates:
+        gate(simple.builder, simple.qubits)
+    return simple
+
+
+def _calls(simple: SimpleModule) -> List[pyqir.Call]:
+    return [i for i in simple.entry_block.instructions if isinstance(i, pyqir.Call)]
+
+
+def _assert_identity(matrix: List[List[complex]]) -> None:
+    for i, row in enumerate(matrix):
+        for j, x in enumerate(row):
+            assert abs(x - (1 if i == j else 0)) < 1e-9
+
+
+@pytest.mark.parametrize("gate, adjoint_name", _GATES)
+def test_gate_adjoint(gate: Gate, adjoint_name: str) -> None:
+    simple = _circuit(gate)
+    adjoint = simple.adjoint()
+    [call] = _calls(adjoint)
+    assert call.callee.name == adjoint_name
+
+    expected = simple.to_matrix()
+    actual = adjoint.to_matrix()
+    for i, row in enumerate(actual):
+        for j, x in enumerate(row):
+            assert abs(x - expected[j][i].conjugate()) < 1e-9
+
+
+@pytest.mark.parametrize("gate, adjoint_name", _GATES)
+def test_gate_mirror_is_identity(gate: Gate, adjoint_name: str) -> None:
+    simple = _circuit(gate)
+    identity = {i: i for i in range(3)}
+    simple.extend(simple.adjoint(), identity, {0: 0})
+    _assert_identity(simple.to_matrix())
+
+
+def test_rotation_angle_is_negated() -> None:
+    adjoint = _circuit(lambda b, q: qis.rx(b, 0.5, q[1])).adjoint()
+    assert (
+        "call void @__quantum__qis__rx__body(double -5.000000e-01, %Qubit* inttoptr (i64 1"
+        in adjoint.ir()
+    )
+
+
+def test_gates_are_reversed() -> None:
+    simple = _circuit(*(gate for gate, _ in _GATES))
+    adjoint = simple.adjoint()
+    names = [call.callee.name for call in _calls(adjoint)]
+    assert names == [name for _, name in reversed(_GATES)]
+
+    simple.extend(adjoint, {i: i for i in range(3)}, {0: 0})
+    _assert_identity(simple.to_matrix())
+
+
+def test_original_is_unchanged() -> None:
+    simple = _circuit(lambda b, q: qis.h(b, q[0]), lambda b, q: qis.s(b, q[0]))
+    ir = simple.ir()
+    simple.adjoint()
+    assert simple.ir() == ir
+
+
+def test_adjoint_can_be_built() -> None:
+    adjoint = _circuit(lambda b, q: qis.s(b, q[0])).adjoint()
+    qis.x(adjoint.builder, adjoint.qubits[0])
+    names = [call.callee.name for call in _calls(adjoint)]
+    assert names == ["__quantum__qis__s__adj", "__quantum__qis__x__body"]
+
+
+def test_measurement_has_no_adjoint() -> None:
+    simple = _circuit(lambda b, q: qis.h(b, q[0]))
+    qis.mz(simple.builder, simple.qubits[0], simple.results[0])
+    with pytest.raises(
+        ValueError, match="^Circuits with measurements don't have an adjoint.$"
+    ):
+        simple.adjoint()
+
+
+def test_reset_has_no_adjoint() -> None:
+    simple = _circuit(lambda b, q: qis.reset(b, q[0]))
+    with pytest.raises(
+        ValueError, match="^Circuits with resets don't have an adjoint.$"
+    ):
+        simple.adjoint()
+
+
+def test_conditional_has_no_adjoint() -> None:
+    simple = _circuit()
+    qis.if_result(
+        simple.builder,
+        simple.results[0],
+        one=lambda: qis.x(simple.builder, simple.qubits[0]),
+    )
+    with pytest.raises(
+        ValueError, match="^Circuits with control flow don't have an adjoint.$"
+    ):
+        simple.adjoint()
+
+
+def test_unknown_function_has_no_adjoint() -> None:
+    simple = _circuit()
+    ty = pyqir.FunctionType(pyqir.Type.void(simple.context), [])
+    simple.builder.call(simple.add_external_function("foo", ty), [])
+    with pytest.raises(ValueError, match="^Function foo has no adjoint.$"):
+        simple.adjoint()
@@ -0,0 +1,266 @@
+# Copyright (c) Microsoft Corporation.
+# Licensed under the MIT License.
+
+import math
+from typing import Callable, List
+
+import pytest
+
+import pyqir
+from pyqir import BasicQisBuilder, SimpleModule, Value
+
+
+def _assert_close(actual: List[List[complex]], expected: List[List[complex]]) -> None:
+    assert len(actual) == len(expected)
+    for actual_row, expected_row in zip(actual, expected):
+        assert len(actual_row) == len(expected_row)
+        for a, e in zip(actual_row, expected_row):
+            assert abs(a - e) < 1e-9
+
+
+def test_empty_circuit_is_identity() -> None:
+    simple = SimpleModule("test_to_matrix", 2, 0)
+    assert simple.to_matrix() == [
+        [1, 0, 0, 0],
+        [0, 1, 0, 0],
+        [0, 0, 1, 0],
+        [0, 0, 0, 1],
+    ]
+
+
+def test_bell_circuit() -> None:
+    simple = SimpleModule("test_to_matrix", 2, 0)
+    qis = BasicQisBuilder(simple.builder)
+    qis.h(simple.qubits[0])
+    qis.cx(simple.qubits[0], simple.qubits[1])
+    h = 1 / math.sqrt(2)
+    _assert_close(
+        simple.to_matrix(),
+        [[h, h, 0, 0], [0, 0, h, -h], [0, 0, h, h], [h, -h, 0, 0]],
+    )
+
+
+def test_gates_are_unitary() -> None:
+    simple = SimpleModule("test_to_matrix", 3, 0)
+    qis = BasicQisBuilder(simple.builder)
+    q = simple.qubits
+    qis.h(q[0])
+    qis.t(q[1])
+    pyqir.qis.ccx(simple.builder, q[0], q[1], q[2])
+    qis.rx(0.5, q[2])
+    pyqir.qis.swap(simple.builder, q[0], q[2])
+    qis.s_adj(q[1])
+    qis.ry(1.5, q[0])
+    matrix = simple.to_matrix()
+    identity = [[complex(i == j) for j in range(8)] for i in range(8)]
+    product = [
+        [
+            sum(matrix[k][i].conjugate() * matrix[k][j] for k in range(8))
+            for j in range(8)
+        ]
+        for i in range(8)
+    ]
+    _assert_close(product, identity)
+
+
+def test_rotation() -> None:
+    simple = SimpleModule("test_to_matrix", 1, 0)
+    BasicQisBuilder(simple.builder).rz(math.pi, simple.qubits[0])
+    _assert_close(simple.to_matrix(), [[-1j, 0], [0, 1j]])
+
+
+def test_dynamic_rotation_angle() -> None:
+    simple = SimpleModule("test_to_matrix", 1, 0)
+    double = pyqir.Type.double(simple.context)
+    get_angle = simple.add_external_function(
+        "get_angle", pyqir.FunctionType(double, [])
+    )
+    angle = simple.builder.call(get_angle, [])
+    assert angle is not None
+    BasicQisBuilder(simple.builder).rx(angle, simple.qubits[0])
+    with pytest.raises(ValueError, match="^Rotation angles must be constant.$"):
+        simple.to_matrix()
+
+
+def test_measurement_is_not_unitary() -> None:
+    simple = SimpleModule("test_to_matrix", 1, 1)
+    qis = BasicQisBuilder(simple.builder)
+    qis.h(simple.qubits[0])
+    qis.mz(simple.qubits[0], simple.results[0])
+    with pytest.raises(
+        ValueError, match="^Circuits with measurements aren't unitary.$"
+    ):
+        simple.to_matrix()
+
+
+def test_reset_is_not_unitary() -> None:
+    simple = SimpleModule("test_to_matrix", 1, 0)
+    BasicQisBuilder(simple.builder).reset(simple.qubits[0])
+    with pytest.raises(ValueError, match="^Circuits with resets aren't unitary.$"):
+        simple.to_matrix()
+
+
+@pytest.mark.parametrize("num_qubits", [11, 20])
+def test_too_many_qubits(num_qubits: int) -> None:
+    simple = SimpleModule("test_to_matrix", num_qubits, 0)
+    with pytest.raises(ValueError, match="limited to 10 qubits"):
+        simple.to_matrix()
+
+
+_PAULIS = {
+    "x": [[0, 1], [1, 0]],
+    "y": [[0, -1j], [1j, 0]],
+    "z": [[1, 0], [0, -1]],
+}
+
+
+@pytest.mark.parametrize("paulis", ["xx", "xy", "xz", "yy", "yz", "zz"])
+def test_two_qubit_rotation(paulis: str) -> None:
+    simple = SimpleModule("test_to_matrix", 2, 0)
+    qis = BasicQisBuilder(simple.builder)
+    theta = 0.7
+    getattr(qis, f"r{paulis}")(theta, simple.qubits[0], simple.qubits[1])
+
+    # Qubit 0 is the least significant bit of the index.
+    p0 = _PAULIS[paulis[0]]
+    p1 = _PAULIS[paulis[1]]
+    c = math.cos(theta / 2)
+    s = math.sin(theta / 2)
+    expected = [
+        [
+            (c if i == j else 0) - 1j * s * p1[i >> 1][j >> 1] * p0[i & 1][j & 1]
+            for j in range(4)
+        ]
+        for i in range(4)
+    ]
+    _assert_close(simple.to_matrix(), expected)
+
+
+def test_two_qubit_rotation_same_qubit() -> None:
+    simple = SimpleModule("test_to_matrix", 1, 0)
+    qis = BasicQisBuilder(simple.builder)
+    with pytest.raises(ValueError, match="distinct"):
+        qis.rzz(0.5, simple.qubits[0], simple.qubits[0])
+
+
+def test_controlled_circuit() -> None:
+    simple = SimpleModule("test_to_matrix", 1, 0)
+    qis = BasicQisBuilder(simple.builder)
+    qis.h(simple.qubits[0])
+    qis.t(simple.qubits[0])
+    controlled = simple.controlled()
+    assert controlled.num_qubits == 2
+    assert pyqir.required_num_qubits(controlled.entry_point) == 2
+    assert simple.num_qubits == 1
+
+    # The control is qubit 1, the most significant bit of the index. The controlled forms of
+    # the gates are only exact up to a global phase.
+    u = simple.to_matrix()
+    actual = controlled.to_matrix()
+    phase = actual[0][0]
+    _assert_close(
+        actual,
+        [
+            [phase, 0, 0, 0],
+            [0, phase, 0, 0],
+            [0, 0, phase * u[0][0], phase * u[0][1]],
+            [0, 0, phase * u[1][0], phase * u[1][1]],
+        ],
+    )
+
+
+@pytest.mark.parametrize(
+    "gate",
+    [
+        lambda qis, q: qis.h(q),
+        lambda qis, q: qis.rx(0.7, q),
+        lambda qis, q: qis.s(q),
+        lambda qis, q: qis.t(q),
+        lambda qis, q: qis.u1(0.7, q),
+    ],
+    ids=["h", "rx", "s", "t", "u1"],
+)
+def test_controlled_gate(gate: Callable[[BasicQisBuilder, Value], None]) -> None:
+    simple = SimpleModule("test_to_matrix", 1, 0)
+    gate(BasicQisBuilder(simple.builder), simple.qubits[0])
+    u = simple.to_matrix()
+
+    # |0><0| (x) I + |1><1| (x) U up to a global phase, where the control is qubit 1.
+    actual = simple.controlled().to_matrix()
+    phase = actual[0][0]
+    blocks = ([[1, 0], [0, 1]], u)
+    expected = [
+        [
+            phase * blocks[i >> 1][i & 1][j & 1] if i >> 1 == j >> 1 else 0
+            for j in range(4)
+        ]
+        for i in range(4)
+    ]
+    _assert_close(actual, expected)
+
+
+@pytest.mark.parametrize("as_value", [False, True])
+def test_controlled_circuit_with_existing_control(as_value: bool) -> None:
+    simple = SimpleModule("test_to_matrix", 2, 0)
+    qis = BasicQisBuilder(simple.builder)
+    qis.h(simple.qubits[1])
+    controlled = simple.controlled(simple.qubits[0] if as_value else 0)
+    assert controlled.num_qubits == 2
+    assert pyqir.required_num_qubits(controlled.entry_point) == 2
+
+    # The control is qubit 0, the least significant bit of the index.
+    actual = controlled.to_matrix()
+    phase = actual[0][0]
+    h = phase / math.sqrt(2)
+    _assert_close(
+        actual,
+        [[phase, 0, 0, 0], [0, h, 0, h], [0, 0, phase, 0], [0, h, 0, -h]],
+    )
+
+
+def test_controlled_circuit_with_used_control() -> None:
+    simple = SimpleModule("test_to_matrix", 2, 0)
+    BasicQisBuilder(simple.builder).cx(simple.qubits[0], simple.qubits[1])
+    with pytest.raises(ValueError, match="^Qubit 1 can't be the control"):
+        simple.controlled(1)
+
+
+def test_controlled_circuit_with_invalid_control() -> None:
+    simple = SimpleModule("test_to_matrix", 2, 1)
+    with pytest.raises(ValueError, match="^Qubit 2 is out of range."):
+        simple.controlled(2)
+    with pytest.raises(ValueError, match="^The control must be a statically"):
+        simple.controlled(simple.results[0])
+
+
+def test_controlled_circuit_without_controlled_form() -> None:
+    simple = SimpleModule("test_to_matrix", 2, 1)
+    qis = BasicQisBuilder(simple.builder)
+    qis.cz(simple.qubits[0], simple.qubits[1])
+    with pytest.raises(ValueError, match="^Gate __quantum__qis__cz__body has no controlled"):
+        simple.controlled()
+
+
+def test_power() -> None:
+    simple = SimpleModule("test_to_matrix", 1, 0)
+    qis = BasicQisBuilder(simple.builder)
+    qis.rz(0.8, simple.qubits[0])
+    qis.rz(0.4, simple.qubits[0])
+    squared = SimpleModule("test_to_matrix", 1, 0)
+    BasicQisBuilder(squared.builder).rz(2.4, squared.qubits[0])
+
+    _assert_close(simple.power(2).to_matrix(), squared.to_matrix())
+    assert simple.instructions == [
+        pyqir.Operation("rz", (0,), angles=(0.8,)),
+        pyqir.Operation("rz", (0,), angles=(0.4,)),
+    ]
+
+
+def test_power_of_gate_without_angle() -> None:
+    simple = SimpleModule("test_to_matrix", 1, 0)
+    qis = BasicQisBuilder(simple.builder)
+    qis.rx(0.5, simple.qubits[0])
+    qis.h(simple.qubits[0])
+    message = "^Function __quantum__qis__h__body can't be raised to a power.$"
+    with pytest.raises(ValueError, match=message):
+        simple.power(0.5)
@@ -0,0 +1,89 @@
+# Copyright (c) Microsoft Corporation.
+# Licensed under the MIT License.
+
+import math
+
+import pytest
+
+import pyqir.angles as angles
+import pyqir.qis as qis
+from pyqir import BasicQisBuilder, FunctionType, Operation, SimpleModule, Type
+
+
+def test_angles_match_math() -> None:
+    assert angles.PI == math.pi
+    assert angles.PI_OVER_2 == math.pi / 2
+    assert angles.PI_OVER_4 == math.pi / 4
+    assert angles.TAU == math.tau
+
+
+def test_rotation_by_named_angle() -> None:
+    simple = SimpleModule("test_angles", 1, 0)
+    qis.rx(simple.builder, angles.PI_OVER_4, simple.qubits[0])
+    expected = SimpleModule("test_angles", 1, 0)
+    qis.rx(expected.builder, math.pi / 4, expected.qubits[0])
+    assert simple.ir() == expected.ir()
+
+
+@pytest.mark.parametrize(
+    "angle, expected",
+    [
+        (0.0, 0.0),
+        (-1.5, -1.5),
+        (math.pi, math.pi),
+        (-math.pi, math.pi),
+        (math.tau, 0.0),
+        (3 * math.pi, math.pi),
+        (math.pi + 0.5, 0.5 - math.pi),
+        (-1e-20, -1e-20),
+        (-math.tau - 0.25, -0.25),
+    ],
+)
+def test_normalize_angle(angle: float, expected: float) -> None:
+    assert angles.normalize_angle(angle) == expected
+
+
+def test_normalize_large_angle() -> None:
+    assert angles.normalize_angle(1e6 * math.pi + 0.125) == pytest.approx(
+        0.125, abs=1e-9
+    )
+
+
+def test_normalize_non_finite_angle() -> None:
+    assert angles.normalize_angle(math.inf) == math.inf
+    assert math.isnan(angles.normalize_angle(math.nan))
+
+
+def test_builder_normalizes_angles() -> None:
+    simple = SimpleModule("test_angles", 2, 0)
+    builder = BasicQisBuilder(simple.builder, normalize_angles=True)
+    builder.rx(5 * math.pi, simple.qubits[0])
+    builder.rz(-math.tau - 0.25, simple.qubits[0])
+    builder.u2(3 * math.tau, math.pi + 0.5, simple.qubits[1])
+    builder.rzz(math.tau + 0.25, simple.qubits[0], simple.qubits[1])
+    assert simple.instructions == [
+        Operation("rx", (0,), angles=(math.pi,)),
+        Operation("rz", (0,), angles=(-0.25,)),
+        Operation("u2", (1,), angles=(0.0, 0.5 - math.pi)),
+        Operation("cx", (0, 1)),
+        Operation("rz", (1,), angles=(0.25,)),
+        Operation("cx", (0, 1)),
+    ]
+
+
+def test_builder_keeps_angles_by_default() -> None:
+    simple = SimpleModule("test_angles", 1, 0)
+    BasicQisBuilder(simple.builder).rx(5 * math.pi, simple.qubits[0])
+    assert simple.instructions == [Operation("rx", (0,), angles=(5 * math.pi,))]
+
+
+def test_builder_keeps_dynamic_angles() -> None:
+    simple = SimpleModule("test_angles", 1, 0)
+    builder = BasicQisBuilder(simple.builder, normalize_angles=True)
+    angle = simple.add_external_function(
+        "angle", FunctionType(Type.double(simple.context), [])
+    )
+    theta = simple.builder.call(angle, [])
+    assert theta is not None
+    builder.rx(theta, simple.qubits[0])
+    assert "call void @__quantum__qis__rx__body(double %0" in simple.ir()
@@ -0,0 +1,71 @@
+# Copyright (c) Microsoft Corporation.
+# Licensed under the MIT License.
+
+import pytest
+
+import pyqir
+from pyqir import BasicQisBuilder, SimpleModule
+
+
+def _module() -> SimpleModule:
+    module = SimpleModule("test_annotations", 2, 1)
+    qis = BasicQisBuilder(module.builder)
+    qis.h(module.qubits[0])
+    qis.cx(module.qubits[0], module.qubits[1])
+    qis.mz(module.qubits[1], module.results[0])
+    return module
+
+
+def test_annotate() -> None:
+    module = _module()
+    module.builder.annotate(1, {"noise_probability": 0.001, "timing_ns": 50})
+    ir = module.ir()
+    assert (
+        "call void @__quantum__qis__cnot__body"
+        "(%Qubit* null, %Qubit* inttoptr (i64 1 to %Qubit*)), !qir.annotations !4"
+    ) in ir
+    assert "!4 = !{!5, !6}" in ir
+    assert '!5 = !{!"noise_probability", double 1.000000e-03}' in ir
+    assert '!6 = !{!"timing_ns", i64 50}' in ir
+
+
+def test_get_annotation() -> None:
+    module = _module()
+    annotations = {"noise": 0.25, "timing_ns": -3, "native": True, "gate": "h"}
+    module.builder.annotate(0, annotations)
+    assert module.builder.get_annotation(0) == annotations
+    assert module.builder.get_annotation(1) == {}
+
+
+def test_annotations_are_merged() -> None:
+    module = _module()
+    module.builder.annotate(2, {"timing_ns": 50, "gate": "mz"})
+    module.builder.annotate(2, {"timing_ns": 60, "readout_error": 0.02})
+    assert module.builder.get_annotation(2) == {
+        "timing_ns": 60,
+        "gate": "mz",
+        "readout_error": 0.02,
+    }
+
+
+def test_annotations_survive_parsing() -> None:
+    module = _module()
+    module.builder.annotate(0, {"timing_ns": 50})
+    parsed = pyqir.Module.from_ir(pyqir.Context(), module.ir())
+    call = parsed.functions[0].basic_blocks[0].instructions[0]
+    assert "!qir.annotations" in str(call)
+
+
+def test_annotate_index_out_of_range() -> None:
+    module = _module()
+    with pytest.raises(IndexError, match="^Instruction index out of range.$"):
+        module.builder.annotate(3, {"timing_ns": 50})
+    with pytest.raises(IndexError):
+        module.builder.get_annotation(3)
+
+
+def test_annotate_unsupported_value() -> None:
+    module = _module()
+    message = "^Annotation values must be bool, int, float, or str.$"
+    with pytest.raises(TypeError, match=message):
+        module.builder.annotate(0, {"qubits": [0, 1]})
@@ -0,0 +1,207 @@
+# Copyright (c) Microsoft Corporation.
+# Licensed under the MIT License.
+
+import json
+import math
+from typing import Any, Dict
+
+import pytest
+
+import pyqir.qis
+from pyqir import BasicQisBuilder, SimpleModule
+
+
+def _qubit(id: int) -> Dict[str, Any]:
+    return {"cirq_type": "LineQubit", "x": id}
+
+
+def test_bell_state() -> None:
+    simple = SimpleModule("test_cirq", 2, 2)
+    qis = BasicQisBuilder(simple.builder)
+    qis.h(simple.qubits[0])
+    qis.cx(simple.qubits[0], simple.qubits[1])
+    qis.mz(simple.qubits[0], simple.results[0])
+    qis.mz(simple.qubits[1], simple.results[1])
+
+    h = {"cirq_type": "HPowGate", "exponent": 1.0, "global_shift": 0.0}
+    cx = {"cirq_type": "CXPowGate", "exponent": 1.0, "global_shift": 0.0}
+
+    def measure(id: int) -> Dict[str, Any]:
+        return {
+            "cirq_type": "GateOperation",
+            "gate": {
+                "cirq_type": "MeasurementGate",
+                "num_qubits": 1,
+                "key": f"r{id}",
+                "invert_mask": [],
+            },
+            "qubits": [_qubit(id)],
+        }
+
+    assert json.loads(simple.to_cirq_json()) == {
+        "cirq_type": "Circuit",
+        "moments": [
+            {
+                "cirq_type": "Moment",
+                "operations": [
+                    {"cirq_type": "GateOperation", "gate": h, "qubits": [_qubit(0)]}
+                ],
+            },
+            {
+                "cirq_type": "Moment",
+                "operations": [
+                    {
+                        "cirq_type": "GateOperation",
+                        "gate": cx,
+                        "qubits": [_qubit(0), _qubit(1)],
+                    }
+                ],
+            },
+            {"cirq_type": "Moment", "operations": [measure(0), measure(1)]},
+        ],
+    }
+
+
+def test_gates() -> None:
+    simple = SimpleModule("test_cirq", 1, 0)
+    qis = BasicQisBuilder(simple.builder)
+    q = simple.qubits[0]
+    qis.s_adj(q)
+    qis.t(q)
+    qis.rx(0.5, q)
+    qis.u1(math.pi / 2, q)
+    qis.id(q)
+    qis.reset(q)
+    moments = json.loads(simple.to_cirq_json())["moments"]
+    gates = [m["operations"][0]["gate"] for m in moments]
+    assert gates == [
+        {"cirq_type": "ZPowGate", "exponent": -0.5, "global_shift": 0.0},
+        {"cirq_type": "ZPowGate", "exponent": 0.25, "global_shift": 0.0},
+        {"cirq_type": "Rx", "rads": 0.5},
+        {"cirq_type": "ZPowGate", "exponent": 0.5, "global_shift": 0.0},
+        {"cirq_type": "IdentityGate", "qid_shape": [2]},
+        {"cirq_type": "ResetChannel", "dimension": 2},
+    ]
+
+
+def test_barrier_is_left_out() -> None:
+    simple = SimpleModule("test_cirq", 1, 0)
+    pyqir.qis.barrier(simple.builder)
+    assert json.loads(simple.to_cirq_json()) == {"cirq_type": "Circuit", "moments": []}
+
+
+def test_unmapped_gate() -> None:
+    simple = SimpleModule("test_cirq", 2, 0)
+    BasicQisBuilder(simple.builder).cy(simple.qubits[0], simple.qubits[1])
+    with pytest.raises(ValueError, match="^Operation cy has no Cirq equivalent.$"):
+        simple.to_cirq_json()
+
+
+def test_branch() -> None:
+    simple = SimpleModule("test_cirq", 1, 1)
+    qis = BasicQisBuilder(simple.builder)
+    qis.mz(simple.qubits[0], simple.results[0])
+    qis.if_result(simple.results[0], one=lambda: qis.x(simple.qubits[0]))
+    with pytest.raises(ValueError, match="^Branches on results can't be converted"):
+        simple.to_cirq_json()
+
+
+def _circuit(*operations: Dict[str, Any]) -> str:
+    moments = [{"cirq_type": "Moment", "operations": [o]} for o in operations]
+    return json.dumps({"cirq_type": "Circuit", "moments": moments})
+
+
+def _operation(gate: Dict[str, Any], *qubits: int) -> Dict[str, Any]:
+    return {
+        "cirq_type": "GateOperation",
+        "gate": gate,
+        "qubits": [_qubit(q) for q in qubits],
+    }
+
+
+def test_round_trip() -> None:
+    simple = SimpleModule("test_cirq", 3, 2)
+    qis = BasicQisBuilder(simple.builder)
+    qis.h(simple.qubits[0])
+    qis.cx(simple.qubits[0], simple.qubits[2])
+    qis.t_adj(simple.qubits[1])
+    qis.ry(0.25, simple.qubits[1])
+    qis.mz(simple.qubits[2], simple.results[1])
+    qis.reset(simple.qubits[2])
+
+    other = SimpleModule.from_cirq_json(simple.to_cirq_json(), "test_cirq")
+    assert (other.num_qubits, other.num_results) == (3, 2)
+    assert other.to_cirq_json() == simple.to_cirq_json()
+    assert [(op.name, op.qubits, op.results) for op in other.instructions] == [
+        ("h", (0,), ()),
+        ("t_adj", (1,), ()),
+        ("cx", (0, 2), ()),
+        ("ry", (1,), ()),
+        ("mz", (2,), (1,)),
+        ("reset", (2,), ()),
+    ]
+
+
+def test_cirq_gates() -> None:
+    pauli_x = {"cirq_type": "_PauliX", "exponent": 1.0, "global_shift": 0.0}
+    sqrt_x = {"cirq_type": "XPowGate", "exponent": 0.5, "global_shift": 0.0}
+    z_pow = {"cirq_type": "ZPowGate", "exponent": 0.125, "global_shift": 0.0}
+    tagged = {
+        "cirq_type": "TaggedOperation",
+        "sub_operation": _operation({"cirq_type": "Rz", "rads": 1.0}, 0),
+        "tags": [],
+    }
+    simple = SimpleModule.from_cirq_json(
+        _circuit(
+            _operation(pauli_x, 0), _operation(sqrt_x, 0), _operation(z_pow, 0), tagged
+        )
+    )
+    assert [(op.name, op.angles) for op in simple.instructions] == [
+        ("x", ()),
+        ("rx", (math.pi / 2,)),
+        ("u1", (math.pi / 8,)),
+        ("rz", (1.0,)),
+    ]
+
+
+def test_measurement_keys() -> None:
+    def measure(key: Any, qubit: int) -> Dict[str, Any]:
+        gate = {"cirq_type": "MeasurementGate", "num_qubits": 1, "key": key}
+        return _operation(gate, qubit)
+
+    simple = SimpleModule.from_cirq_json(
+        _circuit(
+            measure("a", 0),
+            measure("r1", 1),
+            measure({"cirq_type": "MeasurementKey", "name": "b", "path": []}, 2),
+            measure("a", 3),
+        )
+    )
+    assert simple.num_results == 4
+    assert [op.results for op in simple.instructions] == [(2,), (1,), (3,), (2,)]
+
+
+def test_unmapped_cirq_gate() -> None:
+    sqrt_cz = {"cirq_type": "CZPowGate", "exponent": 0.5, "global_shift": 0.0}
+    with pytest.raises(ValueError, match="^Cirq gate CZPowGate to the power of 0.5 "):
+        SimpleModule.from_cirq_json(_circuit(_operation(sqrt_cz, 0, 1)))
+    fsim = {"cirq_type": "FSimGate", "theta": 0.5, "phi": 0.25}
+    with pytest.raises(ValueError, match="^Cirq gate FSimGate has no QIR equivalent.$"):
+        SimpleModule.from_cirq_json(_circuit(_operation(fsim, 0, 1)))
+
+
+def test_unmapped_cirq_operation() -> None:
+    operation = {"cirq_type": "PauliString", "qubit_pauli_map": [], "coefficient": 1}
+    with pytest.raises(ValueError, match="^Cirq operation PauliString has no QIR"):
+        SimpleModule.from_cirq_json(_circuit(operation))
+
+
+def test_grid_qubit() -> None:
+    h = {"cirq_type": "HPowGate", "exponent": 1.0, "global_shift": 0.0}
+    operation = {
+        "cirq_type": "GateOperation",
+        "gate": h,
+        "qubits": [{"cirq_type": "GridQubit", "row": 0, "col": 0}],
+    }
+    with pytest.raises(ValueError, match="^Cirq qubit GridQubit isn't a line qubit.$"):
+        SimpleModule.from_cirq_json(_circuit(operation))
@@ -0,0 +1,68 @@
+# Copyright (c) Microsoft Corporation.
+# Licensed under the MIT License.
+
+import pytest
+
+import pyqir.qis
+from pyqir import BasicQisBuilder, SimpleModule
+
+
+def _circuit() -> SimpleModule:
+    simple = SimpleModule("test_draw", 3, 1)
+    qis = BasicQisBuilder(simple.builder)
+    qis.h(simple.qubits[0])
+    qis.cx(simple.qubits[0], simple.qubits[2])
+    qis.rx(0.5, simple.qubits[1])
+    qis.s_adj(simple.qubits[2])
+    pyqir.qis.swap(simple.builder, simple.qubits[0], simple.qubits[1])
+    qis.mz(simple.qubits[0], simple.results[0])
+    qis.if_result(
+        simple.results[0],
+        one=lambda: qis.cx(simple.qubits[0], simple.qubits[1]),
+        zero=lambda: qis.reset(simple.qubits[2]),
+    )
+    return simple
+
+
+def test_text() -> None:
+    expected = (
+        "q0: --H--*----------------x--M0--*[r0=1]-------------\n"
+        "         |                |         |\n"
+        "q1: --------Rx(0.5)-------x------X[r0=1]-------------\n"
+        "         |\n"
+        "q2: -----X-----------Sdg------------------|0>[r0=0]--"
+    )
+    assert _circuit().draw() == expected
+
+
+def test_latex() -> None:
+    expected = (
+        "\\begin{quantikz}\n"
+        "\\lstick{$q_{0}$} & \\gate{H} & \\ctrl{2} & \\qw & \\qw & \\swap{1} & \\meter{}"
+        " & \\ctrl{1} & \\qw & \\qw \\\\\n"
+        "\\lstick{$q_{1}$} & \\qw & \\qw & \\gate{R_x(0.5)} & \\qw & \\targX{} & \\qw"
+        " & \\gate{X \\mid r_{0} = 1} & \\qw & \\qw \\\\\n"
+        "\\lstick{$q_{2}$} & \\qw & \\targ{} & \\qw & \\gate{S^\\dagger} & \\qw & \\qw"
+        " & \\qw & \\gate{\\ket{0} \\mid r_{0} = 0} & \\qw\n"
+        "\\end{quantikz}"
+    )
+    assert _circuit().draw("latex") == expected
+
+
+def test_controlled_gates() -> None:
+    simple = SimpleModule("test_draw", 2, 0)
+    qis = BasicQisBuilder(simple.builder)
+    qis.ch(simple.qubits[0], simple.qubits[1])
+    qis.cy(simple.qubits[1], simple.qubits[0])
+    assert simple.draw() == "q0: --*--Y--\n      |  |\nq1: --H--*--"
+    assert "\\ctrl{1} & \\gate{Y}" in simple.draw("latex")
+
+
+def test_empty() -> None:
+    simple = SimpleModule("test_draw", 2, 0)
+    assert simple.draw() == "q0: --\n\nq1: --"
+
+
+def test_unsupported_output() -> None:
+    with pytest.raises(ValueError, match="^Unsupported output 'svg'."):
+        SimpleModule("test_draw", 1, 0).draw("svg")
@@ -144,6 +144,86 @@ def test_call_two_results() -> None:
     )
 
 
+def test_call_qubit_array() -> None:
+    mod = SimpleModule("test", 2, 0)
+    f = mod.add_external_function(
+        "test_function",
+        FunctionType(Type.void(mod.context), [pyqir.array_type(mod.context)]),
+    )
+    mod.builder.call(f, [[mod.qubits[1], mod.qubits[0]]])
+    ir = mod.ir()
+    assert "%0 = call %Array* @__quantum__rt__array_create_1d(i32 8, i64 2)" in ir
+    assert (
+        "%1 = call i8* @__quantum__rt__array_get_element_ptr_1d(%Array* %0, i64 0)"
+        in ir
+    )
+    assert "store %Qubit* inttoptr (i64 1 to %Qubit*), %Qubit** %2" in ir
+    assert (
+        "%3 = call i8* @__quantum__rt__array_get_element_ptr_1d(%Array* %0, i64 1)"
+        in ir
+    )
+    assert "store %Qubit* null, %Qubit** %4" in ir
+    assert "call void @test_function(%Array* %0)" in ir
+    assert (
+        "call void @__quantum__rt__array_update_reference_count(%Array* %0, i32 -1)"
+        in ir
+    )
+    assert ir.index("@test_function(%Array* %0)") < ir.index(
+        "@__quantum__rt__array_update_reference_count(%Array* %0"
+    )
+
+
+def test_call_empty_qubit_array() -> None:
+    mod = SimpleModule("test", 0, 0)
+    f = mod.add_external_function(
+        "test_function",
+        FunctionType(Type.void(mod.context), [pyqir.array_type(mod.context)]),
+    )
+    mod.builder.call(f, [[]])
+    ir = mod.ir()
+    assert "call %Array* @__quantum__rt__array_create_1d(i32 8, i64 0)" in ir
+    assert "array_get_element_ptr_1d(" not in ir
+    assert "call void @test_function(%Array* %0)" in ir
+
+
+def test_call_qubit_array_wrong_param_type() -> None:
+    mod = SimpleModule("test", 1, 0)
+    f = mod.add_external_function(
+        "test_function",
+        FunctionType(Type.void(mod.context), [pyqir.qubit_type(mod.context)]),
+    )
+    with pytest.raises(
+        TypeError,
+        match="^A sequence of qubits can only be passed as an array parameter.$",
+    ):
+        mod.builder.call(f, [[mod.qubits[0]]])
+    assert "array_create_1d" not in mod.ir()
+
+
+def test_call_qubit_array_of_results() -> None:
+    mod = SimpleModule("test", 1, 1)
+    f = mod.add_external_function(
+        "test_function",
+        FunctionType(Type.void(mod.context), [pyqir.array_type(mod.context)]),
+    )
+    with pytest.raises(TypeError, match="^Expected a sequence of qubits.$"):
+        mod.builder.call(f, [[mod.qubits[0], mod.results[0]]])
+    assert "array_create_1d" not in mod.ir()
+
+
+def test_call_qubit_array_out_of_range() -> None:
+    mod = SimpleModule("test", 2, 0)
+    f = mod.add_external_function(
+        "test_function",
+        FunctionType(Type.void(mod.context), [pyqir.array_type(mod.context)]),
+    )
+    qubit = pyqir.qubit(mod.context, 2)
+    message = "^Qubit 2 is out of range for module test, which has 2 qubits.$"
+    with pytest.raises(IndexError, match=message):
+        mod.builder.call(f, [[mod.qubits[0], qubit]])
+    assert "array_create_1d" not in mod.ir()
+
+
 def test_call_numbers() -> None:
     mod = SimpleModule("test", 0, 0)
     void = Type.void(mod.context)
@@ -311,8 +391,11 @@ def test_variable_wrong_external_type() -> None:
     mod.builder.call(bar, [x])
 
     with pytest.raises(
-        ValueError,
-        match="^Call parameter type does not match function signature!",
+        pyqir.QirEmitError,
+        match=re.escape(
+            "is invalid at instruction 1 (bar value): "
+            "Call parameter type does not match function signature!"
+        ),
     ):
         mod.ir()
 
@@ -326,7 +409,11 @@ def test_variable_wrong_angle_type() -> None:
     assert x is not None
     qis.rz(x, mod.qubits[0])
     with pytest.raises(
-        ValueError, match=r"^Call parameter type does not match function signature!"
+        pyqir.QirEmitError,
+        match=re.escape(
+            "is invalid at instruction 1 (rz value, qubit 0): "
+            "Call parameter type does not match function signature!"
+        ),
     ):
         mod.ir()
 
@@ -435,3 +522,11 @@ def test_null_pointer() -> None:
     f = mod.add_external_function("f", FunctionType(Type.void(mod.context), [i8p]))
     mod.builder.call(f, [Constant.null(i8p)])
     assert "call void @f(i8* null)" in mod.ir()
+
+
+def test_name_with_null() -> None:
+    mod = SimpleModule("test", 0, 0)
+    with pytest.raises(ValueError, match="contains a null character at index 4."):
+        mod.add_external_function("test\0", FunctionType(Type.void(mod.context), []))
+    with pytest.raises(ValueError, match="contains a null character at index 0."):
+        BasicBlock(mod.context, "\0", mod.entry_point)
@@ -9,7 +9,9 @@ from pathlib import Path
 
 import pyqir
 from pyqir import (
+    BasicBlock,
     BasicQisBuilder,
+    BuilderReentrancyError,
     Context,
     Function,
     FunctionType,
@@ -144,3 +146,82 @@ def test_nested_blocks() -> None:
     file = os.path.join(os.path.dirname(__file__), "resources/test_nested_blocks.ll")
     expected = Path(file).read_text()
     assert ir == expected
+
+
+def test_explicit_none_blocks() -> None:
+    module = SimpleModule("test_if", 0, 1)
+    qis = BasicQisBuilder(module.builder)
+    qis.if_result(module.results[0], one=None, zero=None)
+
+    file = os.path.join(os.path.dirname(__file__), "resources/test_if_empty_blocks.ll")
+    assert module.ir() == Path(file).read_text()
+
+
+def test_non_callable_block() -> None:
+    module = SimpleModule("test_if", 1, 1)
+    qis = BasicQisBuilder(module.builder)
+    ir = module.ir()
+
+    with pytest.raises(
+        TypeError, match=r"^The one branch must be callable or None, not int\.$"
+    ):
+        qis.if_result(module.results[0], one=1)  # type: ignore[arg-type]
+
+    with pytest.raises(
+        TypeError, match=r"^The zero branch must be callable or None, not str\.$"
+    ):
+        qis.if_result(module.results[0], one=lambda: None, zero="x")  # type: ignore[arg-type]
+
+    cond = pyqir.const(IntType(module.context, 1), 1)
+    with pytest.raises(
+        TypeError, match=r"^The false branch must be callable or None, not float\.$"
+    ):
+        module.builder.if_(cond, false=1.0)  # type: ignore[arg-type]
+
+    assert module.ir() == ir
+
+
+def test_move_builder_in_block() -> None:
+    module = SimpleModule("test_if", 1, 1)
+    builder = module.builder
+    qis = BasicQisBuilder(builder)
+
+    def one() -> None:
+        # Moving the builder re-enters it while if_result is still running.
+        extra = BasicBlock(module.context, "extra", module.entry_point)
+        builder.br(extra)
+        builder.insert_at_end(extra)
+        qis.x(module.qubits[0])
+
+    qis.if_result(module.results[0], one=one)
+    qis.h(module.qubits[0])
+    ir = module.ir()
+    extra = ir[ir.index("\nextra:") : ir.index("\n}")]
+    assert "call void @__quantum__qis__x__body(%Qubit* null)" in extra
+    assert "br label %continue" in extra
+
+
+def test_branch_ends_block() -> None:
+    module = SimpleModule("test_if", 0, 1)
+    qis = BasicQisBuilder(module.builder)
+    with pytest.raises(
+        BuilderReentrancyError,
+        match=r"^The branch body left the builder after a terminator\.$",
+    ):
+        qis.if_result(module.results[0], zero=lambda: module.builder.ret(None))
+
+
+def test_branch_leaves_function() -> None:
+    module = SimpleModule("test_if", 0, 1)
+    other = module.add_external_function(
+        "other", FunctionType(pyqir.Type.void(module.context), [])
+    )
+    block = BasicBlock(module.context, "body", other)
+    qis = BasicQisBuilder(module.builder)
+    with pytest.raises(
+        BuilderReentrancyError,
+        match=r"^The branch body left the builder outside the function of the branch\.$",
+    ):
+        qis.if_result(
+            module.results[0], one=lambda: module.builder.insert_at_end(block)
+        )
@@ -1,6 +1,7 @@
 # Copyright (c) Microsoft Corporation.
 # Licensed under the MIT License.
 
+import re
 from functools import partial
 from typing import Callable, List, Tuple
 
@@ -85,7 +86,10 @@ def test_type_mismatch() -> None:
     y = mod.builder.add(x, pyqir.const(IntType(mod.context, 18), 2))
     mod.builder.call(sink, [y])
     with pytest.raises(
-        ValueError,
-        match="^Both operands to a binary operator are not of the same type!",
+        pyqir.QirEmitError,
+        match=re.escape(
+            "is invalid at instruction 1 (%1 = add i16 %0, i18 2): "
+            "Both operands to a binary operator are not of the same type!"
+        ),
     ):
         mod.ir()
@@ -0,0 +1,206 @@
+# Copyright (c) Microsoft Corporation.
+# Licensed under the MIT License.
+
+import dataclasses
+from typing import Dict
+
+import pytest
+
+import pyqir
+import pyqir.rt as rt
+from pyqir import BasicQisBuilder, Conditional, Operation, SimpleModule
+
+
+def test_gates() -> None:
+    simple = SimpleModule("test_operations", 2, 2)
+    qis = BasicQisBuilder(simple.builder)
+    qis.h(simple.qubits[0])
+    qis.cx(simple.qubits[0], simple.qubits[1])
+    qis.s_adj(simple.qubits[1])
+    qis.rx(0.5, simple.qubits[1])
+    qis.mz(simple.qubits[1], simple.results[1])
+    assert simple.instructions == [
+        Operation("h", (0,)),
+        Operation("cx", (0, 1)),
+        Operation("s_adj", (1,)),
+        Operation("rx", (1,), angles=(0.5,)),
+        Operation("mz", (1,), (1,)),
+    ]
+
+
+def test_other_calls() -> None:
+    simple = SimpleModule("test_operations", 1, 1)
+    qis = BasicQisBuilder(simple.builder)
+    qis.mz(simple.qubits[0], simple.results[0])
+    rt.result_record_output(
+        simple.builder, simple.results[0], simple.add_byte_string(b"r")
+    )
+    assert simple.instructions[1] == Operation(
+        "__quantum__rt__result_record_output", results=(0,)
+    )
+
+
+def test_conditionals() -> None:
+    simple = SimpleModule("test_operations", 2, 2)
+    qis = BasicQisBuilder(simple.builder)
+    qis.mz(simple.qubits[0], simple.results[0])
+    qis.if_result(
+        simple.results[0],
+        one=lambda: qis.if_result(
+            simple.results[1], zero=lambda: qis.x(simple.qubits[1])
+        ),
+        zero=lambda: qis.z(simple.qubits[0]),
+    )
+    qis.h(simple.qubits[1])
+    assert simple.instructions == [
+        Operation("mz", (0,), (0,)),
+        Conditional(
+            0,
+            one=(Conditional(1, zero=(Operation("x", (1,)),)),),
+            zero=(Operation("z", (0,)),),
+        ),
+        Operation("h", (1,)),
+    ]
+
+
+def test_empty_conditional() -> None:
+    simple = SimpleModule("test_operations", 1, 1)
+    qis = BasicQisBuilder(simple.builder)
+    qis.if_result(simple.results[0])
+    assert simple.instructions == [Conditional(0)]
+
+
+def test_dynamic_result_conditional() -> None:
+    simple = SimpleModule("test_operations", 1, 0)
+    qis = BasicQisBuilder(simple.builder)
+    qis.if_result(qis.m(simple.qubits[0]), one=lambda: qis.x(simple.qubits[0]))
+    assert simple.instructions == [
+        Operation("m", (0,)),
+        Conditional(None, one=(Operation("x", (0,)),)),
+    ]
+
+
+def test_snapshots_are_read_only() -> None:
+    simple = SimpleModule("test_operations", 1, 0)
+    BasicQisBuilder(simple.builder).h(simple.qubits[0])
+    [op] = simple.instructions
+    with pytest.raises(dataclasses.FrozenInstanceError):
+        op.name = "x"  # type: ignore[misc]
+
+    BasicQisBuilder(simple.builder).x(simple.qubits[0])
+    assert op == Operation("h", (0,))
+    assert len(simple.instructions) == 2
+
+
+def test_dynamic_angle() -> None:
+    simple = SimpleModule("test_operations", 1, 0)
+    ty = pyqir.FunctionType(pyqir.Type.double(simple.context), [])
+    angle = simple.builder.call(simple.add_external_function("angle", ty), [])
+    assert angle is not None
+    BasicQisBuilder(simple.builder).rz(angle, simple.qubits[0])
+    with pytest.raises(ValueError, match="^Angle of rz isn't constant.$"):
+        simple.instructions
+
+
+def test_len() -> None:
+    simple = SimpleModule("test_operations", 1, 1)
+    assert len(simple) == 0
+    assert not simple
+
+    qis = BasicQisBuilder(simple.builder)
+    qis.h(simple.qubits[0])
+    qis.mz(simple.qubits[0], simple.results[0])
+    qis.if_result(
+        simple.results[0],
+        one=lambda: qis.x(simple.qubits[0]),
+        zero=lambda: qis.z(simple.qubits[0]),
+    )
+    assert len(simple) == 3
+    assert simple
+
+
+def test_len_dynamic_operands() -> None:
+    simple = SimpleModule("test_operations", 1, 1)
+    ty = pyqir.FunctionType(pyqir.Type.double(simple.context), [])
+    angle = simple.builder.call(simple.add_external_function("angle", ty), [])
+    assert angle is not None
+    ty = pyqir.FunctionType(pyqir.qubit_type(simple.context), [])
+    qubit = simple.builder.call(simple.add_external_function("qubit", ty), [])
+    assert qubit is not None
+    qis = BasicQisBuilder(simple.builder)
+    qis.rx(angle, simple.qubits[0])
+    qis.h(qubit)
+    with pytest.raises(ValueError):
+        simple.instructions
+
+    # The calls to angle and qubit are operations too.
+    assert len(simple) == 4
+    assert simple
+
+
+def test_iter() -> None:
+    simple = SimpleModule("test_operations", 2, 1)
+    qis = BasicQisBuilder(simple.builder)
+    qis.h(simple.qubits[0])
+    qis.cx(simple.qubits[0], simple.qubits[1])
+    qis.mz(simple.qubits[1], simple.results[0])
+
+    assert list(simple) == simple.instructions
+    assert [op.name for op in simple if isinstance(op, Operation)] == ["h", "cx", "mz"]
+    assert any(isinstance(op, Operation) and op.results == (0,) for op in simple)
+
+    for op in simple:
+        qis.x(simple.qubits[0])
+    assert len(simple) == 6
+
+
+def test_relabel() -> None:
+    simple = SimpleModule("test_relabel", 2, 1)
+    qis = BasicQisBuilder(simple.builder)
+    qis.h(simple.qubits[0])
+    qis.cx(simple.qubits[0], simple.qubits[1])
+    qis.mz(simple.qubits[1], simple.results[0])
+    qis.if_result(simple.results[0], one=lambda: qis.x(simple.qubits[0]))
+    simple.relabel({0: 1, 1: 0})
+    assert simple.instructions == [
+        Operation("h", (1,)),
+        Operation("cx", (1, 0)),
+        Operation("mz", (0,), (0,)),
+        Conditional(0, one=(Operation("x", (1,)),)),
+    ]
+
+
+def test_relabel_qubits() -> None:
+    simple = SimpleModule("test_relabel_qubits", 4, 0)
+    qis = BasicQisBuilder(simple.builder)
+    qis.cx(simple.qubits[0], simple.qubits[1])
+    qis.h(simple.qubits[2])
+    other = simple.relabel_qubits({0: 3, 3: 0})
+    assert other.instructions == [Operation("cx", (3, 1)), Operation("h", (2,))]
+    assert simple.instructions == [Operation("cx", (0, 1)), Operation("h", (2,))]
+
+
+def test_relabel_qubits_collision() -> None:
+    simple = SimpleModule("test_relabel_qubits", 2, 0)
+    qis = BasicQisBuilder(simple.builder)
+    qis.cx(simple.qubits[0], simple.qubits[1])
+    with pytest.raises(
+        ValueError, match="^The mapping must be a permutation of the 2 qubits.$"
+    ):
+        simple.relabel_qubits({0: 1})
+    assert simple.instructions == [Operation("cx", (0, 1))]
+
+
+@pytest.mark.parametrize(
+    "mapping",
+    [{0: 1}, {0: 1, 1: 1, 2: 0}, {0: 0, 1: 1, 2: 3}, {0: 0, 1: 2, 3: 1}],
+)
+def test_relabel_not_a_permutation(mapping: Dict[int, int]) -> None:
+    simple = SimpleModule("test_relabel", 3, 0)
+    qis = BasicQisBuilder(simple.builder)
+    qis.cx(simple.qubits[0], simple.qubits[2])
+    with pytest.raises(
+        ValueError, match="^The mapping must be a permutation of the 3 qubits.$"
+    ):
+        simple.relabel(mapping)
+    assert simple.instructions == [Operation("cx", (0, 2))]
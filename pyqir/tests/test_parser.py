@@ -2,11 +2,13 @@
 # Licensed under the MIT License.
 
 from pathlib import Path
+from typing import Dict
 
 import pytest
 
 from pyqir import (
     BasicBlock,
+    BasicQisBuilder,
     Call,
     Constant,
     Context,
@@ -15,6 +17,7 @@ from pyqir import (
     IntType,
     Module,
     Opcode,
+    SimpleModule,
     extract_byte_string,
     is_entry_point,
     is_interop_friendly,
@@ -23,6 +26,7 @@ from pyqir import (
     required_num_qubits,
     result_id,
 )
+from pyqir.parser import QirModule
 
 
 def test_parser() -> None:
@@ -300,3 +304,60 @@ def test_not_contains_attribute() -> None:
     assert "foo" not in attributes.ret
     with pytest.raises(KeyError):
         attributes.ret["foo"]
+
+
+def test_qir_module_summary() -> None:
+    simple = SimpleModule("test_qir_module", 2, 1)
+    qis = BasicQisBuilder(simple.builder)
+    qis.h(simple.qubits[0])
+    qis.cx(simple.qubits[0], simple.qubits[1])
+    qis.if_result(qis.m(simple.qubits[1]), one=lambda: qis.x(simple.qubits[0]))
+    qis.mz(simple.qubits[0], simple.results[0])
+
+    mod = QirModule.from_ir(simple.ir())
+    assert [f.name for f in mod.entry_points] == ["main"]
+    main = mod.entry_points[0]
+    assert main.required_num_qubits == 2
+    assert main.required_num_results == 1
+    assert [(i.name, i.operands) for i in main.instructions] == [
+        ("h", ("%Qubit* null",)),
+        ("cx", ("%Qubit* null", "%Qubit* inttoptr (i64 1 to %Qubit*)")),
+        ("m", ("%Qubit* inttoptr (i64 1 to %Qubit*)",)),
+        ("read_result", ("%0",)),
+        ("br", ("%1", "label %else", "label %then")),
+        ("x", ("%Qubit* null",)),
+        ("br", ("label %continue",)),
+        ("br", ("label %continue",)),
+        ("mz", ("%Qubit* null", "%Result* null")),
+        ("ret", ()),
+    ]
+    assert main.instructions[0].text == "call void @__quantum__qis__h__body(%Qubit* null)"
+
+    h = mod.get_function("__quantum__qis__h__body")
+    assert not h.is_entry_point
+    assert h.required_num_qubits is None
+    assert h.instructions == ()
+    with pytest.raises(KeyError, match="has no function named 'f'."):
+        mod.get_function("f")
+
+    assert QirModule.from_bitcode(simple.bitcode()).functions == mod.functions
+
+
+def test_qir_module_gate_counts() -> None:
+    mod = QirModule.from_file("tests/teleportchain.baseprofile.bc")
+    assert mod.entry_points == ()
+    func = mod.get_function(
+        "TeleportChain__DemonstrateTeleportationUsingPresharedEntanglement__Interop"
+    )
+    counts: Dict[str, int] = {}
+    for instruction in func.instructions:
+        counts[instruction.name] = counts.get(instruction.name, 0) + 1
+    assert counts["h"] == 5
+    assert counts["cx"] == 5
+    assert counts["mz"] == 6
+    assert counts["reset"] == 6
+
+
+def test_qir_module_unsupported_extension() -> None:
+    with pytest.raises(ValueError, match="^Unsupported file extension '.txt'."):
+        QirModule.from_file("tests/qir.txt")
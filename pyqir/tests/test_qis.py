@@ -1,13 +1,27 @@
 # Copyright (c) Microsoft Corporation.
 # Licensed under the MIT License.
 
-from typing import Callable, Union
+import cmath
+import math
+import warnings
+from typing import Callable, List, Union
 
 import pytest
 
 import pyqir
 import pyqir.qis
-from pyqir import BasicQisBuilder, Builder, Context, SimpleModule, Type, Value
+from pyqir import (
+    BasicQisBuilder,
+    Builder,
+    Call,
+    Context,
+    NoisyBasicQisBuilder,
+    Operation,
+    PointerType,
+    SimpleModule,
+    Type,
+    Value,
+)
 
 
 @pytest.mark.parametrize(
@@ -30,6 +44,7 @@ def test_zero_param_gates(
     "name, get_gate",
     [
         ("h", lambda qis: qis.h),
+        ("id", lambda qis: qis.id),
         ("reset", lambda qis: qis.reset),
         ("s", lambda qis: qis.s),
         ("t", lambda qis: qis.t),
@@ -84,7 +99,9 @@ def test_three_qubit_gates(
 @pytest.mark.parametrize(
     "name, get_gate",
     [
+        ("ch", lambda qis: qis.ch),
         ("cnot", lambda qis: qis.cx),
+        ("cy", lambda qis: qis.cy),
         ("cz", lambda qis: qis.cz),
     ],
 )
@@ -95,7 +112,27 @@ def test_controlled(
     qis = BasicQisBuilder(mod.builder)
     get_gate(qis)(mod.qubits[0], mod.qubits[1])
     call = f"call void @__quantum__qis__{name}__body(%Qubit* null, %Qubit* inttoptr (i64 1 to %Qubit*))"
-    assert call in mod.ir()
+    ir = mod.ir()
+    assert call in ir
+    assert ir.count("call ") == 1
+
+
+@pytest.mark.parametrize("name", ["ch", "cx", "cy", "cz"])
+def test_controlled_same_qubit(name: str) -> None:
+    mod = SimpleModule("test_controlled", 2, 0)
+    qis = BasicQisBuilder(mod.builder)
+    message = "^The qubits of a controlled gate must be distinct.$"
+    with pytest.raises(ValueError, match=message):
+        getattr(qis, name)(mod.qubits[1], mod.qubits[1])
+    assert len(mod) == 0
+
+
+def test_ccx_same_qubit() -> None:
+    mod = SimpleModule("test_controlled", 2, 0)
+    message = "^The qubits of a controlled gate must be distinct.$"
+    with pytest.raises(ValueError, match=message):
+        pyqir.qis.ccx(mod.builder, mod.qubits[0], mod.qubits[1], mod.qubits[0])
+    assert len(mod) == 0
 
 
 @pytest.mark.parametrize(
@@ -121,6 +158,7 @@ def test_adjoint(
         ("rx", lambda qis: qis.rx),
         ("ry", lambda qis: qis.ry),
         ("rz", lambda qis: qis.rz),
+        ("u1", lambda qis: qis.u1),
     ],
 )
 @pytest.mark.parametrize(
@@ -142,9 +180,876 @@ def test_rotated(
     assert call in mod.ir()
 
 
+@pytest.mark.parametrize("name", ["rx", "ry", "rz", "u1"])
+@pytest.mark.parametrize("angle", [math.nan, math.inf, -math.inf])
+def test_rotated_non_finite_angle(name: str, angle: float) -> None:
+    mod = SimpleModule("test_rotated", 1, 0)
+    gate = getattr(pyqir.qis, name)
+    message = f"^Angle of {name} on qubit 0 must be finite, not"
+    with pytest.raises(ValueError, match=message):
+        gate(mod.builder, angle, mod.qubits[0])
+    assert f"__quantum__qis__{name}__body" not in mod.ir()
+
+
+@pytest.mark.parametrize("name", ["rx", "ry", "rz", "u1"])
+@pytest.mark.parametrize("angle", [math.nan, math.inf, -math.inf])
+def test_rotated_non_finite_constant(name: str, angle: float) -> None:
+    mod = SimpleModule("test_rotated", 1, 0)
+    gate = getattr(pyqir.qis, name)
+    value = pyqir.const(pyqir.Type.double(mod.context), angle)
+    message = f"^Angle of {name} on qubit 0 must be finite, not"
+    with pytest.raises(ValueError, match=message):
+        gate(mod.builder, value, mod.qubits[0])
+    assert f"__quantum__qis__{name}__body" not in mod.ir()
+
+
+def test_id_is_identity() -> None:
+    mod = SimpleModule("test_id", 1, 1)
+    qis = BasicQisBuilder(mod.builder)
+    qis.id(mod.qubits[0])
+    assert mod.instructions == [Operation("id", (0,))]
+    assert mod.to_matrix() == [[1, 0], [0, 1]]
+    assert mod.adjoint().instructions == mod.instructions
+    assert mod.power(0.5).instructions == mod.instructions
+    qis.mz(mod.qubits[0], mod.results[0])
+    assert mod.simulate(10, seed=0) == {"0": 10}
+    assert mod.draw() == "q0: --I--M0--"
+
+
 def test_mz() -> None:
     mod = SimpleModule("test_mz", 1, 1)
     qis = BasicQisBuilder(mod.builder)
     qis.mz(mod.qubits[0], mod.results[0])
     call = f"call void @__quantum__qis__mz__body(%Qubit* null, %Result* null)"
     assert call in mod.ir()
+
+
+def test_m() -> None:
+    mod = SimpleModule("test_m", 1, 0)
+    qis = BasicQisBuilder(mod.builder)
+    result = qis.m(mod.qubits[0])
+    assert isinstance(result.type, PointerType)
+    call = "%0 = call %Result* @__quantum__qis__m__body(%Qubit* null)"
+    assert call in mod.ir()
+
+
+def test_if_m() -> None:
+    mod = SimpleModule("test_if_m", 1, 0)
+    qis = BasicQisBuilder(mod.builder)
+    qis.if_result(qis.m(mod.qubits[0]), one=lambda: qis.x(mod.qubits[0]))
+    ir = mod.ir()
+    assert "%1 = call i1 @__quantum__qis__read_result__body(%Result* %0)" in ir
+    assert "br i1 %1, label %then, label %else" in ir
+
+
+def test_read_result() -> None:
+    mod = SimpleModule("test_read_result", 2, 2)
+    qis = BasicQisBuilder(mod.builder)
+    builder = mod.builder
+    qis.mz(mod.qubits[0], mod.results[0])
+    qis.mz(mod.qubits[1], mod.results[1])
+    a = qis.read_result(mod.results[0])
+    b = pyqir.qis.read_result(builder, mod.results[1])
+    assert isinstance(a.type, pyqir.IntType) and a.type.width == 1
+    same = builder.icmp(pyqir.IntPredicate.EQ, a, b)
+    then = pyqir.BasicBlock(mod.context, "then", mod.entry_point)
+    footer = pyqir.BasicBlock(mod.context, "footer", mod.entry_point)
+    builder.condbr(same, then, footer)
+    builder.insert_at_end(then)
+    qis.x(mod.qubits[0])
+    builder.br(footer)
+    builder.insert_at_end(footer)
+
+    ir = mod.ir()
+    assert (
+        "  %0 = call i1 @__quantum__qis__read_result__body(%Result* null)\n"
+        "  %1 = call i1 @__quantum__qis__read_result__body"
+        "(%Result* inttoptr (i64 1 to %Result*))\n"
+        "  %2 = icmp eq i1 %0, %1\n"
+        "  br i1 %2, label %then, label %footer\n"
+    ) in ir
+    assert ir.count("__quantum__qis__mz__body(") == 3
+
+
+def test_read_result_out_of_range() -> None:
+    mod = SimpleModule("test_read_result", 1, 1)
+    result = pyqir.result(mod.context, 1)
+    message = "^Result 1 is out of range for module test_read_result, which has 1 result.$"
+    with pytest.raises(IndexError, match=message):
+        pyqir.qis.read_result(mod.builder, result)
+
+
+def test_if_result_label() -> None:
+    mod = SimpleModule("test_if_result_label", 1, 2)
+    qis = BasicQisBuilder(mod.builder)
+    qis.mz(mod.qubits[0], mod.results[0])
+    qis.if_result(mod.results[0], one=lambda: qis.x(mod.qubits[0]), label="correct")
+    pyqir.qis.if_result(mod.builder, mod.results[1], label="correct")
+    qis.if_result(mod.results[1])
+    ir = mod.ir()
+    assert "br i1 %0, label %correct_then, label %correct_else" in ir
+    assert "\ncorrect_continue:" in ir
+    assert "br i1 %1, label %correct_then1, label %correct_else2" in ir
+    assert "br i1 %2, label %then, label %else" in ir
+
+
+@pytest.mark.parametrize("label", ["", "a\0b"])
+def test_if_result_invalid_label(label: str) -> None:
+    mod = SimpleModule("test_if_result_label", 1, 1)
+    with pytest.raises(ValueError, match="^Label must be non-empty"):
+        pyqir.qis.if_result(mod.builder, mod.results[0], label=label)
+    assert "read_result" not in mod.ir()
+
+
+def test_cx_ladder_decomposes_into_toffolis() -> None:
+    mod = SimpleModule("test_cx_ladder", 7, 0)
+    qis = BasicQisBuilder(mod.builder)
+    q = mod.qubits
+    qis.cx_ladder(q[0:4], q[4], q[5:7])
+
+    def ccx(a: int, b: int, c: int) -> str:
+        args = ", ".join(
+            "%Qubit* null" if i == 0 else f"%Qubit* inttoptr (i64 {i} to %Qubit*)"
+            for i in (a, b, c)
+        )
+        return f"call void @__quantum__qis__ccx__body({args})"
+
+    calls = [
+        line.strip()
+        for line in mod.ir().splitlines()
+        if line.strip().startswith("call void @__quantum__qis__ccx__body")
+    ]
+    assert calls == [
+        ccx(0, 1, 5),
+        ccx(2, 5, 6),
+        ccx(3, 6, 4),
+        ccx(2, 5, 6),
+        ccx(0, 1, 5),
+    ]
+
+
+@pytest.mark.parametrize("num_controls, gate", [(0, "x"), (1, "cnot"), (2, "ccx")])
+def test_cx_ladder_with_few_controls(num_controls: int, gate: str) -> None:
+    mod = SimpleModule("test_cx_ladder", 3, 0)
+    qis = BasicQisBuilder(mod.builder)
+    qis.cx_ladder(mod.qubits[:num_controls], mod.qubits[2])
+    ir = mod.ir()
+    assert ir.count("call void @__quantum__qis__") == 1
+    assert f"call void @__quantum__qis__{gate}__body" in ir
+
+
+def test_cx_ladder_not_enough_ancillas() -> None:
+    mod = SimpleModule("test_cx_ladder", 6, 0)
+    qis = BasicQisBuilder(mod.builder)
+    with pytest.raises(ValueError, match="4 controls need 2 ancilla qubits"):
+        qis.cx_ladder(mod.qubits[0:4], mod.qubits[4], [mod.qubits[5]])
+
+
+def test_cx_ladder_overlapping_qubits() -> None:
+    mod = SimpleModule("test_cx_ladder", 4, 0)
+    qis = BasicQisBuilder(mod.builder)
+    with pytest.raises(ValueError, match="distinct"):
+        qis.cx_ladder(mod.qubits[0:3], mod.qubits[3], [mod.qubits[0]])
+
+
+def test_base_profile_declarations() -> None:
+    ir = pyqir.qis_declarations("base_profile")
+    assert "define" not in ir
+    assert "declare void @__quantum__qis__h__body(%Qubit*)" in ir
+    assert (
+        "declare void @__quantum__qis__mz__body(%Qubit*, %Result* writeonly) #0" in ir
+    )
+    assert "read_result" not in ir
+    assert "__quantum__qis__m__body" not in ir
+
+
+def test_adaptive_profile_declarations() -> None:
+    ir = pyqir.qis_declarations("adaptive_profile")
+    assert "declare i1 @__quantum__qis__read_result__body(%Result*)" in ir
+    assert "declare void @__quantum__qis__reset__body(%Qubit*)" in ir
+    assert "__quantum__qis__m__body" not in ir
+
+
+def test_declarations_match_builder() -> None:
+    mod = SimpleModule("test_declarations_match_builder", 2, 1)
+    qis = BasicQisBuilder(mod.builder)
+    qis.cx(mod.qubits[0], mod.qubits[1])
+    qis.rx(1.0, mod.qubits[0])
+    qis.if_result(qis.m(mod.qubits[1]))
+    # Attribute group numbers depend on the rest of the module, so leave them out.
+    def declarations(ir: str) -> List[str]:
+        lines = ir.splitlines()
+        return [line.split(" #")[0] for line in lines if line.startswith("declare")]
+
+    built = declarations(mod.ir())
+    assert len(built) == 4
+    assert set(built) <= set(declarations(pyqir.qis_declarations("custom")))
+
+
+def test_unknown_profile_declarations() -> None:
+    with pytest.raises(ValueError):
+        pyqir.qis_declarations("unknown")
+
+
+def _callees(mod: SimpleModule) -> List[str]:
+    return [i.callee.name for i in mod.entry_block.instructions if isinstance(i, Call)]
+
+
+@pytest.mark.parametrize(
+    "get_gate, gates",
+    [
+        (lambda qis: qis.x, ["cnot"]),
+        (lambda qis: qis.y, ["s__adj", "cnot", "s"]),
+        (lambda qis: qis.z, ["cz"]),
+        (lambda qis: qis.h, ["ry", "cz", "ry"]),
+        (lambda qis: qis.id, ["id"]),
+        (lambda qis: qis.s, ["t", "t", "cnot", "t__adj", "cnot"]),
+        (lambda qis: qis.s_adj, ["t__adj", "t__adj", "cnot", "t", "cnot"]),
+        (lambda qis: qis.t, ["rz", "rz", "cnot", "rz", "cnot"]),
+        (lambda qis: qis.t_adj, ["rz", "rz", "cnot", "rz", "cnot"]),
+    ],
+)
+def test_controlled_form(
+    get_gate: Callable[[BasicQisBuilder], Callable[[Value], None]], gates: List[str]
+) -> None:
+    mod = SimpleModule("test_controlled_form", 2, 0)
+    qis = BasicQisBuilder(mod.builder)
+    get_gate(qis)(mod.qubits[1])
+    mod.builder.controlled(mod.entry_block.instructions[-1], mod.qubits[0])
+    names = [f"__quantum__qis__{g}" for g in gates]
+    names = [n if n.endswith("__adj") else f"{n}__body" for n in names]
+    assert _callees(mod) == names
+
+
+def test_controlled_cx() -> None:
+    mod = SimpleModule("test_controlled_cx", 3, 0)
+    qis = BasicQisBuilder(mod.builder)
+    qis.cx(mod.qubits[1], mod.qubits[2])
+    mod.builder.controlled(mod.entry_block.instructions[-1], mod.qubits[0])
+    call = (
+        "call void @__quantum__qis__ccx__body(%Qubit* null, "
+        "%Qubit* inttoptr (i64 1 to %Qubit*), %Qubit* inttoptr (i64 2 to %Qubit*))"
+    )
+    assert call in mod.ir()
+    assert "cnot" not in str(mod.entry_point)
+
+
+def test_controlled_rotation_with_dynamic_angle() -> None:
+    mod = SimpleModule("test_controlled_rotation", 2, 0)
+    double = Type.double(mod.context)
+    theta = mod.add_external_function("theta", pyqir.FunctionType(double, []))
+    qis = BasicQisBuilder(mod.builder)
+    qis.rx(mod.builder.call(theta, []), mod.qubits[1])
+    mod.builder.controlled(mod.entry_block.instructions[-1], mod.qubits[0])
+    assert _callees(mod) == [
+        "theta",
+        "__quantum__qis__h__body",
+        "__quantum__qis__rz__body",
+        "__quantum__qis__cnot__body",
+        "__quantum__qis__rz__body",
+        "__quantum__qis__cnot__body",
+        "__quantum__qis__h__body",
+    ]
+    assert "fmul double %0, 5.000000e-01" in mod.ir()
+    assert "fmul double %0, -5.000000e-01" in mod.ir()
+
+
+def test_controlled_keeps_builder_position() -> None:
+    mod = SimpleModule("test_controlled_position", 2, 0)
+    qis = BasicQisBuilder(mod.builder)
+    qis.x(mod.qubits[1])
+    call = mod.entry_block.instructions[-1]
+    qis.h(mod.qubits[1])
+    mod.builder.controlled(call, mod.qubits[0])
+    qis.z(mod.qubits[1])
+    assert _callees(mod) == [
+        "__quantum__qis__cnot__body",
+        "__quantum__qis__h__body",
+        "__quantum__qis__z__body",
+    ]
+
+
+def test_controlled_without_controlled_form() -> None:
+    mod = SimpleModule("test_controlled_mz", 2, 1)
+    qis = BasicQisBuilder(mod.builder)
+    qis.mz(mod.qubits[1], mod.results[0])
+    call = mod.entry_block.instructions[-1]
+    message = "^Gate __quantum__qis__mz__body has no controlled form.$"
+    with pytest.raises(ValueError, match=message):
+        mod.builder.controlled(call, mod.qubits[0])
+    assert _callees(mod) == ["__quantum__qis__mz__body"]
+
+
+def test_controlled_on_zero() -> None:
+    mod = SimpleModule("test_controlled_on_zero", 2, 1)
+    qis = BasicQisBuilder(mod.builder)
+    qis.x(mod.qubits[1])
+    mod.builder.controlled(mod.entry_block.instructions[-1], mod.qubits[0], 0)
+    qis.mz(mod.qubits[1], mod.results[0])
+    call = mod.entry_block.instructions[-1]
+    with pytest.raises(ValueError, match="has no controlled form"):
+        mod.builder.controlled(call, mod.qubits[0], 0)
+    assert mod.instructions == [
+        Operation("x", (0,)),
+        Operation("cx", (0, 1)),
+        Operation("x", (0,)),
+        Operation("mz", (1,), (0,)),
+    ]
+
+
+@pytest.mark.parametrize("name", ["ch", "cx", "cy", "cz"])
+def test_basic_controlled_on_zero(name: str) -> None:
+    mod = SimpleModule("test_controlled_on_zero", 2, 0)
+    qis = BasicQisBuilder(mod.builder)
+    getattr(qis, name)(mod.qubits[0], mod.qubits[1], control_state=0)
+    assert mod.instructions == [
+        Operation("x", (0,)),
+        Operation(name, (0, 1)),
+        Operation("x", (0,)),
+    ]
+
+
+def test_invalid_control_state() -> None:
+    mod = SimpleModule("test_controlled_on_zero", 2, 0)
+    qis = BasicQisBuilder(mod.builder)
+    message = "^Control state must be 0 or 1, not 2.$"
+    with pytest.raises(ValueError, match=message):
+        qis.cx(mod.qubits[0], mod.qubits[1], control_state=2)
+    qis.x(mod.qubits[1])
+    with pytest.raises(ValueError, match=message):
+        mod.builder.controlled(mod.entry_block.instructions[-1], mod.qubits[0], 2)
+    assert mod.instructions == [Operation("x", (1,))]
+
+
+def test_measure_reset() -> None:
+    mod = SimpleModule("test_measure_reset", 2, 2)
+    qis = BasicQisBuilder(mod.builder)
+    qis.h(mod.qubits[0])
+    qis.measure_reset(mod.qubits[0], mod.results[1])
+    qis.measure_reset(mod.qubits[1], mod.results[0])
+    assert mod.instructions == [
+        Operation("h", (0,)),
+        Operation("mz", (0,), (1,)),
+        Operation("reset", (0,)),
+        Operation("mz", (1,), (0,)),
+        Operation("reset", (1,)),
+    ]
+    calls = [i.callee.name for i in mod.entry_block.instructions if isinstance(i, Call)]
+    assert calls[1:3] == ["__quantum__qis__mz__body", "__quantum__qis__reset__body"]
+
+
+def test_remeasure_allowed_by_default() -> None:
+    mod = SimpleModule("test_remeasure", 1, 2)
+    qis = BasicQisBuilder(mod.builder)
+    with warnings.catch_warnings():
+        warnings.simplefilter("error")
+        qis.mz(mod.qubits[0], mod.results[0])
+        qis.mz(mod.qubits[0], mod.results[1])
+    assert [op.name for op in mod.instructions] == ["mz", "mz"]
+
+
+def test_remeasure_warn() -> None:
+    mod = SimpleModule("test_remeasure", 2, 2)
+    qis = BasicQisBuilder(mod.builder, on_remeasure="warn")
+    with warnings.catch_warnings(record=True) as caught:
+        warnings.simplefilter("always")
+        qis.mz(mod.qubits[0], mod.results[0])
+        qis.mz(mod.qubits[1], mod.results[1])
+        qis.m(mod.qubits[0])
+    assert len(caught) == 1
+    assert issubclass(caught[0].category, UserWarning)
+    assert str(caught[0].message).startswith(
+        "Qubit 0 is measured again without a reset since it was last measured."
+    )
+    assert caught[0].filename == __file__
+    assert [op.name for op in mod.instructions] == ["mz", "mz", "m"]
+
+
+def test_remeasure_error() -> None:
+    mod = SimpleModule("test_remeasure", 1, 2)
+    qis = BasicQisBuilder(mod.builder, on_remeasure="error")
+    qis.mz(mod.qubits[0], mod.results[0])
+    message = "^Qubit 0 is measured again without a reset since it was last measured."
+    with pytest.raises(ValueError, match=message):
+        qis.mz(mod.qubits[0], mod.results[1])
+    with pytest.raises(ValueError, match=message):
+        qis.measure_reset(mod.qubits[0], mod.results[1])
+    assert [op.name for op in mod.instructions] == ["mz"]
+
+
+def test_remeasure_after_reset() -> None:
+    mod = SimpleModule("test_remeasure", 1, 3)
+    qis = BasicQisBuilder(mod.builder, on_remeasure="error")
+    qis.mz(mod.qubits[0], mod.results[0])
+    qis.reset(mod.qubits[0])
+    qis.measure_reset(mod.qubits[0], mod.results[1])
+    qis.mz(mod.qubits[0], mod.results[2])
+    assert [op.name for op in mod.instructions] == ["mz", "reset", "mz", "reset", "mz"]
+
+
+def test_remeasure_in_branches() -> None:
+    mod = SimpleModule("test_remeasure", 2, 3)
+    qis = BasicQisBuilder(mod.builder, on_remeasure="error")
+    qis.mz(mod.qubits[0], mod.results[0])
+    message = "^Qubit 1 is measured again .* in either branch of a conditional"
+
+    # A measurement in one branch counts after the branch.
+    qis.if_result(mod.results[0], one=lambda: qis.mz(mod.qubits[1], mod.results[1]))
+    with pytest.raises(ValueError, match=message):
+        qis.mz(mod.qubits[1], mod.results[2])
+
+    # Each branch starts from the state before the branch.
+    qis.if_result(
+        mod.results[0],
+        one=lambda: qis.reset(mod.qubits[1]),
+        zero=lambda: qis.x(mod.qubits[1]),
+    )
+    with pytest.raises(ValueError, match=message):
+        qis.mz(mod.qubits[1], mod.results[2])
+
+    # A reset in both branches clears the qubit.
+    qis.if_result(
+        mod.results[0],
+        one=lambda: qis.reset(mod.qubits[1]),
+        zero=lambda: qis.reset(mod.qubits[1]),
+    )
+    qis.mz(mod.qubits[1], mod.results[2])
+
+
+def test_remeasure_invalid_policy() -> None:
+    mod = SimpleModule("test_remeasure", 1, 1)
+    message = "^on_remeasure must be 'allow', 'warn' or 'error', not 'ignore'.$"
+    with pytest.raises(ValueError, match=message):
+        BasicQisBuilder(mod.builder, on_remeasure="ignore")
+
+
+def test_with_noise() -> None:
+    mod = SimpleModule("test_with_noise", 2, 1)
+    qis = BasicQisBuilder(mod.builder).with_noise(0.25)
+    assert isinstance(qis, NoisyBasicQisBuilder)
+    assert qis.p == 0.25
+    qis.h(mod.qubits[0])
+    qis.cx(mod.qubits[0], mod.qubits[1])
+    qis.rz(1.0, mod.qubits[1])
+    qis.mz(mod.qubits[0], mod.results[0])
+    assert mod.instructions == [
+        Operation("h", (0,)),
+        Operation("depolarize", (0,), (), (0.25,)),
+        Operation("cx", (0, 1)),
+        Operation("depolarize", (0,), (), (0.25,)),
+        Operation("depolarize", (1,), (), (0.25,)),
+        Operation("rz", (1,), (), (1.0,)),
+        Operation("depolarize", (1,), (), (0.25,)),
+        Operation("mz", (0,), (0,)),
+    ]
+
+
+def test_with_noise_composite() -> None:
+    mod = SimpleModule("test_with_noise_composite", 2, 0)
+    qis = BasicQisBuilder(mod.builder).with_noise(0.5)
+    qis.cz(mod.qubits[0], mod.qubits[1], control_state=0)
+    assert [op.name for op in mod.instructions] == [
+        "x",
+        "depolarize",
+        "cz",
+        "depolarize",
+        "depolarize",
+        "x",
+        "depolarize",
+    ]
+
+
+@pytest.mark.parametrize("p", [-0.1, 1.5, math.nan])
+def test_with_noise_out_of_range(p: float) -> None:
+    mod = SimpleModule("test_with_noise_out_of_range", 1, 0)
+    with pytest.raises(ValueError, match="^The probability of depolarizing must be"):
+        BasicQisBuilder(mod.builder).with_noise(p)
+    with pytest.raises(ValueError, match="^The probability of depolarizing must be"):
+        pyqir.qis.depolarize(mod.builder, p, mod.qubits[0])
+
+
+def test_depolarize_not_unitary() -> None:
+    mod = SimpleModule("test_depolarize_not_unitary", 1, 0)
+    BasicQisBuilder(mod.builder).with_noise(0.1).x(mod.qubits[0])
+    with pytest.raises(ValueError, match="^Circuits with noise aren't unitary.$"):
+        mod.to_matrix()
+
+
+def test_linear_entangle() -> None:
+    mod = SimpleModule("test_linear_entangle", 3, 0)
+    qis = BasicQisBuilder(mod.builder)
+    qis.linear_entangle(mod.qubits)
+    assert mod.instructions == [
+        Operation("h", (0,)),
+        Operation("cx", (0, 1)),
+        Operation("cx", (1, 2)),
+    ]
+
+
+def test_linear_entangle_overlapping_qubits() -> None:
+    mod = SimpleModule("test_linear_entangle", 2, 0)
+    qis = BasicQisBuilder(mod.builder)
+    with pytest.raises(ValueError, match="distinct"):
+        qis.linear_entangle([mod.qubits[0], mod.qubits[1], mod.qubits[0]])
+
+
+def test_swap_network_reverses_qubits() -> None:
+    mod = SimpleModule("test_swap_network", 4, 0)
+    qis = BasicQisBuilder(mod.builder)
+    qis.swap_network(mod.qubits)
+
+    swaps = [
+        tuple(pyqir.qubit_id(arg) for arg in i.args)
+        for i in mod.entry_block.instructions
+        if isinstance(i, Call)
+    ]
+    assert swaps == [
+        (0, 1),
+        (2, 3),
+        (1, 2),
+        (0, 1),
+        (2, 3),
+        (1, 2),
+    ]
+
+    # Track the qubit states to check that they end up reversed.
+    positions = [0, 1, 2, 3]
+    for a, b in swaps:
+        positions[a], positions[b] = positions[b], positions[a]
+    assert positions == [3, 2, 1, 0]
+
+
+def test_swap_network_interacts_every_pair() -> None:
+    mod = SimpleModule("test_swap_network", 5, 0)
+    qis = BasicQisBuilder(mod.builder)
+    states = list(range(5))
+    pairs = set()
+
+    def interact(a: Value, b: Value) -> None:
+        i, j = pyqir.qubit_id(a), pyqir.qubit_id(b)
+        assert i is not None and j is not None
+        pairs.add(frozenset((states[i], states[j])))
+        states[i], states[j] = states[j], states[i]
+        qis.cz(a, b)
+
+    qis.swap_network(mod.qubits, interact)
+    assert len(pairs) == 10
+    assert mod.ir().count("call void @__quantum__qis__cz__body(") == 10
+
+
+@pytest.mark.parametrize("num_qubits", [0, 1])
+def test_swap_network_trivial(num_qubits: int) -> None:
+    mod = SimpleModule("test_swap_network", num_qubits, 0)
+    qis = BasicQisBuilder(mod.builder)
+    qis.swap_network(mod.qubits)
+    assert "__quantum__qis__swap__body" not in mod.ir()
+
+
+def test_swap_network_overlapping_qubits() -> None:
+    mod = SimpleModule("test_swap_network", 2, 0)
+    qis = BasicQisBuilder(mod.builder)
+    with pytest.raises(ValueError, match="distinct"):
+        qis.swap_network([mod.qubits[0], mod.qubits[1], mod.qubits[0]])
+
+
+@pytest.mark.parametrize(
+    "variant, state",
+    [
+        (0, [1, 0, 0, 1]),
+        (1, [1, 0, 0, -1]),
+        (2, [0, 1, 1, 0]),
+        (3, [0, -1, 1, 0]),
+    ],
+)
+def test_prepare_bell_state(variant: int, state: List[int]) -> None:
+    mod = SimpleModule("test_prepare_bell_state", 2, 0)
+    qis = BasicQisBuilder(mod.builder)
+    qis.prepare_bell_state(mod.qubits[0], mod.qubits[1], variant)
+    prepared = [row[0] for row in mod.to_matrix()]
+    for actual, expected in zip(prepared, state):
+        assert abs(actual - expected / math.sqrt(2)) < 1e-9
+
+
+def _assert_equal_up_to_phase(
+    actual: List[List[complex]], expected: List[List[complex]]
+) -> None:
+    phase = actual[0][0] / expected[0][0]
+    assert abs(abs(phase) - 1) < 1e-9
+    for actual_row, expected_row in zip(actual, expected):
+        for a, e in zip(actual_row, expected_row):
+            assert abs(a - phase * e) < 1e-9
+
+
+@pytest.mark.parametrize("num_qubits", [1, 2, 3])
+def test_qft_is_dft(num_qubits: int) -> None:
+    mod = SimpleModule("test_qft", num_qubits, 0)
+    qis = BasicQisBuilder(mod.builder)
+    qis.qft(mod.qubits)
+    n = 2**num_qubits
+    dft = [
+        [cmath.exp(2j * math.pi * j * k / n) / math.sqrt(n) for k in range(n)]
+        for j in range(n)
+    ]
+    _assert_equal_up_to_phase(mod.to_matrix(), dft)
+
+
+def test_two_qubit_qft() -> None:
+    mod = SimpleModule("test_qft", 2, 0)
+    qis = BasicQisBuilder(mod.builder)
+    qis.qft(mod.qubits)
+    dft = [[1, 1, 1, 1], [1, 1j, -1, -1j], [1, -1, 1, -1], [1, -1j, -1, 1j]]
+    _assert_equal_up_to_phase(mod.to_matrix(), [[x / 2 for x in row] for row in dft])
+
+
+@pytest.mark.parametrize("num_qubits", [2, 3])
+def test_inverse_qft(num_qubits: int) -> None:
+    mod = SimpleModule("test_qft", num_qubits, 0)
+    qis = BasicQisBuilder(mod.builder)
+    qis.qft(mod.qubits, inverse=True)
+    n = 2**num_qubits
+    inverse_dft = [
+        [cmath.exp(-2j * math.pi * j * k / n) / math.sqrt(n) for k in range(n)]
+        for j in range(n)
+    ]
+    _assert_equal_up_to_phase(mod.to_matrix(), inverse_dft)
+
+
+def test_qft_overlapping_qubits() -> None:
+    mod = SimpleModule("test_qft", 2, 0)
+    qis = BasicQisBuilder(mod.builder)
+    with pytest.raises(ValueError, match="distinct"):
+        qis.qft([mod.qubits[0], mod.qubits[1], mod.qubits[0]])
+
+
+def test_prepare_bell_state_invalid_variant() -> None:
+    mod = SimpleModule("test_prepare_bell_state", 2, 0)
+    qis = BasicQisBuilder(mod.builder)
+    with pytest.raises(ValueError, match="between 0 and 3, not 4"):
+        qis.prepare_bell_state(mod.qubits[0], mod.qubits[1], 4)
+
+
+def test_qubit_of_larger_module_out_of_range() -> None:
+    context = Context()
+    a = SimpleModule("a", 3, 1, context)
+    b = SimpleModule("b", 1, 1, context)
+    qis = BasicQisBuilder(b.builder)
+    message = "^Qubit 2 is out of range for module b, which has 1 qubit.$"
+    with pytest.raises(IndexError, match=message):
+        qis.cx(b.qubits[0], a.qubits[2])
+    assert "__quantum__qis__cnot__body" not in b.ir()
+
+
+def test_qubit_of_same_size_module_is_shared() -> None:
+    # Qubits are constants of the context, so modules can't tell whose qubit is whose.
+    context = Context()
+    a = SimpleModule("a", 2, 0, context)
+    b = SimpleModule("b", 2, 0, context)
+    assert a.qubits[1] == b.qubits[1]
+    BasicQisBuilder(b.builder).cx(b.qubits[0], a.qubits[1])
+    assert b.instructions == [pyqir.Operation("cx", (0, 1))]
+
+
+def test_crossed_cx_in_different_contexts() -> None:
+    a = SimpleModule("a", 2, 0)
+    b = SimpleModule("b", 2, 0)
+    qis = BasicQisBuilder(b.builder)
+    with pytest.raises(ValueError, match="different contexts"):
+        qis.cx(b.qubits[0], a.qubits[1])
+
+
+def test_result_out_of_range() -> None:
+    context = Context()
+    a = SimpleModule("a", 1, 2, context)
+    b = SimpleModule("b", 1, 1, context)
+    qis = BasicQisBuilder(b.builder)
+    message = "^Result 1 is out of range for module b, which has 1 result.$"
+    with pytest.raises(IndexError, match=message):
+        qis.mz(b.qubits[0], a.results[1])
+
+
+def test_qubit_out_of_range_after_resize() -> None:
+    mod = SimpleModule("test_resize", 2, 0)
+    qis = BasicQisBuilder(mod.builder)
+    qubit = mod.qubits[1]
+    qis.h(qubit)
+    pyqir.add_string_attribute(mod.entry_point, "required_num_qubits", "1")
+    message = "^Qubit 1 is out of range for module test_resize, which has 1 qubit.$"
+    with pytest.raises(IndexError, match=message):
+        qis.h(qubit)
+
+
+def test_u2() -> None:
+    mod = SimpleModule("test_u2", 1, 0)
+    qis = BasicQisBuilder(mod.builder)
+    qis.u2(0.0, math.pi, mod.qubits[0])
+    call = (
+        "call void @__quantum__qis__u2__body(double 0.000000e+00, "
+        "double 0x400921FB54442D18, %Qubit* null)"
+    )
+    assert call in mod.ir()
+
+    # u2(0, pi) is a Hadamard gate.
+    h = 1 / math.sqrt(2)
+    for row, expected in zip(mod.to_matrix(), [[h, h], [h, -h]]):
+        assert all(abs(x - e) < 1e-9 for x, e in zip(row, expected))
+
+
+def test_u2_non_finite_angle() -> None:
+    mod = SimpleModule("test_u2", 1, 0)
+    with pytest.raises(ValueError, match="^Angle of u2 on qubit 0 must be finite"):
+        pyqir.qis.u2(mod.builder, 0.0, math.inf, mod.qubits[0])
+
+
+def _equal_up_to_phase(a: List[List[complex]], b: List[List[complex]]) -> bool:
+    i, j = max(
+        ((i, j) for i in range(2) for j in range(2)), key=lambda k: abs(b[k[0]][k[1]])
+    )
+    phase = a[i][j] / b[i][j]
+    return all(
+        abs(a[i][j] - phase * b[i][j]) < 1e-9 for i in range(2) for j in range(2)
+    )
+
+
+@pytest.mark.parametrize(
+    "matrix",
+    [
+        [[1, 0], [0, 1]],
+        [[0, 1], [1, 0]],
+        [[0, -1j], [1j, 0]],
+        [[1, 0], [0, -1]],
+        [[1 / math.sqrt(2), 1 / math.sqrt(2)], [1 / math.sqrt(2), -1 / math.sqrt(2)]],
+        [[1, 0], [0, cmath.exp(1j * math.pi / 4)]],
+        [[0, cmath.exp(0.3j)], [cmath.exp(-1.1j), 0]],
+        [
+            [cmath.exp(0.2j) * 0.6, cmath.exp(1.3j) * 0.8],
+            [cmath.exp(-0.4j) * 0.8, -cmath.exp(0.7j) * 0.6],
+        ],
+    ],
+)
+def test_unitary_1q(matrix: List[List[complex]]) -> None:
+    mod = SimpleModule("test_unitary_1q", 1, 0)
+    qis = BasicQisBuilder(mod.builder)
+    qis.unitary_1q(matrix, mod.qubits[0])
+    assert [op.name for op in mod.instructions] == ["rz", "ry", "rz"]
+    assert _equal_up_to_phase(mod.to_matrix(), matrix)
+
+
+@pytest.mark.parametrize(
+    "matrix, message",
+    [
+        ([[1, 0]], "must be 2x2"),
+        ([[1, 0, 0], [0, 1, 0]], "must be 2x2"),
+        ([[1, 1], [0, 1]], "must be unitary"),
+        ([[2, 0], [0, 0.5]], "must be unitary"),
+    ],
+)
+def test_unitary_1q_invalid(matrix: List[List[complex]], message: str) -> None:
+    mod = SimpleModule("test_unitary_1q", 1, 0)
+    qis = BasicQisBuilder(mod.builder)
+    with pytest.raises(ValueError, match=message):
+        qis.unitary_1q(matrix, mod.qubits[0])
+    assert mod.instructions == []
+
+
+def test_extend() -> None:
+    expected = SimpleModule("test_extend", 3, 1)
+    qis = BasicQisBuilder(expected.builder)
+    q = expected.qubits
+    qis.h(q[0])
+    qis.cx(q[0], q[1])
+    pyqir.qis.ccx(expected.builder, q[0], q[1], q[2])
+    qis.rz(0.5, q[2])
+    qis.u2(0.25, 0.75, q[1])
+    pyqir.qis.barrier(expected.builder)
+    qis.mz(q[2], expected.results[0])
+    qis.reset(q[2])
+
+    actual = SimpleModule("test_extend", 3, 1)
+    q = actual.qubits
+    BasicQisBuilder(actual.builder).extend(
+        [
+            ("h", q[0]),
+            ("cx", q[0], q[1]),
+            ("ccx", q[0], q[1], q[2]),
+            ("rz", 0.5, q[2]),
+            ("u2", 0.25, 0.75, q[1]),
+            ("barrier",),
+            ("mz", q[2], actual.results[0]),
+            ("reset", q[2]),
+        ]
+    )
+    assert actual.ir() == expected.ir()
+
+
+@pytest.mark.parametrize(
+    "instruction, error, message",
+    [
+        (("cnot", 0, 1), ValueError, '^Instruction 1: Unknown gate "cnot".$'),
+        (("cx", 0), ValueError, "^Instruction 1: Gate cx takes 2 arguments, not 1.$"),
+        ((), ValueError, "^Instruction 1: Expected a gate name, not an empty tuple.$"),
+        (["h", 0], TypeError, "^Instruction 1: Expected a tuple, not list.$"),
+        (("h", 0.5), TypeError, "^Instruction 1: "),
+        (("rx", "pi", 0), TypeError, "^Instruction 1: "),
+        (("rx", math.nan, 0), ValueError, "^Instruction 1: Angle of rx on qubit 0 "),
+        (("cz", 1, 1), ValueError, "^Instruction 1: The qubits of a controlled gate "),
+        (("x", 2), IndexError, "^Instruction 1: Qubit 2 is out of range for module "),
+    ],
+)
+def test_extend_invalid(instruction: tuple, error: type, message: str) -> None:
+    mod = SimpleModule("test_extend", 2, 0)
+    ir = mod.ir()
+    qubits = {i: pyqir.qubit(mod.context, i) for i in range(3)}
+    instruction = type(instruction)(qubits.get(a, a) for a in instruction)
+    with pytest.raises(error, match=message):
+        BasicQisBuilder(mod.builder).extend([("h", mod.qubits[0]), instruction])
+    assert mod.ir() == ir
+
+
+def test_extend_reports_first_invalid() -> None:
+    mod = SimpleModule("test_extend", 2, 0)
+    qubit = pyqir.qubit(mod.context, 2)
+    message = "^Instruction 0: Qubit 2 is out of range for module test_extend"
+    with pytest.raises(IndexError, match=message):
+        BasicQisBuilder(mod.builder).extend([("x", qubit), ("cnot",)])
+
+
+def test_extend_normalizes_angles() -> None:
+    mod = SimpleModule("test_extend", 1, 0)
+    qis = BasicQisBuilder(mod.builder, normalize_angles=True)
+    qis.extend([("rx", 3 * math.pi, mod.qubits[0])])
+    assert [op.angles for op in mod.instructions] == [(math.pi,)]
+
+
+def test_extend_remeasure() -> None:
+    mod = SimpleModule("test_extend", 1, 3)
+    qis = BasicQisBuilder(mod.builder, on_remeasure="error")
+    q, r = mod.qubits[0], mod.results
+    qis.extend([("mz", q, r[0]), ("reset", q), ("mz", q, r[1])])
+    with pytest.raises(ValueError, match="^Instruction 1: Qubit 0 is measured again"):
+        qis.extend([("h", q), ("mz", q, r[2])])
+    assert [op.name for op in mod.instructions] == ["mz", "reset", "mz"]
+
+    qis = BasicQisBuilder(mod.builder, on_remeasure="warn")
+    qis.mz(q, r[0])
+    with warnings.catch_warnings(record=True) as caught:
+        warnings.simplefilter("always")
+        qis.extend([("mz", q, r[1])])
+    assert len(caught) == 1
+    assert str(caught[0].message).startswith("Instruction 0: Qubit 0 is measured again")
+    assert caught[0].filename == __file__
+
+
+def test_extend_with_noise() -> None:
+    expected = SimpleModule("test_extend", 2, 1)
+    qis = BasicQisBuilder(expected.builder).with_noise(0.25)
+    qis.cx(expected.qubits[0], expected.qubits[1])
+    qis.mz(expected.qubits[1], expected.results[0])
+
+    actual = SimpleModule("test_extend", 2, 1)
+    BasicQisBuilder(actual.builder).with_noise(0.25).extend(
+        [
+            ("cx", actual.qubits[0], actual.qubits[1]),
+            ("mz", actual.qubits[1], actual.results[0]),
+        ]
+    )
+    assert actual.ir() == expected.ir()
@@ -0,0 +1,42 @@
+# Copyright (c) Microsoft Corporation.
+# Licensed under the MIT License.
+
+from pathlib import Path
+
+import pytest
+
+import pyqir
+from pyqir import random_circuit
+
+
+def test_random_circuit_is_deterministic() -> None:
+    a = random_circuit(5, 10, seed=42)
+    b = random_circuit(5, 10, seed=42)
+    assert a.ir() == b.ir()
+    assert a.instructions != random_circuit(5, 10, seed=43).instructions
+
+
+def test_random_circuit_layers() -> None:
+    simple = random_circuit(4, 6, seed=1)
+    assert simple.num_qubits == 4
+    assert simple.num_results == 0
+    touched = sum(len(op.qubits) for op in simple.instructions)
+    assert touched == 4 * 6
+
+
+def test_random_circuit_round_trips(tmp_path: Path) -> None:
+    simple = random_circuit(3, 8, seed=7)
+    path = tmp_path / "random_circuit.ll"
+    path.write_text(simple.ir())
+    assert pyqir.load_module(str(path)).instructions == simple.instructions
+
+
+@pytest.mark.parametrize("num_qubits, depth", [(0, 3), (3, 0), (1, 4)])
+def test_random_circuit_small(num_qubits: int, depth: int) -> None:
+    simple = random_circuit(num_qubits, depth, seed=0)
+    assert len(simple) == (depth if num_qubits == 1 else 0)
+
+
+def test_random_circuit_negative() -> None:
+    with pytest.raises(ValueError, match="must not be negative"):
+        random_circuit(-1, 2)
@@ -1,6 +1,9 @@
 # Copyright (c) Microsoft Corporation.
 # Licensed under the MIT License.
 
+import pytest
+
+import pyqir
 from pyqir import (
     Constant,
     IntType,
@@ -40,6 +43,17 @@ def test_initialize() -> None:
     assert call in mod.ir()
 
 
+def test_int_record_output_untagged() -> None:
+    mod = SimpleModule("int_record_output", 0, 0)
+    i8p = PointerType(IntType(mod.context, 8))
+    rt.int_record_output(
+        mod.builder, const(IntType(mod.context, 64), 42), Constant.null(i8p)
+    )
+    name = "int_record_output"
+    call = f"call void @__quantum__rt__{name}(i64 42, i8* null)"
+    assert call in mod.ir()
+
+
 def test_tuple_record_output_untagged() -> None:
     mod = SimpleModule("tuple_record_output", 0, 0)
     i8p = PointerType(IntType(mod.context, 8))
@@ -69,6 +83,16 @@ def test_result_record_output_untagged() -> None:
     assert call in mod.ir()
 
 
+def test_result_record_output_out_of_range() -> None:
+    mod = SimpleModule("result_record_output", 0, 1)
+    i8p = PointerType(IntType(mod.context, 8))
+    result = pyqir.result(mod.context, 1)
+    message = "^Result 1 is out of range for module result_record_output, which has 1 result.$"
+    with pytest.raises(IndexError, match=message):
+        rt.result_record_output(mod.builder, result, Constant.null(i8p))
+    assert mod.instructions == []
+
+
 def test_result_record_output_tagged() -> None:
     mod = SimpleModule("result_record_output", 0, 1)
     label = mod.add_byte_string(b"some tag")
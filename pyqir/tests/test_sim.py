@@ -0,0 +1,115 @@
+# Copyright (c) Microsoft Corporation.
+# Licensed under the MIT License.
+
+import math
+
+import pytest
+
+import pyqir.qis
+from pyqir import BasicQisBuilder, FunctionType, SimpleModule, Type
+
+
+def test_deterministic() -> None:
+    simple = SimpleModule("test_sim", 3, 3)
+    qis = BasicQisBuilder(simple.builder)
+    qis.x(simple.qubits[0])
+    qis.cx(simple.qubits[0], simple.qubits[2])
+    qis.mz(simple.qubits[0], simple.results[0])
+    qis.mz(simple.qubits[1], simple.results[1])
+    qis.mz(simple.qubits[2], simple.results[2])
+    assert simple.simulate(10) == {"101": 10}
+
+
+def test_bell_state() -> None:
+    simple = SimpleModule("test_sim", 2, 2)
+    qis = BasicQisBuilder(simple.builder)
+    qis.prepare_bell_state(simple.qubits[0], simple.qubits[1])
+    qis.mz(simple.qubits[0], simple.results[0])
+    qis.mz(simple.qubits[1], simple.results[1])
+    counts = simple.simulate(1000, seed=1)
+    assert set(counts) == {"00", "11"}
+    assert sum(counts.values()) == 1000
+    assert 400 < counts["00"] < 600
+
+
+def test_seed_is_reproducible() -> None:
+    simple = SimpleModule("test_sim", 1, 1)
+    qis = BasicQisBuilder(simple.builder)
+    qis.ry(1.0, simple.qubits[0])
+    qis.mz(simple.qubits[0], simple.results[0])
+    assert simple.simulate(100, seed=7) == simple.simulate(100, seed=7)
+
+
+def test_interference() -> None:
+    simple = SimpleModule("test_sim", 2, 2)
+    qis = BasicQisBuilder(simple.builder)
+    qis.h(simple.qubits[0])
+    qis.t(simple.qubits[0])
+    qis.t_adj(simple.qubits[0])
+    qis.h(simple.qubits[0])
+    qis.rx(math.pi, simple.qubits[1])
+    qis.rz(math.pi / 3, simple.qubits[1])
+    qis.mz(simple.qubits[0], simple.results[0])
+    qis.mz(simple.qubits[1], simple.results[1])
+    assert simple.simulate(10) == {"01": 10}
+
+
+def test_conditional_and_reset() -> None:
+    simple = SimpleModule("test_sim", 2, 2)
+    qis = BasicQisBuilder(simple.builder)
+    qis.h(simple.qubits[0])
+    qis.mz(simple.qubits[0], simple.results[0])
+    qis.if_result(simple.results[0], one=lambda: qis.x(simple.qubits[1]))
+    qis.mz(simple.qubits[1], simple.results[1])
+    qis.reset(simple.qubits[0])
+    qis.reset(simple.qubits[1])
+    counts = simple.simulate(100, seed=3)
+    assert set(counts) == {"00", "11"}
+
+
+def test_swap() -> None:
+    simple = SimpleModule("test_sim", 2, 2)
+    qis = BasicQisBuilder(simple.builder)
+    qis.x(simple.qubits[0])
+    pyqir.qis.swap(simple.builder, simple.qubits[0], simple.qubits[1])
+    qis.mz(simple.qubits[0], simple.results[0])
+    qis.mz(simple.qubits[1], simple.results[1])
+    assert simple.simulate(5) == {"01": 5}
+
+
+def test_noise() -> None:
+    noiseless = SimpleModule("test_sim", 1, 1)
+    qis = BasicQisBuilder(noiseless.builder).with_noise(0)
+    qis.x(noiseless.qubits[0])
+    qis.mz(noiseless.qubits[0], noiseless.results[0])
+    assert noiseless.simulate(100) == {"1": 100}
+
+    # Fully depolarizing flips the bit with probability 1/2.
+    noisy = SimpleModule("test_sim", 1, 1)
+    qis = BasicQisBuilder(noisy.builder).with_noise(1)
+    qis.x(noisy.qubits[0])
+    qis.mz(noisy.qubits[0], noisy.results[0])
+    counts = noisy.simulate(1000, seed=3)
+    assert 400 < counts["0"] < 600
+
+
+def test_unsupported_operation() -> None:
+    simple = SimpleModule("test_sim", 1, 0)
+    ty = FunctionType(Type.void(simple.context), [])
+    function = simple.add_external_function("f", ty)
+    simple.builder.call(function, [])
+    with pytest.raises(ValueError, match="^Operation f can't be simulated.$"):
+        simple.simulate(1)
+
+
+def test_phase_gates() -> None:
+    simple = SimpleModule("test_sim", 2, 2)
+    qis = BasicQisBuilder(simple.builder)
+    qis.u2(0.0, math.pi, simple.qubits[0])
+    qis.u1(math.pi, simple.qubits[0])
+    qis.h(simple.qubits[0])
+    qis.u2(math.pi / 2, math.pi / 2, simple.qubits[1])
+    qis.u2(math.pi / 2, math.pi / 2, simple.qubits[1])
+    qis.mz(simple.qubits[0], simple.results[0])
+    qis.mz(simple.qubits[1], simple.results[1])
+    assert simple.simulate(10) == {"10": 10}
@@ -1,7 +1,25 @@
 # Copyright (c) Microsoft Corporation.
 # Licensed under the MIT License.
 
+import contextlib
+import copy
+import ctypes
+import gc
+import inspect
+import io
+import math
+import os
+import re
+import subprocess
+import sys
+import threading
+from pathlib import Path
+from typing import Any, Callable, Dict, List
+
+import pytest
+
 import pyqir
+import pyqir.rt as rt
 from pyqir import (
     required_num_qubits,
     required_num_results,
@@ -36,3 +54,1776 @@ def test_entry_point_override_is_applied() -> None:
 
     entry = next(filter(is_entry_point, mod.functions))
     assert entry.name == "new_entry"
+
+
+def test_ir_reflects_instructions_added_after_emitting() -> None:
+    simple = pyqir.SimpleModule("test", 2, 0)
+    qis = pyqir.BasicQisBuilder(simple.builder)
+    qis.h(simple.qubits[0])
+    first = simple.ir()
+    assert first == simple.ir()
+    assert "cnot" not in first
+
+    qis.cx(simple.qubits[0], simple.qubits[1])
+    second = simple.ir()
+    assert second != first
+    assert "call void @__quantum__qis__h__body(%Qubit* null)" in second
+    assert "call void @__quantum__qis__cnot__body" in second
+    assert second.count("ret void") == 1
+
+
+def test_bitcode_reflects_instructions_added_after_emitting() -> None:
+    simple = pyqir.SimpleModule("test", 1, 0)
+    qis = pyqir.BasicQisBuilder(simple.builder)
+    simple.bitcode()
+    qis.x(simple.qubits[0])
+
+    mod = pyqir.Module.from_bitcode(pyqir.Context(), simple.bitcode())
+    entry = next(filter(is_entry_point, mod.functions))
+    calls = [str(i) for i in entry.basic_blocks[0].instructions]
+    assert calls == [
+        "  call void @__quantum__qis__x__body(%Qubit* null)",
+        "  ret void",
+    ]
+
+
+def test_source_location_survives_bitcode() -> None:
+    simple = pyqir.SimpleModule("test", 1, 0)
+    simple.set_source_location("/src/program.py", producer="my-generator 1.0")
+
+    mod = pyqir.Module.from_bitcode(pyqir.Context(), simple.bitcode())
+    assert mod.source_filename == "/src/program.py"
+    assert str(mod.get_flag("Debug Info Version")) == "i32 3"
+    ir = str(mod)
+    assert "!llvm.dbg.cu" in ir
+    assert 'producer: "my-generator 1.0"' in ir
+    assert '!DIFile(filename: "program.py", directory: "/src")' in ir
+
+
+def test_source_location_defaults_to_caller_file() -> None:
+    simple = pyqir.SimpleModule("test", 1, 0)
+    simple.set_source_location()
+    ir = simple.ir()
+    assert f'source_filename = "{__file__}"' in ir
+    assert f'filename: "{os.path.basename(__file__)}"' in ir
+    assert 'producer: "pyqir"' in ir
+
+
+def test_source_location_can_only_be_set_once() -> None:
+    simple = pyqir.SimpleModule("test", 1, 0)
+    simple.set_source_location("program.py")
+    with pytest.raises(ValueError):
+        simple.set_source_location("other.py")
+
+
+def test_debug_locations_are_attached_to_gates() -> None:
+    simple = pyqir.SimpleModule("test", 1, 0)
+    qis = pyqir.BasicQisBuilder(simple.builder)
+    simple.set_source_location("/src/program.py")
+    simple.mark_location(10, 4)
+    qis.h(simple.qubits[0])
+    simple.mark_location()
+    line = inspect.currentframe().f_lineno - 1  # type: ignore
+    qis.x(simple.qubits[0])
+    simple.builder.clear_debug_location()
+    qis.z(simple.qubits[0])
+
+    mod = pyqir.Module.from_bitcode(pyqir.Context(), simple.bitcode())
+    entry = next(filter(is_entry_point, mod.functions))
+    instructions = [str(i) for i in entry.basic_blocks[0].instructions]
+    assert "!dbg" in instructions[0]
+    assert "!dbg" in instructions[1]
+    assert "!dbg" not in instructions[2]
+    ir = str(mod)
+    assert "!DILocation(line: 10, column: 4, scope:" in ir
+    assert f"!DILocation(line: {line}, scope:" in ir
+
+
+def test_ir_without_debug_locations_is_unchanged() -> None:
+    simple = pyqir.SimpleModule("test", 1, 0)
+    qis = pyqir.BasicQisBuilder(simple.builder)
+    qis.h(simple.qubits[0])
+    assert "dbg" not in simple.ir()
+
+
+def test_debug_location_requires_source_location() -> None:
+    simple = pyqir.SimpleModule("test", 1, 0)
+    with pytest.raises(ValueError):
+        simple.mark_location(1)
+    with pytest.raises(ValueError):
+        simple.builder.set_debug_location(1)
+
+
+def test_copies_are_built_independently() -> None:
+    base = pyqir.SimpleModule("test", 2, 2)
+    qis = pyqir.BasicQisBuilder(base.builder)
+    qis.h(base.qubits[0])
+    qis.mz(base.qubits[0], base.results[0])
+    qis.if_result(base.results[0], one=lambda: qis.x(base.qubits[1]))
+
+    experiment = copy.copy(base)
+    pyqir.BasicQisBuilder(experiment.builder).z(experiment.qubits[1])
+    qis.y(base.qubits[1])
+
+    base_ir = base.ir()
+    experiment_ir = experiment.ir()
+    assert experiment.context is base.context
+    assert base_ir.replace("__y__", "__z__") == experiment_ir
+    assert "call void @__quantum__qis__h__body(%Qubit* null)" in experiment_ir
+    assert "__z__" not in base_ir
+    assert "__y__" not in experiment_ir
+
+
+def test_deepcopy_copies_the_module() -> None:
+    base = pyqir.SimpleModule("test", 1, 0, entry_point_name="start")
+    pyqir.BasicQisBuilder(base.builder).h(base.qubits[0])
+    other = copy.deepcopy(base)
+    pyqir.BasicQisBuilder(other.builder).x(other.qubits[0])
+
+    assert other.entry_point.name == "start"
+    assert "__x__" not in base.ir()
+    assert "__x__" in other.ir()
+
+
+def _module_with_dead_code() -> pyqir.SimpleModule:
+    simple = pyqir.SimpleModule("test", 1, 1)
+    qis = pyqir.BasicQisBuilder(simple.builder)
+    qis.h(simple.qubits[0])
+    qis.mz(simple.qubits[0], simple.results[0])
+    qis.if_result(simple.results[0])
+    read = simple.add_external_function(
+        "read", pyqir.FunctionType(pyqir.IntType(simple.context, 1), [])
+    )
+    value = simple.builder.call(read, [])
+    assert value is not None
+    simple.builder.xor(value, value)
+    return simple
+
+
+@pytest.mark.parametrize("level", [1, 2, 3])
+def test_optimize_removes_dead_code(level: int) -> None:
+    simple = _module_with_dead_code()
+    assert simple.optimize(level) is simple
+
+    ir = simple.ir()
+    assert "xor" not in ir
+    assert "br " not in ir
+    assert "call void @__quantum__qis__h__body(%Qubit* null)" in ir
+    assert "call void @__quantum__qis__mz__body" in ir
+    assert "call i1 @read()" in ir
+
+
+def test_optimize_level_zero_keeps_instructions() -> None:
+    simple = _module_with_dead_code()
+    before = simple.ir()
+    simple.optimize(0)
+    assert simple.ir() == before
+
+
+def test_building_continues_after_optimize() -> None:
+    simple = _module_with_dead_code()
+    qis = pyqir.BasicQisBuilder(simple.builder)
+    simple.optimize(2)
+    qis.x(simple.qubits[0])
+
+    mod = pyqir.Module.from_ir(pyqir.Context(), simple.ir())
+    entry = next(filter(is_entry_point, mod.functions))
+    instructions = [str(i) for b in entry.basic_blocks for i in b.instructions]
+    assert instructions[-2:] == [
+        "  call void @__quantum__qis__x__body(%Qubit* null)",
+        "  ret void",
+    ]
+
+
+def test_optimize_rejects_invalid_level() -> None:
+    simple = _module_with_dead_code()
+    before = simple.ir()
+    with pytest.raises(ValueError):
+        simple.optimize(4)
+    assert simple.ir() == before
+
+
+def _pointer_style_module() -> pyqir.SimpleModule:
+    simple = pyqir.SimpleModule("test_pointers", 2, 1)
+    qis = pyqir.BasicQisBuilder(simple.builder)
+    qis.h(simple.qubits[0])
+    qis.cx(simple.qubits[0], simple.qubits[1])
+    qis.mz(simple.qubits[1], simple.results[0])
+    qis.if_result(simple.results[0], one=lambda: qis.x(simple.qubits[1]))
+    return simple
+
+
+@pytest.mark.parametrize("opaque", [False, True])
+def test_pointer_style(opaque: bool) -> None:
+    simple = _pointer_style_module()
+    simple.use_opaque_pointers(opaque)
+    ir = simple.ir()
+
+    name = "test_opaque_pointers.ll" if opaque else "test_typed_pointers.ll"
+    file = os.path.join(os.path.dirname(__file__), "resources", name)
+    assert ir == Path(file).read_text()
+
+    context = pyqir.Context(opaque_pointers=opaque)
+    module = pyqir.Module.from_bitcode(context, simple.bitcode(), "test_pointers")
+    assert module.verify() is None
+    assert str(module) == ir
+
+
+def test_pointer_style_can_be_switched_back() -> None:
+    simple = _pointer_style_module()
+    typed = simple.ir()
+    simple.use_opaque_pointers()
+    assert "%Qubit*" not in simple.ir()
+    simple.use_opaque_pointers(False)
+    assert simple.ir() == typed
+
+
+def test_target_is_unset_by_default() -> None:
+    simple = pyqir.SimpleModule("test_target", 1, 0)
+    assert "target triple" not in simple.ir()
+    assert "target datalayout" not in simple.ir()
+
+
+def test_set_target() -> None:
+    triple = "aarch64-unknown-linux-gnu"
+    data_layout = "e-m:e-i8:8:32-i16:16:32-i64:64-i128:128-n32:64-S128"
+    simple = pyqir.SimpleModule("test_target", 1, 0)
+    simple.set_target(triple, data_layout)
+    ir = simple.ir()
+    assert f'target datalayout = "{data_layout}"' in ir
+    assert f'target triple = "{triple}"' in ir
+
+    module = pyqir.Module.from_bitcode(pyqir.Context(), simple.bitcode())
+    assert module.triple == triple
+    assert module.data_layout == data_layout
+
+
+def test_set_target_default_data_layout() -> None:
+    simple = pyqir.SimpleModule("test_target", 1, 0)
+    simple.set_target("aarch64-unknown-linux-gnu", "e")
+    simple.set_target("x86_64-unknown-linux-gnu")
+    assert 'target triple = "x86_64-unknown-linux-gnu"' in simple.ir()
+    assert "target datalayout" not in simple.ir()
+
+
+def test_set_target_invalid_triple() -> None:
+    simple = pyqir.SimpleModule("test_target", 1, 0)
+    message = "^Unknown architecture in target triple: qpu-unknown-unknown$"
+    with pytest.raises(ValueError, match=message):
+        simple.set_target("qpu-unknown-unknown")
+    assert "target triple" not in simple.ir()
+
+
+def test_set_target_invalid_data_layout() -> None:
+    simple = pyqir.SimpleModule("test_target", 1, 0)
+    simple.set_target("x86_64-unknown-linux-gnu")
+    with pytest.raises(ValueError):
+        simple.set_target("aarch64-unknown-linux-gnu", "e-i64:sixty-four")
+    assert 'target triple = "x86_64-unknown-linux-gnu"' in simple.ir()
+
+
+def test_qubit_and_result_ids() -> None:
+    simple = pyqir.SimpleModule("test_ids", 3, 2)
+    assert [pyqir.qubit_id(q) for q in simple.qubits] == [0, 1, 2]
+    assert [pyqir.result_id(r) for r in simple.results] == [0, 1]
+    assert pyqir.qubit_id(simple.results[1]) is None
+    assert pyqir.result_id(simple.qubits[1]) is None
+
+
+def test_defer_measurements() -> None:
+    simple = pyqir.SimpleModule("test_defer", 3, 2)
+    qis = pyqir.BasicQisBuilder(simple.builder)
+    qis.h(simple.qubits[0])
+    qis.mz(simple.qubits[0], simple.results[0])
+    qis.cx(simple.qubits[1], simple.qubits[2])
+    qis.mz(simple.qubits[1], simple.results[1])
+    qis.x(simple.qubits[2])
+    simple.defer_measurements()
+
+    gates = [
+        i.callee.name.split("__")[3]
+        for i in simple.entry_block.instructions
+        if isinstance(i, pyqir.Call)
+    ]
+    assert gates == ["h", "cnot", "x", "mz", "mz"]
+    mz = [i for i in simple.entry_block.instructions if "mz" in str(i)]
+    assert [pyqir.result_id(i.args[1]) for i in mz] == [0, 1]
+
+
+def test_defer_measurements_measure_then_gate() -> None:
+    simple = pyqir.SimpleModule("test_defer", 1, 1)
+    qis = pyqir.BasicQisBuilder(simple.builder)
+    qis.mz(simple.qubits[0], simple.results[0])
+    qis.x(simple.qubits[0])
+    before = simple.ir()
+    with pytest.raises(ValueError, match="^Qubit 0 is used after it is measured.$"):
+        simple.defer_measurements()
+    assert simple.ir() == before
+
+
+def test_defer_measurements_with_branch() -> None:
+    simple = pyqir.SimpleModule("test_defer", 2, 1)
+    qis = pyqir.BasicQisBuilder(simple.builder)
+    qis.mz(simple.qubits[0], simple.results[0])
+    qis.if_result(simple.results[0], one=lambda: qis.x(simple.qubits[1]))
+    message = "^Measurements can't be deferred past control flow.$"
+    with pytest.raises(ValueError, match=message):
+        simple.defer_measurements()
+
+
+def test_entry_point_attributes() -> None:
+    simple = pyqir.SimpleModule("test_attributes", 1, 0)
+    simple.add_entry_point_attribute("maxShots", "1000")
+    simple.add_entry_point_attribute("backend", "h1-1")
+    simple.add_entry_point_attribute("backend", "h1-2")
+    ir = simple.ir()
+    assert '"backend"="h1-2"' in ir
+    assert '"maxShots"="1000"' in ir
+    assert "h1-1" not in ir
+
+    attributes = simple.entry_point.attributes.func
+    assert attributes["backend"].string_value == "h1-2"
+    assert attributes["maxShots"].string_value == "1000"
+    assert attributes["required_num_qubits"].string_value == "1"
+
+
+def test_entry_point_attribute_values_are_escaped() -> None:
+    simple = pyqir.SimpleModule("test_attributes", 1, 0)
+    value = 'say "hi"\\\n\x01'
+    simple.add_entry_point_attribute("greeting", value)
+    ir = simple.ir()
+    assert '"greeting"="say \\22hi\\22\\\\\\0A\\01"' in ir
+
+    module = pyqir.Module.from_ir(pyqir.Context(), ir)
+    attributes = module.functions[0].attributes.func
+    assert attributes["greeting"].string_value == value
+
+
+@pytest.mark.parametrize("key", ['say "hi"', "back\\slash", "new\nline"])
+def test_entry_point_attribute_invalid_key(key: str) -> None:
+    simple = pyqir.SimpleModule("test_attributes", 1, 0)
+    with pytest.raises(ValueError, match="can't be written as IR"):
+        simple.add_entry_point_attribute(key, "value")
+
+
+def test_entry_point_attribute_reserved() -> None:
+    simple = pyqir.SimpleModule("test_attributes", 1, 0)
+    message = "^The entry point attribute required_num_qubits is reserved.$"
+    with pytest.raises(ValueError, match=message):
+        simple.add_entry_point_attribute("required_num_qubits", "2")
+    assert '"required_num_qubits"="1"' in simple.ir()
+
+
+def test_num_qubits_and_results() -> None:
+    simple = pyqir.SimpleModule("test_counts", 3, 2)
+    assert simple.num_qubits == 3
+    assert simple.num_results == 2
+    assert simple.num_qubits == len(simple.qubits)
+    assert simple.num_results == len(simple.results)
+    assert pyqir.required_num_qubits(simple.entry_point) == simple.num_qubits
+    assert pyqir.required_num_results(simple.entry_point) == simple.num_results
+
+
+def test_num_qubits_and_results_of_copy() -> None:
+    simple = pyqir.SimpleModule("test_counts", 4, 1)
+    other = copy.copy(simple)
+    assert (other.num_qubits, other.num_results) == (4, 1)
+
+
+def test_num_qubits_and_results_with_second_entry_point() -> None:
+    simple = pyqir.SimpleModule("test_counts", 2, 1)
+    other = pyqir.entry_point(simple._module, "other", 5, 3)
+    assert pyqir.required_num_qubits(other) == 5
+    assert (simple.num_qubits, simple.num_results) == (2, 1)
+
+
+def _capsule_pointer(capsule: Any) -> int:
+    get_pointer = ctypes.pythonapi.PyCapsule_GetPointer
+    get_pointer.argtypes = [ctypes.py_object, ctypes.c_char_p]
+    get_pointer.restype = ctypes.c_void_p
+    return get_pointer(capsule, b"LLVMModuleRef")
+
+
+def test_llvm_module_ptr() -> None:
+    module = pyqir.Module(pyqir.Context(), "test")
+    assert _capsule_pointer(module.llvm_module_ptr()) != 0
+    assert _capsule_pointer(module.llvm_module_ptr()) == _capsule_pointer(
+        module.llvm_module_ptr()
+    )
+
+
+def test_llvm_module_ptr_keeps_module_alive() -> None:
+    simple = pyqir.SimpleModule("test_llvm_module_ptr", 1, 0)
+    pyqir.BasicQisBuilder(simple.builder).x(simple.qubits[0])
+    capsule = simple.llvm_module_ptr()
+    del simple
+    gc.collect()
+    assert _capsule_pointer(capsule) != 0
+
+
+def test_llvm_module_ptr_is_a_copy() -> None:
+    simple = pyqir.SimpleModule("test_llvm_module_ptr", 1, 0)
+    pyqir.BasicQisBuilder(simple.builder).x(simple.qubits[0])
+    ir = simple.ir()
+    first = simple.llvm_module_ptr()
+    second = simple.llvm_module_ptr()
+    assert _capsule_pointer(first) != _capsule_pointer(second)
+    assert simple.ir() == ir
+    pyqir.BasicQisBuilder(simple.builder).h(simple.qubits[0])
+    assert "__quantum__qis__h__body" in simple.ir()
+
+
+def _measure_all() -> pyqir.SimpleModule:
+    simple = pyqir.SimpleModule("test_batched_measurements", 2, 2)
+    qis = pyqir.BasicQisBuilder(simple.builder)
+    qis.h(simple.qubits[0])
+    qis.cx(simple.qubits[0], simple.qubits[1])
+    qis.mz(simple.qubits[0], simple.results[0])
+    qis.mz(simple.qubits[1], simple.results[1])
+    return simple
+
+
+def test_measurements_are_not_batched_by_default() -> None:
+    ir = _measure_all().ir()
+    assert ir.count("call void @__quantum__qis__mz__body(") == 2
+    assert "mz_batch" not in ir
+
+
+def test_batched_measurements() -> None:
+    simple = _measure_all()
+    per_qubit = simple.ir()
+    simple.use_batched_measurements()
+    batched = simple.ir()
+    assert "call void @__quantum__qis__mz__body(" not in batched
+    assert (
+        "call void @__quantum__qis__mz_batch__body(i64 2, %Qubit** getelementptr"
+        in batched
+    )
+    assert "internal constant [2 x %Qubit*]" in batched
+    assert "internal constant [2 x %Result*]" in batched
+
+    # Batching only changes the emitted module.
+    simple.use_batched_measurements(False)
+    assert simple.ir() == per_qubit
+
+
+def test_batched_measurements_of_copy() -> None:
+    simple = _measure_all()
+    simple.use_batched_measurements()
+    other = copy.copy(simple)
+    assert "mz_batch" in other.ir()
+    assert "mz_batch" in simple.ir()
+
+
+def _bell_with_correction(context: pyqir.Context) -> pyqir.SimpleModule:
+    library = pyqir.SimpleModule("library", 2, 1, context)
+    qis = pyqir.BasicQisBuilder(library.builder)
+    qis.h(library.qubits[0])
+    qis.cx(library.qubits[0], library.qubits[1])
+    qis.mz(library.qubits[1], library.results[0])
+    qis.if_result(library.results[0], one=lambda: qis.x(library.qubits[0]))
+    return library
+
+
+def _expected_extended_ir(name: str) -> str:
+    expected = pyqir.SimpleModule(name, 3, 2)
+    qis = pyqir.BasicQisBuilder(expected.builder)
+    qis.x(expected.qubits[0])
+    qis.h(expected.qubits[2])
+    qis.cx(expected.qubits[2], expected.qubits[0])
+    qis.mz(expected.qubits[0], expected.results[1])
+    qis.if_result(expected.results[1], one=lambda: qis.x(expected.qubits[2]))
+    qis.h(expected.qubits[1])
+    return expected.ir()
+
+
+def test_extend() -> None:
+    simple = pyqir.SimpleModule("test_extend", 3, 2)
+    library = _bell_with_correction(simple.context)
+    library_ir = library.ir()
+    qis = pyqir.BasicQisBuilder(simple.builder)
+    qis.x(simple.qubits[0])
+    simple.extend(library, {0: 2, 1: 0}, {0: 1})
+    qis.h(simple.qubits[1])
+    assert simple.ir() == _expected_extended_ir("test_extend")
+    assert library.ir() == library_ir
+
+
+def test_extend_with_unmapped_qubit() -> None:
+    simple = pyqir.SimpleModule("test_extend", 3, 2)
+    library = _bell_with_correction(simple.context)
+    with pytest.raises(ValueError, match="^Qubit 1 isn't in the qubit map.$"):
+        simple.extend(library, {0: 2}, {0: 1})
+    with pytest.raises(ValueError, match="^Result 0 isn't in the result map.$"):
+        simple.extend(library, {0: 2, 1: 0}, {})
+    assert "__quantum__qis__h__body" not in simple.ir()
+
+
+def test_extend_out_of_range() -> None:
+    simple = pyqir.SimpleModule("test_extend", 3, 2)
+    library = _bell_with_correction(simple.context)
+    with pytest.raises(ValueError, match="^Qubit 3 is out of range.$"):
+        simple.extend(library, {0: 3, 1: 0}, {0: 1})
+    with pytest.raises(ValueError, match="^Result 2 is out of range.$"):
+        simple.extend(library, {0: 2, 1: 0}, {0: 2})
+
+
+def test_extend_copies_labels() -> None:
+    simple = pyqir.SimpleModule("test_extend", 2, 2)
+    library = pyqir.SimpleModule("library", 1, 1, simple.context)
+    pyqir.BasicQisBuilder(library.builder).mz(library.qubits[0], library.results[0])
+    label = library.add_byte_string(b"r0")
+    rt.result_record_output(library.builder, library.results[0], label)
+
+    simple.extend(library, {0: 1}, {0: 1})
+    ir = simple.ir()
+    assert 'c"r0\\00"' in ir
+    assert (
+        "call void @__quantum__rt__result_record_output(%Result* inttoptr (i64 1"
+        in ir
+    )
+
+
+def test_extend_with_branches_before_instruction() -> None:
+    simple = pyqir.SimpleModule("test_extend", 3, 2)
+    qis = pyqir.BasicQisBuilder(simple.builder)
+    qis.x(simple.qubits[0])
+    simple.builder.insert_before(simple.entry_block.instructions[0])
+    library = _bell_with_correction(simple.context)
+    with pytest.raises(
+        ValueError, match="^Functions that branch must be appended at the end of a block.$"
+    ):
+        simple.extend(library, {0: 2, 1: 0}, {0: 1})
+    assert len(simple.entry_point.basic_blocks) == 1
+    assert simple.instructions == [pyqir.Operation("x", (0,))]
+
+    straight = pyqir.SimpleModule("straight", 1, 0, simple.context)
+    pyqir.BasicQisBuilder(straight.builder).h(straight.qubits[0])
+    simple.extend(straight, {0: 1}, {})
+    assert simple.instructions == [pyqir.Operation("h", (1,)), pyqir.Operation("x", (0,))]
+
+
+def test_extend_from_another_context() -> None:
+    simple = pyqir.SimpleModule("test_extend", 3, 2)
+    library = _bell_with_correction(pyqir.Context())
+    with pytest.raises(
+        ValueError, match="^Modules must share a context to be combined.$"
+    ):
+        simple.extend(library, {0: 2, 1: 0}, {0: 1})
+
+
+def test_add() -> None:
+    first = pyqir.SimpleModule("first", 2, 1)
+    qis = pyqir.BasicQisBuilder(first.builder)
+    qis.h(first.qubits[0])
+    second = pyqir.SimpleModule("second", 2, 1, first.context)
+    qis = pyqir.BasicQisBuilder(second.builder)
+    qis.cx(second.qubits[0], second.qubits[1])
+    qis.mz(second.qubits[1], second.results[0])
+    first_ir = first.ir()
+    second_ir = second.ir()
+
+    composed = first + second
+    assert composed.instructions == [
+        pyqir.Operation("h", (0,)),
+        pyqir.Operation("cx", (0, 1)),
+        pyqir.Operation("mz", (1,), (0,)),
+    ]
+    assert first.ir() == first_ir
+    assert second.ir() == second_ir
+
+    first += second
+    assert first.ir() == composed.ir()
+    assert second.ir() == second_ir
+
+
+def test_add_too_many_qubits() -> None:
+    small = pyqir.SimpleModule("small", 1, 1)
+    large = pyqir.SimpleModule("large", 2, 1, small.context)
+    message = (
+        "^Module large doesn't fit in module small, which has 1 qubit and 1 result.$"
+    )
+    with pytest.raises(ValueError, match=message):
+        small + large
+    with pytest.raises(ValueError, match=message):
+        small += large
+    assert len(large + small) == 0
+
+
+def test_mul() -> None:
+    layer = pyqir.SimpleModule("layer", 2, 0)
+    qis = pyqir.BasicQisBuilder(layer.builder)
+    qis.rx(0.5, layer.qubits[0])
+    qis.cx(layer.qubits[0], layer.qubits[1])
+    layer_ir = layer.ir()
+
+    repeated = layer * 3
+    assert repeated.instructions == 3 * [
+        pyqir.Operation("rx", (0,), angles=(0.5,)),
+        pyqir.Operation("cx", (0, 1)),
+    ]
+    assert (3 * layer).ir() == repeated.ir()
+    assert layer.ir() == layer_ir
+
+    empty = layer * 0
+    assert len(empty) == 0
+    assert empty.instructions == []
+
+
+def test_mul_negative() -> None:
+    layer = pyqir.SimpleModule("layer", 1, 0)
+    with pytest.raises(
+        ValueError, match="^The number of repetitions must not be negative, not -1.$"
+    ):
+        layer * -1
+
+
+def _reset_on_one(name: str, reset: bool, measure: bool) -> pyqir.SimpleModule:
+    simple = pyqir.SimpleModule(name, 2, 1)
+    qis = pyqir.BasicQisBuilder(simple.builder)
+    qis.h(simple.qubits[0])
+    if measure:
+        qis.mz(simple.qubits[0], simple.results[0])
+    qis.if_result(
+        simple.results[0],
+        one=lambda: qis.reset(simple.qubits[1]) if reset else None,
+    )
+    if reset:
+        qis.reset(simple.qubits[0])
+    qis.x(simple.qubits[1])
+    return simple
+
+
+def test_strip_resets_leaves_empty_branch() -> None:
+    simple = _reset_on_one("test_strip", reset=True, measure=True)
+    ir = simple.ir()
+    stripped = simple.strip_resets()
+    expected = _reset_on_one("test_strip", reset=False, measure=True)
+    assert str(stripped.entry_point) == str(expected.entry_point)
+    assert simple.ir() == ir
+
+
+def test_strip_measurements() -> None:
+    simple = _reset_on_one("test_strip", reset=True, measure=True)
+    ir = simple.ir()
+    stripped = simple.strip_measurements()
+    expected = _reset_on_one("test_strip", reset=True, measure=False)
+    assert str(stripped.entry_point) == str(expected.entry_point)
+    assert simple.ir() == ir
+
+
+def test_stripped_module_can_be_built() -> None:
+    simple = _reset_on_one("test_strip", reset=True, measure=True)
+    stripped = simple.strip_resets()
+    pyqir.BasicQisBuilder(stripped.builder).z(stripped.qubits[0])
+    assert "__quantum__qis__z__body" in stripped.ir()
+    assert "__quantum__qis__z__body" not in simple.ir()
+
+
+def test_filter_instructions() -> None:
+    simple = pyqir.SimpleModule("test_filter", 1, 0)
+    qis = pyqir.BasicQisBuilder(simple.builder)
+    qis.h(simple.qubits[0])
+    qis.x(simple.qubits[0])
+    qis.h(simple.qubits[0])
+
+    def keep(instruction: pyqir.Instruction) -> bool:
+        return (
+            not isinstance(instruction, pyqir.Call)
+            or instruction.callee.name != "__quantum__qis__x__body"
+        )
+
+    filtered = simple.filter_instructions(keep)
+    assert "call void @__quantum__qis__x__body(" not in filtered.ir()
+    assert filtered.ir().count("call void @__quantum__qis__h__body(") == 2
+
+
+def test_filter_instructions_with_used_instruction() -> None:
+    simple = pyqir.SimpleModule("test_filter", 1, 0)
+    qis = pyqir.BasicQisBuilder(simple.builder)
+    qis.if_result(qis.m(simple.qubits[0]), one=lambda: qis.x(simple.qubits[0]))
+    with pytest.raises(
+        ValueError,
+        match="^A removed instruction is used by an instruction that is kept.$",
+    ):
+        simple.strip_measurements()
+    assert "__quantum__qis__m__body" in simple.ir()
+
+
+def test_check_use_after_measure() -> None:
+    simple = pyqir.SimpleModule("test_use_after_measure", 2, 2)
+    qis = pyqir.BasicQisBuilder(simple.builder)
+    qis.h(simple.qubits[0])
+    qis.mz(simple.qubits[0], simple.results[0])
+    qis.mz(simple.qubits[0], simple.results[1])
+    qis.cx(simple.qubits[1], simple.qubits[0])
+    qis.reset(simple.qubits[0])
+    qis.x(simple.qubits[0])
+    qis.if_result(simple.results[0], one=lambda: qis.z(simple.qubits[0]))
+
+    findings = simple.check_use_after_measure()
+    assert [f.callee.name for f in findings] == ["__quantum__qis__cnot__body"]
+
+
+def test_check_use_after_measure_in_branch() -> None:
+    simple = pyqir.SimpleModule("test_use_after_measure", 1, 1)
+    qis = pyqir.BasicQisBuilder(simple.builder)
+    qis.mz(simple.qubits[0], simple.results[0])
+    qis.if_result(simple.results[0], one=lambda: qis.x(simple.qubits[0]))
+
+    findings = simple.check_use_after_measure()
+    assert [f.callee.name for f in findings] == ["__quantum__qis__x__body"]
+    assert "__quantum__qis__x__body" in simple.ir()
+
+
+def test_check_use_after_measure_without_measurements() -> None:
+    simple = pyqir.SimpleModule("test_use_after_measure", 2, 0)
+    qis = pyqir.BasicQisBuilder(simple.builder)
+    qis.h(simple.qubits[0])
+    qis.cx(simple.qubits[0], simple.qubits[1])
+    assert simple.check_use_after_measure() == []
+
+
+def test_check_gate_set() -> None:
+    simple = pyqir.SimpleModule("test_gate_set", 2, 1)
+    qis = pyqir.BasicQisBuilder(simple.builder)
+    qis.h(simple.qubits[0])
+    qis.t(simple.qubits[0])
+    qis.cx(simple.qubits[0], simple.qubits[1])
+    qis.rz(0.5, simple.qubits[1])
+    qis.mz(simple.qubits[1], simple.results[0])
+    qis.if_result(simple.results[0], one=lambda: qis.t_adj(simple.qubits[0]))
+    label = simple.add_byte_string(b"r0")
+    rt.result_record_output(simple.builder, simple.results[0], label)
+
+    findings = simple.check_gate_set({"h", "cx", "rz"})
+    assert [f.callee.name for f in findings] == [
+        "__quantum__qis__t__body",
+        "__quantum__qis__mz__body",
+        "__quantum__qis__t__adj",
+    ]
+    assert simple.check_gate_set({"h", "cx", "rz", "t", "t_adj", "mz"}) == []
+
+
+def test_num_required_results() -> None:
+    simple = pyqir.SimpleModule("test_num_required_results", 2, 4)
+    qis = pyqir.BasicQisBuilder(simple.builder)
+    assert simple.num_required_results() == 0
+    qis.mz(simple.qubits[0], simple.results[1])
+    qis.mz(simple.qubits[1], simple.results[3])
+    qis.mz(simple.qubits[0], simple.results[1])
+    assert simple.num_required_results() == 2
+    assert simple.num_results == 4
+
+
+def test_num_required_results_in_branch() -> None:
+    simple = pyqir.SimpleModule("test_num_required_results", 1, 2)
+    qis = pyqir.BasicQisBuilder(simple.builder)
+    qis.mz(simple.qubits[0], simple.results[0])
+    qis.if_result(
+        simple.results[0],
+        one=lambda: qis.mz(simple.qubits[0], simple.results[1]),
+    )
+    assert simple.num_required_results() == 2
+
+
+def test_result_sources() -> None:
+    simple = pyqir.SimpleModule("test_result_sources", 3, 3)
+    qis = pyqir.BasicQisBuilder(simple.builder)
+    assert simple.result_sources() == {0: [], 1: [], 2: []}
+    qis.mz(simple.qubits[2], simple.results[0])
+    qis.mz(simple.qubits[1], simple.results[2])
+    qis.mz(simple.qubits[0], simple.results[0])
+    qis.if_result(
+        simple.results[2],
+        one=lambda: qis.mz(simple.qubits[1], simple.results[0]),
+    )
+    assert simple.result_sources() == {0: [2, 0, 1], 1: [], 2: [1]}
+
+
+def test_count_qubits_used() -> None:
+    simple = pyqir.SimpleModule("test_count_qubits_used", 10, 1)
+    qis = pyqir.BasicQisBuilder(simple.builder)
+    assert simple.count_qubits_used() == 0
+    qis.h(simple.qubits[2])
+    qis.cx(simple.qubits[2], simple.qubits[7])
+    qis.mz(simple.qubits[7], simple.results[0])
+    qis.if_result(simple.results[0], one=lambda: qis.x(simple.qubits[4]))
+    qis.x(simple.qubits[2])
+    assert simple.count_qubits_used() == 3
+    assert simple.num_qubits == 10
+
+
+def test_invalid_module_emit_error() -> None:
+    simple = pyqir.SimpleModule("test_emit_error", 2, 1)
+    qis = pyqir.BasicQisBuilder(simple.builder)
+    qis.x(qis.m(simple.qubits[0]))
+    for emit in [simple.ir, simple.bitcode]:
+        with pytest.raises(pyqir.QirEmitError) as info:
+            emit()
+        message = str(info.value)
+        assert message.startswith(
+            "Module test_emit_error with 2 qubits and 1 result is invalid at "
+            "instruction 1 (x dynamic result): "
+            "Call parameter type does not match function signature!"
+        )
+        assert "call void @__quantum__qis__x__body(%Result* %0)" in message
+        assert isinstance(info.value, ValueError)
+
+
+def test_invalid_module_emit_error_location() -> None:
+    simple = pyqir.SimpleModule("test_emit_error", 2, 2)
+    qis = pyqir.BasicQisBuilder(simple.builder)
+    qis.h(simple.qubits[0])
+    qis.rx(0.5, simple.qubits[1])
+    qis.mz(simple.qubits[0], simple.results[1])
+    f = simple.add_external_function(
+        "f",
+        pyqir.FunctionType(
+            pyqir.Type.void(simple.context),
+            [pyqir.qubit_type(simple.context), pyqir.qubit_type(simple.context)],
+        ),
+    )
+    simple.builder.call(f, [simple.qubits[1], pyqir.result(simple.context, 1)])
+    with pytest.raises(
+        pyqir.QirEmitError,
+        match=re.escape("is invalid at instruction 3 (f qubit 1, result 1): "),
+    ):
+        simple.ir()
+
+
+def test_invalid_module_emit_error_without_instruction() -> None:
+    simple = pyqir.SimpleModule("test_emit_error", 0, 0)
+    simple.builder.ret(None)
+    with pytest.raises(pyqir.QirEmitError) as info:
+        simple.ir()
+    assert str(info.value).startswith(
+        "Module test_emit_error with 0 qubits and 0 results is invalid: "
+    )
+
+
+def test_invalid_module_emit_without_verify() -> None:
+    simple = pyqir.SimpleModule("test_emit_error", 2, 1)
+    qis = pyqir.BasicQisBuilder(simple.builder)
+    qis.x(qis.m(simple.qubits[0]))
+    assert "call void @__quantum__qis__x__body(%Result* %0)" in simple.ir(verify=False)
+    assert len(simple.bitcode(verify=False)) > 0
+    with pytest.raises(pyqir.QirEmitError):
+        simple.ir()
+
+
+def test_empty_module() -> None:
+    simple = pyqir.SimpleModule("test_empty", 0, 0)
+    file = os.path.join(os.path.dirname(__file__), "resources", "test_empty_module.ll")
+    assert simple.ir() == Path(file).read_text()
+    assert simple.instructions == []
+
+
+def test_zero_sized_module() -> None:
+    simple = pyqir.SimpleModule("test_empty", 0, 0)
+    assert simple.qubits == []
+    assert simple.results == []
+
+    qis = pyqir.BasicQisBuilder(simple.builder)
+    qubit = pyqir.qubit(simple.context, 0)
+    result = pyqir.result(simple.context, 0)
+    message = "^Qubit 0 is out of range for module test_empty, which has 0 qubits.$"
+    with pytest.raises(IndexError, match=message):
+        qis.h(qubit)
+    message = "^Result 0 is out of range for module test_empty, which has 0 results.$"
+    with pytest.raises(IndexError, match=message):
+        qis.if_result(result)
+    assert simple.instructions == []
+
+
+def test_classical_only_module() -> None:
+    simple = pyqir.SimpleModule("test_classical", 0, 0)
+    i64 = pyqir.IntType(simple.context, 64)
+    function = simple.add_external_function("f", pyqir.FunctionType(i64, []))
+    value = simple.builder.call(function, [])
+    assert value is not None
+    simple.builder.add(value, pyqir.const(i64, 1))
+    assert "add i64 %0, 1" in simple.ir()
+
+
+@pytest.mark.parametrize("num_qubits, num_results", [(0, 0), (2, 3)])
+@pytest.mark.parametrize("opaque", [False, True])
+@pytest.mark.parametrize("batched", [False, True])
+def test_empty_module_is_valid(
+    num_qubits: int, num_results: int, opaque: bool, batched: bool
+) -> None:
+    simple = pyqir.SimpleModule("test_empty", num_qubits, num_results)
+    simple.use_opaque_pointers(opaque)
+    simple.use_batched_measurements(batched)
+
+    context = pyqir.Context(opaque_pointers=opaque)
+    for module in [
+        pyqir.Module.from_ir(context, simple.ir()),
+        pyqir.Module.from_bitcode(context, simple.bitcode()),
+    ]:
+        assert module.verify() is None
+        [entry_point] = module.functions
+        assert is_entry_point(entry_point)
+        assert required_num_qubits(entry_point) == num_qubits
+        assert required_num_results(entry_point) == num_results
+        [block] = entry_point.basic_blocks
+        [ret] = block.instructions
+        assert ret.opcode == pyqir.Opcode.RET
+
+
+def test_empty_module_with_result_output() -> None:
+    simple = pyqir.SimpleModule("empty", 2, 2)
+    simple.use_result_output()
+    for module in [
+        pyqir.Module.from_ir(pyqir.Context(), simple.ir()),
+        pyqir.Module.from_bitcode(pyqir.Context(), simple.bitcode()),
+    ]:
+        assert module.verify() is None
+        entry_point = next(filter(is_entry_point, module.functions))
+        [block] = entry_point.basic_blocks
+        assert [i.opcode for i in block.instructions] == [pyqir.Opcode.CALL] * 3 + [
+            pyqir.Opcode.RET
+        ]
+    assert simple.instructions == []
+
+
+@pytest.mark.parametrize("opaque", [False, True])
+@pytest.mark.parametrize("batched", [False, True])
+def test_reset_only_module_is_valid(opaque: bool, batched: bool) -> None:
+    simple = pyqir.SimpleModule("test_resets", 2, 2)
+    simple.use_opaque_pointers(opaque)
+    simple.use_batched_measurements(batched)
+    qis = pyqir.BasicQisBuilder(simple.builder)
+    qis.reset(simple.qubits[0])
+    qis.reset(simple.qubits[1])
+
+    context = pyqir.Context(opaque_pointers=opaque)
+    for module in [
+        pyqir.Module.from_ir(context, simple.ir()),
+        pyqir.Module.from_bitcode(context, simple.bitcode()),
+    ]:
+        assert module.verify() is None
+        entry_point = next(filter(is_entry_point, module.functions))
+        [block] = entry_point.basic_blocks
+        assert [i.opcode for i in block.instructions] == [
+            pyqir.Opcode.CALL,
+            pyqir.Opcode.CALL,
+            pyqir.Opcode.RET,
+        ]
+    assert simple.instructions == [
+        pyqir.Operation("reset", (0,)),
+        pyqir.Operation("reset", (1,)),
+    ]
+
+
+def test_empty_module_transforms() -> None:
+    simple = pyqir.SimpleModule("test_empty", 1, 1)
+    ir = simple.ir()
+    assert copy.copy(simple).ir() == ir
+    assert simple.adjoint().ir() == ir
+    assert simple.strip_measurements().ir() == ir
+    simple.defer_measurements()
+    assert simple.ir() == ir
+    assert simple.to_matrix() == [[1, 0], [0, 1]]
+    assert simple.num_required_results() == 0
+    assert simple.check_use_after_measure() == []
+
+
+def test_clear() -> None:
+    simple = pyqir.SimpleModule("test_clear", 2, 1)
+    qis = pyqir.BasicQisBuilder(simple.builder)
+    qis.h(simple.qubits[0])
+    result = qis.m(simple.qubits[0])
+    qis.mz(simple.qubits[1], simple.results[0])
+    qis.if_result(result, one=lambda: qis.x(simple.qubits[1]))
+    qis.if_result(result, zero=lambda: qis.z(simple.qubits[1]))
+
+    simple.clear()
+    fresh = pyqir.SimpleModule("test_clear", 2, 1)
+    assert str(simple.entry_point) == str(fresh.entry_point)
+    assert len(simple) == 0
+
+    qis.x(simple.qubits[1])
+    assert simple.instructions == [pyqir.Operation("x", (1,))]
+    assert "call void @__quantum__qis__x__body" in simple.ir()
+
+
+def test_strict_results_double_write() -> None:
+    simple = pyqir.SimpleModule("test_strict", 2, 1)
+    qis = pyqir.BasicQisBuilder(simple.builder)
+    qis.mz(simple.qubits[0], simple.results[0])
+    qis.mz(simple.qubits[1], simple.results[0])
+    simple.ir()
+
+    simple.use_strict_results()
+    message = "^Result 0 is written twice in module test_strict.$"
+    with pytest.raises(pyqir.QirEmitError, match=message):
+        simple.ir()
+    with pytest.raises(pyqir.QirEmitError, match=message):
+        simple.bitcode()
+
+    simple.use_strict_results(False)
+    simple.ir()
+
+
+def test_strict_results_distinct_writes() -> None:
+    simple = pyqir.SimpleModule("test_strict", 2, 2)
+    simple.use_strict_results()
+    qis = pyqir.BasicQisBuilder(simple.builder)
+    qis.mz(simple.qubits[0], simple.results[0])
+    qis.mz(simple.qubits[1], simple.results[1])
+    simple.ir()
+    assert copy.copy(simple).ir() == simple.ir()
+
+
+def test_strict_results_write_in_one_branch() -> None:
+    simple = pyqir.SimpleModule("test_strict", 2, 2)
+    simple.use_strict_results()
+    qis = pyqir.BasicQisBuilder(simple.builder)
+    qis.mz(simple.qubits[0], simple.results[0])
+    qis.if_result(
+        simple.results[0], one=lambda: qis.mz(simple.qubits[1], simple.results[1])
+    )
+    qis.mz(simple.qubits[1], simple.results[1])
+    simple.ir()
+
+
+def test_strict_results_write_in_both_branches() -> None:
+    simple = pyqir.SimpleModule("test_strict", 2, 2)
+    simple.use_strict_results()
+    qis = pyqir.BasicQisBuilder(simple.builder)
+    qis.mz(simple.qubits[0], simple.results[0])
+    qis.if_result(
+        simple.results[0],
+        one=lambda: qis.mz(simple.qubits[1], simple.results[1]),
+        zero=lambda: qis.mz(simple.qubits[1], simple.results[1]),
+    )
+    qis.mz(simple.qubits[1], simple.results[1])
+    with pytest.raises(pyqir.QirEmitError, match="^Result 1 is written twice"):
+        simple.ir()
+
+
+def test_strict_results_write_in_branch_after_write() -> None:
+    simple = pyqir.SimpleModule("test_strict", 2, 2)
+    simple.use_strict_results()
+    qis = pyqir.BasicQisBuilder(simple.builder)
+    qis.mz(simple.qubits[0], simple.results[0])
+    qis.mz(simple.qubits[1], simple.results[1])
+    qis.if_result(
+        simple.results[0], zero=lambda: qis.mz(simple.qubits[1], simple.results[1])
+    )
+    with pytest.raises(pyqir.QirEmitError, match="^Result 1 is written twice"):
+        simple.ir()
+
+
+@pytest.mark.parametrize("extension", [".ll", ".bc"])
+def test_load_module(tmp_path: Path, extension: str) -> None:
+    simple = pyqir.SimpleModule("test_load", 2, 1)
+    qis = pyqir.BasicQisBuilder(simple.builder)
+    qis.h(simple.qubits[0])
+    qis.cx(simple.qubits[0], simple.qubits[1])
+    path = tmp_path / f"test_load{extension}"
+    if extension == ".ll":
+        path.write_text(simple.ir())
+    else:
+        path.write_bytes(simple.bitcode())
+
+    loaded = pyqir.load_module(str(path))
+    assert loaded.ir() == simple.ir()
+    assert loaded.num_qubits == 2
+    assert loaded.num_results == 1
+
+    qis = pyqir.BasicQisBuilder(loaded.builder)
+    qis.mz(loaded.qubits[1], loaded.results[0])
+    assert loaded.instructions == [
+        pyqir.Operation("h", (0,)),
+        pyqir.Operation("cx", (0, 1)),
+        pyqir.Operation("mz", (1,), (0,)),
+    ]
+
+
+def test_copied_and_loaded_modules_have_every_field(tmp_path: Path) -> None:
+    simple = pyqir.SimpleModule("test_fields", 1, 1)
+    path = tmp_path / "test_fields.ll"
+    path.write_text(simple.ir())
+    fields = vars(simple).keys()
+    assert vars(copy.copy(simple)).keys() == fields
+    assert vars(pyqir.load_module(str(path))).keys() == fields
+
+
+def test_load_module_unsupported_extension(tmp_path: Path) -> None:
+    path = tmp_path / "test_load.qir"
+    path.write_text(pyqir.SimpleModule("test_load", 0, 0).ir())
+    with pytest.raises(ValueError, match="^Unsupported file extension '.qir'. .*'.bc'"):
+        pyqir.load_module(str(path))
+
+
+def test_load_module_without_entry_point(tmp_path: Path) -> None:
+    path = tmp_path / "test_load.ll"
+    path.write_text("declare void @f()\n")
+    with pytest.raises(ValueError, match="^Module test_load has 0 entry points"):
+        pyqir.load_module(str(path))
+
+
+def test_pi_comments() -> None:
+    simple = pyqir.SimpleModule("test_pi_comments", 1, 0)
+    qis = pyqir.BasicQisBuilder(simple.builder)
+    qis.rz(math.pi / 2, simple.qubits[0])
+    qis.rx(-3 * math.pi / 4, simple.qubits[0])
+    qis.ry(2 * math.pi, simple.qubits[0])
+    qis.u2(math.pi, math.pi / 3, simple.qubits[0])
+    qis.rz(0.5, simple.qubits[0])
+    qis.rz(0.0, simple.qubits[0])
+    plain = simple.ir()
+    assert "; pi" not in plain
+
+    simple.use_pi_comments()
+    ir = simple.ir()
+    lines = [line for line in ir.splitlines() if "call void @__quantum__qis__" in line]
+    assert [line.partition("  ; ")[2] for line in lines] == [
+        "pi/2",
+        "-3*pi/4",
+        "2*pi",
+        "pi, pi/3",
+        "",
+        "",
+    ]
+    assert [line.partition("  ; ")[0] for line in lines] == [
+        line for line in plain.splitlines() if "call void @__quantum__qis__" in line
+    ]
+
+
+@pytest.mark.parametrize("angle", [math.nan, math.inf, -math.inf])
+def test_pi_comments_non_finite_angle(angle: float) -> None:
+    simple = pyqir.SimpleModule("test_pi_comments", 1, 0)
+    double = pyqir.Type.double(simple.context)
+    rx = simple.add_external_function(
+        "__quantum__qis__rx__body",
+        pyqir.FunctionType(
+            pyqir.Type.void(simple.context), [double, pyqir.qubit_type(simple.context)]
+        ),
+    )
+    simple.builder.call(rx, [pyqir.const(double, angle), simple.qubits[0]])
+    simple.use_pi_comments()
+    line = next(line for line in simple.ir().splitlines() if "__rx__body(" in line)
+    assert "; " not in line
+
+
+
+_DETERMINISTIC_PROGRAM = """
+import pyqir
+import pyqir.rt as rt
+
+
+def build() -> str:
+    simple = pyqir.SimpleModule("test_deterministic", 6, 3)
+    qis = pyqir.BasicQisBuilder(simple.builder)
+    qis.linear_entangle(simple.qubits)
+    qis.qft(simple.qubits[3:])
+    for i in range(3):
+        qis.mz(simple.qubits[i], simple.results[i])
+    qis.if_result(
+        simple.results[0],
+        one=lambda: qis.if_result(
+            simple.results[1], zero=lambda: qis.x(simple.qubits[5])
+        ),
+        zero=lambda: qis.rz(0.5, simple.qubits[4]),
+    )
+    for i in range(3):
+        label = simple.add_byte_string(f"r{i}".encode())
+        rt.result_record_output(simple.builder, simple.results[i], label)
+    return simple.ir()
+
+
+if __name__ == "__main__":
+    print(build(), end="")
+"""
+
+
+def test_ir_is_deterministic_across_processes(tmp_path: Path) -> None:
+    outputs = []
+    for hash_seed in ["1", "2"]:
+        process = subprocess.run(
+            [sys.executable, "-c", _DETERMINISTIC_PROGRAM],
+            cwd=tmp_path,
+            env={**os.environ, "PYTHONHASHSEED": hash_seed},
+            capture_output=True,
+            text=True,
+            check=True,
+        )
+        outputs.append(process.stdout)
+
+    namespace: Dict[str, Any] = {}
+    exec(_DETERMINISTIC_PROGRAM, namespace)
+    ir = namespace["build"]()
+    assert outputs[0] == outputs[1] == ir
+    assert ir == namespace["build"]()
+    assert "__quantum__rt__result_record_output" in ir
+
+
+def test_named_qubits() -> None:
+    simple = pyqir.SimpleModule("test_named_qubits", 3, 0)
+    simple.name_qubit("data", 0)
+    simple.name_qubit("ancilla", 2)
+    qis = pyqir.BasicQisBuilder(simple.builder)
+    qis.cx(simple.qubit("data"), simple.qubit("ancilla"))
+    assert simple.instructions == [pyqir.Operation("cx", (0, 2))]
+
+    simple.relabel({0: 1, 1: 2, 2: 0})
+    assert pyqir.qubit_id(simple.qubit("data")) == 1
+    other = copy.copy(simple)
+    other.name_qubit("spare", 2)
+    assert pyqir.qubit_id(other.qubit("ancilla")) == 0
+    with pytest.raises(KeyError, match="No qubit is named 'spare'."):
+        simple.qubit("spare")
+
+
+def test_name_qubit_errors() -> None:
+    simple = pyqir.SimpleModule("test_named_qubits", 2, 0)
+    simple.name_qubit("data", 0)
+    with pytest.raises(ValueError, match="^Name 'data' is already used by qubit 0.$"):
+        simple.name_qubit("data", 1)
+    with pytest.raises(ValueError, match="^Qubit 2 is out of range.$"):
+        simple.name_qubit("ancilla", 2)
+
+
+def test_result_output() -> None:
+    simple = pyqir.SimpleModule("test_result_output", 2, 2)
+    qis = pyqir.BasicQisBuilder(simple.builder)
+    qis.mz(simple.qubits[0], simple.results[0])
+    assert "record_output" not in simple.ir()
+
+    simple.use_result_output()
+    ending = (
+        "  call void @__quantum__rt__array_record_output(i64 2, i8* null)\n"
+        "  call void @__quantum__rt__result_record_output(%Result* null, i8* null)\n"
+        "  call void @__quantum__rt__result_record_output"
+        "(%Result* inttoptr (i64 1 to %Result*), i8* null)\n"
+        "  ret void\n"
+    )
+    assert ending in simple.ir()
+    assert len(simple.bitcode()) > 0
+
+    qis.mz(simple.qubits[1], simple.results[1])
+    ir = simple.ir()
+    assert ir.index("__quantum__qis__mz__body(%Qubit* inttoptr") < ir.index(ending)
+    assert ir.count("call void @__quantum__rt__array_record_output") == 1
+    assert simple.validate() == []
+
+
+def test_build_on_threads(tmp_path: Path) -> None:
+    def build(i: int) -> None:
+        simple = pyqir.SimpleModule(f"part{i}", 2, 1)
+        qis = pyqir.BasicQisBuilder(simple.builder)
+        for _ in range(20):
+            qis.h(simple.qubits[0])
+            qis.cx(simple.qubits[0], simple.qubits[1])
+        qis.mz(simple.qubits[1], simple.results[0])
+        (tmp_path / f"part{i}.bc").write_bytes(simple.bitcode())
+
+    threads = [threading.Thread(target=build, args=(i,)) for i in range(8)]
+    for thread in threads:
+        thread.start()
+    for thread in threads:
+        thread.join()
+
+    simple = pyqir.SimpleModule("test_threads", 2, 8)
+    for i in range(8):
+        part = pyqir.load_module(str(tmp_path / f"part{i}.bc"), simple.context)
+        simple.extend(part, {0: 0, 1: 1}, {0: i})
+    assert len(simple) == 8 * 41
+    assert simple.instructions[-1] == pyqir.Operation("mz", (1,), (7,))
+
+
+def test_use_on_other_thread() -> None:
+    simple = pyqir.SimpleModule("test_threads", 1, 0)
+    errors: List[Exception] = []
+
+    def use() -> None:
+        attempts: List[Callable[[], Any]] = [
+            lambda: simple.builder,
+            simple.ir,
+            lambda: copy.copy(simple),
+            lambda: simple.num_qubits,
+            lambda: simple.num_results,
+            simple.num_required_results,
+        ]
+        for f in attempts:
+            try:
+                f()
+            except RuntimeError as e:
+                errors.append(e)
+
+    thread = threading.Thread(target=use)
+    thread.start()
+    thread.join()
+    assert [str(e) for e in errors] == [
+        "Module test_threads can only be used on the thread that created it."
+    ] * 6
+    assert simple.ir() == copy.copy(simple).ir()
+
+
+def test_use_builder_on_other_thread() -> None:
+    simple = pyqir.SimpleModule("test_threads", 2, 0)
+    builder = simple.builder
+    qubits = simple.qubits
+    one = pyqir.const(pyqir.IntType(simple.context, 64), 1)
+    null = pyqir.Constant.null(pyqir.PointerType(pyqir.IntType(simple.context, 8)))
+    ir = simple.ir()
+    errors: List[Exception] = []
+
+    def use() -> None:
+        attempts: List[Callable[[], Any]] = [
+            lambda: builder.add(one, one),
+            lambda: builder.ret(None),
+            lambda: pyqir.qis.cx(builder, qubits[0], qubits[1]),
+            lambda: pyqir.rt.initialize(builder, null),
+            lambda: pyqir.BasicQisBuilder(builder).h(qubits[0]),
+        ]
+        for attempt in attempts:
+            try:
+                attempt()
+            except RuntimeError as e:
+                errors.append(e)
+
+    thread = threading.Thread(target=use)
+    thread.start()
+    thread.join()
+    assert [str(e) for e in errors] == [
+        "The builder can only be used on the thread that created it."
+    ] * 5
+    assert simple.ir() == ir
+
+
+def _one_qubit_circuit(*gates: str, angle: float = 0.0) -> pyqir.SimpleModule:
+    simple = pyqir.SimpleModule("test_equivalent", 1, 1)
+    qis = pyqir.BasicQisBuilder(simple.builder)
+    for gate in gates:
+        if gate in ("rx", "rz"):
+            getattr(qis, gate)(angle, simple.qubits[0])
+        elif gate == "mz":
+            qis.mz(simple.qubits[0], simple.results[0])
+        else:
+            getattr(qis, gate)(simple.qubits[0])
+    return simple
+
+
+def test_approx_equal() -> None:
+    unmerged = pyqir.SimpleModule("test_approx_equal", 1, 1)
+    qis = pyqir.BasicQisBuilder(unmerged.builder)
+    qis.rz(0.1, unmerged.qubits[0])
+    qis.rz(0.2, unmerged.qubits[0])
+    qis.mz(unmerged.qubits[0], unmerged.results[0])
+
+    merged = pyqir.SimpleModule("test_approx_equal", 1, 1)
+    qis = pyqir.BasicQisBuilder(merged.builder)
+    ops = unmerged.instructions
+    assert isinstance(ops[0], pyqir.Operation) and isinstance(ops[1], pyqir.Operation)
+    qis.rz(ops[0].angles[0] + ops[1].angles[0], merged.qubits[0])
+    qis.mz(merged.qubits[0], merged.results[0])
+
+    expected = _one_qubit_circuit("rz", "mz", angle=0.3)
+    assert merged.instructions != expected.instructions
+    assert merged.approx_equal(expected)
+    assert not merged.approx_equal(expected, atol=0.0)
+    assert not merged.approx_equal(unmerged, atol=1.0)
+
+
+def test_approx_equal_branches() -> None:
+    def build(angle: float) -> pyqir.SimpleModule:
+        simple = pyqir.SimpleModule("test_approx_equal", 1, 1)
+        qis = pyqir.BasicQisBuilder(simple.builder)
+        qis.mz(simple.qubits[0], simple.results[0])
+        qis.if_result(simple.results[0], one=lambda: qis.rx(angle, simple.qubits[0]))
+        return simple
+
+    assert build(0.5).approx_equal(build(0.5 + 1e-12))
+    assert not build(0.5).approx_equal(build(0.6))
+    assert not build(0.5).approx_equal(_one_qubit_circuit("mz", "rx", angle=0.5))
+
+
+def test_equivalent_to() -> None:
+    assert _one_qubit_circuit("h", "x", "h").equivalent_to(_one_qubit_circuit("z"))
+    assert _one_qubit_circuit("s", "s").equivalent_to(_one_qubit_circuit("z"))
+    assert not _one_qubit_circuit("x").equivalent_to(_one_qubit_circuit("z"))
+    # Rx(pi) is -iX, which is X up to a global phase.
+    assert _one_qubit_circuit("rx", angle=math.pi).equivalent_to(
+        _one_qubit_circuit("x")
+    )
+
+
+def test_equivalent_to_tolerance() -> None:
+    rz = _one_qubit_circuit("rz", angle=1e-6)
+    identity = _one_qubit_circuit()
+    assert not rz.equivalent_to(identity)
+    assert rz.equivalent_to(identity, tolerance=1e-5)
+
+
+def test_equivalent_to_different_sizes() -> None:
+    assert not pyqir.SimpleModule("a", 1, 0).equivalent_to(
+        pyqir.SimpleModule("b", 2, 0)
+    )
+
+
+def test_equivalent_to_measurement() -> None:
+    with pytest.raises(ValueError, match="^Circuits with measurements aren't unitary.$"):
+        _one_qubit_circuit("h", "mz").equivalent_to(_one_qubit_circuit("h"))
+
+
+def test_dependency_graph() -> None:
+    simple = pyqir.SimpleModule("test_dependency_graph", 3, 1)
+    qis = pyqir.BasicQisBuilder(simple.builder)
+    qis.h(simple.qubits[0])
+    qis.x(simple.qubits[1])
+    qis.cx(simple.qubits[0], simple.qubits[1])
+    qis.z(simple.qubits[2])
+    qis.mz(simple.qubits[0], simple.results[0])
+    qis.if_result(simple.results[0], one=lambda: qis.x(simple.qubits[2]))
+    qis.h(simple.qubits[1])
+    assert simple.dependency_graph() == {
+        0: [],
+        1: [],
+        2: [0, 1],
+        3: [],
+        4: [2],
+        5: [3, 4],
+        6: [2],
+    }
+
+
+def test_dependency_graph_empty() -> None:
+    assert pyqir.SimpleModule("test_dependency_graph", 2, 0).dependency_graph() == {}
+
+
+def test_layers() -> None:
+    simple = pyqir.SimpleModule("test_layers", 3, 1)
+    qis = pyqir.BasicQisBuilder(simple.builder)
+    qis.h(simple.qubits[0])
+    qis.x(simple.qubits[1])
+    qis.cx(simple.qubits[0], simple.qubits[1])
+    qis.z(simple.qubits[2])
+    qis.mz(simple.qubits[0], simple.results[0])
+    qis.if_result(simple.results[0], one=lambda: qis.x(simple.qubits[2]))
+    qis.h(simple.qubits[1])
+    assert simple.layers() == [[0, 1, 3], [2], [4, 6], [5]]
+
+
+def test_layers_empty() -> None:
+    assert pyqir.SimpleModule("test_layers", 2, 0).layers() == []
+
+
+@pytest.mark.parametrize("num_qubits, depth", [(0, 0), (2, 3), (5, 50), (20, 200)])
+def test_estimated_ir_lines(num_qubits: int, depth: int) -> None:
+    simple = pyqir.random_circuit(num_qubits, depth, seed=1)
+    lines = len(simple.ir().splitlines())
+    assert lines / 1.5 <= simple.estimated_ir_lines() <= lines * 1.5
+
+
+def test_estimated_ir_lines_with_branches_and_output() -> None:
+    simple = pyqir.SimpleModule("test_estimated_ir_lines", 2, 2)
+    qis = pyqir.BasicQisBuilder(simple.builder)
+    qis.h(simple.qubits[0])
+    qis.mz(simple.qubits[0], simple.results[0])
+    qis.if_result(simple.results[0], one=lambda: qis.x(simple.qubits[1]))
+    simple.use_result_output()
+    before = simple.estimated_ir_lines()
+    lines = len(simple.ir().splitlines())
+    assert lines / 1.5 <= before <= lines * 1.5
+    assert simple.estimated_ir_lines() == before
+
+
+def test_function_ir() -> None:
+    simple = pyqir.SimpleModule("test_function_ir", 2, 1)
+    qis = pyqir.BasicQisBuilder(simple.builder)
+    qis.h(simple.qubits[0])
+    qis.cx(simple.qubits[0], simple.qubits[1])
+    qis.h(simple.qubits[1])
+    qis.mz(simple.qubits[1], simple.results[0])
+    simple.add_external_function(
+        "unused", pyqir.FunctionType(pyqir.Type.void(simple.context), [])
+    )
+    assert simple.function_ir("main") == "\n".join(
+        [
+            "define void @main() #0 {",
+            "entry:",
+            "  call void @__quantum__qis__h__body(%Qubit* null)",
+            "  call void @__quantum__qis__cnot__body(%Qubit* null, %Qubit* inttoptr (i64 1 to %Qubit*))",
+            "  call void @__quantum__qis__h__body(%Qubit* inttoptr (i64 1 to %Qubit*))",
+            "  call void @__quantum__qis__mz__body(%Qubit* inttoptr (i64 1 to %Qubit*), %Result* null)",
+            "  ret void",
+            "}",
+            "",
+            "declare void @__quantum__qis__h__body(%Qubit*)",
+            "",
+            "declare void @__quantum__qis__cnot__body(%Qubit*, %Qubit*)",
+            "",
+            "declare void @__quantum__qis__mz__body(%Qubit*, %Result* writeonly) #1",
+            "",
+        ]
+    )
+    assert simple.function_ir("unused") == "declare void @unused()\n"
+    assert "ret void" not in str(simple.entry_point)
+
+
+def test_ir_crlf() -> None:
+    simple = pyqir.SimpleModule("test_ir_crlf", 2, 1)
+    qis = pyqir.BasicQisBuilder(simple.builder)
+    qis.cx(simple.qubits[0], simple.qubits[1])
+    qis.mz(simple.qubits[1], simple.results[0])
+    ir = simple.ir()
+    crlf = simple.ir(options=pyqir.EmitOptions(newline="\r\n"))
+    assert crlf.count("\r\n") == ir.count("\n") > 0
+    assert "\n" not in crlf.replace("\r\n", "")
+    assert crlf.replace("\r\n", "\n") == ir
+    assert simple.ir(options=pyqir.EmitOptions()) == ir
+
+
+def test_ir_indent() -> None:
+    simple = pyqir.SimpleModule("test_ir_indent", 1, 0)
+    pyqir.BasicQisBuilder(simple.builder).h(simple.qubits[0])
+    ir = simple.function_ir("main", options=pyqir.EmitOptions(indent=4))
+    assert ir.splitlines()[:4] == [
+        "define void @main() #0 {",
+        "entry:",
+        "    call void @__quantum__qis__h__body(%Qubit* null)",
+        "    ret void",
+    ]
+    unindented = simple.ir(options=pyqir.EmitOptions(indent=0))
+    assert "\nret void\n" in unindented
+    module = pyqir.Module.from_ir(pyqir.Context(), unindented, "test_ir_indent")
+    assert str(module) == simple.ir()
+
+
+@pytest.mark.parametrize(
+    "options, message",
+    [
+        ({"indent": -1}, "Indentation must not be negative, not -1."),
+        ({"newline": "\r"}, "Unsupported newline '\\r'."),
+    ],
+)
+def test_emit_options_invalid(options: Dict[str, Any], message: str) -> None:
+    with pytest.raises(ValueError, match=re.escape(message)):
+        pyqir.EmitOptions(**options)
+
+
+def test_write_ir() -> None:
+    simple = pyqir.SimpleModule("test_write_ir", 1, 0)
+    pyqir.BasicQisBuilder(simple.builder).x(simple.qubits[0])
+    file = io.StringIO(newline="")
+    simple.write_ir(file, options=pyqir.EmitOptions(newline="\r\n"))
+    assert file.getvalue() == simple.ir(options=pyqir.EmitOptions(newline="\r\n"))
+
+    stdout = io.StringIO()
+    with contextlib.redirect_stdout(stdout):
+        simple.write_ir()
+    assert stdout.getvalue() == simple.ir()
+
+
+@pytest.mark.parametrize("opaque_pointers", [False, True])
+def test_verify_consistency(opaque_pointers: bool) -> None:
+    simple = pyqir.random_circuit(3, 4, seed=5)
+    simple.use_opaque_pointers(opaque_pointers)
+    simple.use_pi_comments()
+    qis = pyqir.BasicQisBuilder(simple.builder)
+    qis.if_result(qis.m(simple.qubits[0]), one=lambda: qis.rx(math.pi, simple.qubits[1]))
+    simple.verify_consistency()
+
+
+def test_verify_consistency_mismatch() -> None:
+    class Drifted(pyqir.SimpleModule):
+        def ir(self, verify: bool = True, options: Any = None) -> str:
+            return super().ir(verify, options).replace("__body(", "__body (", 1)
+
+    simple = Drifted("test_drift", 1, 0)
+    pyqir.BasicQisBuilder(simple.builder).h(simple.qubits[0])
+    with pytest.raises(ValueError) as info:
+        simple.verify_consistency()
+    lines = str(info.value).splitlines()
+    assert lines[0] == "The IR and bitcode of module test_drift differ:"
+    assert "-  call void @__quantum__qis__h__body (%Qubit* null)" in lines
+    assert "+  call void @__quantum__qis__h__body(%Qubit* null)" in lines
+
+
+def test_function_ir_unknown() -> None:
+    simple = pyqir.SimpleModule("test_function_ir", 1, 0)
+    with pytest.raises(
+        KeyError, match="Module test_function_ir has no function named 'f'."
+    ):
+        simple.function_ir("f")
+
+
+@pytest.mark.parametrize(
+    "name",
+    ["with space", 'with "quotes"', "with\nnewline", "ünïcödé ⟨ψ|", "", "x" * 100_000],
+)
+def test_names_round_trip(name: str) -> None:
+    entry_point_name = name if name != "" else "main"
+    simple = pyqir.SimpleModule(name, 1, 0, entry_point_name=entry_point_name)
+    pyqir.BasicQisBuilder(simple.builder).h(simple.qubits[0])
+    for module in [
+        pyqir.Module.from_ir(pyqir.Context(), simple.ir()),
+        pyqir.Module.from_bitcode(pyqir.Context(), simple.bitcode()),
+    ]:
+        assert module.source_filename == name
+        assert [f.name for f in module.functions if is_entry_point(f)] == [
+            entry_point_name
+        ]
+
+
+def test_name_with_null() -> None:
+    with pytest.raises(
+        ValueError, match='^Name "a\\\\0b" contains a null character at index 1.$'
+    ):
+        pyqir.SimpleModule("a\0b", 1, 0)
+    with pytest.raises(ValueError, match="contains a null character at index 3."):
+        pyqir.SimpleModule("test", 1, 0, entry_point_name="mai\0n")
+
+
+def test_empty_entry_point_name() -> None:
+    with pytest.raises(ValueError, match="^The entry point name must not be empty.$"):
+        pyqir.SimpleModule("test", 1, 0, entry_point_name="")
+
+
+@pytest.mark.parametrize(
+    "num_qubits, num_results, kind, size",
+    [
+        (2**64 - 1, 0, "qubits", 2**64 - 1),
+        (10**18, 0, "qubits", 10**18),
+        (-1, 0, "qubits", -1),
+        (0, 2**64, "results", 2**64),
+    ],
+)
+def test_register_size_out_of_range(
+    num_qubits: int, num_results: int, kind: str, size: int
+) -> None:
+    message = f"^The number of {kind} must be between 0 and 16777216, not {size}.$"
+    with pytest.raises(ValueError, match=message):
+        pyqir.SimpleModule("test", num_qubits, num_results)
+
+
+def test_max_register_size() -> None:
+    class SmallModule(pyqir.SimpleModule):
+        max_register_size = 4
+
+    assert len(SmallModule("test", 4, 4).qubits) == 4
+    with pytest.raises(ValueError, match="between 0 and 4, not 5"):
+        SmallModule("test", 5, 0)
+
+
+def test_share_across_threads() -> None:
+    simple = pyqir.SimpleModule("test_threads", 2, 0)
+    builder = simple.builder
+    qubits = simple.qubits
+    qis = pyqir.BasicQisBuilder(builder)
+    qis.h(qubits[0])
+    ir = simple.ir()
+    errors: List[str] = []
+    lock = threading.Lock()
+
+    def use() -> None:
+        attempts: List[Callable[[], Any]] = [
+            lambda: pyqir.qis.x(simple.builder, qubits[0]),
+            lambda: simple.add_entry_point_attribute("key", "value"),
+            simple.clear,
+            lambda: simple.extend(simple, {0: 1}, {}),
+            lambda: qis.cx(qubits[0], qubits[1]),
+            lambda: builder.ret(None),
+        ]
+        for _ in range(20):
+            for attempt in attempts:
+                try:
+                    attempt()
+                    error = "none"
+                except RuntimeError:
+                    error = "RuntimeError"
+                except BaseException as e:
+                    error = type(e).__name__
+                with lock:
+                    errors.append(error)
+
+    threads = [threading.Thread(target=use) for _ in range(8)]
+    for thread in threads:
+        thread.start()
+    for thread in threads:
+        thread.join()
+
+    assert errors == ["RuntimeError"] * (8 * 20 * 6)
+    assert simple.ir() == ir
+
+
+def test_int_registers() -> None:
+    simple = pyqir.SimpleModule("test_int_registers", 2, 2)
+    qis = pyqir.BasicQisBuilder(simple.builder)
+    qis.mz(simple.qubits[0], simple.results[0])
+    count = simple.add_int_register()
+    qis.if_result(
+        simple.results[0], one=lambda: simple.increment_if(count, simple.results[1])
+    )
+    total = simple.add_int_register(5)
+    assert (count, total) == (0, 1)
+
+    simple.increment_if(count, simple.results[0], 2)
+    simple.add_register(total, count)
+    simple.record_int_register(total)
+
+    ir = simple.ir()
+    entry = ir[ir.index("entry:") : ir.index("__quantum__qis__mz__body")]
+    assert "%0 = alloca i64" in entry
+    assert "%1 = alloca i64" in entry
+    assert "store i64 0, i64* %0" in entry
+    assert "store i64 5, i64* %1" in entry
+    assert ir.count("add i64") == 3
+    assert "call void @__quantum__rt__int_record_output(i64 %" in ir
+
+    other = copy.copy(simple)
+    other.add_register(count, total)
+    assert other.ir().count("add i64") == 4
+
+
+def test_int_register_out_of_range() -> None:
+    simple = pyqir.SimpleModule("test_int_registers", 0, 1)
+    simple.add_int_register()
+    with pytest.raises(ValueError, match=r"^Integer register 1 is out of range\.$"):
+        simple.increment_if(1, simple.results[0])
+    with pytest.raises(ValueError, match=r"^Integer register -1 is out of range\.$"):
+        simple.add_register(0, -1)
+
+    simple.clear()
+    with pytest.raises(ValueError, match=r"^Integer register 0 was removed by clear"):
+        simple.record_int_register(0)
+    assert simple.add_int_register() == 1
+
+
+def test_int_register_after_optimize() -> None:
+    simple = pyqir.SimpleModule("test_int_registers", 1, 1)
+    qis = pyqir.BasicQisBuilder(simple.builder)
+    qis.mz(simple.qubits[0], simple.results[0])
+    count = simple.add_int_register()
+    simple.increment_if(count, simple.results[0])
+
+    # Level 0 runs no passes, so the register is kept.
+    simple.optimize(0)
+    simple.increment_if(count, simple.results[0])
+
+    simple.optimize(2)
+    with pytest.raises(ValueError, match=r"^Integer register 0 was removed by clear"):
+        simple.increment_if(count, simple.results[0])
+    other = simple.add_int_register(3)
+    simple.record_int_register(other)
+    assert "store i64 3, i64* %" in simple.ir()
+
+
+def test_int_registers_in_copy_and_loaded_module(tmp_path: Path) -> None:
+    simple = pyqir.SimpleModule("test_int_registers", 1, 1)
+    simple.add_int_register()
+    total = simple.add_int_register(5)
+    other = copy.copy(simple)
+    other.record_int_register(total)
+    assert "call void @__quantum__rt__int_record_output(i64 %" in other.ir()
+    assert "int_record_output" not in simple.ir()
+
+    path = tmp_path / "test_int_registers.ll"
+    path.write_text(simple.ir())
+    loaded = pyqir.load_module(str(path))
+    loaded.record_int_register(total)
+    assert loaded.ir() == other.ir()
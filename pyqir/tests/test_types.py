@@ -55,6 +55,14 @@ def test_qubit() -> None:
     assert pyqir.is_qubit_type(qubit)
 
 
+def test_array() -> None:
+    context = Context()
+    array = pyqir.array_type(context)
+    assert pyqir.is_array_type(array)
+    assert not pyqir.is_qubit_type(array)
+    assert not pyqir.is_array_type(pyqir.qubit_type(context))
+
+
 def test_result() -> None:
     result = pyqir.result_type(Context())
     assert pyqir.is_result_type(result)
@@ -0,0 +1,82 @@
+# Copyright (c) Microsoft Corporation.
+# Licensed under the MIT License.
+
+import pytest
+
+import pyqir.rt as rt
+from pyqir import BasicQisBuilder, SimpleModule, Violation
+
+
+def test_conforming_module() -> None:
+    simple = SimpleModule("test_validate", 2, 2)
+    qis = BasicQisBuilder(simple.builder)
+    qis.h(simple.qubits[0])
+    qis.cx(simple.qubits[0], simple.qubits[1])
+    qis.mz(simple.qubits[0], simple.results[0])
+    qis.mz(simple.qubits[1], simple.results[1])
+    qis.mz(simple.qubits[1], simple.results[1])
+    label = simple.add_byte_string(b"r0")
+    rt.result_record_output(simple.builder, simple.results[0], label)
+    assert simple.validate() == []
+    simple.validate(raise_errors=True)
+
+
+def test_violations() -> None:
+    simple = SimpleModule("test_validate", 2, 1)
+    qis = BasicQisBuilder(simple.builder)
+    qis.mz(simple.qubits[0], simple.results[0])
+    qis.x(simple.qubits[0])
+    qis.reset(simple.qubits[1])
+    qis.if_result(simple.results[0], one=lambda: qis.h(simple.qubits[1]))
+    assert simple.validate() == [
+        Violation(
+            1,
+            "use_after_measure",
+            "Qubit 0 is used by __quantum__qis__x__body after it is measured.",
+        ),
+        Violation(
+            2, "gate_set", "__quantum__qis__reset__body isn't in the base profile."
+        ),
+        Violation(
+            3,
+            "gate_set",
+            "__quantum__qis__read_result__body isn't in the base profile.",
+        ),
+        Violation(
+            3,
+            "result_use",
+            "Result 0 is used by __quantum__qis__read_result__body, but results can only "
+            "be recorded as output.",
+        ),
+        Violation(
+            4, "branching", "Conditional branches aren't allowed in the base profile."
+        ),
+    ]
+
+
+def test_reset_clears_measurement() -> None:
+    simple = SimpleModule("test_validate", 1, 1)
+    qis = BasicQisBuilder(simple.builder)
+    qis.mz(simple.qubits[0], simple.results[0])
+    qis.reset(simple.qubits[0])
+    qis.x(simple.qubits[0])
+    assert [v.rule for v in simple.validate()] == ["gate_set"]
+
+
+def test_raise_errors() -> None:
+    simple = SimpleModule("test_validate", 1, 1)
+    qis = BasicQisBuilder(simple.builder)
+    qis.mz(simple.qubits[0], simple.results[0])
+    qis.h(simple.qubits[0])
+    message = (
+        "^Module test_validate doesn't conform to base_profile:\n"
+        "- Qubit 0 is used by __quantum__qis__h__body after it is measured.$"
+    )
+    with pytest.raises(ValueError, match=message):
+        simple.validate(raise_errors=True)
+
+
+def test_unsupported_profile() -> None:
+    simple = SimpleModule("test_validate", 1, 0)
+    with pytest.raises(ValueError, match="^Unsupported profile 'adaptive_profile'."):
+        simple.validate("adaptive_profile")
@@ -97,6 +97,8 @@ fn main() -> Result<(), Box<dyn Error>> {
     println!("cargo:rerun-if-changed=external.rs");
     println!("cargo:rerun-if-changed=target.c");
     println!("cargo:rerun-if-changed=llvm-wrapper/LLVMWrapper.h");
+    println!("cargo:rerun-if-changed=llvm-wrapper/ContextWrapper.cpp");
+    println!("cargo:rerun-if-changed=llvm-wrapper/InstructionWrapper.cpp");
     println!("cargo:rerun-if-changed=llvm-wrapper/MetadataWrapper.cpp");
     println!("cargo:rerun-if-changed=llvm-wrapper/ModuleWrapper.cpp");
 
@@ -306,6 +308,8 @@ fn compile_llvm_wrapper() -> Result<(), Box<dyn Error>> {
     cfg.cpp(true)
         .cpp_link_stdlib(None)
         .static_crt(true)
+        .file("llvm-wrapper/ContextWrapper.cpp")
+        .file("llvm-wrapper/InstructionWrapper.cpp")
         .file("llvm-wrapper/MetadataWrapper.cpp")
         .file("llvm-wrapper/ModuleWrapper.cpp")
         .compile("llvm-wrapper");
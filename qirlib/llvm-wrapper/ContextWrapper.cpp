@@ -0,0 +1,24 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include "LLVMWrapper.h"
+#include "llvm/IR/LLVMContext.h"
+
+#ifdef _WIN32
+#define QIR_SHARED_API __declspec(dllexport)
+#else
+#define QIR_SHARED_API
+#endif
+
+using namespace llvm;
+
+extern "C"
+{
+#if LLVM_VERSION_GE(14, 0)
+    QIR_SHARED_API void LLVMRustContextEnableOpaquePointers(LLVMContextRef C)
+    {
+        unwrap(C)->enableOpaquePointers();
+    }
+#endif
+
+} // extern "C"
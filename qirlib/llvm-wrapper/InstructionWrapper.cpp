@@ -0,0 +1,164 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+#include "LLVMWrapper.h"
+#include "llvm/ADT/DenseMap.h"
+#include "llvm/ADT/SmallVector.h"
+#include "llvm/IR/DerivedTypes.h"
+#include "llvm/IR/Function.h"
+#include "llvm/IR/GlobalVariable.h"
+#include "llvm/IR/IRBuilder.h"
+#include "llvm/IR/Instruction.h"
+#include "llvm/IR/Module.h"
+#include "llvm/Transforms/Utils/ValueMapper.h"
+
+#ifdef _WIN32
+#define QIR_SHARED_API __declspec(dllexport)
+#else
+#define QIR_SHARED_API
+#endif
+
+using namespace llvm;
+
+namespace
+{
+    // Maps opaque struct types that were renamed because their name was already taken in the
+    // context, like %Qubit.0 in a module read into a context that has %Qubit, back to the original
+    // types, along with the pointer and function types built from them.
+    class RenamedTypeRemapper final : public ValueMapTypeRemapper
+    {
+    public:
+        explicit RenamedTypeRemapper(Module &Dest) : Dest(Dest) {}
+
+        Type *remapType(Type *Ty) override
+        {
+            auto It = Cache.find(Ty);
+            if (It != Cache.end())
+            {
+                return It->second;
+            }
+
+            Type *Result = Ty;
+            if (auto *ST = dyn_cast<StructType>(Ty))
+            {
+                Result = original(ST);
+            }
+            else if (auto *PT = dyn_cast<PointerType>(Ty))
+            {
+#if LLVM_VERSION_GE(13, 0)
+                if (!PT->isOpaque())
+#endif
+                {
+                    Result = PointerType::get(remapType(PT->getPointerElementType()), PT->getAddressSpace());
+                }
+            }
+            else if (auto *FT = dyn_cast<FunctionType>(Ty))
+            {
+                SmallVector<Type *, 8> Params;
+                for (Type *Param : FT->params())
+                {
+                    Params.push_back(remapType(Param));
+                }
+                Result = FunctionType::get(remapType(FT->getReturnType()), Params, FT->isVarArg());
+            }
+
+            Cache[Ty] = Result;
+            return Result;
+        }
+
+    private:
+        Module &Dest;
+        DenseMap<Type *, Type *> Cache;
+
+        Type *original(StructType *ST)
+        {
+            if (!ST->isOpaque() || !ST->hasName())
+            {
+                return ST;
+            }
+
+            auto Parts = ST->getName().rsplit('.');
+            if (Parts.second.empty() || Parts.second.find_first_not_of("0123456789") != StringRef::npos)
+            {
+                return ST;
+            }
+
+#if LLVM_VERSION_GE(12, 0)
+            auto *Original = StructType::getTypeByName(Dest.getContext(), Parts.first);
+#else
+            auto *Original = Dest.getTypeByName(Parts.first);
+#endif
+            return Original != nullptr && Original->isOpaque() ? Original : ST;
+        }
+    };
+
+    // Maps global values from another module to equivalent global values in the destination
+    // module, declaring functions and copying global variables as needed.
+    class GlobalMaterializer final : public ValueMaterializer
+    {
+    public:
+        GlobalMaterializer(Module &Dest, RenamedTypeRemapper &Types) : Dest(Dest), Types(Types) {}
+
+        Value *materialize(Value *V) override
+        {
+            if (auto *F = dyn_cast<Function>(V))
+            {
+                if (F->getParent() == &Dest)
+                {
+                    return F;
+                }
+                auto *Ty = cast<FunctionType>(Types.remapType(F->getFunctionType()));
+                return Dest.getOrInsertFunction(F->getName(), Ty, F->getAttributes()).getCallee();
+            }
+
+            if (auto *GV = dyn_cast<GlobalVariable>(V))
+            {
+                if (GV->getParent() == &Dest)
+                {
+                    return GV;
+                }
+                auto *Copy = new GlobalVariable(Dest, Types.remapType(GV->getValueType()), GV->isConstant(),
+                                                GV->getLinkage(),
+                                                GV->hasInitializer() ? GV->getInitializer() : nullptr,
+                                                GV->getName(), nullptr, GV->getThreadLocalMode(),
+                                                GV->getAddressSpace());
+                Copy->copyAttributesFrom(GV);
+                return Copy;
+            }
+
+            return nullptr;
+        }
+
+    private:
+        Module &Dest;
+        RenamedTypeRemapper &Types;
+    };
+} // namespace
+
+extern "C"
+{
+    QIR_SHARED_API void LLVMRustRemapInstructions(LLVMValueRef *Instructions, size_t NumInstructions,
+                                                  LLVMValueRef *From, LLVMValueRef *To, size_t NumValues,
+                                                  LLVMModuleRef Dest)
+    {
+        ValueToValueMapTy Map;
+        for (size_t i = 0; i < NumValues; ++i)
+        {
+            Map[unwrap(From[i])] = unwrap(To[i]);
+        }
+
+        RenamedTypeRemapper Types(*unwrap(Dest));
+        GlobalMaterializer Materializer(*unwrap(Dest), Types);
+        for (size_t i = 0; i < NumInstructions; ++i)
+        {
+            RemapInstruction(unwrap<Instruction>(Instructions[i]), Map, RF_None, &Types, &Materializer);
+        }
+    }
+
+    QIR_SHARED_API LLVMBool LLVMRustBuilderIsAtEnd(LLVMBuilderRef Builder)
+    {
+        IRBuilder<> *B = unwrap(Builder);
+        return B->GetInsertPoint() == B->GetInsertBlock()->end();
+    }
+
+} // extern "C"
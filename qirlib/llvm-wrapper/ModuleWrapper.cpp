@@ -3,7 +3,10 @@
 
 #include "LLVMWrapper.h"
 
+#include "llvm/ADT/Triple.h"
+#include "llvm/IR/DataLayout.h"
 #include "llvm/IR/Module.h"
+#include "llvm/Support/Error.h"
 
 #ifdef _WIN32
 #define QIR_SHARED_API __declspec(dllexport)
@@ -72,4 +75,37 @@ extern "C"
     llvm::unwrap(M)->addModuleFlag(map_to_llvmRustModFlagBehavior(Behavior), {Key, KeyLen}, llvm::unwrap(Val));
   }
 
+  QIR_SHARED_API LLVMBool LLVMRustSetTargetTriple(LLVMModuleRef M, const char *TripleStr,
+                                                  char **ErrorMessage)
+  {
+    llvm::Triple T(TripleStr);
+    if (!T.str().empty() && T.getArch() == llvm::Triple::UnknownArch)
+    {
+      std::string Message = "Unknown architecture in target triple: " + T.str();
+      *ErrorMessage = LLVMCreateMessage(Message.c_str());
+      return true;
+    }
+
+    llvm::unwrap(M)->setTargetTriple(T.str());
+    return false;
+  }
+
+  QIR_SHARED_API LLVMBool LLVMRustSetDataLayout(LLVMModuleRef M, const char *DataLayoutStr,
+                                                char **ErrorMessage)
+  {
+#if LLVM_VERSION_GE(13, 0)
+    auto Layout = llvm::DataLayout::parse(DataLayoutStr);
+    if (!Layout)
+    {
+      *ErrorMessage = LLVMCreateMessage(llvm::toString(Layout.takeError()).c_str());
+      return true;
+    }
+    llvm::unwrap(M)->setDataLayout(*Layout);
+#else
+    // Older versions of LLVM can't report an invalid data layout without aborting.
+    llvm::unwrap(M)->setDataLayout(DataLayoutStr);
+#endif
+    return false;
+  }
+
 } // extern "C"
@@ -0,0 +1,18 @@
+; ModuleID = 'compile_unit'
+source_filename = "compile_unit"
+
+define void @main() #0 !dbg !3 {
+  ret void
+}
+
+attributes #0 = { "entry_point" "output_labeling_schema" "qir_profiles"="custom" "required_num_qubits"="0" "required_num_results"="0" }
+
+!llvm.dbg.cu = !{!0}
+!llvm.module.flags = !{!2}
+
+!0 = distinct !DICompileUnit(language: DW_LANG_Python, file: !1, producer: "pyqir", isOptimized: false, runtimeVersion: 0, emissionKind: LineTablesOnly, splitDebugInlining: false)
+!1 = !DIFile(filename: "program.py", directory: "/home/user")
+!2 = !{i32 2, !"Debug Info Version", i32 3}
+!3 = distinct !DISubprogram(name: "main", linkageName: "main", scope: null, file: !1, type: !4, spFlags: DISPFlagDefinition, unit: !0, retainedNodes: !5)
+!4 = !DISubroutineType(types: !5)
+!5 = !{}
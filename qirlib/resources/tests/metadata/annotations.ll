@@ -0,0 +1,17 @@
+; ModuleID = 'annotations'
+source_filename = "annotations"
+
+%Qubit = type opaque
+
+define void @main() #0 {
+  call void @__quantum__qis__x__body(%Qubit* null), !qir.annotations !0
+  ret void
+}
+
+declare void @__quantum__qis__x__body(%Qubit*)
+
+attributes #0 = { "entry_point" "output_labeling_schema" "qir_profiles"="custom" "required_num_qubits"="1" "required_num_results"="0" }
+
+!0 = !{!1, !2}
+!1 = !{!"noise", double 5.000000e-01}
+!2 = !{!"gate", !"not"}
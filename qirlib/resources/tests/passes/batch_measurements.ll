@@ -0,0 +1,31 @@
+; ModuleID = 'batch_measurements'
+source_filename = "batch_measurements"
+
+%Qubit = type opaque
+%Result = type opaque
+
+@0 = internal constant [2 x %Qubit*] [%Qubit* null, %Qubit* inttoptr (i64 1 to %Qubit*)]
+@1 = internal constant [2 x %Result*] [%Result* null, %Result* inttoptr (i64 1 to %Result*)]
+
+define void @main() #0 {
+  call void @__quantum__qis__h__body(%Qubit* null)
+  call void @__quantum__qis__mz__body(%Qubit* inttoptr (i64 2 to %Qubit*), %Result* inttoptr (i64 2 to %Result*))
+  call void @__quantum__qis__cnot__body(%Qubit* null, %Qubit* inttoptr (i64 1 to %Qubit*))
+  call void @__quantum__qis__mz_batch__body(i64 2, %Qubit** getelementptr inbounds ([2 x %Qubit*], [2 x %Qubit*]* @0, i32 0, i32 0), %Result** getelementptr inbounds ([2 x %Result*], [2 x %Result*]* @1, i32 0, i32 0))
+  call void @__quantum__rt__result_record_output(%Result* null, i8* null)
+  call void @__quantum__rt__result_record_output(%Result* inttoptr (i64 1 to %Result*), i8* null)
+  ret void
+}
+
+declare void @__quantum__qis__h__body(%Qubit*)
+
+declare void @__quantum__qis__mz__body(%Qubit*, %Result* writeonly) #1
+
+declare void @__quantum__qis__cnot__body(%Qubit*, %Qubit*)
+
+declare void @__quantum__rt__result_record_output(%Result*, i8*)
+
+declare void @__quantum__qis__mz_batch__body(i64, %Qubit**, %Result**) #1
+
+attributes #0 = { "entry_point" "output_labeling_schema" "qir_profiles"="custom" "required_num_qubits"="3" "required_num_results"="3" }
+attributes #1 = { "irreversible" }
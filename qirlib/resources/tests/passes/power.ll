@@ -0,0 +1,19 @@
+; ModuleID = 'power'
+source_filename = "power"
+
+%Qubit = type opaque
+
+define void @main() #0 {
+  call void @__quantum__qis__rx__body(double 1.250000e-01, %Qubit* null)
+  call void @__quantum__qis__barrier__body()
+  call void @__quantum__qis__u1__body(double -2.500000e-01, %Qubit* inttoptr (i64 1 to %Qubit*))
+  ret void
+}
+
+declare void @__quantum__qis__rx__body(double, %Qubit*)
+
+declare void @__quantum__qis__barrier__body()
+
+declare void @__quantum__qis__u1__body(double, %Qubit*)
+
+attributes #0 = { "entry_point" "output_labeling_schema" "qir_profiles"="custom" "required_num_qubits"="2" "required_num_results"="0" }
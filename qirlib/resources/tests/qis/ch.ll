@@ -0,0 +1,13 @@
+; ModuleID = 'ch'
+source_filename = "ch"
+
+%Qubit = type opaque
+
+define void @main() #0 {
+  call void @__quantum__qis__ch__body(%Qubit* null, %Qubit* inttoptr (i64 1 to %Qubit*))
+  ret void
+}
+
+declare void @__quantum__qis__ch__body(%Qubit*, %Qubit*)
+
+attributes #0 = { "entry_point" "output_labeling_schema" "qir_profiles"="custom" "required_num_qubits"="2" "required_num_results"="0" }
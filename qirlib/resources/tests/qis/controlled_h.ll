@@ -0,0 +1,19 @@
+; ModuleID = 'controlled_h'
+source_filename = "controlled_h"
+
+%Qubit = type opaque
+
+define void @main() #0 {
+  call void @__quantum__qis__ry__body(double 0xBFE921FB54442D18, %Qubit* inttoptr (i64 1 to %Qubit*))
+  call void @__quantum__qis__cz__body(%Qubit* null, %Qubit* inttoptr (i64 1 to %Qubit*))
+  call void @__quantum__qis__ry__body(double 0x3FE921FB54442D18, %Qubit* inttoptr (i64 1 to %Qubit*))
+  ret void
+}
+
+declare void @__quantum__qis__h__body(%Qubit*)
+
+declare void @__quantum__qis__ry__body(double, %Qubit*)
+
+declare void @__quantum__qis__cz__body(%Qubit*, %Qubit*)
+
+attributes #0 = { "entry_point" "output_labeling_schema" "qir_profiles"="custom" "required_num_qubits"="2" "required_num_results"="0" }
@@ -0,0 +1,23 @@
+; ModuleID = 'controlled_s'
+source_filename = "controlled_s"
+
+%Qubit = type opaque
+
+define void @main() #0 {
+  call void @__quantum__qis__t__body(%Qubit* null)
+  call void @__quantum__qis__t__body(%Qubit* inttoptr (i64 1 to %Qubit*))
+  call void @__quantum__qis__cnot__body(%Qubit* null, %Qubit* inttoptr (i64 1 to %Qubit*))
+  call void @__quantum__qis__t__adj(%Qubit* inttoptr (i64 1 to %Qubit*))
+  call void @__quantum__qis__cnot__body(%Qubit* null, %Qubit* inttoptr (i64 1 to %Qubit*))
+  ret void
+}
+
+declare void @__quantum__qis__s__body(%Qubit*)
+
+declare void @__quantum__qis__t__body(%Qubit*)
+
+declare void @__quantum__qis__cnot__body(%Qubit*, %Qubit*)
+
+declare void @__quantum__qis__t__adj(%Qubit*)
+
+attributes #0 = { "entry_point" "output_labeling_schema" "qir_profiles"="custom" "required_num_qubits"="2" "required_num_results"="0" }
@@ -0,0 +1,21 @@
+; ModuleID = 'controlled_y'
+source_filename = "controlled_y"
+
+%Qubit = type opaque
+
+define void @main() #0 {
+  call void @__quantum__qis__s__adj(%Qubit* inttoptr (i64 1 to %Qubit*))
+  call void @__quantum__qis__cnot__body(%Qubit* null, %Qubit* inttoptr (i64 1 to %Qubit*))
+  call void @__quantum__qis__s__body(%Qubit* inttoptr (i64 1 to %Qubit*))
+  ret void
+}
+
+declare void @__quantum__qis__y__body(%Qubit*)
+
+declare void @__quantum__qis__s__adj(%Qubit*)
+
+declare void @__quantum__qis__cnot__body(%Qubit*, %Qubit*)
+
+declare void @__quantum__qis__s__body(%Qubit*)
+
+attributes #0 = { "entry_point" "output_labeling_schema" "qir_profiles"="custom" "required_num_qubits"="2" "required_num_results"="0" }
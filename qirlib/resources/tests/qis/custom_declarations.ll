@@ -0,0 +1,64 @@
+; ModuleID = 'custom_declarations'
+source_filename = "custom_declarations"
+
+%Qubit = type opaque
+%Result = type opaque
+
+define void @main() #0 {
+  ret void
+}
+
+declare void @__quantum__qis__barrier__body()
+
+declare void @__quantum__qis__ccx__body(%Qubit*, %Qubit*, %Qubit*)
+
+declare void @__quantum__qis__ch__body(%Qubit*, %Qubit*)
+
+declare void @__quantum__qis__cnot__body(%Qubit*, %Qubit*)
+
+declare void @__quantum__qis__cy__body(%Qubit*, %Qubit*)
+
+declare void @__quantum__qis__cz__body(%Qubit*, %Qubit*)
+
+declare void @__quantum__qis__depolarize__body(double, %Qubit*)
+
+declare void @__quantum__qis__h__body(%Qubit*)
+
+declare void @__quantum__qis__id__body(%Qubit*)
+
+declare %Result* @__quantum__qis__m__body(%Qubit*) #1
+
+declare void @__quantum__qis__mz__body(%Qubit*, %Result* writeonly) #1
+
+declare i1 @__quantum__qis__read_result__body(%Result*)
+
+declare void @__quantum__qis__reset__body(%Qubit*)
+
+declare void @__quantum__qis__rx__body(double, %Qubit*)
+
+declare void @__quantum__qis__ry__body(double, %Qubit*)
+
+declare void @__quantum__qis__rz__body(double, %Qubit*)
+
+declare void @__quantum__qis__s__body(%Qubit*)
+
+declare void @__quantum__qis__s__adj(%Qubit*)
+
+declare void @__quantum__qis__swap__body(%Qubit*, %Qubit*)
+
+declare void @__quantum__qis__t__body(%Qubit*)
+
+declare void @__quantum__qis__t__adj(%Qubit*)
+
+declare void @__quantum__qis__u1__body(double, %Qubit*)
+
+declare void @__quantum__qis__u2__body(double, double, %Qubit*)
+
+declare void @__quantum__qis__x__body(%Qubit*)
+
+declare void @__quantum__qis__y__body(%Qubit*)
+
+declare void @__quantum__qis__z__body(%Qubit*)
+
+attributes #0 = { "entry_point" "output_labeling_schema" "qir_profiles"="custom" "required_num_qubits"="0" "required_num_results"="0" }
+attributes #1 = { "irreversible" }
@@ -0,0 +1,13 @@
+; ModuleID = 'depolarize'
+source_filename = "depolarize"
+
+%Qubit = type opaque
+
+define void @main() #0 {
+  call void @__quantum__qis__depolarize__body(double 1.250000e-01, %Qubit* null)
+  ret void
+}
+
+declare void @__quantum__qis__depolarize__body(double, %Qubit*)
+
+attributes #0 = { "entry_point" "output_labeling_schema" "qir_profiles"="custom" "required_num_qubits"="1" "required_num_results"="0" }
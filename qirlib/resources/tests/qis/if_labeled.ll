@@ -0,0 +1,41 @@
+; ModuleID = 'if_labeled'
+source_filename = "if_labeled"
+
+%Qubit = type opaque
+%Result = type opaque
+
+define void @main() #0 {
+  call void @__quantum__qis__mz__body(%Qubit* null, %Result* null)
+  %1 = call i1 @__quantum__qis__read_result__body(%Result* null)
+  br i1 %1, label %correct_then, label %correct_else
+
+correct_then:                                     ; preds = %0
+  call void @__quantum__qis__x__body(%Qubit* null)
+  br label %correct_continue
+
+correct_else:                                     ; preds = %0
+  br label %correct_continue
+
+correct_continue:                                 ; preds = %correct_else, %correct_then
+  %2 = call i1 @__quantum__qis__read_result__body(%Result* inttoptr (i64 1 to %Result*))
+  br i1 %2, label %correct_then1, label %correct_else2
+
+correct_then1:                                    ; preds = %correct_continue
+  call void @__quantum__qis__x__body(%Qubit* null)
+  br label %correct_continue3
+
+correct_else2:                                    ; preds = %correct_continue
+  br label %correct_continue3
+
+correct_continue3:                                ; preds = %correct_else2, %correct_then1
+  ret void
+}
+
+declare void @__quantum__qis__mz__body(%Qubit*, %Result* writeonly) #1
+
+declare i1 @__quantum__qis__read_result__body(%Result*)
+
+declare void @__quantum__qis__x__body(%Qubit*)
+
+attributes #0 = { "entry_point" "output_labeling_schema" "qir_profiles"="custom" "required_num_qubits"="1" "required_num_results"="2" }
+attributes #1 = { "irreversible" }
@@ -0,0 +1,30 @@
+; ModuleID = 'if_m'
+source_filename = "if_m"
+
+%Result = type opaque
+%Qubit = type opaque
+
+define void @main() #0 {
+  %1 = call %Result* @__quantum__qis__m__body(%Qubit* null)
+  %2 = call i1 @__quantum__qis__read_result__body(%Result* %1)
+  br i1 %2, label %then, label %else
+
+then:                                             ; preds = %0
+  call void @__quantum__qis__x__body(%Qubit* null)
+  br label %continue
+
+else:                                             ; preds = %0
+  br label %continue
+
+continue:                                         ; preds = %else, %then
+  ret void
+}
+
+declare %Result* @__quantum__qis__m__body(%Qubit*) #1
+
+declare i1 @__quantum__qis__read_result__body(%Result*)
+
+declare void @__quantum__qis__x__body(%Qubit*)
+
+attributes #0 = { "entry_point" "output_labeling_schema" "qir_profiles"="custom" "required_num_qubits"="1" "required_num_results"="0" }
+attributes #1 = { "irreversible" }
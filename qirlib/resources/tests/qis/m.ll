@@ -0,0 +1,15 @@
+; ModuleID = 'm'
+source_filename = "m"
+
+%Result = type opaque
+%Qubit = type opaque
+
+define void @main() #0 {
+  %1 = call %Result* @__quantum__qis__m__body(%Qubit* null)
+  ret void
+}
+
+declare %Result* @__quantum__qis__m__body(%Qubit*) #1
+
+attributes #0 = { "entry_point" "output_labeling_schema" "qir_profiles"="custom" "required_num_qubits"="1" "required_num_results"="0" }
+attributes #1 = { "irreversible" }
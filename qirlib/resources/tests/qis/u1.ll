@@ -0,0 +1,13 @@
+; ModuleID = 'u1'
+source_filename = "u1"
+
+%Qubit = type opaque
+
+define void @main() #0 {
+  call void @__quantum__qis__u1__body(double 5.000000e-01, %Qubit* null)
+  ret void
+}
+
+declare void @__quantum__qis__u1__body(double, %Qubit*)
+
+attributes #0 = { "entry_point" "output_labeling_schema" "qir_profiles"="custom" "required_num_qubits"="1" "required_num_results"="0" }
@@ -0,0 +1,13 @@
+; ModuleID = 'u2'
+source_filename = "u2"
+
+%Qubit = type opaque
+
+define void @main() #0 {
+  call void @__quantum__qis__u2__body(double 5.000000e-01, double 1.500000e+00, %Qubit* null)
+  ret void
+}
+
+declare void @__quantum__qis__u2__body(double, double, %Qubit*)
+
+attributes #0 = { "entry_point" "output_labeling_schema" "qir_profiles"="custom" "required_num_qubits"="1" "required_num_results"="0" }
@@ -0,0 +1,11 @@
+; ModuleID = 'int_record_output'
+source_filename = "int_record_output"
+
+define void @main() #0 {
+  call void @__quantum__rt__int_record_output(i64 3, i8* null)
+  ret void
+}
+
+declare void @__quantum__rt__int_record_output(i64, i8*)
+
+attributes #0 = { "entry_point" "output_labeling_schema" "qir_profiles"="custom" "required_num_qubits"="0" "required_num_results"="0" }
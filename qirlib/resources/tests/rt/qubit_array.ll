@@ -0,0 +1,25 @@
+; ModuleID = 'qubit_array'
+source_filename = "qubit_array"
+
+%Array = type opaque
+%Qubit = type opaque
+
+define void @main() #0 {
+  %1 = call %Array* @__quantum__rt__array_create_1d(i32 8, i64 2)
+  %2 = call i8* @__quantum__rt__array_get_element_ptr_1d(%Array* %1, i64 0)
+  %3 = bitcast i8* %2 to %Qubit**
+  store %Qubit* inttoptr (i64 1 to %Qubit*), %Qubit** %3, align 8
+  %4 = call i8* @__quantum__rt__array_get_element_ptr_1d(%Array* %1, i64 1)
+  %5 = bitcast i8* %4 to %Qubit**
+  store %Qubit* null, %Qubit** %5, align 8
+  call void @__quantum__rt__array_update_reference_count(%Array* %1, i32 -1)
+  ret void
+}
+
+declare %Array* @__quantum__rt__array_create_1d(i32, i64)
+
+declare i8* @__quantum__rt__array_get_element_ptr_1d(%Array*, i64)
+
+declare void @__quantum__rt__array_update_reference_count(%Array*, i32)
+
+attributes #0 = { "entry_point" "output_labeling_schema" "qir_profiles"="custom" "required_num_qubits"="2" "required_num_results"="0" }
@@ -0,0 +1,440 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use crate::{qis::Intrinsic, types, values};
+#[allow(clippy::wildcard_imports)]
+use llvm_sys::{core::*, prelude::*, LLVMTypeKind};
+use std::{
+    f64::consts::{FRAC_1_SQRT_2, FRAC_PI_4},
+    iter,
+    ops::{Add, Mul},
+    ptr::NonNull,
+    slice,
+};
+
+/// The most qubits that [`to_matrix`] supports. The matrix has `4^n` entries of 16 bytes, so 10
+/// qubits take 16 MiB, and every extra qubit multiplies that by four.
+pub const MAX_QUBITS: u64 = 10;
+
+/// A complex number.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Complex {
+    pub re: f64,
+    pub im: f64,
+}
+
+impl Complex {
+    const ZERO: Self = Self::new(0.0, 0.0);
+    const ONE: Self = Self::new(1.0, 0.0);
+    const I: Self = Self::new(0.0, 1.0);
+
+    #[must_use]
+    pub const fn new(re: f64, im: f64) -> Self {
+        Self { re, im }
+    }
+
+    fn from_polar(theta: f64) -> Self {
+        Self::new(theta.cos(), theta.sin())
+    }
+}
+
+impl Add for Complex {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self::new(self.re + rhs.re, self.im + rhs.im)
+    }
+}
+
+impl Mul for Complex {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        Self::new(
+            self.re * rhs.re - self.im * rhs.im,
+            self.re * rhs.im + self.im * rhs.re,
+        )
+    }
+}
+
+/// A gate acting on some qubits. The matrix is in row-major order, and bit `i` of a row or column
+/// index is the state of the `i`th qubit.
+struct Gate {
+    matrix: Vec<Complex>,
+    qubits: Vec<usize>,
+}
+
+/// Computes the unitary matrix of the circuit in the function by multiplying the matrices of its
+/// gates in order. Bit `i` of a row or column index is the state of qubit `i`, so qubit 0 is the
+/// least significant.
+///
+/// The number of qubits is the function's required number of qubits, or one more than the largest
+/// qubit ID used if it has no such attribute, and may be at most [`MAX_QUBITS`]. The function must
+/// have at most one basic block, every qubit must be static, and every rotation angle must be a
+/// constant. Calls to functions that don't take qubits are ignored, but measurements, resets, and
+/// calls to unknown functions that take qubits make the circuit non-unitary and return an error.
+pub unsafe fn to_matrix(function: LLVMValueRef) -> Result<Vec<Vec<Complex>>, String> {
+    let mut gates = Vec::new();
+    match LLVMCountBasicBlocks(function) {
+        0 => {}
+        1 => {
+            let block = LLVMGetFirstBasicBlock(function);
+            let instructions =
+                iter::successors(NonNull::new(LLVMGetFirstInstruction(block)), |i| {
+                    NonNull::new(LLVMGetNextInstruction(i.as_ptr()))
+                });
+            for instruction in instructions {
+                if let Some(gate) = gate(instruction.as_ptr())? {
+                    gates.push(gate);
+                }
+            }
+        }
+        _ => return Err("Circuits with control flow don't have a unitary matrix.".to_string()),
+    }
+
+    let num_qubits = match values::required_num_qubits(function) {
+        Some(n) => n,
+        None => gates.iter().flat_map(|g| &g.qubits).max().map_or(0, |&q| {
+            u64::try_from(q).map_or(u64::MAX, |q| q.saturating_add(1))
+        }),
+    };
+    if num_qubits > MAX_QUBITS {
+        return Err(format!(
+            "Unitary matrices are limited to {MAX_QUBITS} qubits, but the circuit has {num_qubits}."
+        ));
+    }
+    let num_qubits: usize = num_qubits.try_into().unwrap();
+    if let Some(&qubit) = gates
+        .iter()
+        .flat_map(|g| &g.qubits)
+        .find(|&&q| q >= num_qubits)
+    {
+        let s = if num_qubits == 1 { "" } else { "s" };
+        return Err(format!(
+            "Qubit {qubit} is out of range for a circuit with {num_qubits} qubit{s}."
+        ));
+    }
+
+    let dim = 1 << num_qubits;
+    let mut unitary = vec![Complex::ZERO; dim * dim];
+    for i in 0..dim {
+        unitary[i * dim + i] = Complex::ONE;
+    }
+    for gate in &gates {
+        apply(&mut unitary, dim, gate);
+    }
+    Ok(unitary.chunks(dim).map(<[_]>::to_vec).collect())
+}
+
+/// The gate that the instruction applies, if any.
+unsafe fn gate(instruction: LLVMValueRef) -> Result<Option<Gate>, String> {
+    if LLVMIsACallInst(instruction).is_null() {
+        return Ok(None);
+    }
+
+    let mut qubits = Vec::new();
+    let mut angles = Vec::new();
+    for i in 0..LLVMGetNumArgOperands(instruction) {
+        let operand = LLVMGetOperand(instruction, i);
+        if types::is_qubit(LLVMTypeOf(operand)) {
+            let id = values::qubit_id(operand)
+                .ok_or("Circuits with dynamic qubits don't have a unitary matrix.")?;
+            let id: usize = id.try_into().map_err(|_| {
+                format!(
+                    "Unitary matrices are limited to {MAX_QUBITS} qubits, but the circuit uses \
+                     qubit {id}."
+                )
+            })?;
+            if qubits.contains(&id) {
+                return Err(format!("Qubit {id} is used twice by the same gate."));
+            }
+            qubits.push(id);
+        } else if LLVMGetTypeKind(LLVMTypeOf(operand)) == LLVMTypeKind::LLVMDoubleTypeKind {
+            angles.push(if LLVMIsAConstantFP(operand).is_null() {
+                None
+            } else {
+                let mut loses_info = 0;
+                Some(LLVMConstRealGetDouble(operand, &mut loses_info))
+            });
+        }
+    }
+
+    let callee = LLVMGetCalledValue(instruction);
+    let intrinsic = match Intrinsic::from_function(callee) {
+        Some(intrinsic) => intrinsic,
+        None if qubits.is_empty() => return Ok(None),
+        None => {
+            let mut len = 0;
+            let name = LLVMGetValueName2(callee, &mut len);
+            let name = String::from_utf8_lossy(slice::from_raw_parts(name.cast(), len));
+            return Err(format!("Unknown gate {name}."));
+        }
+    };
+
+    let rotation = |i: usize| {
+        angles
+            .get(i)
+            .copied()
+            .flatten()
+            .ok_or_else(|| "Rotation angles must be constant.".to_string())
+    };
+    let matrix = match intrinsic {
+        Intrinsic::Barrier | Intrinsic::Id => return Ok(None),
+        Intrinsic::M | Intrinsic::Mz | Intrinsic::ReadResult => {
+            return Err("Circuits with measurements aren't unitary.".to_string())
+        }
+        Intrinsic::Reset => return Err("Circuits with resets aren't unitary.".to_string()),
+        Intrinsic::Depolarize => return Err("Circuits with noise aren't unitary.".to_string()),
+        Intrinsic::Ccx => permutation(8, |i| if i & 3 == 3 { i ^ 4 } else { i }),
+        Intrinsic::Ch => controlled(&hadamard()),
+        Intrinsic::Cx => permutation(4, |i| if i & 1 == 1 { i ^ 2 } else { i }),
+        Intrinsic::Cy => controlled(&pauli_y()),
+        Intrinsic::Cz => diagonal(&[1.0, 1.0, 1.0, -1.0].map(|x| Complex::new(x, 0.0))),
+        Intrinsic::Swap => permutation(4, |i| [0, 2, 1, 3][i]),
+        Intrinsic::H => hadamard(),
+        Intrinsic::X => permutation(2, |i| i ^ 1),
+        Intrinsic::Y => pauli_y(),
+        Intrinsic::Z => diagonal(&[Complex::ONE, Complex::new(-1.0, 0.0)]),
+        Intrinsic::S => diagonal(&[Complex::ONE, Complex::I]),
+        Intrinsic::SAdj => diagonal(&[Complex::ONE, Complex::new(0.0, -1.0)]),
+        Intrinsic::T => diagonal(&[Complex::ONE, Complex::from_polar(FRAC_PI_4)]),
+        Intrinsic::TAdj => diagonal(&[Complex::ONE, Complex::from_polar(-FRAC_PI_4)]),
+        Intrinsic::Rx => {
+            let (sin, cos) = (rotation(0)? / 2.0).sin_cos();
+            let (c, s) = (Complex::new(cos, 0.0), Complex::new(0.0, -sin));
+            vec![c, s, s, c]
+        }
+        Intrinsic::Ry => {
+            let (sin, cos) = (rotation(0)? / 2.0).sin_cos();
+            [cos, -sin, sin, cos].map(|x| Complex::new(x, 0.0)).to_vec()
+        }
+        Intrinsic::Rz => {
+            let half = rotation(0)? / 2.0;
+            diagonal(&[Complex::from_polar(-half), Complex::from_polar(half)])
+        }
+        Intrinsic::U1 => diagonal(&[Complex::ONE, Complex::from_polar(rotation(0)?)]),
+        Intrinsic::U2 => {
+            let (phi, lambda) = (rotation(0)?, rotation(1)?);
+            [
+                Complex::ONE,
+                Complex::new(-1.0, 0.0) * Complex::from_polar(lambda),
+                Complex::from_polar(phi),
+                Complex::from_polar(phi + lambda),
+            ]
+            .map(|x| Complex::new(FRAC_1_SQRT_2, 0.0) * x)
+            .to_vec()
+        }
+    };
+
+    Ok(Some(Gate { matrix, qubits }))
+}
+
+fn hadamard() -> Vec<Complex> {
+    [1.0, 1.0, 1.0, -1.0]
+        .map(|x| Complex::new(x * FRAC_1_SQRT_2, 0.0))
+        .to_vec()
+}
+
+fn pauli_y() -> Vec<Complex> {
+    vec![
+        Complex::ZERO,
+        Complex::new(0.0, -1.0),
+        Complex::I,
+        Complex::ZERO,
+    ]
+}
+
+/// The two-qubit matrix that applies the one-qubit gate to the second qubit when the first qubit,
+/// the least significant bit of the basis state, is one.
+fn controlled(gate: &[Complex]) -> Vec<Complex> {
+    let mut matrix = diagonal(&[Complex::ONE; 4]);
+    for (row, col) in [(0, 0), (0, 1), (1, 0), (1, 1)] {
+        matrix[(2 * row + 1) * 4 + 2 * col + 1] = gate[row * 2 + col];
+    }
+    matrix
+}
+
+/// The matrix that maps basis state `i` to `f(i)`.
+fn permutation(size: usize, f: impl Fn(usize) -> usize) -> Vec<Complex> {
+    let mut matrix = vec![Complex::ZERO; size * size];
+    for i in 0..size {
+        matrix[f(i) * size + i] = Complex::ONE;
+    }
+    matrix
+}
+
+fn diagonal(entries: &[Complex]) -> Vec<Complex> {
+    let size = entries.len();
+    let mut matrix = vec![Complex::ZERO; size * size];
+    for (i, &entry) in entries.iter().enumerate() {
+        matrix[i * size + i] = entry;
+    }
+    matrix
+}
+
+/// Multiplies the unitary by the gate on the left.
+fn apply(unitary: &mut [Complex], dim: usize, gate: &Gate) {
+    let size = 1 << gate.qubits.len();
+    let mask = gate.qubits.iter().fold(0, |mask, q| mask | 1 << q);
+    let offsets: Vec<usize> = (0..size)
+        .map(|i| {
+            gate.qubits
+                .iter()
+                .enumerate()
+                .filter(|&(bit, _)| i >> bit & 1 == 1)
+                .fold(0, |offset, (_, q)| offset | 1 << q)
+        })
+        .collect();
+
+    let mut amplitudes = vec![Complex::ZERO; size];
+    for base in (0..dim).filter(|base| base & mask == 0) {
+        for column in 0..dim {
+            for (amplitude, offset) in amplitudes.iter_mut().zip(&offsets) {
+                *amplitude = unitary[(base | offset) * dim + column];
+            }
+            for (row, offset) in offsets.iter().enumerate() {
+                let products = gate.matrix[row * size..(row + 1) * size]
+                    .iter()
+                    .zip(&amplitudes)
+                    .map(|(&g, &a)| g * a);
+                unitary[(base | offset) * dim + column] = products.fold(Complex::ZERO, Add::add);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{to_matrix, Complex};
+    use crate::{
+        qis::{build_cx, build_cy, build_h, build_mz, build_rz, build_u2, build_x},
+        tests::{Builder, Context, Module},
+        values::{entry_point, qubit, result},
+    };
+    use const_str::{cstr, raw_cstr};
+    use llvm_sys::{
+        core::{
+            LLVMAppendBasicBlockInContext, LLVMConstReal, LLVMDoubleTypeInContext,
+            LLVMPositionBuilderAtEnd,
+        },
+        prelude::*,
+    };
+    use std::f64::consts::{FRAC_1_SQRT_2, PI};
+
+    fn matrix(
+        num_qubits: u64,
+        build: impl FnOnce(LLVMContextRef, LLVMBuilderRef),
+    ) -> Result<Vec<Vec<Complex>>, String> {
+        let context = Context::new();
+        let module = Module::new(&context, cstr!("test"));
+        let builder = Builder::new(&context);
+        unsafe {
+            let function = entry_point(module.as_ptr(), cstr!("main"), num_qubits, 1, "custom", "");
+            let block = LLVMAppendBasicBlockInContext(context.as_ptr(), function, raw_cstr!(""));
+            LLVMPositionBuilderAtEnd(builder.as_ptr(), block);
+            build(context.as_ptr(), builder.as_ptr());
+            to_matrix(function)
+        }
+    }
+
+    fn assert_close(actual: &[Vec<Complex>], expected: &[&[Complex]]) {
+        assert_eq!(actual.len(), expected.len());
+        for (actual, expected) in actual.iter().zip(expected) {
+            assert_eq!(actual.len(), expected.len());
+            for (a, e) in actual.iter().zip(*expected) {
+                assert!((a.re - e.re).abs() < 1e-9 && (a.im - e.im).abs() < 1e-9);
+            }
+        }
+    }
+
+    const O: Complex = Complex::new(0.0, 0.0);
+    const I: Complex = Complex::new(1.0, 0.0);
+
+    #[test]
+    fn qubit_zero_is_least_significant() {
+        let unitary = matrix(2, |context, builder| unsafe {
+            build_x(builder, qubit(context, 0));
+        })
+        .unwrap();
+        assert_close(
+            &unitary,
+            &[&[O, I, O, O], &[I, O, O, O], &[O, O, O, I], &[O, O, I, O]],
+        );
+    }
+
+    #[test]
+    fn gates_are_multiplied_in_order() {
+        let unitary = matrix(2, |context, builder| unsafe {
+            build_h(builder, qubit(context, 0));
+            build_cx(builder, qubit(context, 0), qubit(context, 1));
+        })
+        .unwrap();
+        let h = Complex::new(FRAC_1_SQRT_2, 0.0);
+        let m = Complex::new(-FRAC_1_SQRT_2, 0.0);
+        assert_close(
+            &unitary,
+            &[&[h, h, O, O], &[O, O, h, m], &[O, O, h, h], &[h, m, O, O]],
+        );
+    }
+
+    #[test]
+    fn controlled_y_acts_on_target() {
+        let unitary = matrix(2, |context, builder| unsafe {
+            build_cy(builder, qubit(context, 0), qubit(context, 1));
+        })
+        .unwrap();
+        let (i, m) = (Complex::new(0.0, 1.0), Complex::new(0.0, -1.0));
+        assert_close(
+            &unitary,
+            &[&[I, O, O, O], &[O, O, O, m], &[O, O, I, O], &[O, i, O, O]],
+        );
+    }
+
+    #[test]
+    fn constant_rotation() {
+        let unitary = matrix(1, |context, builder| unsafe {
+            let theta = LLVMConstReal(LLVMDoubleTypeInContext(context), PI);
+            build_rz(builder, theta, qubit(context, 0));
+        })
+        .unwrap();
+        assert_close(
+            &unitary,
+            &[&[Complex::new(0.0, -1.0), O], &[O, Complex::new(0.0, 1.0)]],
+        );
+    }
+
+    #[test]
+    fn u2_angle_order() {
+        let unitary = matrix(1, |context, builder| unsafe {
+            let double = LLVMDoubleTypeInContext(context);
+            build_u2(
+                builder,
+                LLVMConstReal(double, 0.0),
+                LLVMConstReal(double, PI),
+                qubit(context, 0),
+            );
+        })
+        .unwrap();
+        let h = Complex::new(FRAC_1_SQRT_2, 0.0);
+        let m = Complex::new(-FRAC_1_SQRT_2, 0.0);
+        assert_close(&unitary, &[&[h, h], &[h, m]]);
+    }
+
+    #[test]
+    fn measurement_is_not_unitary() {
+        let error = matrix(1, |context, builder| unsafe {
+            build_mz(builder, qubit(context, 0), result(context, 0));
+        })
+        .unwrap_err();
+        assert_eq!(error, "Circuits with measurements aren't unitary.");
+    }
+
+    #[test]
+    fn too_many_qubits() {
+        let error = matrix(11, |_, _| ()).unwrap_err();
+        assert_eq!(
+            error,
+            "Unitary matrices are limited to 10 qubits, but the circuit has 11."
+        );
+    }
+}
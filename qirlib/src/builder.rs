@@ -1,15 +1,16 @@
 // Copyright (c) Microsoft Corporation.
 // Licensed under the MIT License.
 
-use const_str::raw_cstr;
-use llvm_sys::{
-    core::{
-        LLVMAppendBasicBlockInContext, LLVMBuildBr, LLVMBuildCondBr, LLVMGetBasicBlockParent,
-        LLVMGetInsertBlock, LLVMGetTypeContext, LLVMPositionBuilderAtEnd, LLVMTypeOf,
-    },
-    prelude::*,
+use crate::{
+    llvm_wrapper::{LLVMRustBuilderIsAtEnd, LLVMRustRemapInstructions},
+    values,
+};
+#[allow(clippy::wildcard_imports)]
+use llvm_sys::{core::*, debuginfo::LLVMInstructionSetDebugLoc, prelude::*};
+use std::{
+    collections::HashMap, convert::Infallible, ffi::CString, hash::BuildHasher, iter, ptr::NonNull,
+    slice,
 };
-use std::{convert::Infallible, ptr::NonNull};
 
 pub unsafe fn build_if(
     builder: LLVMBuilderRef,
@@ -21,6 +22,7 @@ pub unsafe fn build_if(
     try_build_if(
         builder,
         cond,
+        None,
         || {
             build_true();
             always_ok
@@ -33,9 +35,13 @@ pub unsafe fn build_if(
     .unwrap();
 }
 
+/// Builds a branch on a boolean condition into blocks named ``then``, ``else`` and ``continue``,
+/// or with a label, ``{label}_then``, ``{label}_else`` and ``{label}_continue``. LLVM appends a
+/// number to a name that's already taken in the function.
 pub unsafe fn try_build_if<E>(
     builder: LLVMBuilderRef,
     cond: LLVMValueRef,
+    label: Option<&str>,
     build_true: impl FnOnce() -> Result<(), E>,
     build_false: impl FnOnce() -> Result<(), E>,
 ) -> Result<(), E> {
@@ -45,10 +51,15 @@ pub unsafe fn try_build_if<E>(
         .as_ptr();
 
     let context = LLVMGetTypeContext(LLVMTypeOf(function));
-    let then_block = LLVMAppendBasicBlockInContext(context, function, raw_cstr!("then"));
-    let else_block = LLVMAppendBasicBlockInContext(context, function, raw_cstr!("else"));
+    let block_name = |name: &str| {
+        let name = label.map_or_else(|| name.to_string(), |l| format!("{l}_{name}"));
+        CString::new(name).expect("Label should not contain a null byte.")
+    };
+    let then_block = LLVMAppendBasicBlockInContext(context, function, block_name("then").as_ptr());
+    let else_block = LLVMAppendBasicBlockInContext(context, function, block_name("else").as_ptr());
     LLVMBuildCondBr(builder, cond, then_block, else_block);
-    let continue_block = LLVMAppendBasicBlockInContext(context, function, raw_cstr!("continue"));
+    let continue_name = block_name("continue");
+    let continue_block = LLVMAppendBasicBlockInContext(context, function, continue_name.as_ptr());
 
     LLVMPositionBuilderAtEnd(builder, then_block);
     build_true()?;
@@ -61,3 +72,224 @@ pub unsafe fn try_build_if<E>(
     LLVMPositionBuilderAtEnd(builder, continue_block);
     Ok(())
 }
+
+/// Copies the body of the function to the builder's position, replacing each static qubit and
+/// result with the one that its ID maps to. The function must be in the same context as the
+/// builder, but may be in another module, in which case the functions and global variables it uses
+/// are declared or copied into the builder's module.
+///
+/// The first basic block is copied to the builder's position, and the other blocks are appended
+/// to the builder's function, with branches between them remapped. Afterwards the builder is at
+/// the end of the copy of the block that has no terminator, or the last block if every block has
+/// one. Functions that return can't be appended. If a qubit or result isn't in its map, nothing is
+/// copied and an error is returned.
+///
+/// The builder's position must be set. If the function has more than one block, the builder must
+/// be at the end of its block, or nothing is copied and an error is returned.
+pub unsafe fn append_function<S: BuildHasher>(
+    builder: LLVMBuilderRef,
+    function: LLVMValueRef,
+    qubit_map: &HashMap<u64, u64, S>,
+    result_map: &HashMap<u64, u64, S>,
+) -> Result<(), String> {
+    let blocks: Vec<_> = iter::successors(NonNull::new(LLVMGetFirstBasicBlock(function)), |b| {
+        NonNull::new(LLVMGetNextBasicBlock(b.as_ptr()))
+    })
+    .map(NonNull::as_ptr)
+    .collect();
+    let instructions = |block| {
+        iter::successors(NonNull::new(LLVMGetFirstInstruction(block)), |i| {
+            NonNull::new(LLVMGetNextInstruction(i.as_ptr()))
+        })
+        .map(NonNull::as_ptr)
+    };
+
+    let target = LLVMGetBasicBlockParent(LLVMGetInsertBlock(builder));
+    if target == function {
+        return Err("A function can't be appended to itself.".to_string());
+    }
+    if blocks.len() > 1 && LLVMRustBuilderIsAtEnd(builder) == 0 {
+        return Err("Functions that branch must be appended at the end of a block.".to_string());
+    }
+
+    let context = LLVMGetTypeContext(LLVMTypeOf(function));
+    let mut from = Vec::new();
+    let mut to = Vec::new();
+    for &block in &blocks {
+        for instruction in instructions(block) {
+            if !LLVMIsAReturnInst(instruction).is_null() {
+                return Err("Functions that return can't be appended.".to_string());
+            }
+            for i in 0..LLVMGetNumOperands(instruction) {
+                let operand = LLVMGetOperand(instruction, i.try_into().unwrap());
+                let mapped = if let Some(id) = values::qubit_id(operand) {
+                    let id = qubit_map
+                        .get(&id)
+                        .ok_or_else(|| format!("Qubit {id} isn't in the qubit map."))?;
+                    Some(values::qubit(context, *id))
+                } else if let Some(id) = values::result_id(operand) {
+                    let id = result_map
+                        .get(&id)
+                        .ok_or_else(|| format!("Result {id} isn't in the result map."))?;
+                    Some(values::result(context, *id))
+                } else {
+                    None
+                };
+                if let Some(mapped) = mapped {
+                    if !from.contains(&operand) {
+                        from.push(operand);
+                        to.push(mapped);
+                    }
+                }
+            }
+        }
+    }
+
+    let first = match blocks.first() {
+        Some(&first) => first,
+        None => return Ok(()),
+    };
+    let mut copies = vec![LLVMGetInsertBlock(builder)];
+    for &block in &blocks[1..] {
+        copies.push(LLVMAppendBasicBlockInContext(
+            context,
+            target,
+            LLVMGetBasicBlockName(block),
+        ));
+    }
+    for (&block, &copy) in blocks.iter().zip(&copies) {
+        from.push(LLVMBasicBlockAsValue(block));
+        to.push(LLVMBasicBlockAsValue(copy));
+    }
+
+    let location = LLVMGetCurrentDebugLocation2(builder);
+    let mut clones = Vec::new();
+    for (&block, &copy) in blocks.iter().zip(&copies) {
+        if block != first {
+            LLVMPositionBuilderAtEnd(builder, copy);
+        }
+        for instruction in instructions(block) {
+            let clone = LLVMInstructionClone(instruction);
+            let mut len = 0;
+            let name = LLVMGetValueName2(instruction, &mut len);
+            let name = CString::new(slice::from_raw_parts(name.cast::<u8>(), len)).unwrap();
+            LLVMInsertIntoBuilderWithName(builder, clone, name.as_ptr());
+            LLVMInstructionSetDebugLoc(clone, location);
+            from.push(instruction);
+            to.push(clone);
+            clones.push(clone);
+        }
+    }
+
+    LLVMRustRemapInstructions(
+        clones.as_mut_ptr(),
+        clones.len(),
+        from.as_mut_ptr(),
+        to.as_mut_ptr(),
+        from.len(),
+        LLVMGetGlobalParent(target),
+    );
+
+    // Without control flow, the builder is already right after the copied instructions.
+    let open = blocks
+        .iter()
+        .position(|&b| LLVMGetBasicBlockTerminator(b).is_null())
+        .unwrap_or(blocks.len() - 1);
+    if open != 0 {
+        LLVMPositionBuilderAtEnd(builder, copies[open]);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::append_function;
+    use crate::{
+        qis::{build_cx, build_h, build_if_result, build_mz, build_x},
+        rt::build_result_record_output,
+        tests::{assert_reference_ir, Builder, Context, Module},
+        utils::builder_module,
+        values::{entry_point, global_string, qubit, result},
+    };
+    use const_str::{cstr, raw_cstr};
+    use llvm_sys::{
+        core::{
+            LLVMAppendBasicBlockInContext, LLVMCreateBuilderInContext, LLVMDisposeBuilder,
+            LLVMDisposeModule, LLVMGetModuleContext, LLVMModuleCreateWithNameInContext,
+            LLVMPositionBuilderAtEnd,
+        },
+        prelude::*,
+    };
+    use std::collections::HashMap;
+
+    /// Builds a circuit in a new function in its own module.
+    unsafe fn source(
+        context: LLVMContextRef,
+        build: impl FnOnce(LLVMBuilderRef),
+    ) -> (LLVMModuleRef, LLVMValueRef) {
+        let module = LLVMModuleCreateWithNameInContext(raw_cstr!("source"), context);
+        let function = entry_point(module, cstr!("source"), 2, 1, "custom", "");
+        let builder = LLVMCreateBuilderInContext(context);
+        LLVMPositionBuilderAtEnd(
+            builder,
+            LLVMAppendBasicBlockInContext(context, function, raw_cstr!("")),
+        );
+        build(builder);
+        LLVMDisposeBuilder(builder);
+        (module, function)
+    }
+
+    #[test]
+    fn function_is_appended() {
+        assert_reference_ir("builder/append_function", 3, 2, |builder| unsafe {
+            let context = LLVMGetModuleContext(builder_module(builder));
+            build_x(builder, qubit(context, 0));
+            let (module, function) = source(context, |builder| {
+                let module = builder_module(builder);
+                build_h(builder, qubit(context, 0));
+                build_cx(builder, qubit(context, 0), qubit(context, 1));
+                build_mz(builder, qubit(context, 1), result(context, 0));
+                build_if_result(
+                    builder,
+                    result(context, 0),
+                    || build_x(builder, qubit(context, 0)),
+                    || (),
+                );
+                build_result_record_output(
+                    builder,
+                    result(context, 0),
+                    global_string(module, b"r"),
+                );
+            });
+            let qubit_map = HashMap::from([(0, 2), (1, 0)]);
+            let result_map = HashMap::from([(0, 1)]);
+            append_function(builder, function, &qubit_map, &result_map).unwrap();
+            LLVMDisposeModule(module);
+            build_h(builder, qubit(context, 1));
+        });
+    }
+
+    #[test]
+    fn unmapped_qubit() {
+        let context = Context::new();
+        let module = Module::new(&context, cstr!("test"));
+        let builder = Builder::new(&context);
+        unsafe {
+            let function = entry_point(module.as_ptr(), cstr!("main"), 2, 1, "custom", "");
+            let block = LLVMAppendBasicBlockInContext(context.as_ptr(), function, raw_cstr!(""));
+            LLVMPositionBuilderAtEnd(builder.as_ptr(), block);
+            let (source_module, source) = source(context.as_ptr(), |builder| {
+                build_cx(
+                    builder,
+                    qubit(context.as_ptr(), 0),
+                    qubit(context.as_ptr(), 1),
+                );
+            });
+            let qubit_map = HashMap::from([(0, 1)]);
+            let error =
+                append_function(builder.as_ptr(), source, &qubit_map, &HashMap::new()).unwrap_err();
+            LLVMDisposeModule(source_module);
+            assert_eq!(error, "Qubit 1 isn't in the qubit map.");
+        }
+    }
+}
@@ -0,0 +1,268 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use crate::{types, values};
+use llvm_sys::{
+    core::{
+        LLVMGetFirstInstruction, LLVMGetInstructionParent, LLVMGetNextInstruction,
+        LLVMGetNumOperands, LLVMGetOperand, LLVMIsACallInst, LLVMIsAInstruction,
+        LLVMIsATerminatorInst, LLVMTypeOf,
+    },
+    prelude::*,
+};
+use std::{
+    cmp::Reverse,
+    collections::{BTreeSet, BinaryHeap, HashMap},
+};
+
+#[derive(Clone, Copy, Eq, Hash, PartialEq)]
+enum Resource {
+    Qubit(u64),
+    Result(u64),
+}
+
+/// A directed acyclic graph of the instructions in a basic block, where an edge from one
+/// instruction to another means that the first must run before the second.
+///
+/// An instruction depends on:
+///
+/// - The instructions in the block that define its operands.
+/// - The last instruction before it that uses any of the same static qubits or results.
+/// - The last barrier before it.
+///
+/// A barrier is an instruction that every instruction before it must run before, and every
+/// instruction after it must run after. Terminators, instructions with a qubit or result operand
+/// that isn't a static ID, and calls with no qubit or result operands are barriers. Calls like
+/// output recording are ordered with respect to each other this way, since their side effects
+/// aren't visible through their operands.
+pub struct Dag {
+    nodes: Vec<LLVMValueRef>,
+    predecessors: Vec<BTreeSet<usize>>,
+    successors: Vec<BTreeSet<usize>>,
+}
+
+impl Dag {
+    /// Builds the graph for the instructions in the block.
+    pub unsafe fn from_block(block: LLVMBasicBlockRef) -> Self {
+        let mut dag = Self {
+            nodes: Vec::new(),
+            predecessors: Vec::new(),
+            successors: Vec::new(),
+        };
+        let mut indices = HashMap::new();
+        let mut last_use = HashMap::new();
+        let mut since_barrier = Vec::new();
+        let mut last_barrier = None;
+
+        let mut instruction = LLVMGetFirstInstruction(block);
+        while !instruction.is_null() {
+            let node = dag.nodes.len();
+            dag.nodes.push(instruction);
+            dag.predecessors.push(BTreeSet::new());
+            dag.successors.push(BTreeSet::new());
+            indices.insert(instruction, node);
+
+            let mut resources = Vec::new();
+            let mut is_barrier = !LLVMIsATerminatorInst(instruction).is_null();
+            let mut has_resource_operand = false;
+            for i in 0..LLVMGetNumOperands(instruction) {
+                let operand = LLVMGetOperand(instruction, i.try_into().unwrap());
+                if !LLVMIsAInstruction(operand).is_null()
+                    && LLVMGetInstructionParent(operand) == block
+                {
+                    dag.add_edge(indices[&operand], node);
+                }
+
+                let ty = LLVMTypeOf(operand);
+                if types::is_qubit(ty) {
+                    has_resource_operand = true;
+                    match values::qubit_id(operand) {
+                        Some(id) => resources.push(Resource::Qubit(id)),
+                        None => is_barrier = true,
+                    }
+                } else if types::is_result(ty) {
+                    has_resource_operand = true;
+                    match values::result_id(operand) {
+                        Some(id) => resources.push(Resource::Result(id)),
+                        None => is_barrier = true,
+                    }
+                }
+            }
+
+            if !LLVMIsACallInst(instruction).is_null() && !has_resource_operand {
+                is_barrier = true;
+            }
+
+            if let Some(barrier) = last_barrier {
+                dag.add_edge(barrier, node);
+            }
+
+            if is_barrier {
+                for &before in &since_barrier {
+                    dag.add_edge(before, node);
+                }
+                since_barrier.clear();
+                last_use.clear();
+                last_barrier = Some(node);
+            } else {
+                for resource in resources {
+                    if let Some(before) = last_use.insert(resource, node) {
+                        dag.add_edge(before, node);
+                    }
+                }
+                since_barrier.push(node);
+            }
+
+            instruction = LLVMGetNextInstruction(instruction);
+        }
+
+        dag
+    }
+
+    /// The instructions in the graph, indexed by node, in their original order.
+    #[must_use]
+    pub fn nodes(&self) -> &[LLVMValueRef] {
+        &self.nodes
+    }
+
+    /// The nodes that must run before the node.
+    pub fn predecessors(&self, node: usize) -> impl Iterator<Item = usize> + '_ {
+        self.predecessors[node].iter().copied()
+    }
+
+    /// The nodes that must run after the node.
+    pub fn successors(&self, node: usize) -> impl Iterator<Item = usize> + '_ {
+        self.successors[node].iter().copied()
+    }
+
+    /// Orders the instructions so that every instruction comes after the instructions it depends
+    /// on. Among instructions that are ready to run at the same time, the one that came first in
+    /// the original block goes first, so the original order is kept if it is still valid.
+    #[must_use]
+    pub fn topological_order(&self) -> Vec<LLVMValueRef> {
+        let mut in_degree: Vec<_> = self.predecessors.iter().map(BTreeSet::len).collect();
+        let mut ready: BinaryHeap<_> = (0..self.nodes.len())
+            .filter(|&node| in_degree[node] == 0)
+            .map(Reverse)
+            .collect();
+        let mut order = Vec::with_capacity(self.nodes.len());
+
+        while let Some(Reverse(node)) = ready.pop() {
+            order.push(self.nodes[node]);
+            for successor in self.successors(node) {
+                in_degree[successor] -= 1;
+                if in_degree[successor] == 0 {
+                    ready.push(Reverse(successor));
+                }
+            }
+        }
+
+        order
+    }
+
+    fn add_edge(&mut self, from: usize, to: usize) {
+        self.successors[from].insert(to);
+        self.predecessors[to].insert(from);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Dag;
+    use crate::{
+        qis::{build_barrier, build_cx, build_h, build_mz, build_x},
+        tests::{Builder, Context, Module},
+        values::{entry_point, qubit, result},
+    };
+    use const_str::{cstr, raw_cstr};
+    use llvm_sys::{
+        core::{LLVMAppendBasicBlockInContext, LLVMBuildRetVoid, LLVMPositionBuilderAtEnd},
+        prelude::*,
+    };
+
+    fn assert_valid_order(dag: &Dag, order: &[LLVMValueRef]) {
+        assert_eq!(order.len(), dag.nodes().len());
+        let position = |node: usize| order.iter().position(|&i| i == dag.nodes()[node]);
+        for node in 0..dag.nodes().len() {
+            let after = position(node).expect("Instruction is missing from the order.");
+            for predecessor in dag.predecessors(node) {
+                assert!(position(predecessor).unwrap() < after);
+            }
+        }
+    }
+
+    fn with_block(build: impl FnOnce(&Context, LLVMBuilderRef, LLVMBasicBlockRef)) {
+        let context = Context::new();
+        let module = Module::new(&context, cstr!("test"));
+        let builder = Builder::new(&context);
+        unsafe {
+            let function = entry_point(module.as_ptr(), cstr!("main"), 2, 1, "custom", "");
+            let block = LLVMAppendBasicBlockInContext(context.as_ptr(), function, raw_cstr!(""));
+            LLVMPositionBuilderAtEnd(builder.as_ptr(), block);
+            build(&context, builder.as_ptr(), block);
+        }
+    }
+
+    #[test]
+    fn edges_follow_qubits_and_results() {
+        with_block(|context, builder, block| unsafe {
+            let q0 = qubit(context.as_ptr(), 0);
+            let q1 = qubit(context.as_ptr(), 1);
+            let r0 = result(context.as_ptr(), 0);
+            build_h(builder, q0);
+            build_x(builder, q1);
+            build_cx(builder, q0, q1);
+            build_mz(builder, q0, r0);
+            build_h(builder, q1);
+            LLVMBuildRetVoid(builder);
+
+            let dag = Dag::from_block(block);
+            let predecessors = |node| dag.predecessors(node).collect::<Vec<_>>();
+            assert!(predecessors(0).is_empty());
+            assert!(predecessors(1).is_empty());
+            assert_eq!(predecessors(2), [0, 1]);
+            assert_eq!(predecessors(3), [2]);
+            assert_eq!(predecessors(4), [2]);
+            assert_eq!(predecessors(5), [0, 1, 2, 3, 4]);
+        });
+    }
+
+    #[test]
+    fn barriers_order_everything() {
+        with_block(|context, builder, block| unsafe {
+            let q0 = qubit(context.as_ptr(), 0);
+            let q1 = qubit(context.as_ptr(), 1);
+            build_h(builder, q0);
+            build_barrier(builder);
+            build_x(builder, q1);
+            LLVMBuildRetVoid(builder);
+
+            let dag = Dag::from_block(block);
+            assert_eq!(dag.predecessors(1).collect::<Vec<_>>(), [0]);
+            assert_eq!(dag.predecessors(2).collect::<Vec<_>>(), [1]);
+            assert_eq!(dag.successors(0).collect::<Vec<_>>(), [1]);
+        });
+    }
+
+    #[test]
+    fn topological_order_is_valid() {
+        with_block(|context, builder, block| unsafe {
+            let q0 = qubit(context.as_ptr(), 0);
+            let q1 = qubit(context.as_ptr(), 1);
+            let r0 = result(context.as_ptr(), 0);
+            build_x(builder, q1);
+            build_h(builder, q0);
+            build_cx(builder, q1, q0);
+            build_h(builder, q1);
+            build_mz(builder, q0, r0);
+            build_barrier(builder);
+            build_x(builder, q0);
+            LLVMBuildRetVoid(builder);
+
+            let dag = Dag::from_block(block);
+            let order = dag.topological_order();
+            assert_valid_order(&dag, &order);
+            assert_eq!(order, dag.nodes());
+        });
+    }
+}
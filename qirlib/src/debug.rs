@@ -0,0 +1,166 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use crate::{
+    module::{add_flag, FlagBehavior},
+    utils::builder_module,
+};
+use llvm_sys::{
+    core::{
+        LLVMConstInt, LLVMGetFirstFunction, LLVMGetModuleContext, LLVMGetNextFunction,
+        LLVMGetValueName2, LLVMInt32TypeInContext, LLVMIsDeclaration, LLVMSetCurrentDebugLocation2,
+        LLVMValueAsMetadata,
+    },
+    debuginfo::{
+        LLVMCreateDIBuilder, LLVMDIBuilderCreateCompileUnit, LLVMDIBuilderCreateDebugLocation,
+        LLVMDIBuilderCreateFile, LLVMDIBuilderCreateFunction, LLVMDIBuilderCreateSubroutineType,
+        LLVMDIBuilderFinalize, LLVMDIFlags, LLVMDWARFEmissionKind, LLVMDWARFSourceLanguage,
+        LLVMDebugMetadataVersion, LLVMDisposeDIBuilder, LLVMGetSubprogram, LLVMSetSubprogram,
+    },
+    prelude::{LLVMBuilderRef, LLVMMetadataRef, LLVMModuleRef, LLVMValueRef},
+};
+use std::ptr::{self, NonNull};
+
+/// Adds a compile unit describing the source file that generated the module to `llvm.dbg.cu`,
+/// along with the "Debug Info Version" module flag that LLVM requires for the debug info to be
+/// kept. Every function defined in the module is given a subprogram in the compile unit, which is
+/// the scope for debug locations in that function.
+pub unsafe fn add_compile_unit(
+    module: LLVMModuleRef,
+    filename: &str,
+    directory: &str,
+    producer: &str,
+) -> LLVMMetadataRef {
+    let builder = LLVMCreateDIBuilder(module);
+    let file = LLVMDIBuilderCreateFile(
+        builder,
+        filename.as_ptr().cast(),
+        filename.len(),
+        directory.as_ptr().cast(),
+        directory.len(),
+    );
+    let compile_unit = LLVMDIBuilderCreateCompileUnit(
+        builder,
+        LLVMDWARFSourceLanguage::LLVMDWARFSourceLanguagePython,
+        file,
+        producer.as_ptr().cast(),
+        producer.len(),
+        0,
+        ptr::null(),
+        0,
+        0,
+        ptr::null(),
+        0,
+        LLVMDWARFEmissionKind::LLVMDWARFEmissionKindLineTablesOnly,
+        0,
+        0,
+        0,
+        ptr::null(),
+        0,
+        ptr::null(),
+        0,
+    );
+
+    let subroutine_type = LLVMDIBuilderCreateSubroutineType(
+        builder,
+        file,
+        ptr::null_mut(),
+        0,
+        LLVMDIFlags::default(),
+    );
+    let mut function = LLVMGetFirstFunction(module);
+    while !function.is_null() {
+        if LLVMIsDeclaration(function) == 0 {
+            let mut name_len = 0;
+            let name = LLVMGetValueName2(function, &mut name_len);
+            let subprogram = LLVMDIBuilderCreateFunction(
+                builder,
+                compile_unit,
+                name,
+                name_len,
+                name,
+                name_len,
+                file,
+                0,
+                subroutine_type,
+                0,
+                1,
+                0,
+                LLVMDIFlags::default(),
+                0,
+            );
+            LLVMSetSubprogram(function, subprogram);
+        }
+        function = LLVMGetNextFunction(function);
+    }
+
+    LLVMDIBuilderFinalize(builder);
+    LLVMDisposeDIBuilder(builder);
+
+    let context = LLVMGetModuleContext(module);
+    let version = LLVMConstInt(
+        LLVMInt32TypeInContext(context),
+        LLVMDebugMetadataVersion().into(),
+        0,
+    );
+    add_flag(
+        module,
+        FlagBehavior::Warning,
+        "Debug Info Version",
+        LLVMValueAsMetadata(version),
+    );
+
+    compile_unit
+}
+
+/// The subprogram attached to a function, if it has one.
+pub unsafe fn subprogram(function: LLVMValueRef) -> Option<LLVMMetadataRef> {
+    NonNull::new(LLVMGetSubprogram(function)).map(NonNull::as_ptr)
+}
+
+/// Attaches a source location to the instructions that the builder inserts from now on.
+pub unsafe fn set_debug_location(
+    builder: LLVMBuilderRef,
+    line: u32,
+    column: u32,
+    scope: LLVMMetadataRef,
+) {
+    let context = LLVMGetModuleContext(builder_module(builder));
+    let location = LLVMDIBuilderCreateDebugLocation(context, line, column, scope, ptr::null_mut());
+    LLVMSetCurrentDebugLocation2(builder, location);
+}
+
+/// Stops attaching a source location to the instructions that the builder inserts.
+pub unsafe fn clear_debug_location(builder: LLVMBuilderRef) {
+    LLVMSetCurrentDebugLocation2(builder, ptr::null_mut());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{add_compile_unit, clear_debug_location, set_debug_location, subprogram};
+    use crate::{qis::build_x, tests::assert_reference_ir, utils::builder_module, values::qubit};
+    use llvm_sys::core::{LLVMGetBasicBlockParent, LLVMGetInsertBlock, LLVMGetModuleContext};
+
+    #[test]
+    fn compile_unit() {
+        assert_reference_ir("debug/compile_unit", 0, 0, |builder| unsafe {
+            add_compile_unit(builder_module(builder), "program.py", "/home/user", "pyqir");
+        });
+    }
+
+    #[test]
+    fn debug_location() {
+        assert_reference_ir("debug/debug_location", 1, 0, |builder| unsafe {
+            add_compile_unit(builder_module(builder), "program.py", "/home/user", "pyqir");
+            let function = LLVMGetBasicBlockParent(LLVMGetInsertBlock(builder));
+            let scope = subprogram(function).unwrap();
+            let context = LLVMGetModuleContext(builder_module(builder));
+            set_debug_location(builder, 3, 0, scope);
+            build_x(builder, qubit(context, 0));
+            set_debug_location(builder, 5, 4, scope);
+            build_x(builder, qubit(context, 0));
+            clear_debug_location(builder);
+            build_x(builder, qubit(context, 0));
+        });
+    }
+}
@@ -17,20 +17,29 @@ extern crate llvm_sys_130 as llvm_sys;
 #[cfg(feature = "llvm14-0")]
 extern crate llvm_sys_140 as llvm_sys;
 
+#[cfg(not(feature = "no-llvm-linking"))]
+pub mod analysis;
 #[cfg(not(feature = "no-llvm-linking"))]
 pub mod builder;
 #[cfg(not(feature = "no-llvm-linking"))]
+pub mod dag;
+#[cfg(not(feature = "no-llvm-linking"))]
+pub mod debug;
+#[cfg(not(feature = "no-llvm-linking"))]
 pub(crate) mod llvm_wrapper;
 #[cfg(not(feature = "no-llvm-linking"))]
 pub mod metadata;
 #[cfg(not(feature = "no-llvm-linking"))]
 pub mod module;
 #[cfg(not(feature = "no-llvm-linking"))]
+pub mod passes;
+#[cfg(not(feature = "no-llvm-linking"))]
 pub mod qis;
 #[cfg(not(feature = "no-llvm-linking"))]
 pub mod rt;
 #[cfg(all(test, not(feature = "no-llvm-linking")))]
 mod tests;
+pub mod text;
 #[cfg(not(feature = "no-llvm-linking"))]
 pub mod types;
 #[cfg(not(feature = "no-llvm-linking"))]
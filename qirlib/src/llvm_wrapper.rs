@@ -1,7 +1,10 @@
 // Copyright (c) Microsoft Corporation.
 // Licensed under the MIT License.
 
-use llvm_sys::prelude::{LLVMMetadataRef, LLVMModuleRef, LLVMValueRef};
+use llvm_sys::prelude::{
+    LLVMBool, LLVMBuilderRef, LLVMContextRef, LLVMMetadataRef, LLVMModuleRef, LLVMValueRef,
+};
+use std::ffi::c_char;
 
 #[repr(C)]
 #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
@@ -27,4 +30,33 @@ extern "C" {
         Val: LLVMMetadataRef,
     );
     pub fn LLVMRustExtractMDConstant(Val: LLVMValueRef) -> LLVMValueRef;
+    /// Set the module's target triple. Fails if the triple's architecture is unknown to LLVM.
+    pub fn LLVMRustSetTargetTriple(
+        M: LLVMModuleRef,
+        Triple: *const c_char,
+        ErrorMessage: *mut *mut c_char,
+    ) -> LLVMBool;
+    /// Set the module's data layout. Fails if the data layout string can't be parsed.
+    pub fn LLVMRustSetDataLayout(
+        M: LLVMModuleRef,
+        DataLayout: *const c_char,
+        ErrorMessage: *mut *mut c_char,
+    ) -> LLVMBool;
+    /// Replace the operands of each instruction that are in `From` with the corresponding values in
+    /// `To`. Functions and global variables from another module are replaced with equivalents in
+    /// the destination module, which are declared or copied if they don't exist yet.
+    pub fn LLVMRustRemapInstructions(
+        Instructions: *mut LLVMValueRef,
+        NumInstructions: usize,
+        From: *mut LLVMValueRef,
+        To: *mut LLVMValueRef,
+        NumValues: usize,
+        Dest: LLVMModuleRef,
+    );
+    /// Whether the builder inserts at the end of its block. The builder must have a block.
+    pub fn LLVMRustBuilderIsAtEnd(Builder: LLVMBuilderRef) -> LLVMBool;
+    /// Make every pointer type in the context opaque. Must be called before any pointer types are
+    /// created in the context.
+    #[cfg(feature = "llvm14-0")]
+    pub fn LLVMRustContextEnableOpaquePointers(C: LLVMContextRef);
 }
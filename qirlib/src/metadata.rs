@@ -1,9 +1,12 @@
 // Copyright (c) Microsoft Corporation.
 // Licensed under the MIT License.
 
-use llvm_sys::prelude::LLVMValueRef;
-
 use crate::llvm_wrapper::LLVMRustExtractMDConstant;
+#[allow(clippy::wildcard_imports)]
+use llvm_sys::{core::*, prelude::*};
+use std::{ptr, slice, str};
+
+const ANNOTATIONS_KIND: &str = "qir.annotations";
 
 pub unsafe fn extract_constant(value: LLVMValueRef) -> Option<LLVMValueRef> {
     let constant_value = LLVMRustExtractMDConstant(value);
@@ -13,3 +16,132 @@ pub unsafe fn extract_constant(value: LLVMValueRef) -> Option<LLVMValueRef> {
         Some(constant_value)
     }
 }
+
+/// Adds key-value annotations to an instruction. The annotations are kept in a single
+/// `!qir.annotations` node on the instruction, which holds a `!{!"key", value}` pair for each key.
+/// An annotation replaces any existing annotation with the same key.
+pub unsafe fn annotate(instruction: LLVMValueRef, annotations: &[(&str, LLVMMetadataRef)]) {
+    if annotations.is_empty() {
+        return;
+    }
+
+    let context = LLVMGetTypeContext(LLVMTypeOf(instruction));
+    let mut entries = self::annotations(instruction);
+    for &(key, value) in annotations {
+        match entries.iter_mut().find(|(k, _)| k == key) {
+            Some(entry) => entry.1 = value,
+            None => entries.push((key.to_string(), value)),
+        }
+    }
+
+    let mut pairs: Vec<_> = entries
+        .iter()
+        .map(|(key, value)| {
+            let mut pair = [
+                LLVMMDStringInContext2(context, key.as_ptr().cast(), key.len()),
+                *value,
+            ];
+            LLVMMDNodeInContext2(context, pair.as_mut_ptr(), pair.len())
+        })
+        .collect();
+    let node = LLVMMDNodeInContext2(context, pairs.as_mut_ptr(), pairs.len());
+    LLVMSetMetadata(
+        instruction,
+        annotations_kind(context),
+        LLVMMetadataAsValue(context, node),
+    );
+}
+
+/// The key-value annotations on an instruction, in the order they were first added.
+pub unsafe fn annotations(instruction: LLVMValueRef) -> Vec<(String, LLVMMetadataRef)> {
+    let context = LLVMGetTypeContext(LLVMTypeOf(instruction));
+    let node = LLVMGetMetadata(instruction, annotations_kind(context));
+    if node.is_null() {
+        return Vec::new();
+    }
+
+    node_operands(node)
+        .into_iter()
+        .filter_map(|pair| match node_operands(pair)[..] {
+            [key, value] => {
+                let mut len = 0;
+                let key = LLVMGetMDString(key, &mut len);
+                if key.is_null() {
+                    None
+                } else {
+                    let key =
+                        str::from_utf8(slice::from_raw_parts(key.cast(), len.try_into().unwrap()));
+                    Some((key.ok()?.to_string(), LLVMValueAsMetadata(value)))
+                }
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+unsafe fn annotations_kind(context: LLVMContextRef) -> u32 {
+    LLVMGetMDKindIDInContext(
+        context,
+        ANNOTATIONS_KIND.as_ptr().cast(),
+        ANNOTATIONS_KIND.len().try_into().unwrap(),
+    )
+}
+
+unsafe fn node_operands(node: LLVMValueRef) -> Vec<LLVMValueRef> {
+    if LLVMIsAMDNode(node).is_null() {
+        return Vec::new();
+    }
+
+    let len = LLVMGetMDNodeNumOperands(node).try_into().unwrap();
+    let mut operands = vec![ptr::null_mut(); len];
+    LLVMGetMDNodeOperands(node, operands.as_mut_ptr());
+    operands
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{annotate, annotations, extract_constant};
+    use crate::{qis::build_x, tests::assert_reference_ir, utils::builder_module, values::qubit};
+    use llvm_sys::core::{
+        LLVMConstReal, LLVMDoubleTypeInContext, LLVMGetInsertBlock, LLVMGetLastInstruction,
+        LLVMGetMDString, LLVMGetModuleContext, LLVMMDStringInContext2, LLVMMetadataAsValue,
+        LLVMValueAsMetadata,
+    };
+    use std::slice;
+
+    #[test]
+    fn annotations_round_trip() {
+        assert_reference_ir("metadata/annotations", 1, 0, |builder| unsafe {
+            let context = LLVMGetModuleContext(builder_module(builder));
+            build_x(builder, qubit(context, 0));
+            let instruction = LLVMGetLastInstruction(LLVMGetInsertBlock(builder));
+
+            let noise = LLVMValueAsMetadata(LLVMConstReal(LLVMDoubleTypeInContext(context), 0.5));
+            let gate = LLVMMDStringInContext2(context, "x".as_ptr().cast(), 1);
+            annotate(instruction, &[("noise", noise), ("gate", gate)]);
+            let gate = LLVMMDStringInContext2(context, "not".as_ptr().cast(), 3);
+            annotate(instruction, &[("gate", gate)]);
+
+            let entries = annotations(instruction);
+            let keys: Vec<_> = entries.iter().map(|(key, _)| key.as_str()).collect();
+            assert_eq!(keys, ["noise", "gate"]);
+            assert!(extract_constant(LLVMMetadataAsValue(context, entries[0].1)).is_some());
+            let mut len = 0;
+            let gate = LLVMGetMDString(LLVMMetadataAsValue(context, entries[1].1), &mut len);
+            assert_eq!(
+                slice::from_raw_parts(gate.cast::<u8>(), len.try_into().unwrap()),
+                b"not"
+            );
+        });
+    }
+
+    #[test]
+    fn no_annotations() {
+        assert_reference_ir("qis/x", 1, 0, |builder| unsafe {
+            let context = LLVMGetModuleContext(builder_module(builder));
+            build_x(builder, qubit(context, 0));
+            let instruction = LLVMGetLastInstruction(LLVMGetInsertBlock(builder));
+            assert!(annotations(instruction).is_empty());
+        });
+    }
+}
@@ -3,14 +3,27 @@
 
 use llvm_sys::{
     core::{
-        LLVMConstInt, LLVMConstIntGetZExtValue, LLVMGetModuleContext, LLVMGetModuleFlag,
-        LLVMInt1TypeInContext, LLVMInt32TypeInContext, LLVMMetadataAsValue, LLVMValueAsMetadata,
+        LLVMConstInt, LLVMConstIntGetZExtValue, LLVMCreatePassManager, LLVMDisposeMessage,
+        LLVMDisposePassManager, LLVMGetModuleContext, LLVMGetModuleFlag, LLVMInt1TypeInContext,
+        LLVMInt32TypeInContext, LLVMMetadataAsValue, LLVMRunPassManager, LLVMValueAsMetadata,
     },
     prelude::{LLVMMetadataRef, LLVMModuleRef},
+    transforms::pass_manager_builder::{
+        LLVMPassManagerBuilderCreate, LLVMPassManagerBuilderDispose,
+        LLVMPassManagerBuilderPopulateModulePassManager, LLVMPassManagerBuilderSetOptLevel,
+    },
+};
+
+use std::{
+    ffi::{c_char, CStr, CString},
+    ptr,
 };
 
 use crate::{
-    llvm_wrapper::{LLVMRustAddModuleFlag, LLVMRustModFlagBehavior},
+    llvm_wrapper::{
+        LLVMRustAddModuleFlag, LLVMRustModFlagBehavior, LLVMRustSetDataLayout,
+        LLVMRustSetTargetTriple,
+    },
     metadata::extract_constant,
 };
 
@@ -142,3 +155,86 @@ pub unsafe fn add_flag(
         md,
     );
 }
+
+/// Runs LLVM's standard module optimization pipeline for the optimization level, from 0 to 3, on
+/// the module. Every function defined in the module must be well-formed, including having a
+/// terminator at the end of each basic block.
+pub unsafe fn optimize(module: LLVMModuleRef, level: u32) {
+    let builder = LLVMPassManagerBuilderCreate();
+    LLVMPassManagerBuilderSetOptLevel(builder, level);
+    let pass_manager = LLVMCreatePassManager();
+    LLVMPassManagerBuilderPopulateModulePassManager(builder, pass_manager);
+    LLVMPassManagerBuilderDispose(builder);
+    LLVMRunPassManager(pass_manager, module);
+    LLVMDisposePassManager(pass_manager);
+}
+
+/// Sets the target triple of the module, or removes it if the triple is empty. Fails with LLVM's
+/// error message if the architecture in the triple is unknown.
+pub unsafe fn set_target_triple(module: LLVMModuleRef, triple: &str) -> Result<(), String> {
+    let triple = CString::new(triple).map_err(|e| e.to_string())?;
+    let mut error = ptr::null_mut();
+    if LLVMRustSetTargetTriple(module, triple.as_ptr(), &mut error) == 0 {
+        Ok(())
+    } else {
+        Err(take_message(error))
+    }
+}
+
+/// Sets the data layout of the module. An empty data layout is LLVM's default layout. Fails with
+/// LLVM's error message if the data layout can't be parsed.
+pub unsafe fn set_data_layout(module: LLVMModuleRef, data_layout: &str) -> Result<(), String> {
+    let data_layout = CString::new(data_layout).map_err(|e| e.to_string())?;
+    let mut error = ptr::null_mut();
+    if LLVMRustSetDataLayout(module, data_layout.as_ptr(), &mut error) == 0 {
+        Ok(())
+    } else {
+        Err(take_message(error))
+    }
+}
+
+unsafe fn take_message(message: *mut c_char) -> String {
+    let string = CStr::from_ptr(message).to_string_lossy().into_owned();
+    LLVMDisposeMessage(message);
+    string
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{set_data_layout, set_target_triple};
+    use crate::tests::{Context, Module};
+    use const_str::cstr;
+    use llvm_sys::core::{LLVMGetDataLayoutStr, LLVMGetTarget};
+    use std::ffi::CStr;
+
+    #[test]
+    fn target() {
+        let context = Context::new();
+        let module = Module::new(&context, cstr!("test"));
+        unsafe {
+            set_target_triple(module.as_ptr(), "x86_64-unknown-linux-gnu").unwrap();
+            set_data_layout(module.as_ptr(), "e-m:e-i64:64-n8:16:32:64-S128").unwrap();
+            let triple = CStr::from_ptr(LLVMGetTarget(module.as_ptr()));
+            assert_eq!(triple.to_str(), Ok("x86_64-unknown-linux-gnu"));
+            let data_layout = CStr::from_ptr(LLVMGetDataLayoutStr(module.as_ptr()));
+            assert_eq!(data_layout.to_str(), Ok("e-m:e-i64:64-n8:16:32:64-S128"));
+
+            set_target_triple(module.as_ptr(), "").unwrap();
+            assert!(CStr::from_ptr(LLVMGetTarget(module.as_ptr())).is_empty());
+        }
+    }
+
+    #[test]
+    fn invalid_target() {
+        let context = Context::new();
+        let module = Module::new(&context, cstr!("test"));
+        unsafe {
+            assert_eq!(
+                set_target_triple(module.as_ptr(), "qpu-unknown-unknown"),
+                Err("Unknown architecture in target triple: qpu-unknown-unknown".to_string())
+            );
+            assert!(set_data_layout(module.as_ptr(), "e-i64:sixty-four").is_err());
+            assert!(CStr::from_ptr(LLVMGetTarget(module.as_ptr())).is_empty());
+        }
+    }
+}
@@ -0,0 +1,681 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use crate::{
+    qis::{declare_mz_batch, Intrinsic},
+    types,
+    utils::build_call,
+    values,
+};
+use const_str::raw_cstr;
+#[allow(clippy::wildcard_imports)]
+use llvm_sys::{core::*, debuginfo::LLVMInstructionGetDebugLoc, prelude::*, LLVMLinkage};
+use std::{
+    collections::{HashMap, HashSet},
+    f64::consts::PI,
+    ffi::CString,
+    hash::BuildHasher,
+    iter,
+    ptr::NonNull,
+    slice,
+};
+
+struct Measurement {
+    index: usize,
+    result: LLVMValueRef,
+}
+
+/// Moves every measurement in the function to the end of the circuit, after the last instruction
+/// that uses a qubit, keeping the measurements in their original order. This is only done if it
+/// doesn't change the meaning of the program, which requires that:
+///
+/// - The function has at most one basic block.
+/// - Every qubit is a static qubit, and no qubit is used after it is measured.
+/// - Every instruction that reads a measured result stays on the same side of the measurement.
+///
+/// Otherwise the function is left unchanged and an error describing the problem is returned.
+pub unsafe fn defer_measurements(function: LLVMValueRef) -> Result<(), String> {
+    let block = match LLVMCountBasicBlocks(function) {
+        0 => return Ok(()),
+        1 => LLVMGetFirstBasicBlock(function),
+        _ => return Err("Measurements can't be deferred past control flow.".to_string()),
+    };
+
+    let instructions: Vec<_> =
+        iter::successors(NonNull::new(LLVMGetFirstInstruction(block)), |i| {
+            NonNull::new(LLVMGetNextInstruction(i.as_ptr()))
+        })
+        .map(NonNull::as_ptr)
+        .filter(|&i| LLVMIsATerminatorInst(i).is_null())
+        .collect();
+
+    let mut measured = HashSet::new();
+    let mut measurements = Vec::new();
+    let mut readers = Vec::new();
+    let mut last_gate = None;
+    for (index, &instruction) in instructions.iter().enumerate() {
+        let operands: Vec<_> = (0..LLVMGetNumOperands(instruction))
+            .map(|i| LLVMGetOperand(instruction, i.try_into().unwrap()))
+            .collect();
+        let mut qubits = Vec::new();
+        for &operand in &operands {
+            if types::is_qubit(LLVMTypeOf(operand)) {
+                let id = values::qubit_id(operand).ok_or_else(|| {
+                    "Measurements can't be deferred with dynamic qubits.".to_string()
+                })?;
+                if measured.contains(&id) {
+                    return Err(format!("Qubit {id} is used after it is measured."));
+                }
+                qubits.push(id);
+            }
+        }
+
+        if let Some(result) = measurement(instruction) {
+            measured.extend(qubits);
+            measurements.push(Measurement { index, result });
+        } else {
+            if !qubits.is_empty() {
+                last_gate = Some(index);
+            }
+            let results: Vec<_> = operands
+                .into_iter()
+                .filter(|&o| types::is_result(LLVMTypeOf(o)))
+                .collect();
+            if !results.is_empty() {
+                readers.push((index, results));
+            }
+        }
+    }
+
+    // The measurements are moved to just after the last gate. Readers after the last gate end up
+    // after every measurement, and the other readers end up before every measurement, so each
+    // reader must already be on that side of the measurements of the results it reads.
+    for (index, results) in &readers {
+        let after_last_gate = last_gate.map_or(true, |gate| *index > gate);
+        for measurement in &measurements {
+            if results.contains(&measurement.result)
+                && (measurement.index < *index) != after_last_gate
+            {
+                return Err(match values::result_id(measurement.result) {
+                    Some(id) => format!("Result {id} is read before the end of the circuit."),
+                    None => "A measurement result is read before the end of the circuit.".into(),
+                });
+            }
+        }
+    }
+
+    let target = instructions
+        .iter()
+        .enumerate()
+        .skip(last_gate.map_or(0, |gate| gate + 1))
+        .find(|&(_, &i)| measurement(i).is_none())
+        .map(|(_, &i)| i)
+        .or_else(|| NonNull::new(LLVMGetBasicBlockTerminator(block)).map(NonNull::as_ptr));
+
+    let builder = LLVMCreateBuilderInContext(LLVMGetTypeContext(LLVMTypeOf(function)));
+    for measurement in &measurements {
+        let instruction = instructions[measurement.index];
+        match target {
+            Some(target) => LLVMPositionBuilderBefore(builder, target),
+            None => LLVMPositionBuilderAtEnd(builder, block),
+        }
+        // Inserting an instruction gives it the builder's debug location and name, so keep the
+        // instruction's own.
+        LLVMSetCurrentDebugLocation2(builder, LLVMInstructionGetDebugLoc(instruction));
+        let mut len = 0;
+        let name = LLVMGetValueName2(instruction, &mut len);
+        let name = CString::new(slice::from_raw_parts(name.cast::<u8>(), len)).unwrap();
+        LLVMInstructionRemoveFromParent(instruction);
+        LLVMInsertIntoBuilderWithName(builder, instruction, name.as_ptr());
+    }
+    LLVMDisposeBuilder(builder);
+    Ok(())
+}
+
+/// Replaces the contiguous run of `mz` calls at the end of the function's last basic block with a
+/// single call to `__quantum__qis__mz_batch__body`. The batched call takes the number of
+/// measurements and pointers to constant arrays of the qubits and the results they're written to.
+///
+/// The run ends at the last instruction in the block that uses a qubit, and every qubit and result
+/// in it must be static and distinct. Runs with fewer than two measurements are left unchanged.
+/// Returns whether the measurements were batched.
+pub unsafe fn batch_measurements(function: LLVMValueRef) -> bool {
+    let block = LLVMGetLastBasicBlock(function);
+    if block.is_null() {
+        return false;
+    }
+
+    let instructions: Vec<_> =
+        iter::successors(NonNull::new(LLVMGetFirstInstruction(block)), |i| {
+            NonNull::new(LLVMGetNextInstruction(i.as_ptr()))
+        })
+        .map(NonNull::as_ptr)
+        .collect();
+    let last = match instructions.iter().rposition(|&i| uses_qubit(i)) {
+        Some(last) => last,
+        None => return false,
+    };
+
+    let mut batch = Vec::new();
+    for &instruction in instructions[..=last].iter().rev() {
+        match static_mz(instruction) {
+            Some((qubit, result)) if !batch.iter().any(|&(_, q, r)| q == qubit || r == result) => {
+                batch.push((instruction, qubit, result));
+            }
+            _ => break,
+        }
+    }
+    if batch.len() < 2 {
+        return false;
+    }
+    batch.reverse();
+
+    let module = LLVMGetGlobalParent(function);
+    let context = LLVMGetModuleContext(module);
+    let count = LLVMConstInt(
+        LLVMInt64TypeInContext(context),
+        batch.len().try_into().unwrap(),
+        0,
+    );
+    let mut qubits: Vec<_> = batch.iter().map(|&(_, q, _)| q).collect();
+    let qubits = global_array(module, types::qubit(context), &mut qubits);
+    let mut results: Vec<_> = batch.iter().map(|&(_, _, r)| r).collect();
+    let results = global_array(module, types::result(context), &mut results);
+
+    let first = batch[0].0;
+    let builder = LLVMCreateBuilderInContext(context);
+    LLVMPositionBuilderBefore(builder, first);
+    LLVMSetCurrentDebugLocation2(builder, LLVMInstructionGetDebugLoc(first));
+    build_call(
+        builder,
+        declare_mz_batch(module),
+        &mut [count, qubits, results],
+    );
+    LLVMDisposeBuilder(builder);
+    for (instruction, _, _) in batch {
+        LLVMInstructionEraseFromParent(instruction);
+    }
+    true
+}
+
+/// Replaces the circuit in the function with its adjoint, which undoes it. The gates are reversed
+/// and each is replaced by its inverse, with rotation angles negated or adjusted. Instructions other than calls
+/// stay where they are, before the gates.
+///
+/// The function must have at most one basic block, and every call in it must be to a QIS gate.
+/// Measurements and resets aren't unitary, so circuits with them have no adjoint. Otherwise the
+/// function is left unchanged and an error describing the problem is returned.
+pub unsafe fn adjoint(function: LLVMValueRef) -> Result<(), String> {
+    let block = match LLVMCountBasicBlocks(function) {
+        0 => return Ok(()),
+        1 => LLVMGetFirstBasicBlock(function),
+        _ => return Err("Circuits with control flow don't have an adjoint.".to_string()),
+    };
+
+    let mut gates = Vec::new();
+    let instructions = iter::successors(NonNull::new(LLVMGetFirstInstruction(block)), |i| {
+        NonNull::new(LLVMGetNextInstruction(i.as_ptr()))
+    });
+    for instruction in instructions.map(NonNull::as_ptr) {
+        if LLVMIsACallInst(instruction).is_null() {
+            continue;
+        }
+
+        let callee = LLVMGetCalledValue(instruction);
+        match Intrinsic::from_function(callee) {
+            Some(Intrinsic::M | Intrinsic::Mz | Intrinsic::ReadResult) => {
+                return Err("Circuits with measurements don't have an adjoint.".to_string())
+            }
+            Some(Intrinsic::Reset) => {
+                return Err("Circuits with resets don't have an adjoint.".to_string())
+            }
+            Some(Intrinsic::Depolarize) => {
+                return Err("Circuits with noise don't have an adjoint.".to_string())
+            }
+            Some(intrinsic) => gates.push((instruction, intrinsic.adjoint().unwrap())),
+            None => {
+                return Err(format!(
+                    "Function {} has no adjoint.",
+                    function_name(callee)
+                ))
+            }
+        }
+    }
+
+    let module = LLVMGetGlobalParent(function);
+    let builder = LLVMCreateBuilderInContext(LLVMGetModuleContext(module));
+    match NonNull::new(LLVMGetBasicBlockTerminator(block)) {
+        Some(terminator) => LLVMPositionBuilderBefore(builder, terminator.as_ptr()),
+        None => LLVMPositionBuilderAtEnd(builder, block),
+    }
+    for &(gate, adjoint) in gates.iter().rev() {
+        LLVMSetCurrentDebugLocation2(builder, LLVMInstructionGetDebugLoc(gate));
+        let mut args: Vec<_> = (0..LLVMGetNumArgOperands(gate))
+            .map(|i| LLVMGetOperand(gate, i))
+            .collect();
+        match adjoint {
+            Intrinsic::Rx | Intrinsic::Ry | Intrinsic::Rz | Intrinsic::U1 => {
+                args[0] = LLVMBuildFNeg(builder, args[0], raw_cstr!(""));
+            }
+            Intrinsic::U2 => {
+                // u2(phi, lambda) is undone by u2(pi - lambda, -phi - pi).
+                let (phi, lambda) = (args[0], args[1]);
+                let pi = LLVMConstReal(LLVMTypeOf(phi), PI);
+                let neg_phi = LLVMBuildFNeg(builder, phi, raw_cstr!(""));
+                args[0] = LLVMBuildFSub(builder, pi, lambda, raw_cstr!(""));
+                args[1] = LLVMBuildFSub(builder, neg_phi, pi, raw_cstr!(""));
+            }
+            _ => {}
+        }
+        build_call(builder, adjoint.declare(module), &mut args);
+    }
+    LLVMDisposeBuilder(builder);
+
+    for (gate, _) in gates {
+        LLVMInstructionEraseFromParent(gate);
+    }
+    Ok(())
+}
+
+/// Raises the circuit in the function to a power by multiplying the angle of every rotation by the
+/// exponent. This is only the power of the whole circuit if its gates commute, like rotations about
+/// the same axis.
+///
+/// The function must have at most one basic block, and every call in it must be to an Rx, Ry, Rz
+/// or U1 rotation, or a barrier. Other gates, like H, have no power that's given by their
+/// arguments. Otherwise the function is left unchanged and an error describing the problem is
+/// returned.
+pub unsafe fn power(function: LLVMValueRef, exponent: f64) -> Result<(), String> {
+    let block = match LLVMCountBasicBlocks(function) {
+        0 => return Ok(()),
+        1 => LLVMGetFirstBasicBlock(function),
+        _ => return Err("Circuits with control flow can't be raised to a power.".to_string()),
+    };
+
+    let mut rotations = Vec::new();
+    let instructions = iter::successors(NonNull::new(LLVMGetFirstInstruction(block)), |i| {
+        NonNull::new(LLVMGetNextInstruction(i.as_ptr()))
+    });
+    for instruction in instructions.map(NonNull::as_ptr) {
+        if LLVMIsACallInst(instruction).is_null() {
+            continue;
+        }
+
+        let callee = LLVMGetCalledValue(instruction);
+        match Intrinsic::from_function(callee) {
+            Some(Intrinsic::Rx | Intrinsic::Ry | Intrinsic::Rz | Intrinsic::U1) => {
+                rotations.push(instruction);
+            }
+            Some(Intrinsic::Barrier | Intrinsic::Id) => {}
+            _ => {
+                return Err(format!(
+                    "Function {} can't be raised to a power.",
+                    function_name(callee)
+                ))
+            }
+        }
+    }
+
+    let builder = LLVMCreateBuilderInContext(LLVMGetModuleContext(LLVMGetGlobalParent(function)));
+    for rotation in rotations {
+        LLVMPositionBuilderBefore(builder, rotation);
+        let angle = LLVMGetOperand(rotation, 0);
+        let exponent = LLVMConstReal(LLVMTypeOf(angle), exponent);
+        let scaled = LLVMBuildFMul(builder, angle, exponent, raw_cstr!(""));
+        LLVMSetOperand(rotation, 0, scaled);
+    }
+    LLVMDisposeBuilder(builder);
+    Ok(())
+}
+
+unsafe fn function_name(function: LLVMValueRef) -> String {
+    let mut len = 0;
+    let name = LLVMGetValueName2(function, &mut len);
+    String::from_utf8_lossy(slice::from_raw_parts(name.cast(), len)).into_owned()
+}
+
+/// Replaces every static qubit operand in the function with the qubit that its ID maps to. Qubits
+/// that aren't in the map, and dynamic qubits, are left unchanged.
+pub unsafe fn relabel_qubits<S: BuildHasher>(function: LLVMValueRef, map: &HashMap<u64, u64, S>) {
+    let context = LLVMGetTypeContext(LLVMTypeOf(function));
+    let blocks = iter::successors(NonNull::new(LLVMGetFirstBasicBlock(function)), |b| {
+        NonNull::new(LLVMGetNextBasicBlock(b.as_ptr()))
+    });
+    for block in blocks {
+        let instructions =
+            iter::successors(NonNull::new(LLVMGetFirstInstruction(block.as_ptr())), |i| {
+                NonNull::new(LLVMGetNextInstruction(i.as_ptr()))
+            });
+        for instruction in instructions.map(NonNull::as_ptr) {
+            for i in 0..LLVMGetNumOperands(instruction).try_into().unwrap() {
+                let operand = LLVMGetOperand(instruction, i);
+                if !types::is_qubit(LLVMTypeOf(operand)) {
+                    continue;
+                }
+                if let Some(&id) = values::qubit_id(operand).and_then(|id| map.get(&id)) {
+                    LLVMSetOperand(instruction, i, values::qubit(context, id));
+                }
+            }
+        }
+    }
+}
+
+unsafe fn uses_qubit(instruction: LLVMValueRef) -> bool {
+    (0..LLVMGetNumOperands(instruction)).any(|i| {
+        let operand = LLVMGetOperand(instruction, i.try_into().unwrap());
+        types::is_qubit(LLVMTypeOf(operand))
+    })
+}
+
+/// The qubit and result of a call to `mz`, if both are static.
+unsafe fn static_mz(instruction: LLVMValueRef) -> Option<(LLVMValueRef, LLVMValueRef)> {
+    if LLVMIsACallInst(instruction).is_null()
+        || Intrinsic::from_function(LLVMGetCalledValue(instruction)) != Some(Intrinsic::Mz)
+    {
+        return None;
+    }
+
+    let qubit = LLVMGetOperand(instruction, 0);
+    let result = LLVMGetOperand(instruction, 1);
+    values::qubit_id(qubit)?;
+    values::result_id(result)?;
+    Some((qubit, result))
+}
+
+/// A constant global array of the values, as a pointer to its first element.
+unsafe fn global_array(
+    module: LLVMModuleRef,
+    ty: LLVMTypeRef,
+    values: &mut [LLVMValueRef],
+) -> LLVMValueRef {
+    let array = LLVMConstArray(ty, values.as_mut_ptr(), values.len().try_into().unwrap());
+    let global = LLVMAddGlobal(module, LLVMTypeOf(array), raw_cstr!(""));
+    LLVMSetLinkage(global, LLVMLinkage::LLVMInternalLinkage);
+    LLVMSetGlobalConstant(global, 1);
+    LLVMSetInitializer(global, array);
+
+    let zero = LLVMConstNull(LLVMInt32TypeInContext(LLVMGetModuleContext(module)));
+    let mut indices = [zero, zero];
+    #[allow(deprecated)]
+    LLVMConstGEP(
+        global,
+        indices.as_mut_ptr(),
+        indices.len().try_into().unwrap(),
+    )
+}
+
+/// The result of a measurement instruction.
+unsafe fn measurement(instruction: LLVMValueRef) -> Option<LLVMValueRef> {
+    if LLVMIsACallInst(instruction).is_null() {
+        return None;
+    }
+
+    match Intrinsic::from_function(LLVMGetCalledValue(instruction))? {
+        Intrinsic::M => Some(instruction),
+        Intrinsic::Mz => Some(LLVMGetOperand(instruction, 1)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{adjoint, batch_measurements, defer_measurements, power, relabel_qubits};
+    use crate::{
+        qis::{
+            build_barrier, build_cx, build_h, build_if_result, build_m, build_mz, build_reset,
+            build_rx, build_s, build_swap, build_t_adj, build_u1, build_x,
+        },
+        rt::build_result_record_output,
+        tests::{assert_reference_ir, Builder, Context, Module},
+        utils::builder_module,
+        values::{entry_point, qubit, result},
+    };
+    use const_str::{cstr, raw_cstr};
+    use llvm_sys::{
+        core::{
+            LLVMAppendBasicBlockInContext, LLVMConstPointerNull, LLVMConstReal,
+            LLVMDoubleTypeInContext, LLVMGetBasicBlockParent, LLVMGetInsertBlock,
+            LLVMGetModuleContext, LLVMInt8TypeInContext, LLVMPointerType, LLVMPositionBuilderAtEnd,
+        },
+        prelude::*,
+    };
+    use std::collections::HashMap;
+
+    unsafe fn record_output(builder: LLVMBuilderRef, context: LLVMContextRef, id: u64) {
+        let label = LLVMConstPointerNull(LLVMPointerType(LLVMInt8TypeInContext(context), 0));
+        build_result_record_output(builder, result(context, id), label);
+    }
+
+    fn defer_error(build: impl FnOnce(LLVMContextRef, LLVMBuilderRef)) -> String {
+        let context = Context::new();
+        let module = Module::new(&context, cstr!("test"));
+        let builder = Builder::new(&context);
+        unsafe {
+            let function = entry_point(module.as_ptr(), cstr!("main"), 3, 2, "custom", "");
+            let block = LLVMAppendBasicBlockInContext(context.as_ptr(), function, raw_cstr!(""));
+            LLVMPositionBuilderAtEnd(builder.as_ptr(), block);
+            build(context.as_ptr(), builder.as_ptr());
+            defer_measurements(function).unwrap_err()
+        }
+    }
+
+    #[test]
+    fn measurements_are_deferred() {
+        assert_reference_ir("passes/defer_measurements", 3, 2, |builder| unsafe {
+            let context = LLVMGetModuleContext(builder_module(builder));
+            build_mz(builder, qubit(context, 0), result(context, 0));
+            build_h(builder, qubit(context, 1));
+            build_mz(builder, qubit(context, 1), result(context, 1));
+            build_x(builder, qubit(context, 2));
+            record_output(builder, context, 0);
+            record_output(builder, context, 1);
+            defer_measurements(LLVMGetBasicBlockParent(LLVMGetInsertBlock(builder))).unwrap();
+        });
+    }
+
+    #[test]
+    fn qubit_used_after_measurement() {
+        let error = defer_error(|context, builder| unsafe {
+            build_h(builder, qubit(context, 0));
+            build_mz(builder, qubit(context, 0), result(context, 0));
+            build_cx(builder, qubit(context, 0), qubit(context, 1));
+        });
+        assert_eq!(error, "Qubit 0 is used after it is measured.");
+    }
+
+    #[test]
+    fn result_read_before_end() {
+        let error = defer_error(|context, builder| unsafe {
+            build_mz(builder, qubit(context, 0), result(context, 0));
+            record_output(builder, context, 0);
+            build_x(builder, qubit(context, 1));
+        });
+        assert_eq!(error, "Result 0 is read before the end of the circuit.");
+    }
+
+    #[test]
+    fn dynamic_result_read_before_end() {
+        let error = defer_error(|context, builder| unsafe {
+            let label = LLVMConstPointerNull(LLVMPointerType(LLVMInt8TypeInContext(context), 0));
+            let r = build_m(builder, qubit(context, 0));
+            build_result_record_output(builder, r, label);
+            build_x(builder, qubit(context, 1));
+        });
+        assert_eq!(
+            error,
+            "A measurement result is read before the end of the circuit."
+        );
+    }
+
+    #[test]
+    fn control_flow() {
+        let error = defer_error(|context, builder| unsafe {
+            build_mz(builder, qubit(context, 0), result(context, 0));
+            build_if_result(
+                builder,
+                result(context, 0),
+                || build_x(builder, qubit(context, 1)),
+                || (),
+            );
+        });
+        assert_eq!(error, "Measurements can't be deferred past control flow.");
+    }
+
+    fn batch(build: impl FnOnce(LLVMContextRef, LLVMBuilderRef)) -> bool {
+        let context = Context::new();
+        let module = Module::new(&context, cstr!("test"));
+        let builder = Builder::new(&context);
+        unsafe {
+            let function = entry_point(module.as_ptr(), cstr!("main"), 3, 3, "custom", "");
+            let block = LLVMAppendBasicBlockInContext(context.as_ptr(), function, raw_cstr!(""));
+            LLVMPositionBuilderAtEnd(builder.as_ptr(), block);
+            build(context.as_ptr(), builder.as_ptr());
+            batch_measurements(function)
+        }
+    }
+
+    #[test]
+    fn measurements_are_batched() {
+        assert_reference_ir("passes/batch_measurements", 3, 3, |builder| unsafe {
+            let context = LLVMGetModuleContext(builder_module(builder));
+            build_h(builder, qubit(context, 0));
+            build_mz(builder, qubit(context, 2), result(context, 2));
+            build_cx(builder, qubit(context, 0), qubit(context, 1));
+            build_mz(builder, qubit(context, 0), result(context, 0));
+            build_mz(builder, qubit(context, 1), result(context, 1));
+            record_output(builder, context, 0);
+            record_output(builder, context, 1);
+            let function = LLVMGetBasicBlockParent(LLVMGetInsertBlock(builder));
+            assert!(batch_measurements(function));
+        });
+    }
+
+    #[test]
+    fn gate_after_measurements() {
+        assert!(!batch(|context, builder| unsafe {
+            build_mz(builder, qubit(context, 0), result(context, 0));
+            build_mz(builder, qubit(context, 1), result(context, 1));
+            build_x(builder, qubit(context, 2));
+        }));
+    }
+
+    #[test]
+    fn single_measurement() {
+        assert!(!batch(|context, builder| unsafe {
+            build_h(builder, qubit(context, 0));
+            build_mz(builder, qubit(context, 0), result(context, 0));
+        }));
+    }
+
+    #[test]
+    fn repeated_qubit_ends_batch() {
+        assert!(!batch(|context, builder| unsafe {
+            build_mz(builder, qubit(context, 0), result(context, 0));
+            build_mz(builder, qubit(context, 0), result(context, 1));
+        }));
+    }
+
+    fn adjoint_error(build: impl FnOnce(LLVMContextRef, LLVMBuilderRef)) -> String {
+        let context = Context::new();
+        let module = Module::new(&context, cstr!("test"));
+        let builder = Builder::new(&context);
+        unsafe {
+            let function = entry_point(module.as_ptr(), cstr!("main"), 2, 1, "custom", "");
+            let block = LLVMAppendBasicBlockInContext(context.as_ptr(), function, raw_cstr!(""));
+            LLVMPositionBuilderAtEnd(builder.as_ptr(), block);
+            build(context.as_ptr(), builder.as_ptr());
+            adjoint(function).unwrap_err()
+        }
+    }
+
+    #[test]
+    fn circuit_is_reversed_and_inverted() {
+        assert_reference_ir("passes/adjoint", 2, 0, |builder| unsafe {
+            let context = LLVMGetModuleContext(builder_module(builder));
+            build_h(builder, qubit(context, 0));
+            build_s(builder, qubit(context, 0));
+            build_t_adj(builder, qubit(context, 1));
+            build_rx(
+                builder,
+                LLVMConstReal(LLVMDoubleTypeInContext(context), 0.5),
+                qubit(context, 1),
+            );
+            build_cx(builder, qubit(context, 0), qubit(context, 1));
+            build_swap(builder, qubit(context, 0), qubit(context, 1));
+            adjoint(LLVMGetBasicBlockParent(LLVMGetInsertBlock(builder))).unwrap();
+        });
+    }
+
+    #[test]
+    fn measurement_has_no_adjoint() {
+        let error = adjoint_error(|context, builder| unsafe {
+            build_h(builder, qubit(context, 0));
+            build_mz(builder, qubit(context, 0), result(context, 0));
+        });
+        assert_eq!(error, "Circuits with measurements don't have an adjoint.");
+    }
+
+    #[test]
+    fn reset_has_no_adjoint() {
+        let error = adjoint_error(|context, builder| unsafe {
+            build_reset(builder, qubit(context, 0));
+        });
+        assert_eq!(error, "Circuits with resets don't have an adjoint.");
+    }
+
+    #[test]
+    fn control_flow_has_no_adjoint() {
+        let error = adjoint_error(|context, builder| unsafe {
+            build_if_result(
+                builder,
+                result(context, 0),
+                || build_x(builder, qubit(context, 1)),
+                || (),
+            );
+        });
+        assert_eq!(error, "Circuits with control flow don't have an adjoint.");
+    }
+
+    #[test]
+    fn rotations_are_raised_to_power() {
+        assert_reference_ir("passes/power", 2, 0, |builder| unsafe {
+            let context = LLVMGetModuleContext(builder_module(builder));
+            let angle = |theta| LLVMConstReal(LLVMDoubleTypeInContext(context), theta);
+            build_rx(builder, angle(0.5), qubit(context, 0));
+            build_barrier(builder);
+            build_u1(builder, angle(-1.0), qubit(context, 1));
+            power(LLVMGetBasicBlockParent(LLVMGetInsertBlock(builder)), 0.25).unwrap();
+        });
+    }
+
+    #[test]
+    fn gate_has_no_power() {
+        let context = Context::new();
+        let module = Module::new(&context, cstr!("test"));
+        let builder = Builder::new(&context);
+        unsafe {
+            let function = entry_point(module.as_ptr(), cstr!("main"), 1, 0, "custom", "");
+            let block = LLVMAppendBasicBlockInContext(context.as_ptr(), function, raw_cstr!(""));
+            LLVMPositionBuilderAtEnd(builder.as_ptr(), block);
+            build_h(builder.as_ptr(), qubit(context.as_ptr(), 0));
+            let error = power(function, 0.5).unwrap_err();
+            assert_eq!(
+                error,
+                "Function __quantum__qis__h__body can't be raised to a power."
+            );
+        }
+    }
+
+    #[test]
+    fn qubits_are_relabeled() {
+        assert_reference_ir("passes/relabel_qubits", 3, 1, |builder| unsafe {
+            let context = LLVMGetModuleContext(builder_module(builder));
+            build_h(builder, qubit(context, 0));
+            build_cx(builder, qubit(context, 0), qubit(context, 1));
+            build_swap(builder, qubit(context, 1), qubit(context, 2));
+            build_mz(builder, qubit(context, 2), result(context, 0));
+            let map = HashMap::from([(0, 1), (1, 2), (2, 0)]);
+            relabel_qubits(LLVMGetBasicBlockParent(LLVMGetInsertBlock(builder)), &map);
+        });
+    }
+}
@@ -6,19 +6,259 @@ use crate::{
     types,
     utils::{
         build_call, builder_module, controlled_gate, declare_qis, doubly_controlled_gate,
-        function_type, no_param, rotation_gate, simple_gate, two_qubit_gate, Functor,
+        function_type, no_param, rotation_gate, simple_gate, two_angle_gate, two_qubit_gate,
+        Functor,
     },
 };
 
+use const_str::raw_cstr;
 use llvm_sys::LLVMAttributeFunctionIndex;
+use std::{
+    f64::consts::{FRAC_PI_4, FRAC_PI_8, PI, TAU},
+    slice,
+};
 
 #[allow(clippy::wildcard_imports)]
 use llvm_sys::{core::*, prelude::*};
 
+/// A QIR profile, which restricts the intrinsics that a program may use.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Profile {
+    /// The base profile, which allows no measurement-dependent control flow or qubit reuse.
+    Base,
+    /// The adaptive profile, which allows branching on measurement results.
+    Adaptive,
+    /// No restrictions. Every intrinsic known to this module is allowed.
+    Custom,
+}
+
+impl Profile {
+    /// The profile named by the value of a `qir_profiles` entry point attribute.
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "base_profile" => Some(Self::Base),
+            "adaptive_profile" => Some(Self::Adaptive),
+            "custom" => Some(Self::Custom),
+            _ => None,
+        }
+    }
+}
+
+/// A QIS intrinsic that the `build_*` functions in this module call.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Intrinsic {
+    Barrier,
+    Ccx,
+    Ch,
+    Cx,
+    Cy,
+    Cz,
+    Depolarize,
+    H,
+    Id,
+    M,
+    Mz,
+    ReadResult,
+    Reset,
+    Rx,
+    Ry,
+    Rz,
+    S,
+    SAdj,
+    Swap,
+    T,
+    TAdj,
+    U1,
+    U2,
+    X,
+    Y,
+    Z,
+}
+
+impl Intrinsic {
+    pub const ALL: [Self; 26] = [
+        Self::Barrier,
+        Self::Ccx,
+        Self::Ch,
+        Self::Cx,
+        Self::Cy,
+        Self::Cz,
+        Self::Depolarize,
+        Self::H,
+        Self::Id,
+        Self::M,
+        Self::Mz,
+        Self::ReadResult,
+        Self::Reset,
+        Self::Rx,
+        Self::Ry,
+        Self::Rz,
+        Self::S,
+        Self::SAdj,
+        Self::Swap,
+        Self::T,
+        Self::TAdj,
+        Self::U1,
+        Self::U2,
+        Self::X,
+        Self::Y,
+        Self::Z,
+    ];
+
+    /// Whether programs for the profile may call this intrinsic.
+    #[must_use]
+    pub fn in_profile(self, profile: Profile) -> bool {
+        match profile {
+            Profile::Base => !matches!(
+                self,
+                Self::Depolarize | Self::M | Self::ReadResult | Self::Reset
+            ),
+            Profile::Adaptive => !matches!(self, Self::Depolarize | Self::M),
+            Profile::Custom => true,
+        }
+    }
+
+    /// The name of the function that implements this intrinsic.
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Barrier => "__quantum__qis__barrier__body",
+            Self::Ccx => "__quantum__qis__ccx__body",
+            Self::Ch => "__quantum__qis__ch__body",
+            Self::Cx => "__quantum__qis__cnot__body",
+            Self::Cy => "__quantum__qis__cy__body",
+            Self::Cz => "__quantum__qis__cz__body",
+            Self::Depolarize => "__quantum__qis__depolarize__body",
+            Self::H => "__quantum__qis__h__body",
+            Self::Id => "__quantum__qis__id__body",
+            Self::M => "__quantum__qis__m__body",
+            Self::Mz => "__quantum__qis__mz__body",
+            Self::ReadResult => "__quantum__qis__read_result__body",
+            Self::Reset => "__quantum__qis__reset__body",
+            Self::Rx => "__quantum__qis__rx__body",
+            Self::Ry => "__quantum__qis__ry__body",
+            Self::Rz => "__quantum__qis__rz__body",
+            Self::S => "__quantum__qis__s__body",
+            Self::SAdj => "__quantum__qis__s__adj",
+            Self::Swap => "__quantum__qis__swap__body",
+            Self::T => "__quantum__qis__t__body",
+            Self::TAdj => "__quantum__qis__t__adj",
+            Self::U1 => "__quantum__qis__u1__body",
+            Self::U2 => "__quantum__qis__u2__body",
+            Self::X => "__quantum__qis__x__body",
+            Self::Y => "__quantum__qis__y__body",
+            Self::Z => "__quantum__qis__z__body",
+        }
+    }
+
+    /// The intrinsic that the function implements, if any.
+    pub unsafe fn from_function(function: LLVMValueRef) -> Option<Self> {
+        let mut len = 0;
+        let name = LLVMGetValueName2(function, &mut len);
+        let name = slice::from_raw_parts(name.cast::<u8>(), len);
+        Self::ALL
+            .into_iter()
+            .find(|intrinsic| intrinsic.name().as_bytes() == name)
+    }
+
+    /// The intrinsic that undoes this one, or `None` if this intrinsic isn't unitary. The adjoint
+    /// of a rotation is the same rotation with different angles.
+    #[must_use]
+    pub fn adjoint(self) -> Option<Self> {
+        match self {
+            Self::Depolarize | Self::M | Self::Mz | Self::ReadResult | Self::Reset => None,
+            Self::S => Some(Self::SAdj),
+            Self::SAdj => Some(Self::S),
+            Self::T => Some(Self::TAdj),
+            Self::TAdj => Some(Self::T),
+            Self::Barrier
+            | Self::Ccx
+            | Self::Ch
+            | Self::Cx
+            | Self::Cy
+            | Self::Cz
+            | Self::H
+            | Self::Id
+            | Self::Rx
+            | Self::Ry
+            | Self::Rz
+            | Self::Swap
+            | Self::U1
+            | Self::U2
+            | Self::X
+            | Self::Y
+            | Self::Z => Some(self),
+        }
+    }
+
+    /// Whether a call to this intrinsic can be given an extra control qubit with
+    /// [`build_controlled`].
+    #[must_use]
+    pub fn is_controllable(self) -> bool {
+        !matches!(
+            self,
+            Self::Barrier
+                | Self::Ccx
+                | Self::Ch
+                | Self::Cy
+                | Self::Cz
+                | Self::Depolarize
+                | Self::M
+                | Self::Mz
+                | Self::ReadResult
+                | Self::Reset
+                | Self::Swap
+                | Self::U2
+        )
+    }
+
+    /// Declares this intrinsic in the module if it isn't declared already.
+    pub unsafe fn declare(self, module: LLVMModuleRef) -> LLVMValueRef {
+        match self {
+            Self::Barrier => no_param(module, "barrier", Functor::Body),
+            Self::Ccx => doubly_controlled_gate(module, "ccx"),
+            Self::Ch => controlled_gate(module, "ch"),
+            Self::Cx => controlled_gate(module, "cnot"),
+            Self::Cy => controlled_gate(module, "cy"),
+            Self::Cz => controlled_gate(module, "cz"),
+            Self::Depolarize => rotation_gate(module, "depolarize"),
+            Self::H => simple_gate(module, "h", Functor::Body),
+            Self::Id => simple_gate(module, "id", Functor::Body),
+            Self::M => m(module),
+            Self::Mz => mz(module),
+            Self::ReadResult => read_result(module),
+            Self::Reset => simple_gate(module, "reset", Functor::Body),
+            Self::Rx => rotation_gate(module, "rx"),
+            Self::Ry => rotation_gate(module, "ry"),
+            Self::Rz => rotation_gate(module, "rz"),
+            Self::S => simple_gate(module, "s", Functor::Body),
+            Self::SAdj => simple_gate(module, "s", Functor::Adjoint),
+            Self::Swap => two_qubit_gate(module, "swap", Functor::Body),
+            Self::T => simple_gate(module, "t", Functor::Body),
+            Self::TAdj => simple_gate(module, "t", Functor::Adjoint),
+            Self::U1 => rotation_gate(module, "u1"),
+            Self::U2 => two_angle_gate(module, "u2"),
+            Self::X => simple_gate(module, "x", Functor::Body),
+            Self::Y => simple_gate(module, "y", Functor::Body),
+            Self::Z => simple_gate(module, "z", Functor::Body),
+        }
+    }
+}
+
+/// Declares every intrinsic that programs for the profile may call.
+pub unsafe fn declare_all(module: LLVMModuleRef, profile: Profile) {
+    for intrinsic in Intrinsic::ALL {
+        if intrinsic.in_profile(profile) {
+            intrinsic.declare(module);
+        }
+    }
+}
+
 pub unsafe fn build_barrier(builder: LLVMBuilderRef) {
     build_call(
         builder,
-        no_param(builder_module(builder), "barrier", Functor::Body),
+        Intrinsic::Barrier.declare(builder_module(builder)),
         &mut [],
     );
 }
@@ -31,15 +271,31 @@ pub unsafe fn build_ccx(
 ) {
     build_call(
         builder,
-        doubly_controlled_gate(builder_module(builder), "ccx"),
+        Intrinsic::Ccx.declare(builder_module(builder)),
         &mut [control1, control2, qubit],
     );
 }
 
+pub unsafe fn build_ch(builder: LLVMBuilderRef, control: LLVMValueRef, qubit: LLVMValueRef) {
+    build_call(
+        builder,
+        Intrinsic::Ch.declare(builder_module(builder)),
+        &mut [control, qubit],
+    );
+}
+
 pub unsafe fn build_cx(builder: LLVMBuilderRef, control: LLVMValueRef, qubit: LLVMValueRef) {
     build_call(
         builder,
-        controlled_gate(builder_module(builder), "cnot"),
+        Intrinsic::Cx.declare(builder_module(builder)),
+        &mut [control, qubit],
+    );
+}
+
+pub unsafe fn build_cy(builder: LLVMBuilderRef, control: LLVMValueRef, qubit: LLVMValueRef) {
+    build_call(
+        builder,
+        Intrinsic::Cy.declare(builder_module(builder)),
         &mut [control, qubit],
     );
 }
@@ -47,15 +303,38 @@ pub unsafe fn build_cx(builder: LLVMBuilderRef, control: LLVMValueRef, qubit: LL
 pub unsafe fn build_cz(builder: LLVMBuilderRef, control: LLVMValueRef, qubit: LLVMValueRef) {
     build_call(
         builder,
-        controlled_gate(builder_module(builder), "cz"),
+        Intrinsic::Cz.declare(builder_module(builder)),
         &mut [control, qubit],
     );
 }
 
+/// Builds a depolarizing noise channel on the qubit, which replaces its state with the maximally
+/// mixed state with the given probability. It's not part of any QIR profile, and only simulators
+/// that model noise are expected to implement it.
+pub unsafe fn build_depolarize(
+    builder: LLVMBuilderRef,
+    probability: LLVMValueRef,
+    qubit: LLVMValueRef,
+) {
+    build_call(
+        builder,
+        Intrinsic::Depolarize.declare(builder_module(builder)),
+        &mut [probability, qubit],
+    );
+}
+
 pub unsafe fn build_h(builder: LLVMBuilderRef, qubit: LLVMValueRef) {
     build_call(
         builder,
-        simple_gate(builder_module(builder), "h", Functor::Body),
+        Intrinsic::H.declare(builder_module(builder)),
+        &mut [qubit],
+    );
+}
+
+pub unsafe fn build_id(builder: LLVMBuilderRef, qubit: LLVMValueRef) {
+    build_call(
+        builder,
+        Intrinsic::Id.declare(builder_module(builder)),
         &mut [qubit],
     );
 }
@@ -63,7 +342,7 @@ pub unsafe fn build_h(builder: LLVMBuilderRef, qubit: LLVMValueRef) {
 pub unsafe fn build_s(builder: LLVMBuilderRef, qubit: LLVMValueRef) {
     build_call(
         builder,
-        simple_gate(builder_module(builder), "s", Functor::Body),
+        Intrinsic::S.declare(builder_module(builder)),
         &mut [qubit],
     );
 }
@@ -71,7 +350,7 @@ pub unsafe fn build_s(builder: LLVMBuilderRef, qubit: LLVMValueRef) {
 pub unsafe fn build_s_adj(builder: LLVMBuilderRef, qubit: LLVMValueRef) {
     build_call(
         builder,
-        simple_gate(builder_module(builder), "s", Functor::Adjoint),
+        Intrinsic::SAdj.declare(builder_module(builder)),
         &mut [qubit],
     );
 }
@@ -79,7 +358,7 @@ pub unsafe fn build_s_adj(builder: LLVMBuilderRef, qubit: LLVMValueRef) {
 pub unsafe fn build_swap(builder: LLVMBuilderRef, qubit1: LLVMValueRef, qubit2: LLVMValueRef) {
     build_call(
         builder,
-        two_qubit_gate(builder_module(builder), "swap", Functor::Body),
+        Intrinsic::Swap.declare(builder_module(builder)),
         &mut [qubit1, qubit2],
     );
 }
@@ -87,7 +366,7 @@ pub unsafe fn build_swap(builder: LLVMBuilderRef, qubit1: LLVMValueRef, qubit2:
 pub unsafe fn build_t(builder: LLVMBuilderRef, qubit: LLVMValueRef) {
     build_call(
         builder,
-        simple_gate(builder_module(builder), "t", Functor::Body),
+        Intrinsic::T.declare(builder_module(builder)),
         &mut [qubit],
     );
 }
@@ -95,7 +374,7 @@ pub unsafe fn build_t(builder: LLVMBuilderRef, qubit: LLVMValueRef) {
 pub unsafe fn build_t_adj(builder: LLVMBuilderRef, qubit: LLVMValueRef) {
     build_call(
         builder,
-        simple_gate(builder_module(builder), "t", Functor::Adjoint),
+        Intrinsic::TAdj.declare(builder_module(builder)),
         &mut [qubit],
     );
 }
@@ -103,7 +382,7 @@ pub unsafe fn build_t_adj(builder: LLVMBuilderRef, qubit: LLVMValueRef) {
 pub unsafe fn build_x(builder: LLVMBuilderRef, qubit: LLVMValueRef) {
     build_call(
         builder,
-        simple_gate(builder_module(builder), "x", Functor::Body),
+        Intrinsic::X.declare(builder_module(builder)),
         &mut [qubit],
     );
 }
@@ -111,7 +390,7 @@ pub unsafe fn build_x(builder: LLVMBuilderRef, qubit: LLVMValueRef) {
 pub unsafe fn build_y(builder: LLVMBuilderRef, qubit: LLVMValueRef) {
     build_call(
         builder,
-        simple_gate(builder_module(builder), "y", Functor::Body),
+        Intrinsic::Y.declare(builder_module(builder)),
         &mut [qubit],
     );
 }
@@ -119,15 +398,31 @@ pub unsafe fn build_y(builder: LLVMBuilderRef, qubit: LLVMValueRef) {
 pub unsafe fn build_z(builder: LLVMBuilderRef, qubit: LLVMValueRef) {
     build_call(
         builder,
-        simple_gate(builder_module(builder), "z", Functor::Body),
+        Intrinsic::Z.declare(builder_module(builder)),
         &mut [qubit],
     );
 }
 
+/// Reduces a rotation angle modulo 2π into (-π, π]. Angles that are already in range, infinity
+/// and NaN are returned unchanged. Otherwise the remainder is exact, so the only rounding is in the
+/// final shift by 2π.
+#[must_use]
+pub fn normalize_angle(angle: f64) -> f64 {
+    if !angle.is_finite() || (-PI < angle && angle <= PI) {
+        return angle;
+    }
+    let reduced = angle.rem_euclid(TAU);
+    if reduced > PI {
+        reduced - TAU
+    } else {
+        reduced
+    }
+}
+
 pub unsafe fn build_rx(builder: LLVMBuilderRef, theta: LLVMValueRef, qubit: LLVMValueRef) {
     build_call(
         builder,
-        rotation_gate(builder_module(builder), "rx"),
+        Intrinsic::Rx.declare(builder_module(builder)),
         &mut [theta, qubit],
     );
 }
@@ -135,7 +430,7 @@ pub unsafe fn build_rx(builder: LLVMBuilderRef, theta: LLVMValueRef, qubit: LLVM
 pub unsafe fn build_ry(builder: LLVMBuilderRef, theta: LLVMValueRef, qubit: LLVMValueRef) {
     build_call(
         builder,
-        rotation_gate(builder_module(builder), "ry"),
+        Intrinsic::Ry.declare(builder_module(builder)),
         &mut [theta, qubit],
     );
 }
@@ -143,21 +438,180 @@ pub unsafe fn build_ry(builder: LLVMBuilderRef, theta: LLVMValueRef, qubit: LLVM
 pub unsafe fn build_rz(builder: LLVMBuilderRef, theta: LLVMValueRef, qubit: LLVMValueRef) {
     build_call(
         builder,
-        rotation_gate(builder_module(builder), "rz"),
+        Intrinsic::Rz.declare(builder_module(builder)),
         &mut [theta, qubit],
     );
 }
 
+/// Builds the phase gate `u1(lambda) = diag(1, e^(i lambda))`, which is `rz(lambda)` up to a
+/// global phase.
+pub unsafe fn build_u1(builder: LLVMBuilderRef, lambda: LLVMValueRef, qubit: LLVMValueRef) {
+    build_call(
+        builder,
+        Intrinsic::U1.declare(builder_module(builder)),
+        &mut [lambda, qubit],
+    );
+}
+
+/// Builds `u2(phi, lambda) = u1(phi) ry(pi / 2) u1(lambda)`.
+pub unsafe fn build_u2(
+    builder: LLVMBuilderRef,
+    phi: LLVMValueRef,
+    lambda: LLVMValueRef,
+    qubit: LLVMValueRef,
+) {
+    build_call(
+        builder,
+        Intrinsic::U2.declare(builder_module(builder)),
+        &mut [phi, lambda, qubit],
+    );
+}
+
 pub unsafe fn build_reset(builder: LLVMBuilderRef, qubit: LLVMValueRef) {
     build_call(
         builder,
-        simple_gate(builder_module(builder), "reset", Functor::Body),
+        Intrinsic::Reset.declare(builder_module(builder)),
         &mut [qubit],
     );
 }
 
+pub unsafe fn build_m(builder: LLVMBuilderRef, qubit: LLVMValueRef) -> LLVMValueRef {
+    build_call(
+        builder,
+        Intrinsic::M.declare(builder_module(builder)),
+        &mut [qubit],
+    )
+}
+
 pub unsafe fn build_mz(builder: LLVMBuilderRef, qubit: LLVMValueRef, result: LLVMValueRef) {
-    build_call(builder, mz(builder_module(builder)), &mut [qubit, result]);
+    build_call(
+        builder,
+        Intrinsic::Mz.declare(builder_module(builder)),
+        &mut [qubit, result],
+    );
+}
+
+/// Builds the controlled form of a call to a QIS gate, with an extra control qubit. Gates without a
+/// controlled intrinsic are decomposed into gates that have one, up to a global phase. Returns
+/// false without building anything if the call isn't to a gate with a controlled form.
+///
+/// The gate applies when the control is in the state given by `control_state`: one for an ordinary
+/// control, or zero for a negative control. A negative control is conjugated with X gates.
+pub unsafe fn build_controlled(
+    builder: LLVMBuilderRef,
+    call: LLVMValueRef,
+    control: LLVMValueRef,
+    control_state: bool,
+) -> bool {
+    let intrinsic = match Intrinsic::from_function(LLVMGetCalledValue(call)) {
+        Some(intrinsic) if intrinsic.is_controllable() => intrinsic,
+        _ => return false,
+    };
+    if !control_state {
+        build_x(builder, control);
+    }
+    let arg = |i| LLVMGetOperand(call, i);
+    let context = LLVMGetModuleContext(builder_module(builder));
+    let angle = |theta| LLVMConstReal(LLVMDoubleTypeInContext(context), theta);
+
+    match intrinsic {
+        Intrinsic::Cx => build_ccx(builder, control, arg(0), arg(1)),
+        Intrinsic::H => {
+            let qubit = arg(0);
+            build_ry(builder, angle(-FRAC_PI_4), qubit);
+            build_cz(builder, control, qubit);
+            build_ry(builder, angle(FRAC_PI_4), qubit);
+        }
+        Intrinsic::Id => build_id(builder, arg(0)),
+        Intrinsic::Rx => {
+            let qubit = arg(1);
+            build_h(builder, qubit);
+            build_controlled_rotation(builder, build_rz, arg(0), control, qubit);
+            build_h(builder, qubit);
+        }
+        Intrinsic::Ry => build_controlled_rotation(builder, build_ry, arg(0), control, arg(1)),
+        Intrinsic::Rz => build_controlled_rotation(builder, build_rz, arg(0), control, arg(1)),
+        Intrinsic::S => {
+            let qubit = arg(0);
+            build_t(builder, control);
+            build_t(builder, qubit);
+            build_cx(builder, control, qubit);
+            build_t_adj(builder, qubit);
+            build_cx(builder, control, qubit);
+        }
+        Intrinsic::SAdj => {
+            let qubit = arg(0);
+            build_t_adj(builder, control);
+            build_t_adj(builder, qubit);
+            build_cx(builder, control, qubit);
+            build_t(builder, qubit);
+            build_cx(builder, control, qubit);
+        }
+        Intrinsic::T | Intrinsic::TAdj => {
+            let qubit = arg(0);
+            let half = if intrinsic == Intrinsic::T {
+                FRAC_PI_8
+            } else {
+                -FRAC_PI_8
+            };
+            build_rz(builder, angle(half), control);
+            build_rz(builder, angle(half), qubit);
+            build_cx(builder, control, qubit);
+            build_rz(builder, angle(-half), qubit);
+            build_cx(builder, control, qubit);
+        }
+        Intrinsic::X => build_cx(builder, control, arg(0)),
+        Intrinsic::Y => {
+            let qubit = arg(0);
+            build_s_adj(builder, qubit);
+            build_cx(builder, control, qubit);
+            build_s(builder, qubit);
+        }
+        Intrinsic::U1 => {
+            let (lambda, qubit) = (arg(0), arg(1));
+            let ty = LLVMTypeOf(lambda);
+            let half = LLVMBuildFMul(builder, lambda, LLVMConstReal(ty, 0.5), raw_cstr!(""));
+            let neg_half = LLVMBuildFMul(builder, lambda, LLVMConstReal(ty, -0.5), raw_cstr!(""));
+            build_u1(builder, half, control);
+            build_cx(builder, control, qubit);
+            build_u1(builder, neg_half, qubit);
+            build_cx(builder, control, qubit);
+            build_u1(builder, half, qubit);
+        }
+        Intrinsic::Z => build_cz(builder, control, arg(0)),
+        Intrinsic::Barrier
+        | Intrinsic::Ccx
+        | Intrinsic::Ch
+        | Intrinsic::Cy
+        | Intrinsic::Cz
+        | Intrinsic::Depolarize
+        | Intrinsic::M
+        | Intrinsic::Mz
+        | Intrinsic::ReadResult
+        | Intrinsic::Reset
+        | Intrinsic::Swap
+        | Intrinsic::U2 => unreachable!("{intrinsic:?} isn't controllable"),
+    }
+    if !control_state {
+        build_x(builder, control);
+    }
+    true
+}
+
+unsafe fn build_controlled_rotation(
+    builder: LLVMBuilderRef,
+    build_rotation: unsafe fn(LLVMBuilderRef, LLVMValueRef, LLVMValueRef),
+    theta: LLVMValueRef,
+    control: LLVMValueRef,
+    qubit: LLVMValueRef,
+) {
+    let ty = LLVMTypeOf(theta);
+    let half = LLVMBuildFMul(builder, theta, LLVMConstReal(ty, 0.5), raw_cstr!(""));
+    let neg_half = LLVMBuildFMul(builder, theta, LLVMConstReal(ty, -0.5), raw_cstr!(""));
+    build_rotation(builder, half, qubit);
+    build_cx(builder, control, qubit);
+    build_rotation(builder, neg_half, qubit);
+    build_cx(builder, control, qubit);
 }
 
 pub unsafe fn build_if_result(
@@ -173,15 +627,28 @@ pub unsafe fn build_if_result(
 pub unsafe fn try_build_if_result<E>(
     builder: LLVMBuilderRef,
     cond: LLVMValueRef,
+    label: Option<&str>,
     build_one: impl FnOnce() -> Result<(), E>,
     build_zero: impl FnOnce() -> Result<(), E>,
 ) -> Result<(), E> {
     let bool_cond = build_read_result(builder, cond);
-    try_build_if(builder, bool_cond, build_one, build_zero)
+    try_build_if(builder, bool_cond, label, build_one, build_zero)
+}
+
+pub unsafe fn build_read_result(builder: LLVMBuilderRef, result: LLVMValueRef) -> LLVMValueRef {
+    build_call(
+        builder,
+        Intrinsic::ReadResult.declare(builder_module(builder)),
+        &mut [result],
+    )
 }
 
-unsafe fn build_read_result(builder: LLVMBuilderRef, result: LLVMValueRef) -> LLVMValueRef {
-    build_call(builder, read_result(builder_module(builder)), &mut [result])
+unsafe fn m(module: LLVMModuleRef) -> LLVMValueRef {
+    let context = LLVMGetModuleContext(module);
+    let ty = function_type(types::result(context), &mut [types::qubit(context)]);
+    let function = declare_qis(module, "m", Functor::Body, ty);
+    add_irreversible_attr(context, function);
+    function
 }
 
 unsafe fn mz(module: LLVMModuleRef) -> LLVMValueRef {
@@ -203,6 +670,23 @@ unsafe fn mz(module: LLVMModuleRef) -> LLVMValueRef {
     function
 }
 
+/// Declares the batched form of `mz`, which measures `count` qubits into `count` results, taking
+/// pointers to arrays of the qubits and results.
+pub(crate) unsafe fn declare_mz_batch(module: LLVMModuleRef) -> LLVMValueRef {
+    let context = LLVMGetModuleContext(module);
+    let ty = function_type(
+        LLVMVoidTypeInContext(context),
+        &mut [
+            LLVMInt64TypeInContext(context),
+            LLVMPointerType(types::qubit(context), 0),
+            LLVMPointerType(types::result(context), 0),
+        ],
+    );
+    let function = declare_qis(module, "mz_batch", Functor::Body, ty);
+    add_irreversible_attr(context, function);
+    function
+}
+
 #[allow(clippy::cast_possible_truncation)]
 unsafe fn add_irreversible_attr(context: LLVMContextRef, function: LLVMValueRef) {
     let irreversable = "irreversible";
@@ -246,6 +730,26 @@ mod tests {
         ))))
     }
 
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn normalize_angles() {
+        assert_eq!(normalize_angle(0.0), 0.0);
+        assert_eq!(normalize_angle(1.5), 1.5);
+        assert_eq!(normalize_angle(-1.5), -1.5);
+        assert_eq!(normalize_angle(PI), PI);
+        assert_eq!(normalize_angle(-PI), PI);
+        assert_eq!(normalize_angle(TAU), 0.0);
+        assert_eq!(normalize_angle(-TAU), 0.0);
+        assert_eq!(normalize_angle(3.0 * PI), PI);
+        assert_eq!(normalize_angle(PI + 0.5), 0.5 - PI);
+        assert_eq!(normalize_angle(-1e-20), -1e-20);
+        assert_eq!(normalize_angle(TAU + 0.25), 0.25);
+        // The input is rounded to the nearest double, which is about 1e-10 away from 1e6 π + 1/8.
+        assert!((normalize_angle(1e6 * PI + 0.125) - 0.125).abs() < 1e-9);
+        assert_eq!(normalize_angle(f64::INFINITY), f64::INFINITY);
+        assert!(normalize_angle(f64::NAN).is_nan());
+    }
+
     #[test]
     #[should_panic(expected = "The builder's position has not been set.")]
     fn builder_not_positioned() {
@@ -276,6 +780,14 @@ mod tests {
         });
     }
 
+    #[test]
+    fn ch() {
+        assert_reference_ir("qis/ch", 2, 0, |builder| unsafe {
+            let context = builder_context(builder).unwrap().as_ptr();
+            build_ch(builder, qubit(context, 0), qubit(context, 1));
+        });
+    }
+
     #[test]
     fn cx() {
         assert_reference_ir("qis/cx", 2, 0, |builder| unsafe {
@@ -284,6 +796,14 @@ mod tests {
         });
     }
 
+    #[test]
+    fn cy() {
+        assert_reference_ir("qis/cy", 2, 0, |builder| unsafe {
+            let context = builder_context(builder).unwrap().as_ptr();
+            build_cy(builder, qubit(context, 0), qubit(context, 1));
+        });
+    }
+
     #[test]
     fn cz() {
         assert_reference_ir("qis/cz", 2, 0, |builder| unsafe {
@@ -292,6 +812,15 @@ mod tests {
         });
     }
 
+    #[test]
+    fn depolarize() {
+        assert_reference_ir("qis/depolarize", 1, 0, |builder| unsafe {
+            let context = builder_context(builder).unwrap().as_ptr();
+            let double = LLVMDoubleTypeInContext(context);
+            build_depolarize(builder, LLVMConstReal(double, 0.125), qubit(context, 0));
+        });
+    }
+
     #[test]
     fn h() {
         assert_reference_ir("qis/h", 1, 0, |builder| unsafe {
@@ -300,6 +829,14 @@ mod tests {
         });
     }
 
+    #[test]
+    fn id() {
+        assert_reference_ir("qis/id", 1, 0, |builder| unsafe {
+            let context = builder_context(builder).unwrap().as_ptr();
+            build_id(builder, qubit(context, 0));
+        });
+    }
+
     #[test]
     fn s() {
         assert_reference_ir("qis/s", 1, 0, |builder| unsafe {
@@ -383,6 +920,29 @@ mod tests {
         });
     }
 
+    #[test]
+    fn u1() {
+        assert_reference_ir("qis/u1", 1, 0, |builder| unsafe {
+            let context = builder_context(builder).unwrap().as_ptr();
+            let double = LLVMDoubleTypeInContext(context);
+            build_u1(builder, LLVMConstReal(double, 0.5), qubit(context, 0));
+        });
+    }
+
+    #[test]
+    fn u2() {
+        assert_reference_ir("qis/u2", 1, 0, |builder| unsafe {
+            let context = builder_context(builder).unwrap().as_ptr();
+            let double = LLVMDoubleTypeInContext(context);
+            build_u2(
+                builder,
+                LLVMConstReal(double, 0.5),
+                LLVMConstReal(double, 1.5),
+                qubit(context, 0),
+            );
+        });
+    }
+
     #[test]
     fn reset() {
         assert_reference_ir("qis/reset", 1, 0, |builder| unsafe {
@@ -399,6 +959,28 @@ mod tests {
         });
     }
 
+    #[test]
+    fn m() {
+        assert_reference_ir("qis/m", 1, 0, |builder| unsafe {
+            let context = builder_context(builder).unwrap().as_ptr();
+            build_m(builder, qubit(context, 0));
+        });
+    }
+
+    #[test]
+    fn if_m() {
+        assert_reference_ir("qis/if_m", 1, 0, |builder| unsafe {
+            let context = builder_context(builder).unwrap().as_ptr();
+            let result = build_m(builder, qubit(context, 0));
+            build_if_result(
+                builder,
+                result,
+                || build_x(builder, qubit(context, 0)),
+                || (),
+            );
+        });
+    }
+
     #[test]
     fn read_result() {
         assert_reference_ir("qis/read_result", 1, 1, |builder| unsafe {
@@ -438,6 +1020,34 @@ mod tests {
         });
     }
 
+    #[test]
+    fn if_labeled() {
+        assert_reference_ir("qis/if_labeled", 1, 2, |builder| unsafe {
+            let context = builder_context(builder).unwrap().as_ptr();
+            build_mz(builder, qubit(context, 0), result(context, 0));
+            let build_x = || -> Result<(), ()> {
+                build_x(builder, qubit(context, 0));
+                Ok(())
+            };
+            try_build_if_result(
+                builder,
+                result(context, 0),
+                Some("correct"),
+                build_x,
+                || Ok(()),
+            )
+            .unwrap();
+            try_build_if_result(
+                builder,
+                result(context, 1),
+                Some("correct"),
+                build_x,
+                || Ok(()),
+            )
+            .unwrap();
+        });
+    }
+
     #[test]
     fn if_else() {
         assert_reference_ir("qis/if_else", 1, 1, |builder| unsafe {
@@ -597,4 +1207,121 @@ mod tests {
             );
         });
     }
+
+    #[test]
+    fn base_profile_declarations() {
+        assert_reference_ir("qis/base_profile_declarations", 0, 0, |builder| unsafe {
+            declare_all(builder_module(builder), Profile::Base);
+        });
+    }
+
+    #[test]
+    fn custom_declarations() {
+        assert_reference_ir("qis/custom_declarations", 0, 0, |builder| unsafe {
+            declare_all(builder_module(builder), Profile::Custom);
+        });
+    }
+
+    unsafe fn replace_with_controlled(builder: LLVMBuilderRef, control: LLVMValueRef) -> bool {
+        let call = LLVMGetLastInstruction(LLVMGetInsertBlock(builder));
+        let built = build_controlled(builder, call, control, true);
+        LLVMInstructionEraseFromParent(call);
+        built
+    }
+
+    #[test]
+    fn controlled_x() {
+        assert_reference_ir("qis/controlled_x", 2, 0, |builder| unsafe {
+            let context = builder_context(builder).unwrap().as_ptr();
+            build_x(builder, qubit(context, 1));
+            assert!(replace_with_controlled(builder, qubit(context, 0)));
+        });
+    }
+
+    #[test]
+    fn zero_controlled_x() {
+        assert_reference_ir("qis/zero_controlled_x", 2, 0, |builder| unsafe {
+            let context = builder_context(builder).unwrap().as_ptr();
+            build_x(builder, qubit(context, 1));
+            let call = LLVMGetLastInstruction(LLVMGetInsertBlock(builder));
+            assert!(build_controlled(builder, call, qubit(context, 0), false));
+            LLVMInstructionEraseFromParent(call);
+        });
+    }
+
+    #[test]
+    fn controlled_cx() {
+        assert_reference_ir("qis/controlled_cx", 3, 0, |builder| unsafe {
+            let context = builder_context(builder).unwrap().as_ptr();
+            build_cx(builder, qubit(context, 1), qubit(context, 2));
+            assert!(replace_with_controlled(builder, qubit(context, 0)));
+        });
+    }
+
+    #[test]
+    fn controlled_y() {
+        assert_reference_ir("qis/controlled_y", 2, 0, |builder| unsafe {
+            let context = builder_context(builder).unwrap().as_ptr();
+            build_y(builder, qubit(context, 1));
+            assert!(replace_with_controlled(builder, qubit(context, 0)));
+        });
+    }
+
+    #[test]
+    fn controlled_h() {
+        assert_reference_ir("qis/controlled_h", 2, 0, |builder| unsafe {
+            let context = builder_context(builder).unwrap().as_ptr();
+            build_h(builder, qubit(context, 1));
+            assert!(replace_with_controlled(builder, qubit(context, 0)));
+        });
+    }
+
+    #[test]
+    fn controlled_s() {
+        assert_reference_ir("qis/controlled_s", 2, 0, |builder| unsafe {
+            let context = builder_context(builder).unwrap().as_ptr();
+            build_s(builder, qubit(context, 1));
+            assert!(replace_with_controlled(builder, qubit(context, 0)));
+        });
+    }
+
+    #[test]
+    fn controlled_t_adj() {
+        assert_reference_ir("qis/controlled_t_adj", 2, 0, |builder| unsafe {
+            let context = builder_context(builder).unwrap().as_ptr();
+            build_t_adj(builder, qubit(context, 1));
+            assert!(replace_with_controlled(builder, qubit(context, 0)));
+        });
+    }
+
+    #[test]
+    fn controlled_rx() {
+        assert_reference_ir("qis/controlled_rx", 2, 0, |builder| unsafe {
+            let context = builder_context(builder).unwrap().as_ptr();
+            let theta = LLVMConstReal(LLVMDoubleTypeInContext(context), 1.0);
+            build_rx(builder, theta, qubit(context, 1));
+            assert!(replace_with_controlled(builder, qubit(context, 0)));
+        });
+    }
+
+    #[test]
+    fn controlled_u1() {
+        assert_reference_ir("qis/controlled_u1", 2, 0, |builder| unsafe {
+            let context = builder_context(builder).unwrap().as_ptr();
+            let lambda = LLVMConstReal(LLVMDoubleTypeInContext(context), 1.0);
+            build_u1(builder, lambda, qubit(context, 1));
+            assert!(replace_with_controlled(builder, qubit(context, 0)));
+        });
+    }
+
+    #[test]
+    fn no_controlled_form() {
+        assert_reference_ir("qis/mz", 1, 1, |builder| unsafe {
+            let context = builder_context(builder).unwrap().as_ptr();
+            build_mz(builder, qubit(context, 0), result(context, 0));
+            let call = LLVMGetLastInstruction(LLVMGetInsertBlock(builder));
+            assert!(!build_controlled(builder, call, qubit(context, 1), true));
+            assert!(!build_controlled(builder, call, qubit(context, 1), false));
+        });
+    }
 }
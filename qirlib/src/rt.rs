@@ -6,9 +6,11 @@ use crate::{
     utils::{build_call, builder_module, declare_external_function, function_type},
 };
 
+use const_str::raw_cstr;
 use llvm_sys::{
     core::{
-        LLVMGetModuleContext, LLVMInt64TypeInContext, LLVMInt8TypeInContext, LLVMPointerType,
+        LLVMBuildBitCast, LLVMBuildStore, LLVMConstInt, LLVMGetModuleContext,
+        LLVMInt32TypeInContext, LLVMInt64TypeInContext, LLVMInt8TypeInContext, LLVMPointerType,
         LLVMVoidTypeInContext,
     },
     prelude::*,
@@ -26,10 +28,63 @@ pub unsafe fn build_array_record_output(
     );
 }
 
+/// Builds a call to `__quantum__rt__array_update_reference_count`, which releases the array when
+/// the count drops to zero.
+pub unsafe fn build_array_update_reference_count(
+    builder: LLVMBuilderRef,
+    array: LLVMValueRef,
+    delta: LLVMValueRef,
+) {
+    build_call(
+        builder,
+        array_update_reference_count(builder_module(builder)),
+        &mut [array, delta],
+    );
+}
+
 pub unsafe fn build_initialize(builder: LLVMBuilderRef, data: LLVMValueRef) {
     build_call(builder, initialize(builder_module(builder)), &mut [data]);
 }
 
+pub unsafe fn build_int_record_output(
+    builder: LLVMBuilderRef,
+    value: LLVMValueRef,
+    label: LLVMValueRef,
+) {
+    build_call(
+        builder,
+        int_record_output(builder_module(builder)),
+        &mut [value, label],
+    );
+}
+
+/// Builds a new one-dimensional array that holds the qubits in order, with
+/// `__quantum__rt__array_create_1d`, and returns it. The array has a reference count of one, so it
+/// must be released with [`build_array_update_reference_count`] when it's no longer used.
+pub unsafe fn build_qubit_array(builder: LLVMBuilderRef, qubits: &[LLVMValueRef]) -> LLVMValueRef {
+    let module = builder_module(builder);
+    let context = LLVMGetModuleContext(module);
+    let i32_ty = LLVMInt32TypeInContext(context);
+    let i64_ty = LLVMInt64TypeInContext(context);
+    // Every element is a qubit pointer.
+    let element_size = LLVMConstInt(i32_ty, 8, 0);
+    let count = LLVMConstInt(i64_ty, qubits.len().try_into().unwrap(), 0);
+    let array = build_call(builder, array_create_1d(module), &mut [element_size, count]);
+
+    let element_ptr_ty = LLVMPointerType(types::qubit(context), 0);
+    for (i, &qubit) in qubits.iter().enumerate() {
+        let index = LLVMConstInt(i64_ty, i.try_into().unwrap(), 0);
+        let ptr = build_call(
+            builder,
+            array_get_element_ptr_1d(module),
+            &mut [array, index],
+        );
+        let ptr = LLVMBuildBitCast(builder, ptr, element_ptr_ty, raw_cstr!(""));
+        LLVMBuildStore(builder, qubit, ptr);
+    }
+    array
+}
+
 pub unsafe fn build_result_record_output(
     builder: LLVMBuilderRef,
     result: LLVMValueRef,
@@ -61,6 +116,37 @@ unsafe fn array_record_output(module: LLVMModuleRef) -> LLVMValueRef {
     record_output(module, name, param_type)
 }
 
+unsafe fn array_create_1d(module: LLVMModuleRef) -> LLVMValueRef {
+    let context = LLVMGetModuleContext(module);
+    let ty = function_type(
+        types::array(context),
+        &mut [
+            LLVMInt32TypeInContext(context),
+            LLVMInt64TypeInContext(context),
+        ],
+    );
+    declare_external_function(module, "__quantum__rt__array_create_1d", ty)
+}
+
+unsafe fn array_get_element_ptr_1d(module: LLVMModuleRef) -> LLVMValueRef {
+    let context = LLVMGetModuleContext(module);
+    let i8p = LLVMPointerType(LLVMInt8TypeInContext(context), 0);
+    let ty = function_type(
+        i8p,
+        &mut [types::array(context), LLVMInt64TypeInContext(context)],
+    );
+    declare_external_function(module, "__quantum__rt__array_get_element_ptr_1d", ty)
+}
+
+unsafe fn array_update_reference_count(module: LLVMModuleRef) -> LLVMValueRef {
+    let context = LLVMGetModuleContext(module);
+    let ty = function_type(
+        LLVMVoidTypeInContext(context),
+        &mut [types::array(context), LLVMInt32TypeInContext(context)],
+    );
+    declare_external_function(module, "__quantum__rt__array_update_reference_count", ty)
+}
+
 unsafe fn initialize(module: LLVMModuleRef) -> LLVMValueRef {
     let context = LLVMGetModuleContext(module);
     let i8type = LLVMInt8TypeInContext(context);
@@ -70,6 +156,13 @@ unsafe fn initialize(module: LLVMModuleRef) -> LLVMValueRef {
     declare_external_function(module, name, ty)
 }
 
+unsafe fn int_record_output(module: LLVMModuleRef) -> LLVMValueRef {
+    let context = LLVMGetModuleContext(module);
+    let param_type = LLVMInt64TypeInContext(context);
+    let name = "int_record_output";
+    record_output(module, name, param_type)
+}
+
 unsafe fn result_record_output(module: LLVMModuleRef) -> LLVMValueRef {
     let context = LLVMGetModuleContext(module);
     let param_type = types::result(context);
@@ -110,7 +203,10 @@ mod tests {
     };
 
     use super::*;
-    use crate::{tests::assert_reference_ir, values::result};
+    use crate::{
+        tests::assert_reference_ir,
+        values::{qubit, result},
+    };
 
     unsafe fn builder_context(builder: LLVMBuilderRef) -> Option<NonNull<LLVMContext>> {
         let block = NonNull::new(LLVMGetInsertBlock(builder))?;
@@ -135,6 +231,16 @@ mod tests {
         });
     }
 
+    #[test]
+    fn qubit_array() {
+        assert_reference_ir("rt/qubit_array", 2, 0, |builder| unsafe {
+            let context = builder_context(builder).unwrap().as_ptr();
+            let array = build_qubit_array(builder, &[qubit(context, 1), qubit(context, 0)]);
+            let delta = LLVMConstInt(LLVMInt32TypeInContext(context), u64::MAX, 1);
+            build_array_update_reference_count(builder, array, delta);
+        });
+    }
+
     #[test]
     fn initialize() {
         assert_reference_ir("rt/initialize", 0, 0, |builder| unsafe {
@@ -146,6 +252,20 @@ mod tests {
         });
     }
 
+    #[test]
+    fn int_record_output() {
+        assert_reference_ir("rt/int_record_output", 0, 0, |builder| unsafe {
+            let context = builder_context(builder).unwrap().as_ptr();
+            let i64_ty = LLVMInt64TypeInContext(context);
+
+            let value = LLVMConstInt(i64_ty, 3, 0);
+            let i8_ty = LLVMInt8TypeInContext(context);
+            let i8_ptr_ty = LLVMPointerType(i8_ty, 0);
+            let i8_null_ptr = LLVMConstPointerNull(i8_ptr_ty);
+            build_int_record_output(builder, value, i8_null_ptr);
+        });
+    }
+
     #[test]
     fn result_record_output() {
         assert_reference_ir("rt/result_record_output", 0, 1, |builder| unsafe {
@@ -106,7 +106,7 @@ impl Drop for Message {
 pub(crate) struct Module(NonNull<LLVMModule>);
 
 impl Module {
-    fn new(context: &Context, name: &CStr) -> Self {
+    pub(crate) fn new(context: &Context, name: &CStr) -> Self {
         let module = unsafe { LLVMModuleCreateWithNameInContext(name.as_ptr(), context.as_ptr()) };
         Self(NonNull::new(module).expect("Module is null."))
     }
@@ -163,7 +163,7 @@ pub(crate) fn assert_reference_ir(
     }
 }
 
-fn build_ir(
+pub(crate) fn build_ir(
     name: &CStr,
     required_num_qubits: u64,
     required_num_results: u64,
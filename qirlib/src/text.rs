@@ -0,0 +1,432 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+//! Writes the LLVM IR of simple QIR programs without going through LLVM. This module doesn't
+//! use the LLVM C API, so it's available even when LLVM isn't linked.
+
+use std::fmt::Write;
+
+/// A call to a QIS intrinsic with static qubit and result IDs. Angles come before qubits, and
+/// qubits come before results, like the parameters of the intrinsic.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Instruction {
+    Barrier,
+    Ccx(u64, u64, u64),
+    Ch(u64, u64),
+    Cx(u64, u64),
+    Cy(u64, u64),
+    Cz(u64, u64),
+    Depolarize(f64, u64),
+    H(u64),
+    Id(u64),
+    Mz(u64, u64),
+    Reset(u64),
+    Rx(f64, u64),
+    Ry(f64, u64),
+    Rz(f64, u64),
+    S(u64),
+    SAdj(u64),
+    Swap(u64, u64),
+    T(u64),
+    TAdj(u64),
+    U1(f64, u64),
+    U2(f64, f64, u64),
+    X(u64),
+    Y(u64),
+    Z(u64),
+}
+
+#[derive(Clone, Copy)]
+enum Arg {
+    Double(f64),
+    Qubit(u64),
+    Result(u64),
+}
+
+impl Instruction {
+    fn function(self) -> &'static str {
+        match self {
+            Self::Barrier => "__quantum__qis__barrier__body",
+            Self::Ccx(..) => "__quantum__qis__ccx__body",
+            Self::Ch(..) => "__quantum__qis__ch__body",
+            Self::Cx(..) => "__quantum__qis__cnot__body",
+            Self::Cy(..) => "__quantum__qis__cy__body",
+            Self::Cz(..) => "__quantum__qis__cz__body",
+            Self::Depolarize(..) => "__quantum__qis__depolarize__body",
+            Self::H(_) => "__quantum__qis__h__body",
+            Self::Id(_) => "__quantum__qis__id__body",
+            Self::Mz(..) => "__quantum__qis__mz__body",
+            Self::Reset(_) => "__quantum__qis__reset__body",
+            Self::Rx(..) => "__quantum__qis__rx__body",
+            Self::Ry(..) => "__quantum__qis__ry__body",
+            Self::Rz(..) => "__quantum__qis__rz__body",
+            Self::S(_) => "__quantum__qis__s__body",
+            Self::SAdj(_) => "__quantum__qis__s__adj",
+            Self::Swap(..) => "__quantum__qis__swap__body",
+            Self::T(_) => "__quantum__qis__t__body",
+            Self::TAdj(_) => "__quantum__qis__t__adj",
+            Self::U1(..) => "__quantum__qis__u1__body",
+            Self::U2(..) => "__quantum__qis__u2__body",
+            Self::X(_) => "__quantum__qis__x__body",
+            Self::Y(_) => "__quantum__qis__y__body",
+            Self::Z(_) => "__quantum__qis__z__body",
+        }
+    }
+
+    fn args(self) -> Vec<Arg> {
+        match self {
+            Self::Barrier => vec![],
+            Self::Ccx(control1, control2, qubit) => vec![
+                Arg::Qubit(control1),
+                Arg::Qubit(control2),
+                Arg::Qubit(qubit),
+            ],
+            Self::Ch(qubit1, qubit2)
+            | Self::Cx(qubit1, qubit2)
+            | Self::Cy(qubit1, qubit2)
+            | Self::Cz(qubit1, qubit2)
+            | Self::Swap(qubit1, qubit2) => vec![Arg::Qubit(qubit1), Arg::Qubit(qubit2)],
+            Self::Depolarize(angle, qubit)
+            | Self::Rx(angle, qubit)
+            | Self::Ry(angle, qubit)
+            | Self::Rz(angle, qubit)
+            | Self::U1(angle, qubit) => vec![Arg::Double(angle), Arg::Qubit(qubit)],
+            Self::H(qubit)
+            | Self::Id(qubit)
+            | Self::Reset(qubit)
+            | Self::S(qubit)
+            | Self::SAdj(qubit)
+            | Self::T(qubit)
+            | Self::TAdj(qubit)
+            | Self::X(qubit)
+            | Self::Y(qubit)
+            | Self::Z(qubit) => vec![Arg::Qubit(qubit)],
+            Self::Mz(qubit, result) => vec![Arg::Qubit(qubit), Arg::Result(result)],
+            Self::U2(phi, lambda, qubit) => {
+                vec![Arg::Double(phi), Arg::Double(lambda), Arg::Qubit(qubit)]
+            }
+        }
+    }
+
+    fn is_irreversible(self) -> bool {
+        matches!(self, Self::Mz(..))
+    }
+}
+
+/// Writes the LLVM IR of a module with one entry point named `main` that calls each
+/// instruction in order. The IR is the same as LLVM prints for the module built by
+/// `values::entry_point` with the `custom` profile and an empty output labeling schema, followed
+/// by the `qis::build_*` function for each instruction, for LLVM versions with typed pointers.
+#[must_use]
+pub fn emit_text(
+    name: &str,
+    required_num_qubits: u64,
+    required_num_results: u64,
+    instructions: &[Instruction],
+) -> String {
+    let mut body = String::new();
+    let mut declarations: Vec<Instruction> = Vec::new();
+    for &instruction in instructions {
+        let function = instruction.function();
+        if !declarations.iter().any(|d| d.function() == function) {
+            declarations.push(instruction);
+        }
+        let args: Vec<_> = instruction.args().into_iter().map(arg).collect();
+        writeln!(body, "  call void @{function}({})", args.join(", ")).unwrap();
+    }
+
+    let mut ir = String::new();
+    writeln!(ir, "; ModuleID = '{name}'").unwrap();
+    writeln!(ir, "source_filename = \"{}\"", escape(name)).unwrap();
+    writeln!(ir).unwrap();
+    // LLVM prints the named types in the order that it finds them in the entry point.
+    let types = type_order(instructions);
+    for ty in &types {
+        writeln!(ir, "%{ty} = type opaque").unwrap();
+    }
+    if !types.is_empty() {
+        writeln!(ir).unwrap();
+    }
+
+    writeln!(ir, "define void @main() #0 {{").unwrap();
+    ir.push_str(&body);
+    writeln!(ir, "  ret void\n}}\n").unwrap();
+
+    let mut irreversible = false;
+    for declaration in declarations {
+        let params: Vec<_> = declaration.args().into_iter().map(param).collect();
+        write!(
+            ir,
+            "declare void @{}({})",
+            declaration.function(),
+            params.join(", ")
+        )
+        .unwrap();
+        if declaration.is_irreversible() {
+            irreversible = true;
+            ir.push_str(" #1");
+        }
+        writeln!(ir, "\n").unwrap();
+    }
+
+    writeln!(
+        ir,
+        "attributes #0 = {{ \"entry_point\" \"output_labeling_schema\" \
+         \"qir_profiles\"=\"custom\" \"required_num_qubits\"=\"{required_num_qubits}\" \
+         \"required_num_results\"=\"{required_num_results}\" }}"
+    )
+    .unwrap();
+    if irreversible {
+        writeln!(ir, "attributes #1 = {{ \"irreversible\" }}").unwrap();
+    }
+    ir
+}
+
+fn type_order(instructions: &[Instruction]) -> Vec<&'static str> {
+    let mut types = Vec::new();
+    for arg in instructions.iter().flat_map(|i| i.args()) {
+        let ty = match arg {
+            Arg::Double(_) => continue,
+            Arg::Qubit(_) => "Qubit",
+            Arg::Result(_) => "Result",
+        };
+        if !types.contains(&ty) {
+            types.push(ty);
+        }
+    }
+    types
+}
+
+fn arg(arg: Arg) -> String {
+    match arg {
+        Arg::Double(value) => format!("double {}", double(value)),
+        Arg::Qubit(id) => format!("%Qubit* {}", pointer(id, "Qubit")),
+        Arg::Result(id) => format!("%Result* {}", pointer(id, "Result")),
+    }
+}
+
+fn param(arg: Arg) -> &'static str {
+    match arg {
+        Arg::Double(_) => "double",
+        Arg::Qubit(_) => "%Qubit*",
+        // The only parameter of this type is the result that mz writes to.
+        Arg::Result(_) => "%Result* writeonly",
+    }
+}
+
+fn pointer(id: u64, ty: &str) -> String {
+    if id == 0 {
+        "null".to_string()
+    } else {
+        // LLVM prints integer constants as signed.
+        #[allow(clippy::cast_possible_wrap)]
+        let id = id as i64;
+        format!("inttoptr (i64 {id} to %{ty}*)")
+    }
+}
+
+/// Formats a double like LLVM does: in scientific notation with six significant digits if that
+/// reads back as the same value, and as the hexadecimal bit pattern otherwise.
+fn double(value: f64) -> String {
+    if value.is_finite() {
+        let short = format!("{value:.5e}");
+        if short.parse::<f64>() == Ok(value) {
+            let (mantissa, exponent) = short.split_once('e').unwrap();
+            let (sign, digits) = match exponent.strip_prefix('-') {
+                Some(digits) => ('-', digits),
+                None => ('+', exponent),
+            };
+            return format!("{mantissa}0e{sign}{digits:0>2}");
+        }
+    }
+    format!("0x{:X}", value.to_bits())
+}
+
+/// Escapes a string like LLVM does in quoted names: backslashes are doubled, printable ASCII
+/// characters other than quotes are written as they are, and other bytes as two hexadecimal
+/// digits.
+fn escape(s: &str) -> String {
+    let mut escaped = String::new();
+    for &byte in s.as_bytes() {
+        if byte == b'\\' {
+            escaped.push_str("\\\\");
+        } else if (b' '..=b'~').contains(&byte) && byte != b'"' {
+            escaped.push(char::from(byte));
+        } else {
+            write!(escaped, "\\{byte:02X}").unwrap();
+        }
+    }
+    escaped
+}
+
+#[cfg(all(test, not(feature = "no-llvm-linking")))]
+mod tests {
+    use super::*;
+    use crate::{
+        qis,
+        tests::build_ir,
+        utils::builder_module,
+        values::{qubit, result},
+    };
+    use llvm_sys::{
+        core::{LLVMConstReal, LLVMDoubleTypeInContext, LLVMGetModuleContext},
+        prelude::*,
+    };
+    use std::{
+        f64::consts::{FRAC_PI_2, PI},
+        ffi::CString,
+    };
+
+    unsafe fn build(builder: LLVMBuilderRef, instruction: Instruction) {
+        let context = LLVMGetModuleContext(builder_module(builder));
+        let qubit = |id| qubit(context, id);
+        let result = |id| result(context, id);
+        let double = |value| LLVMConstReal(LLVMDoubleTypeInContext(context), value);
+        match instruction {
+            Instruction::Barrier => qis::build_barrier(builder),
+            Instruction::Ccx(q1, q2, q3) => {
+                qis::build_ccx(builder, qubit(q1), qubit(q2), qubit(q3));
+            }
+            Instruction::Ch(q1, q2) => qis::build_ch(builder, qubit(q1), qubit(q2)),
+            Instruction::Cx(q1, q2) => qis::build_cx(builder, qubit(q1), qubit(q2)),
+            Instruction::Cy(q1, q2) => qis::build_cy(builder, qubit(q1), qubit(q2)),
+            Instruction::Cz(q1, q2) => qis::build_cz(builder, qubit(q1), qubit(q2)),
+            Instruction::Depolarize(p, q1) => qis::build_depolarize(builder, double(p), qubit(q1)),
+            Instruction::H(q1) => qis::build_h(builder, qubit(q1)),
+            Instruction::Id(q1) => qis::build_id(builder, qubit(q1)),
+            Instruction::Mz(q1, r1) => qis::build_mz(builder, qubit(q1), result(r1)),
+            Instruction::Reset(q1) => qis::build_reset(builder, qubit(q1)),
+            Instruction::Rx(theta, q1) => qis::build_rx(builder, double(theta), qubit(q1)),
+            Instruction::Ry(theta, q1) => qis::build_ry(builder, double(theta), qubit(q1)),
+            Instruction::Rz(theta, q1) => qis::build_rz(builder, double(theta), qubit(q1)),
+            Instruction::S(q1) => qis::build_s(builder, qubit(q1)),
+            Instruction::SAdj(q1) => qis::build_s_adj(builder, qubit(q1)),
+            Instruction::Swap(q1, q2) => qis::build_swap(builder, qubit(q1), qubit(q2)),
+            Instruction::T(q1) => qis::build_t(builder, qubit(q1)),
+            Instruction::TAdj(q1) => qis::build_t_adj(builder, qubit(q1)),
+            Instruction::U1(lambda, q1) => qis::build_u1(builder, double(lambda), qubit(q1)),
+            Instruction::U2(phi, lambda, q1) => {
+                qis::build_u2(builder, double(phi), double(lambda), qubit(q1));
+            }
+            Instruction::X(q1) => qis::build_x(builder, qubit(q1)),
+            Instruction::Y(q1) => qis::build_y(builder, qubit(q1)),
+            Instruction::Z(q1) => qis::build_z(builder, qubit(q1)),
+        }
+    }
+
+    fn assert_same_as_llvm(
+        name: &str,
+        required_num_qubits: u64,
+        required_num_results: u64,
+        instructions: &[Instruction],
+    ) {
+        let c_name = CString::new(name).unwrap();
+        let expected = build_ir(
+            &c_name,
+            required_num_qubits,
+            required_num_results,
+            |builder| {
+                for &instruction in instructions {
+                    unsafe { build(builder, instruction) };
+                }
+            },
+        )
+        .unwrap();
+        let actual = emit_text(
+            name,
+            required_num_qubits,
+            required_num_results,
+            instructions,
+        );
+        assert_eq!(expected.to_str().unwrap(), actual);
+    }
+
+    #[test]
+    fn empty() {
+        assert_same_as_llvm("empty", 0, 0, &[]);
+    }
+
+    #[test]
+    fn each_instruction() {
+        let instructions = [
+            Instruction::Barrier,
+            Instruction::Ccx(0, 1, 2),
+            Instruction::Ch(0, 1),
+            Instruction::Cx(0, 1),
+            Instruction::Cy(0, 1),
+            Instruction::Cz(0, 1),
+            Instruction::Depolarize(0.5, 0),
+            Instruction::H(0),
+            Instruction::Id(0),
+            Instruction::Mz(0, 0),
+            Instruction::Reset(0),
+            Instruction::Rx(0.0, 0),
+            Instruction::Ry(1.0, 0),
+            Instruction::Rz(2.0, 0),
+            Instruction::S(0),
+            Instruction::SAdj(0),
+            Instruction::Swap(0, 1),
+            Instruction::T(0),
+            Instruction::TAdj(0),
+            Instruction::U1(0.25, 0),
+            Instruction::U2(0.5, 0.75, 0),
+            Instruction::X(0),
+            Instruction::Y(0),
+            Instruction::Z(0),
+        ];
+        for instruction in instructions {
+            assert_same_as_llvm("instruction", 3, 1, &[instruction]);
+        }
+        assert_same_as_llvm("all", 3, 1, &instructions);
+    }
+
+    #[test]
+    fn bell() {
+        assert_same_as_llvm(
+            "bell",
+            2,
+            2,
+            &[
+                Instruction::H(0),
+                Instruction::Cx(0, 1),
+                Instruction::Mz(0, 0),
+                Instruction::Mz(1, 1),
+            ],
+        );
+    }
+
+    #[test]
+    fn angles() {
+        let angles = [
+            -0.0,
+            0.1,
+            -2.5,
+            PI,
+            FRAC_PI_2,
+            123_456.0,
+            1_234_567.0,
+            1e-300,
+            1e300,
+            f64::MIN_POSITIVE / 2.0,
+            f64::INFINITY,
+            f64::NAN,
+        ];
+        let instructions: Vec<_> = angles.iter().map(|&a| Instruction::Rx(a, 0)).collect();
+        assert_same_as_llvm("angles", 1, 0, &instructions);
+    }
+
+    #[test]
+    fn large_ids() {
+        assert_same_as_llvm(
+            "large_ids",
+            u64::MAX,
+            u64::MAX,
+            &[Instruction::Mz(u64::MAX, 1 << 40), Instruction::X(7)],
+        );
+    }
+
+    #[test]
+    fn escaped_name() {
+        assert_same_as_llvm("a \"quoted\" name\\é", 1, 0, &[Instruction::H(0)]);
+    }
+}
@@ -12,9 +12,18 @@ use llvm_sys::{
 };
 use std::ffi::CStr;
 
+const ARRAY: &CStr = cstr!("Array");
 const QUBIT: &CStr = cstr!("Qubit");
 const RESULT: &CStr = cstr!("Result");
 
+pub unsafe fn array(context: LLVMContextRef) -> LLVMTypeRef {
+    LLVMPointerType(get_or_create_struct(context, ARRAY), 0)
+}
+
+pub unsafe fn is_array(ty: LLVMTypeRef) -> bool {
+    is_opaque_pointer_to(ty, ARRAY)
+}
+
 pub unsafe fn qubit(context: LLVMContextRef) -> LLVMTypeRef {
     LLVMPointerType(get_or_create_struct(context, QUBIT), 0)
 }
@@ -31,6 +40,13 @@ pub unsafe fn is_result(ty: LLVMTypeRef) -> bool {
     is_opaque_pointer_to(ty, RESULT)
 }
 
+/// Makes every pointer type in the context opaque, so that `%Qubit*` and `%Result*` are both `ptr`.
+/// This must be done before any pointer types are created in the context.
+#[cfg(feature = "llvm14-0")]
+pub unsafe fn enable_opaque_pointers(context: LLVMContextRef) {
+    crate::llvm_wrapper::LLVMRustContextEnableOpaquePointers(context);
+}
+
 unsafe fn get_or_create_struct(context: LLVMContextRef, name: &CStr) -> LLVMTypeRef {
     let ty = LLVMGetTypeByName2(context, name.as_ptr());
     if ty.is_null() {
@@ -42,15 +58,28 @@ unsafe fn get_or_create_struct(context: LLVMContextRef, name: &CStr) -> LLVMType
 
 unsafe fn is_opaque_pointer_to(ty: LLVMTypeRef, name: &CStr) -> bool {
     if LLVMGetTypeKind(ty) == LLVMTypeKind::LLVMPointerTypeKind {
+        // Opaque pointers have no element type.
         let pointee = LLVMGetElementType(ty);
-        LLVMGetTypeKind(pointee) == LLVMTypeKind::LLVMStructTypeKind
+        !pointee.is_null()
+            && LLVMGetTypeKind(pointee) == LLVMTypeKind::LLVMStructTypeKind
             && LLVMIsOpaqueStruct(ty) != 0
-            && CStr::from_ptr(LLVMGetStructName(pointee)) == name
+            && is_name_or_renamed(CStr::from_ptr(LLVMGetStructName(pointee)), name)
     } else {
         false
     }
 }
 
+/// Whether the struct name is the given name, or the given name with the numeric suffix that LLVM
+/// adds when a module with its own copy of the type is read into a context that already has it.
+fn is_name_or_renamed(struct_name: &CStr, name: &CStr) -> bool {
+    let struct_name = struct_name.to_bytes();
+    let name = name.to_bytes();
+    struct_name == name
+        || struct_name.strip_prefix(name).map_or(false, |suffix| {
+            suffix.len() > 1 && suffix[0] == b'.' && suffix[1..].iter().all(u8::is_ascii_digit)
+        })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -75,4 +104,24 @@ mod tests {
             assert!(!is_qubit(result));
         }
     }
+
+    #[test]
+    fn array_type() {
+        unsafe {
+            let context = Context::new();
+            let array = array(context.as_ptr());
+            assert!(is_array(array));
+            assert!(!is_qubit(array));
+            assert!(!is_array(qubit(context.as_ptr())));
+        }
+    }
+
+    #[test]
+    fn renamed_types() {
+        assert!(is_name_or_renamed(cstr!("Qubit.0"), QUBIT));
+        assert!(is_name_or_renamed(cstr!("Result.12"), RESULT));
+        assert!(!is_name_or_renamed(cstr!("Qubit."), QUBIT));
+        assert!(!is_name_or_renamed(cstr!("Qubit.a"), QUBIT));
+        assert!(!is_name_or_renamed(cstr!("Qubits"), QUBIT));
+    }
 }
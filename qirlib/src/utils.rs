@@ -86,6 +86,16 @@ pub(crate) unsafe fn rotation_gate(module: LLVMModuleRef, name: &str) -> LLVMVal
     declare_qis(module, name, Functor::Body, ty)
 }
 
+pub(crate) unsafe fn two_angle_gate(module: LLVMModuleRef, name: &str) -> LLVMValueRef {
+    let context = LLVMGetModuleContext(module);
+    let double = LLVMDoubleTypeInContext(context);
+    let ty = function_type(
+        LLVMVoidTypeInContext(context),
+        &mut [double, double, types::qubit(context)],
+    );
+    declare_qis(module, name, Functor::Body, ty)
+}
+
 pub(crate) unsafe fn function_type(ret: LLVMTypeRef, params: &mut [LLVMTypeRef]) -> LLVMTypeRef {
     LLVMFunctionType(
         ret,